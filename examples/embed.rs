@@ -0,0 +1,78 @@
+//! Runs a typing test from another program, with words from a custom
+//! word source and keys typed by the program itself, and prints the
+//! results.
+//!
+//! The test is displayed on a terminal kept in memory instead of the
+//! real one, so nothing shows up while it runs:
+//!
+//! ```text
+//! cargo run --example embed
+//! ```
+
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+use termion::event::Key;
+use toipe::config::ToipeConfig;
+use toipe::input::KeyEvents;
+use toipe::session::ToipeSession;
+use toipe::textgen::WordSelector;
+use toipe::theme::ThemeChoice;
+use toipe::tui::{FakeTerminal, ToipeTui};
+use toipe::Toipe;
+
+/// Words to type, in order.
+const WORDS: &[&str] = &["fn", "let", "mut", "impl", "trait", "struct"];
+
+/// Selects the words of [`WORDS`] one after the other, starting over
+/// after the last one.
+struct RustKeywords {
+    next: usize,
+}
+
+impl WordSelector for RustKeywords {
+    fn new_word(&mut self) -> Result<String, io::Error> {
+        let word = WORDS[self.next % WORDS.len()];
+        self.next += 1;
+        Ok(word.to_string())
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let config = ToipeConfig {
+        num_words: WORDS.len(),
+        // the background of a terminal in memory cannot be detected
+        theme: ThemeChoice::Dark,
+        ..Default::default()
+    };
+
+    let terminal = FakeTerminal::new(80, 24);
+    let toipe = Toipe::with_tui(config, ToipeTui::from_terminal(terminal))?
+        .with_word_selector(Box::new(RustKeywords { next: 0 }))?;
+    let mut session = ToipeSession::from_toipe(toipe).on_test_end(|results| {
+        println!(
+            "typed {} words at {:.1} wpm with {:.1}% accuracy",
+            results.total_words,
+            results.wpm(),
+            results.accuracy() * 100.0
+        );
+        Ok(())
+    });
+
+    // the text, with a typo that is corrected right away, and then
+    // ctrl-c to quit from the results
+    let mut keys = vec![Key::Char('x'), Key::Backspace];
+    keys.extend(WORDS.join(" ").chars().map(Key::Char));
+    keys.push(Key::Ctrl('c'));
+    // about 60 wpm
+    let keys = keys
+        .into_iter()
+        .inspect(|_| thread::sleep(Duration::from_millis(200)));
+
+    session.run(&mut KeyEvents::from_keys(keys))?;
+    // give the terminal back, reporting any error in doing so
+    session.toipe().shutdown()?;
+
+    Ok(())
+}
@@ -0,0 +1,197 @@
+//! Certificates of typing speed, made by `toipe certify` for job
+//! applications and such.
+//!
+//! See [`Certificate`].
+
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use sha2::{Digest, Sha256};
+
+use crate::results::ToipeResults;
+
+/// Minimum number of columns inside the border of a certificate.
+const MIN_WIDTH: usize = 70;
+
+/// Details of a typing test that are printed on a certificate.
+///
+/// The certificate is printed as plain text in a border by its
+/// [`Display`](fmt::Display) implementation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Certificate {
+    /// name of the person who typed the test
+    pub name: String,
+    /// when the test was typed
+    pub date: SystemTime,
+    /// name of the word list or text the test was typed from
+    pub text_name: String,
+    /// how long the test took
+    pub duration: Duration,
+    pub wpm: f64,
+    /// between 0 and 1
+    pub accuracy: f64,
+    /// hash of the text of the test (see [`text_hash`])
+    pub text_hash: String,
+}
+
+impl Certificate {
+    /// Creates the certificate of a test with the given results.
+    pub fn new(name: &str, text_name: &str, results: &ToipeResults, date: SystemTime) -> Self {
+        Self {
+            name: name.to_string(),
+            date,
+            text_name: text_name.to_string(),
+            duration: results.duration(),
+            wpm: results.wpm(),
+            accuracy: results.accuracy(),
+            text_hash: text_hash(results),
+        }
+    }
+}
+
+impl fmt::Display for Certificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.duration.as_secs_f64().round() as u64;
+        let length = if secs % 60 == 0 {
+            format!("{} minute", secs / 60)
+        } else {
+            format!("{} second", secs)
+        };
+        let (year, month, day) = utc_date(self.date);
+
+        // (line, whether it is centered)
+        let lines = [
+            ("CERTIFICATE OF TYPING SPEED".to_string(), true),
+            (String::new(), false),
+            ("This certifies that".to_string(), false),
+            (self.name.clone(), true),
+            (
+                format!(
+                    "typed a {} test with toipe on {:04}-{:02}-{:02} (UTC).",
+                    length, year, month, day
+                ),
+                false,
+            ),
+            (String::new(), false),
+            (format!("    Speed      {:.1} wpm", self.wpm), false),
+            (
+                format!("    Accuracy   {:.1}%", self.accuracy * 100.0),
+                false,
+            ),
+            (format!("    Text       {}", self.text_name), false),
+            (String::new(), false),
+            ("SHA-256 hash of the text typed:".to_string(), false),
+            (self.text_hash.clone(), false),
+        ];
+
+        let width = lines
+            .iter()
+            .map(|(line, _)| line.chars().count() + 4)
+            .fold(MIN_WIDTH, usize::max);
+        let border = format!("+{}+", "-".repeat(width));
+        writeln!(f, "{}", border)?;
+        for (line, centered) in lines {
+            if centered {
+                writeln!(f, "|{:^width$}|", line, width = width)?;
+            } else {
+                writeln!(f, "|  {:<width$}|", line, width = width - 2)?;
+            }
+        }
+        writeln!(f, "{}", border)
+    }
+}
+
+/// SHA-256 hash of the text of a test, as hex digits.
+///
+/// The text is the chars of the test up to the last one typed. Anyone
+/// with the text can check that it is the one the certificate is for.
+pub fn text_hash(results: &ToipeResults) -> String {
+    let text: String = results
+        .char_records()
+        .iter()
+        .filter_map(|record| record.expected)
+        .collect();
+    Sha256::digest(text.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Year, month and day of the time in UTC.
+fn utc_date(time: SystemTime) -> (i64, u32, u32) {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    // days since 0000-03-01, when leap days are at the end of the year
+    let days = (secs / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month as u32, day as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_are_in_utc() {
+        let date = |secs| utc_date(UNIX_EPOCH + Duration::from_secs(secs));
+        assert_eq!(date(0), (1970, 1, 1));
+        assert_eq!(date(951_782_400), (2000, 2, 29));
+        assert_eq!(date(951_868_799), (2000, 2, 29));
+        assert_eq!(date(951_868_800), (2000, 3, 1));
+        assert_eq!(date(1_798_761_599), (2026, 12, 31));
+    }
+
+    #[test]
+    fn certificates_are_boxed() {
+        let certificate = Certificate {
+            name: "Ada Lovelace".to_string(),
+            date: UNIX_EPOCH + Duration::from_secs(1_792_195_200),
+            text_name: "english/top250".to_string(),
+            duration: Duration::from_millis(300_200),
+            wpm: 72.44,
+            accuracy: 0.9712,
+            text_hash: "ab".repeat(32),
+        };
+        let text = certificate.to_string();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert!(lines
+            .iter()
+            .all(|line| line.chars().count() == MIN_WIDTH + 2));
+        assert_eq!(lines[0], lines[lines.len() - 1]);
+        for expected in [
+            "Ada Lovelace",
+            "typed a 5 minute test with toipe on 2026-10-17 (UTC).",
+            "Speed      72.4 wpm",
+            "Accuracy   97.1%",
+            "Text       english/top250",
+            &"ab".repeat(32),
+        ] {
+            assert!(text.contains(expected), "{}", text);
+        }
+
+        // long names make the certificate wider
+        let certificate = Certificate {
+            name: "A".repeat(100),
+            ..certificate
+        };
+        assert!(certificate
+            .to_string()
+            .lines()
+            .all(|line| line.chars().count() == 106));
+    }
+}
@@ -0,0 +1,38 @@
+//! Copying text to the system clipboard.
+//!
+//! See [`Clipboard`].
+
+/// The system clipboard (X11, Wayland or Mac OS), if toipe was built
+/// with the `clipboard` feature and one is available.
+///
+/// The clipboard is opened when text is first copied. On X11, copied
+/// text is only available while the clipboard is kept open, so it
+/// should live as long as the program.
+#[derive(Default)]
+pub struct Clipboard {
+    #[cfg(feature = "clipboard")]
+    inner: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    /// Copies the text to the clipboard.
+    ///
+    /// Returns whether it was copied. Without the `clipboard` feature,
+    /// nothing is ever copied.
+    pub fn copy(&mut self, text: &str) -> bool {
+        #[cfg(feature = "clipboard")]
+        {
+            if self.inner.is_none() {
+                self.inner = arboard::Clipboard::new().ok();
+            }
+            self.inner
+                .as_mut()
+                .is_some_and(|clipboard| clipboard.set_text(text).is_ok())
+        }
+        #[cfg(not(feature = "clipboard"))]
+        {
+            let _ = text;
+            false
+        }
+    }
+}
@@ -0,0 +1,529 @@
+//! Commands that run instead of the typing test.
+//!
+//! See [`ToipeCommand`] for the list of commands. [`print_text`] runs
+//! for the `--print-text` flag, and [`certify`] after the test of the
+//! `certify` command.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+#[cfg(any(test, feature = "export"))]
+use clap::Parser;
+
+use crate::certificate::Certificate;
+use crate::config::{SheetFormat, ToipeCommand, ToipeConfig, WordlistCommand};
+#[cfg(feature = "export")]
+use crate::export::ExportedResults;
+#[cfg(feature = "history")]
+use crate::history::{History, MIN_DIGRAPH_COUNT};
+use crate::remind;
+use crate::results::ToipeResults;
+use crate::textgen::WordSelector;
+use crate::wordlists::{self, Language};
+use crate::ToipeError;
+
+/// Maximum width of the bars printed in histograms.
+const MAX_BAR_WIDTH: usize = 50;
+
+/// Number of digraphs printed by `stats --digraphs`.
+#[cfg(feature = "history")]
+const NUM_SLOWEST_DIGRAPHS: usize = 20;
+
+/// Runs the given command, writing its output to `out`.
+///
+/// Returns an error for the commands that run a typing test instead,
+/// `drill` and `certify` (see [`ToipeCommand::is_typing_test`]), which
+/// are run with a [`Toipe`](crate::Toipe) made from the config.
+pub fn run(command: &ToipeCommand, config: &ToipeConfig, out: &mut impl Write) -> Result<()> {
+    match command {
+        ToipeCommand::Wordlist(WordlistCommand::Sample {
+            num_samples,
+            histogram,
+        }) => wordlist_sample(config, *num_samples, *histogram, out),
+        ToipeCommand::Wordlist(WordlistCommand::List) => wordlist_list(out),
+        ToipeCommand::Sheet {
+            pages,
+            lines,
+            width,
+            format,
+        } => sheet(config, *pages, *lines, *width, *format, out),
+        ToipeCommand::Stats { digraphs } => stats(config, *digraphs, out),
+        ToipeCommand::Verify { file } => verify(file, out),
+        ToipeCommand::Remind {
+            install: true,
+            every,
+            ..
+        } => remind::install(*every, out),
+        ToipeCommand::Remind { .. } => remind::uninstall(out),
+        ToipeCommand::Drill { .. } => Err(ToipeError::from(
+            "drill runs the typing test instead of a command".to_owned(),
+        ))?,
+        ToipeCommand::Certify { .. } => Err(ToipeError::from(
+            "certify runs the typing test instead of a command".to_owned(),
+        ))?,
+    }
+}
+
+/// Prints the text of one test, as it would be shown in the typing
+/// test, on a single line.
+pub fn print_text(config: &ToipeConfig, out: &mut impl Write) -> Result<()> {
+    let words = config.word_stream()?.next_text()?;
+
+    writeln!(out, "{}", words.join(" "))?;
+
+    Ok(())
+}
+
+/// Selects `num_samples` words from the configured word list and prints
+/// them, or a histogram of how many times each was selected.
+pub fn wordlist_sample(
+    config: &ToipeConfig,
+    num_samples: usize,
+    histogram: bool,
+    out: &mut impl Write,
+) -> Result<()> {
+    let mut word_selector = config.word_selector()?;
+    let words = word_selector.new_words(num_samples)?;
+
+    if !histogram {
+        for word in words {
+            writeln!(out, "{}", word)?;
+        }
+        return Ok(());
+    }
+
+    let mut counts = HashMap::<String, usize>::new();
+    for word in words {
+        *counts.entry(word).or_default() += 1;
+    }
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    // most frequent first, ties broken alphabetically
+    counts.sort_by(|(word_a, count_a), (word_b, count_b)| {
+        count_b.cmp(count_a).then_with(|| word_a.cmp(word_b))
+    });
+
+    let max_count = counts.first().map(|(_, count)| *count).unwrap_or(0);
+    let max_word_len = counts.iter().map(|(word, _)| word.len()).max().unwrap_or(0);
+
+    writeln!(
+        out,
+        "{} samples, {} distinct words, {:.1} samples per word on average",
+        num_samples,
+        counts.len(),
+        num_samples as f64 / counts.len().max(1) as f64,
+    )?;
+    for (word, count) in &counts {
+        let bar_width = (count * MAX_BAR_WIDTH).div_ceil(max_count);
+        writeln!(
+            out,
+            "{:<width$} {:>6} {}",
+            word,
+            count,
+            "#".repeat(bar_width),
+            width = max_word_len,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Prints stats of past tests from the history file, or the slowest
+/// digraphs if `digraphs` is true.
+#[cfg(feature = "history")]
+pub fn stats(config: &ToipeConfig, digraphs: bool, out: &mut impl Write) -> Result<()> {
+    let path = config.history_path()?;
+    let history = History::load(&path)?;
+
+    if !digraphs {
+        let num_typed: u64 = history.digraphs.values().map(|stats| stats.count).sum();
+        writeln!(
+            out,
+            "{} distinct digraphs typed {} times in total, from '{}'",
+            history.digraphs.len(),
+            num_typed,
+            path.display()
+        )?;
+        return Ok(());
+    }
+
+    let slowest = history.slowest_digraphs(NUM_SLOWEST_DIGRAPHS, MIN_DIGRAPH_COUNT);
+    if slowest.is_empty() {
+        writeln!(
+            out,
+            "no digraph was typed at least {} times yet",
+            MIN_DIGRAPH_COUNT
+        )?;
+        return Ok(());
+    }
+    for ((a, b), stats) in slowest {
+        writeln!(
+            out,
+            "{}{} {:>6}ms {:>6} times",
+            a,
+            b,
+            stats.average().as_millis(),
+            stats.count
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Returns an error, since the stats are kept in the history, which
+/// needs the `history` feature.
+#[cfg(not(feature = "history"))]
+pub fn stats(_config: &ToipeConfig, _digraphs: bool, _out: &mut impl Write) -> Result<()> {
+    Err(ToipeError::from(
+        "cannot print the stats, toipe was built without the `history` feature".to_owned(),
+    ))?
+}
+
+/// Prints the built-in word lists as `language/name`, marking the default
+/// one of each language.
+pub fn wordlist_list(out: &mut impl Write) -> Result<()> {
+    for language in Language::all() {
+        for &wordlist in language.wordlists() {
+            let default = if wordlist == language.default_wordlist() {
+                " (default)"
+            } else {
+                ""
+            };
+            writeln!(
+                out,
+                "{}{}",
+                wordlists::wordlist_name(language, wordlist),
+                default
+            )?;
+        }
+    }
+    writeln!(out, "os (any language, see `toipe -w os`)")?;
+
+    Ok(())
+}
+
+/// Prints `pages` practice sheets, each with `lines` numbered lines of
+/// words that are at most `width` chars long.
+///
+/// A line has at least one word even if the word is longer than
+/// `width`.
+pub fn sheet(
+    config: &ToipeConfig,
+    pages: usize,
+    lines: usize,
+    width: usize,
+    format: SheetFormat,
+    out: &mut impl Write,
+) -> Result<()> {
+    let mut word_selector = config.word_selector()?;
+    // word selected but not yet printed since it did not fit a line
+    let mut next_word = None;
+
+    for page in 1..=pages {
+        match format {
+            SheetFormat::Text => {
+                if page > 1 {
+                    write!(out, "\x0c")?;
+                }
+                writeln!(
+                    out,
+                    "Practice sheet {} of {}: {}",
+                    page,
+                    pages,
+                    config.text_name()
+                )?;
+            }
+            SheetFormat::Markdown => {
+                if page > 1 {
+                    writeln!(out)?;
+                }
+                writeln!(
+                    out,
+                    "## Practice sheet {} of {}: {}",
+                    page,
+                    pages,
+                    config.text_name()
+                )?;
+            }
+        }
+        writeln!(out)?;
+
+        for line_no in 1..=lines {
+            let line = sheet_line(word_selector.as_mut(), width, &mut next_word)?;
+            match format {
+                SheetFormat::Text => writeln!(out, "{:>3}  {}", line_no, line)?,
+                SheetFormat::Markdown => writeln!(out, "{}. {}", line_no, line)?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the certificate of the test with the given results to the
+/// `output` file, or to `out` if there is none.
+///
+/// Returns an error if there are no results, when no test was typed
+/// until the end.
+pub fn certify(
+    name: &str,
+    text_name: &str,
+    results: Option<&ToipeResults>,
+    output: Option<&Path>,
+    out: &mut impl Write,
+) -> Result<()> {
+    let results = results.ok_or_else(|| {
+        ToipeError::from("no test was typed until the end, so there is no certificate".to_owned())
+    })?;
+    let certificate = Certificate::new(name, text_name, results, SystemTime::now());
+
+    match output {
+        Some(output) => {
+            std::fs::write(output, certificate.to_string())
+                .with_context(|| format!("writing the certificate to {}", output.display()))?;
+            writeln!(out, "Wrote the certificate to {}", output.display())?;
+        }
+        None => write!(out, "{}", certificate)?,
+    }
+
+    Ok(())
+}
+
+/// Checks the results exported to the file at `path` (see
+/// [`ExportedResults`]), printing what was checked.
+///
+/// Returns an error if the file was corrupted or its stats do not add
+/// up, or if the text typed is not the one selected by their seed and
+/// options. The speed and accuracy themselves cannot be verified, since
+/// anyone can compute the checksum again.
+#[cfg(feature = "export")]
+pub fn verify(path: &Path, out: &mut impl Write) -> Result<()> {
+    let exported = ExportedResults::load(path)?;
+
+    let mut problems = Vec::new();
+    if exported.checksum == exported.expected_checksum() && exported.stats_are_consistent() {
+        writeln!(out, "checksum: ok")?;
+    } else {
+        writeln!(out, "checksum: the file was corrupted or changed")?;
+        problems.push("checksum");
+    }
+
+    let args = std::iter::once("toipe".to_string()).chain(exported.args.iter().cloned());
+    let mut config = ToipeConfig::try_parse_from(args).context("reading the exported options")?;
+    config.max_word_length = Some(exported.max_word_length);
+    let reproducibility_hash =
+        crate::export::reproducibility_hash(&config, exported.max_word_length, &exported.args);
+    if reproducibility_hash == exported.reproducibility_hash {
+        writeln!(out, "reproducibility hash: ok")?;
+    } else {
+        writeln!(
+            out,
+            "reproducibility hash: the word list or toipe version is not the one the test was typed with"
+        )?;
+        problems.push("reproducibility hash");
+    }
+
+    // selecting these texts again would read stdin, move the bookmark of
+    // the book or read the history, and they depend on more than the
+    // seed and options anyway
+    let auto_drill = matches!(config.command, Some(ToipeCommand::Drill { auto: true, .. }));
+    let stateful_source = [
+        (config.stdin_text.is_some(), "text from stdin"),
+        (config.book.is_some(), "--book"),
+        (config.adaptive, "--adaptive"),
+        (auto_drill, "drill --auto"),
+    ]
+    .into_iter()
+    .find_map(|(used, source)| used.then_some(source));
+
+    match (exported.seed, stateful_source) {
+        (Some(_), Some(source)) => writeln!(
+            out,
+            "text: not checked, the text of {} depends on more than the seed and options",
+            source
+        )?,
+        (Some(seed), None) => {
+            let words = select_text(&config, exported.text.chars().count())?;
+            if exported.text_is_start_of(&words.join(" ")) {
+                writeln!(out, "text: ok, selected by seed {}", seed)?;
+            } else {
+                writeln!(
+                    out,
+                    "text: not the one selected by seed {} (only the first test of a run can be checked)",
+                    seed
+                )?;
+                problems.push("text");
+            }
+        }
+        (None, _) => writeln!(out, "text: not checked, the test was typed without --seed")?,
+    }
+
+    if !problems.is_empty() {
+        Err(ToipeError::from(format!(
+            "the results in {} could not be verified: {}",
+            path.display(),
+            problems.join(", ")
+        )))?;
+    }
+
+    Ok(())
+}
+
+/// Returns an error, since exported results need the `export` feature.
+#[cfg(not(feature = "export"))]
+pub fn verify(path: &Path, _out: &mut impl Write) -> Result<()> {
+    Err(ToipeError::from(format!(
+        "cannot verify the results in {}, toipe was built without the `export` feature",
+        path.display()
+    )))?
+}
+
+/// Selects the words of the texts of a test with the given config until
+/// they are at least `text_len` chars long, joined by spaces.
+///
+/// Returns an error if the selector runs out of words before that.
+#[cfg(feature = "export")]
+fn select_text(config: &ToipeConfig, text_len: usize) -> Result<Vec<String>> {
+    // texts of a test are separated like its pages
+    let mut stream = config.word_stream()?;
+    let mut words = Vec::new();
+    // chars of the words so far with a space after each, which is one
+    // more than when they are joined
+    let mut len = 0;
+    while len <= text_len {
+        let text = stream.next_text()?;
+        if text.is_empty() {
+            Err(ToipeError::from(
+                "the options select fewer words than the exported text has".to_owned(),
+            ))?;
+        }
+        len += text
+            .iter()
+            .map(|word| word.chars().count() + 1)
+            .sum::<usize>();
+        words.extend(text);
+    }
+
+    Ok(words)
+}
+
+/// Selects words for a line of a practice sheet.
+///
+/// The first word that does not fit is left in `next_word` for the next
+/// line.
+fn sheet_line(
+    word_selector: &mut dyn WordSelector,
+    width: usize,
+    next_word: &mut Option<String>,
+) -> Result<String> {
+    let mut line = match next_word.take() {
+        Some(word) => word,
+        None => word_selector.new_word()?,
+    };
+
+    loop {
+        let word = word_selector.new_word()?;
+        if line.len() + 1 + word.len() > width {
+            *next_word = Some(word);
+            return Ok(line);
+        }
+        line.push(' ');
+        line.push_str(&word);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "history")]
+    use crate::history::DigraphStats;
+
+    /// Runs the command given by the arguments after `toipe`, returning
+    /// what it printed.
+    fn run_command(args: &[&str]) -> Result<String> {
+        let config = ToipeConfig::try_parse_from(std::iter::once(&"toipe").chain(args))?;
+        let command = config.command.as_ref().expect("a command is given");
+        let mut out = Vec::new();
+        run(command, &config, &mut out)?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    #[test]
+    fn wordlist_commands() {
+        let args = ["--text", "one two three", "wordlist", "sample", "-n", "3"];
+        assert_eq!(run_command(&args).unwrap(), "one\ntwo\nthree\n");
+
+        let args = ["--text", "one one two", "wordlist", "sample", "-n", "3"];
+        let histogram = run_command(&[&args[..], &["--histogram"]].concat()).unwrap();
+        assert!(histogram.starts_with("3 samples, 2 distinct words"));
+        assert!(histogram.contains(&format!("one      2 {}\n", "#".repeat(MAX_BAR_WIDTH))));
+
+        let list = run_command(&["wordlist", "list"]).unwrap();
+        assert!(list.contains("en/top250 (default)\n"), "{}", list);
+        assert!(list.ends_with("os (any language, see `toipe -w os`)\n"));
+    }
+
+    #[test]
+    fn sheet_lines_fit_the_width() {
+        let args = [
+            "--text",
+            "one two three",
+            "sheet",
+            "--lines",
+            "2",
+            "--width",
+            "8",
+        ];
+        assert_eq!(
+            run_command(&args).unwrap(),
+            "Practice sheet 1 of 1: custom text\n\n  1  one two\n  2  three\n"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "history")]
+    fn stats_of_the_history() {
+        let dir = std::env::temp_dir().join(format!("toipe-commands-{}", std::process::id()));
+        let path = dir.join("history");
+        let mut history = History::default();
+        history.digraphs.insert(
+            ('t', 'h'),
+            DigraphStats {
+                count: MIN_DIGRAPH_COUNT,
+                total: std::time::Duration::from_millis(300),
+            },
+        );
+        history.save(&path).unwrap();
+        let path_arg = path.to_str().unwrap();
+
+        let stats = run_command(&["--history", path_arg, "stats"]);
+        let digraphs = run_command(&["--history", path_arg, "stats", "--digraphs"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            stats.unwrap(),
+            format!(
+                "1 distinct digraphs typed {} times in total, from '{}'\n",
+                MIN_DIGRAPH_COUNT,
+                path.display()
+            )
+        );
+        assert_eq!(digraphs.unwrap(), "th    100ms      3 times\n");
+    }
+
+    #[test]
+    #[cfg(feature = "export")]
+    fn texts_are_selected_until_long_enough() {
+        let config = ToipeConfig::try_parse_from(["toipe", "--text", "one two"]).unwrap();
+        assert_eq!(select_text(&config, 7).unwrap(), ["one", "two"]);
+        assert_eq!(select_text(&config, 8).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn typing_test_commands_are_not_run() {
+        assert!(run_command(&["drill", "--digraphs", "th"]).is_err());
+        assert!(run_command(&["certify", "--name", "Ada"]).is_err());
+    }
+}
@@ -3,50 +3,1528 @@
 //! Designed for command-line arguments using [`clap`], but can be used
 //! as a library too.
 
-use clap::{ArgEnum, Parser};
+use std::io::{self, Read, Seek};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use crate::wordlists::BuiltInWordlist;
+use anyhow::{Context, Result};
+use clap::{ArgEnum, Parser, Subcommand};
+use regex::Regex;
+
+use crate::difficulty::WordRarities;
+use crate::end_condition::{
+    CharCount, EndCondition, Endless, FirstError, MinAccuracy, TimeAttack, TimeLimit, WordBudget,
+};
+#[cfg(feature = "history")]
+use crate::history::{self, History, MIN_DIGRAPH_COUNT};
+use crate::remind::ReminderInterval;
+use crate::rng::ToipeRng;
+#[cfg(feature = "history")]
+use crate::textgen::AdaptiveSelector;
+use crate::textgen::{
+    parse_char_class, BookSelector, CapitalizedWordSelector, CharsetSelector, CodeSelector,
+    ExcludeSelector, LettersSelector, MarkdownStripper, MarkovSelector, NgramSelector,
+    NumberPolicy, NumberedWordSelector, PatternSelector, PunctuatedWordSelector, QuoteLength,
+    QuoteSelector, RawWordSelector, Sanitizer, StructuredSelector, StructuredWordList,
+    TextSelector, UntypeablePolicy, WordSelector, WordStream, DEFAULT_MAX_WORD_LEN,
+};
+use crate::theme::ThemeChoice;
+use crate::tui::{HudPosition, DEFAULT_MAX_COLUMNS};
+use crate::wordlists::{self, BuiltInWordlist, Language, OS_WORDLIST_PATH};
+use crate::ToipeError;
 
 const CLI_HELP: &str = "A trusty terminal typing tester.
 
 Keyboard shortcuts:
 ctrl-c: quit
 ctrl-r: restart test with a new set of words
+ctrl-p: restart test with the same words
 ctrc-w: delete last word
+esc: pause test, any key resumes it
+ctrl-z: pause test and suspend toipe, resume it with `fg`
+?: show keyboard shortcuts, before and after a test
 ";
 
+/// Number of the slowest digraphs practiced by `drill --auto`.
+#[cfg(feature = "history")]
+const NUM_DRILL_DIGRAPHS: usize = 5;
+
+/// Smallest value of `--max-columns`, which fits a few words on a line.
+const MIN_MAX_COLUMNS: u16 = 20;
+
 /// Main configuration for Toipe.
 #[derive(Parser)]
 #[clap(author, version, about = CLI_HELP)]
 pub struct ToipeConfig {
     /// Word list name.
-    #[clap(arg_enum, short, long, default_value_t = BuiltInWordlist::Top250)]
-    pub wordlist: BuiltInWordlist,
+    ///
+    /// Defaults to the default word list of the language (see
+    /// `toipe wordlist list`).
+    #[clap(arg_enum, short, long, global = true)]
+    pub wordlist: Option<BuiltInWordlist>,
+    /// Language of the built-in word lists.
+    #[clap(arg_enum, long, global = true, default_value_t = Language::En)]
+    pub language: Language,
     /// Path to custom word list file.
     ///
+    /// A `.json` or `.csv` file, or a file that starts with `{`, is read
+    /// as a structured word list, whose words can have a frequency, a
+    /// difficulty and tags (see `--tags` and `--max-difficulty`). This
+    /// includes the language files of Monkeytype.
+    ///
+    /// Can be given more than once to merge the word lists, keeping each
+    /// word once. If any of them is structured, the others are read as
+    /// structured lists without metadata.
+    ///
     /// This argument cannot be used along with `-w`/`--wordlist`
-    #[clap(short = 'f', long = "file", conflicts_with = "wordlist")]
-    pub wordlist_file: Option<String>,
+    #[clap(
+        short = 'f',
+        long = "file",
+        global = true,
+        multiple_occurrences = true,
+        conflicts_with = "wordlist"
+    )]
+    pub wordlist_file: Vec<String>,
+    /// Only select words with at least one of these tags, separated by
+    /// commas, from a structured word list given by `-f`.
+    #[clap(long, use_delimiter = true, requires = "wordlist-file")]
+    pub tags: Vec<String>,
+    /// Only select words with at most this difficulty from a structured
+    /// word list given by `-f`. Words without a difficulty are left
+    /// out.
+    #[clap(long, requires = "wordlist-file")]
+    pub max_difficulty: Option<f64>,
+    /// Path to a file of words that are never selected, with a word on
+    /// each line, like offensive words.
+    ///
+    /// Words are compared ignoring case and punctuation.
+    #[clap(
+        long,
+        conflicts_with_all = &["quotes", "book", "code", "text", "stdin-text", "charset"]
+    )]
+    pub exclude_file: Option<String>,
     /// Number of words to show on each test.
+    ///
+    /// In a timed test, this many words are shown at a time.
     #[clap(short, long, default_value_t = 30)]
     pub num_words: usize,
+    /// Run the test for this many seconds instead of a fixed number of
+    /// words.
+    ///
+    /// New words keep appearing as you finish typing the ones shown.
+    /// The timer starts when the first key is pressed.
+    #[clap(short, long)]
+    pub time: Option<u64>,
+    /// Show the text before the test and start it when Enter is
+    /// pressed, to read the text first without the timer running.
+    #[clap(long)]
+    pub preview: bool,
+    /// Keep adding words as you type until you press ctrl-c, which
+    /// shows the results of everything typed.
+    #[clap(long, conflicts_with = "time")]
+    pub endless: bool,
+    /// Count down this many seconds before each test starts (3 if no
+    /// number is given, like `--countdown`).
+    ///
+    /// The timer starts when the countdown ends instead of on the first
+    /// key press. Keys pressed during the countdown are ignored.
+    #[clap(
+        long,
+        value_name = "SECS",
+        min_values = 0,
+        require_equals = true,
+        default_missing_value = "3"
+    )]
+    pub countdown: Option<u64>,
+    /// Ignore the first this many keys pressed in each test, like the
+    /// Enter that started toipe repeating, so that they do not start
+    /// the timer or count as errors.
+    ///
+    /// Shortcuts like ctrl-c are not ignored.
+    #[clap(long, value_name = "N", default_value_t = 0)]
+    pub ignore_first_keys: usize,
+    /// Start the next test automatically this many seconds after the
+    /// results are shown.
+    #[clap(long, value_name = "SECS")]
+    pub auto_restart: Option<u64>,
+    /// Type this many tests back to back, then see a summary of all of
+    /// them with the average speed and the best and worst rounds.
+    #[clap(long, value_name = "N")]
+    pub rounds: Option<NonZeroUsize>,
     /// Whether to include punctuation
+    ///
+    /// Words are randomly followed by commas, periods and such, or
+    /// surrounded by quotes and brackets. Sentences start with a capital
+    /// letter.
     #[clap(short, long)]
     pub punctuation: bool,
+    /// Put random numbers among the words.
+    #[clap(long, conflicts_with_all = &["quotes", "book"])]
+    pub numbers: bool,
+    /// Chance of each word being a number, between 0 and 1.
+    #[clap(long, default_value_t = 0.1, validator = validate_chance)]
+    pub number_frequency: f64,
+    /// Capitalize this percentage of the words, to practice the shift
+    /// keys.
+    #[clap(
+        long,
+        value_name = "PERCENT",
+        validator = validate_percentage,
+        conflicts_with_all = &["quotes", "book", "code", "text", "stdin-text", "markov"]
+    )]
+    pub capitalize: Option<f64>,
+    /// Type quotes instead of random words.
+    ///
+    /// Each test is one quote, with capitalization and punctuation.
+    #[clap(
+        short,
+        long,
+        conflicts_with_all = &["wordlist", "wordlist-file", "punctuation", "unique-words"]
+    )]
+    pub quotes: bool,
+    /// Length of quotes to type.
+    #[clap(arg_enum, long, default_value_t = QuoteLength::Any)]
+    pub quote_length: QuoteLength,
+    /// Path to a book or an article to type, in order.
+    ///
+    /// Capitalization and punctuation are kept as in the text. The
+    /// syntax of Markdown files (`.md`) is left out. EPUB books (`.epub`)
+    /// and PDF files (`.pdf`) can be typed when toipe is built with the
+    /// `epub` and `pdf` features.
+    #[clap(
+        long,
+        conflicts_with_all = &["wordlist", "wordlist-file", "quotes", "punctuation", "unique-words"]
+    )]
+    pub book: Option<String>,
+    /// Start the book from the beginning instead of where it was left
+    /// off last time.
+    #[clap(long, requires = "book")]
+    pub restart_book: bool,
+    /// Start the book from the heading of this chapter, counting from 1.
+    ///
+    /// Chapters are found by headings on a line of their own, like
+    /// `Chapter 3` or `III.`.
+    #[clap(
+        long,
+        value_name = "N",
+        requires = "book",
+        conflicts_with = "from-line"
+    )]
+    pub chapter: Option<NonZeroUsize>,
+    /// Start the book from this line, counting from 1.
+    #[clap(long, value_name = "N", requires = "book")]
+    pub from_line: Option<NonZeroUsize>,
+    /// Path to a text to generate sentences from, which sound like the
+    /// text but are made up.
+    ///
+    /// Each word is followed by a word that follows it somewhere in the
+    /// text. Capitalization and punctuation are kept as in the text.
+    #[clap(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = &[
+            "wordlist", "wordlist-file", "quotes", "book", "code", "text", "stdin-text",
+            "charset", "punctuation", "ngram", "letters",
+        ]
+    )]
+    pub markov: Option<String>,
+    /// Pass `-` to type the text piped to the standard input, like
+    /// `cat essay.txt | toipe -`.
+    ///
+    /// Keys are then read from the terminal instead.
+    #[clap(
+        value_name = "-",
+        possible_values = &["-"],
+        conflicts_with_all = &[
+            "wordlist", "wordlist-file", "quotes", "book", "code", "text", "punctuation",
+            "unique-words", "numbers",
+        ]
+    )]
+    pub stdin_text: Option<String>,
+    /// Text to type, instead of randomly selected words.
+    #[clap(
+        long,
+        conflicts_with_all = &[
+            "wordlist", "wordlist-file", "quotes", "book", "code", "punctuation",
+            "unique-words", "numbers",
+        ]
+    )]
+    pub text: Option<String>,
+    /// Path to a source code file to type lines from.
+    ///
+    /// Indentation is kept, Enter is typed at the end of each line and
+    /// Tab types spaces up to the next tab stop. `-n` sets the number of
+    /// lines in each test.
+    #[clap(
+        long,
+        conflicts_with_all = &[
+            "wordlist", "wordlist-file", "quotes", "book", "punctuation",
+            "unique-words", "numbers", "confirm-words",
+        ]
+    )]
+    pub code: Option<String>,
+    /// Type random strings of chars from a character class, like
+    /// `[a-z0-9;:]`, instead of words.
+    ///
+    /// Single chars and ranges like `a-z` are listed between `[` and
+    /// `]`. `--charset-length` sets how long each string is.
+    #[clap(
+        long,
+        value_name = "CLASS",
+        validator = validate_charset,
+        conflicts_with_all = &[
+            "wordlist", "wordlist-file", "quotes", "book", "code", "text", "stdin-text",
+        ]
+    )]
+    pub charset: Option<String>,
+    /// Shortest and longest length of the strings typed with
+    /// `--charset`, separated by a comma.
+    #[clap(
+        long,
+        use_delimiter = true,
+        number_of_values = 2,
+        value_names = &["MIN", "MAX"],
+        default_value = "2,6"
+    )]
+    pub charset_length: Vec<usize>,
+    /// Whether to keep words with digits, like `10,000` or `3.14`, from
+    /// the book or the `--markov` text.
+    #[clap(arg_enum, long, default_value_t = NumberPolicy::Keep)]
+    pub book_numbers: NumberPolicy,
+    /// What to do with words in the book or the `--markov` text that
+    /// have chars which cannot be typed on a normal keyboard, like emoji.
+    #[clap(arg_enum, long, default_value_t = UntypeablePolicy::Skip)]
+    pub untypeable: UntypeablePolicy,
+    /// Confirm each word with a space before moving to the next one.
+    ///
+    /// A word can only be confirmed once it is typed correctly. If it
+    /// is not, the whole word is marked as a mistake.
+    #[clap(long)]
+    pub confirm_words: bool,
+    /// Type the kana of the text as romaji, like `neko` for `ねこ`,
+    /// without an input method.
+    ///
+    /// Letters are shown above the text until they spell a kana. Use it
+    /// with a `--text` or a structured word list (see `-f`) of hiragana
+    /// or katakana.
+    #[clap(long, conflicts_with_all = &["code", "confirm-words"])]
+    pub romaji: bool,
+    /// Do not mark typed characters as correct or wrong during the test.
+    ///
+    /// The text stays faint as it is typed, and mistakes are only shown
+    /// on the results screen.
+    #[clap(long, conflicts_with = "confirm-words")]
+    pub blind: bool,
+    /// Ignore Backspace and ctrl-w, so that mistakes cannot be
+    /// corrected.
+    ///
+    /// The results show how many corrections were attempted.
+    #[clap(long, conflicts_with = "confirm-words")]
+    pub hardcore: bool,
+    /// Give each word this many milliseconds for each of its characters,
+    /// including the space after it.
+    ///
+    /// A word that is not typed in time is marked as failed and skipped,
+    /// and the results show how many words failed.
+    #[clap(long, value_name = "MS", conflicts_with_all = &["confirm-words", "code"])]
+    pub word_budget: Option<u64>,
+    /// Show "PB pace" in the hints while typing as fast as your best
+    /// test of the same text, length and modes, or within 3% of it.
+    ///
+    /// Best tests are read from the history (see `--history`).
+    #[clap(long)]
+    pub pb_pace: bool,
+    /// Show the accuracy in the hints while typing, colored by how it
+    /// compares to `--accuracy-thresholds`.
+    #[clap(long)]
+    pub live_accuracy: bool,
+    /// Percentages of accuracy at or above which the live accuracy is
+    /// good (green) and fair (yellow), separated by a comma. Lower
+    /// accuracy is red.
+    #[clap(
+        long,
+        use_delimiter = true,
+        number_of_values = 2,
+        value_names = &["GOOD", "FAIR"],
+        default_value = "95,90",
+        validator = validate_percentage
+    )]
+    pub accuracy_thresholds: Vec<f64>,
+    /// End the test early if the accuracy drops below this percentage,
+    /// once a few characters have been typed.
+    #[clap(long, validator = validate_percentage)]
+    pub min_accuracy: Option<f64>,
+    /// Show your speed and accuracy in large digits in the top third of
+    /// the screen while typing, readable on a stream, with the text
+    /// below them.
+    #[clap(long)]
+    pub streamer: bool,
+    /// End the test once this many characters of the text have been
+    /// typed.
+    #[clap(long, value_name = "N")]
+    pub chars: Option<usize>,
+    /// End the test at the first mistake.
+    #[clap(long)]
+    pub stop_on_error: bool,
+    /// Practice these runs of consecutive letters, separated by
+    /// commas, like `th,he,ion`.
+    ///
+    /// Words with at least one of them are selected from the word list,
+    /// and pseudo-words made of them are typed when the word list has
+    /// too few such words.
+    #[clap(
+        long,
+        use_delimiter = true,
+        validator = validate_ngram,
+        conflicts_with_all = &["quotes", "book", "code", "text", "stdin-text", "charset"]
+    )]
+    pub ngram: Vec<String>,
+    /// Only select words made entirely of these characters, like
+    /// `asdfjkl;` for the home row.
+    ///
+    /// Case is ignored.
+    #[clap(
+        long,
+        validator = validate_letters,
+        conflicts_with_all = &["quotes", "book", "code", "text", "stdin-text", "charset"]
+    )]
+    pub letters: Option<String>,
+    /// Prefer words with the letters and pairs of letters you mistype
+    /// most often.
+    ///
+    /// Mistakes are read from the history (see `--history`) and updated
+    /// after each test, so the words follow your weak letters as they
+    /// change.
+    #[clap(
+        long,
+        conflicts_with_all = &[
+            "quotes", "book", "code", "text", "stdin-text", "charset", "markov",
+        ]
+    )]
+    pub adaptive: bool,
+    /// Only select words that match this regular expression, like
+    /// `^[a-m]{4,6}$`.
+    ///
+    /// The pattern matches anywhere in a word unless it is anchored with
+    /// `^` and `$`. Words from the built-in word lists are lowercase.
+    #[clap(
+        long,
+        validator = validate_pattern,
+        conflicts_with_all = &["quotes", "book", "code", "text", "stdin-text", "charset"]
+    )]
+    pub pattern: Option<String>,
+    /// Seed for the random selection of words.
+    ///
+    /// Tests with the same seed and options have the same words, for
+    /// comparing runs or sharing a test with others.
+    #[clap(long, global = true)]
+    pub seed: Option<u64>,
+    /// Length of the longest word to select from the word list.
+    ///
+    /// Defaults to 8, or less if the terminal is too narrow for that.
+    #[clap(long, value_name = "LEN")]
+    pub max_word_length: Option<usize>,
+    /// Do not repeat words within a test, including the words added as
+    /// you type in a timed or endless test.
+    ///
+    /// Words are repeated only if the word list does not have enough
+    /// distinct words.
+    #[clap(long)]
+    pub unique_words: bool,
+    /// Deal the words of the word list in a shuffled order, so that
+    /// every word is typed once before any word is repeated, across
+    /// tests.
+    ///
+    /// Frequencies in the word list are not used. Meant for small word
+    /// lists, like the top 250 words.
+    #[clap(
+        long,
+        conflicts_with_all = &[
+            "quotes", "book", "code", "text", "stdin-text", "charset", "markov",
+        ]
+    )]
+    pub shuffle: bool,
+    /// Display the test without styles and colors.
+    ///
+    /// For terminals that cannot display faint text or colors, like the
+    /// Linux virtual console. The next char to be typed is marked with
+    /// `^` and mistakes with `x` in the line below the text.
+    #[clap(long)]
+    pub ascii: bool,
+    /// Colors to use, based on the background color of the terminal.
+    ///
+    /// `auto` detects the background using the `COLORFGBG` environment
+    /// variable or by asking the terminal.
+    #[clap(arg_enum, long, default_value_t = ThemeChoice::Auto)]
+    pub theme: ThemeChoice,
+    /// Where to display hints like keyboard shortcuts.
+    #[clap(arg_enum, long, default_value_t = HudPosition::Bottom)]
+    pub hud: HudPosition,
+    /// Maximum width of the lines of text, in columns.
+    ///
+    /// Lines are 40% of the width of the terminal, up to this, so that
+    /// they stay readable on very wide terminals.
+    #[clap(long, value_name = "N", default_value_t = DEFAULT_MAX_COLUMNS, validator = validate_max_columns)]
+    pub max_columns: u16,
+    /// Speed to beat, in words per minute.
+    ///
+    /// The results show whether the goal was beaten and by how much.
+    /// toipe exits with status 1 if the last completed test did not beat
+    /// it, for scripting drills.
+    #[clap(long, value_name = "WPM")]
+    pub goal_wpm: Option<f64>,
+    /// Show a second, faint caret that moves through the text at this
+    /// speed, in words per minute, to race against.
+    ///
+    /// The caret starts moving when the first key is pressed.
+    #[clap(long, value_name = "WPM")]
+    pub pace: Option<f64>,
+    /// Race against a caret that moves like you did in your fastest test
+    /// with the same word list or text, length and modes.
+    ///
+    /// The fastest test is read from the history file, so a test has to
+    /// be completed first.
+    #[clap(long, conflicts_with = "pace")]
+    pub ghost: bool,
+    /// Type until a deadline sweeping through the text catches up with
+    /// you. The deadline starts at this speed in words per minute (30 if
+    /// no number is given, like `--time-attack`) and keeps speeding up.
+    ///
+    /// Words keep being added like in an endless test, and the score is
+    /// the speed of the deadline when the test ended.
+    #[clap(
+        long,
+        value_name = "WPM",
+        min_values = 0,
+        require_equals = true,
+        default_missing_value = "30",
+        conflicts_with_all = &["pace", "ghost", "time"]
+    )]
+    pub time_attack: Option<f64>,
+    /// Number of seconds at the start of the test to exclude when
+    /// calculating the settled speed.
+    ///
+    /// The settled speed is shown on the results screen along with the
+    /// overall speed.
+    #[clap(long)]
+    pub warmup: Option<u64>,
+    /// Also show the speed adjusted for how difficult the text is, from
+    /// the length and rarity of the words typed.
+    ///
+    /// Use this to compare tests typed with different word lists. The
+    /// rarity of the words comes from the frequencies of the word list
+    /// if it has them, and the best adjusted speed is saved to the
+    /// history.
+    #[clap(long)]
+    pub normalize: bool,
+    /// Do not print a summary of the results after exiting.
+    #[clap(long)]
+    pub no_summary: bool,
+    /// Write the results of the first test to this JSON file after
+    /// exiting, with hashes for checking them with `toipe verify`.
+    ///
+    /// With `--seed`, the text of the first test can be selected again
+    /// to check that it is the text that was typed.
+    #[clap(long, value_name = "PATH")]
+    pub export: Option<PathBuf>,
+    /// Print the text of a test and exit, without starting the test.
+    #[clap(long)]
+    pub print_text: bool,
+    /// Path to a file to write debug logs to.
+    ///
+    /// Logs include every word drawn by the word selector, useful for
+    /// debugging the distribution of words, and how long the screen took
+    /// to update after each key press, which is also summarized in the
+    /// details of the results.
+    #[clap(long, global = true)]
+    pub debug_log: Option<PathBuf>,
+    /// Path to a UNIX socket to create, which serves your speed and
+    /// progress while typing to programs like status bars.
+    ///
+    /// Each connected program reads a line like `typing wpm=52.3
+    /// progress=41.0` whenever a key is pressed, `done wpm=55.1
+    /// accuracy=97.2` when a test is completed and `idle` otherwise.
+    #[clap(long, value_name = "PATH")]
+    pub status_socket: Option<PathBuf>,
+    /// Path to the file that stats of past tests are stored in.
+    ///
+    /// Defaults to `$XDG_DATA_HOME/toipe/history` or
+    /// `~/.local/share/toipe/history`.
+    #[clap(long, global = true)]
+    pub history: Option<PathBuf>,
+    /// Save the stats of a test to the history every this many seconds
+    /// while it is typed, so that a long test is not lost if toipe stops
+    /// before it ends. 0 only saves them when it ends.
+    ///
+    /// Stats are saved when a key is pressed after the interval.
+    #[clap(long, value_name = "SECS", default_value_t = 60)]
+    pub checkpoint_interval: u64,
+    /// Run a command instead of the typing test.
+    #[clap(subcommand)]
+    pub command: Option<ToipeCommand>,
+}
+
+/// Commands that can be run instead of the typing test.
+#[derive(Subcommand)]
+pub enum ToipeCommand {
+    /// Inspect the word list given by `-w`/`--wordlist` or
+    /// `-f`/`--file`.
+    #[clap(subcommand)]
+    Wordlist(WordlistCommand),
+    /// Print practice sheets of numbered lines of words, for typing
+    /// offline or on paper.
+    ///
+    /// Words are selected like in the typing test.
+    Sheet {
+        /// Number of pages to print.
+        #[clap(long, default_value_t = 1)]
+        pages: usize,
+        /// Number of lines on each page.
+        #[clap(long, default_value_t = 20)]
+        lines: usize,
+        /// Maximum number of characters in each line of words.
+        #[clap(long, default_value_t = 60)]
+        width: usize,
+        /// Format of the sheets.
+        #[clap(arg_enum, long, default_value_t = SheetFormat::Text)]
+        format: SheetFormat,
+    },
+    /// Type words that practice the pairs of consecutive letters
+    /// (digraphs) given by `--digraphs`, or the ones you are slowest at
+    /// with `--auto`.
+    ///
+    /// Words with the digraphs are selected like with `--ngram`. With
+    /// `--auto`, the words with the letters and pairs of letters you
+    /// mistype most often are preferred too, like with `--adaptive`.
+    ///
+    /// Words are selected from the word list given by `-w`/`--wordlist`
+    /// or `-f`/`--file`.
+    Drill {
+        /// Practice the slowest digraphs and the most mistyped letters in
+        /// the history file (see `toipe stats`).
+        #[clap(long, required_unless_present = "digraphs")]
+        auto: bool,
+        /// Digraphs to practice, separated by commas, like `th,er`.
+        #[clap(
+            long,
+            conflicts_with = "auto",
+            use_delimiter = true,
+            validator = validate_digraph
+        )]
+        digraphs: Vec<String>,
+    },
+    /// Type a timed test of the standard length and get a certificate
+    /// of your speed, for job applications and such.
+    ///
+    /// The certificate has your name, the date, your speed and accuracy
+    /// and a hash of the text typed. It is made for the last test typed
+    /// until the end.
+    Certify {
+        /// Length of the test in seconds.
+        #[clap(long, value_name = "SECS", default_value_t = 300)]
+        duration: u64,
+        /// Name to put on the certificate.
+        #[clap(long)]
+        name: String,
+        /// Write the certificate to this file instead of printing it.
+        #[clap(long, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+    /// Check results written by `--export`: that the file is not
+    /// corrupted, and that the text typed is the one selected by their
+    /// seed and options.
+    Verify {
+        /// Path to the JSON file of the results.
+        file: PathBuf,
+    },
+    /// Print stats of past tests, read from the history file.
+    Stats {
+        /// Print the pairs of consecutive letters that took the longest
+        /// to type.
+        #[clap(long)]
+        digraphs: bool,
+    },
+    /// Install a desktop notification that reminds you to practice,
+    /// as a user-level systemd timer on Linux or a launchd agent on
+    /// Mac OS.
+    ///
+    /// The reminder has to be enabled with the command that is printed.
+    Remind {
+        /// Write the files of the reminder.
+        #[clap(long, required_unless_present = "uninstall")]
+        install: bool,
+        /// Remove the files of the reminder.
+        #[clap(long, conflicts_with = "install")]
+        uninstall: bool,
+        /// How often to be reminded.
+        #[clap(arg_enum, long, default_value_t = ReminderInterval::Daily)]
+        every: ReminderInterval,
+    },
+}
+
+impl ToipeCommand {
+    /// Whether the command runs a typing test, set up by the config,
+    /// rather than running on its own with [`commands::run`].
+    ///
+    /// [`commands::run`]: crate::commands::run
+    pub fn is_typing_test(&self) -> bool {
+        matches!(
+            self,
+            ToipeCommand::Drill { .. } | ToipeCommand::Certify { .. }
+        )
+    }
+}
+
+/// Formats of practice sheets printed by [`ToipeCommand::Sheet`].
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ArgEnum, Debug)]
+pub enum SheetFormat {
+    /// Plain text, with pages separated by form feeds.
+    Text,
+    /// Markdown, with a heading for each page.
+    Markdown,
+}
+
+/// Commands for inspecting word lists.
+#[derive(Subcommand)]
+pub enum WordlistCommand {
+    /// Print words selected from the word list.
+    Sample {
+        /// Number of words to select.
+        #[clap(short, long = "num-samples", default_value_t = 10)]
+        num_samples: usize,
+        /// Print how many times each word was selected instead of the
+        /// words themselves.
+        #[clap(long)]
+        histogram: bool,
+    },
+    /// Print the built-in word lists of each language, marking the
+    /// default ones.
+    List,
+}
+
+fn validate_digraph(value: &str) -> Result<(), String> {
+    match value.chars().count() {
+        2 => Ok(()),
+        _ => Err("must be two letters".to_string()),
+    }
+}
+
+fn validate_charset(value: &str) -> Result<(), String> {
+    parse_char_class(value).map(|_| ())
+}
+
+fn validate_ngram(value: &str) -> Result<(), String> {
+    match value.chars().count() {
+        2 | 3 if value.chars().all(char::is_alphabetic) => Ok(()),
+        _ => Err("must be two or three letters".to_string()),
+    }
+}
+
+fn validate_letters(value: &str) -> Result<(), String> {
+    if value.is_empty() || value.chars().any(char::is_whitespace) {
+        return Err("must be one or more characters without spaces".to_string());
+    }
+    Ok(())
+}
+
+fn validate_pattern(value: &str) -> Result<(), String> {
+    Regex::new(value).map(|_| ()).map_err(|err| err.to_string())
+}
+
+fn validate_max_columns(value: &str) -> Result<(), String> {
+    match value.parse::<u16>() {
+        Ok(columns) if columns >= MIN_MAX_COLUMNS => Ok(()),
+        _ => Err(format!("must be a number of at least {}", MIN_MAX_COLUMNS)),
+    }
+}
+
+fn validate_chance(value: &str) -> Result<(), String> {
+    match value.parse::<f64>() {
+        Ok(chance) if (0.0..=1.0).contains(&chance) => Ok(()),
+        _ => Err("must be a number between 0 and 1".to_string()),
+    }
+}
+
+fn validate_percentage(value: &str) -> Result<(), String> {
+    match value.parse::<f64>() {
+        Ok(percentage) if (0.0..=100.0).contains(&percentage) => Ok(()),
+        _ => Err("must be a number between 0 and 100".to_string()),
+    }
+}
+
+impl Default for ToipeConfig {
+    /// The config of `toipe` run without any arguments, for running
+    /// tests from another application.
+    fn default() -> Self {
+        Self::parse_from(["toipe"])
+    }
 }
 
 impl ToipeConfig {
+    /// Digraphs to practice if the `drill` command was given.
+    fn drill_digraphs(&self) -> Result<Option<Vec<(char, char)>>> {
+        let (auto, digraphs) = match &self.command {
+            Some(ToipeCommand::Drill { auto, digraphs }) => (*auto, digraphs),
+            _ => return Ok(None),
+        };
+
+        if !auto {
+            let digraphs = digraphs
+                .iter()
+                .map(|digraph| {
+                    let chars: Vec<char> = digraph.to_lowercase().chars().collect();
+                    (chars[0], chars[1])
+                })
+                .collect();
+            return Ok(Some(digraphs));
+        }
+
+        self.slowest_digraphs().map(Some)
+    }
+
+    /// Slowest digraphs in the history, for `drill --auto`.
+    #[cfg(feature = "history")]
+    fn slowest_digraphs(&self) -> Result<Vec<(char, char)>> {
+        let path = self.history_path()?;
+        let slowest = History::load(&path)?.slowest_digraphs(NUM_DRILL_DIGRAPHS, MIN_DIGRAPH_COUNT);
+        if slowest.is_empty() {
+            return Err(ToipeError::from(format!(
+                "no digraph was typed at least {} times yet, type a few tests first",
+                MIN_DIGRAPH_COUNT
+            )))?;
+        }
+
+        Ok(slowest.into_iter().map(|(digraph, _)| digraph).collect())
+    }
+
+    /// Returns an error, since the digraphs are read from the history,
+    /// which needs the `history` feature.
+    #[cfg(not(feature = "history"))]
+    fn slowest_digraphs(&self) -> Result<Vec<(char, char)>> {
+        Err(ToipeError::from(
+            "cannot find the slowest digraphs, toipe was built without the `history` feature"
+                .to_owned(),
+        ))?
+    }
+
+    /// Path of the history file given by `--history`, or the default
+    /// one (see [`history::default_path`]).
+    #[cfg(feature = "history")]
+    pub fn history_path(&self) -> Result<PathBuf> {
+        self.history
+            .clone()
+            .or_else(history::default_path)
+            .context("finding the history file, pass one with --history")
+    }
+
+    /// Path of the history file given by `--history`. The default one
+    /// is only known with the `history` feature.
+    #[cfg(not(feature = "history"))]
+    pub fn history_path(&self) -> Result<PathBuf> {
+        self.history
+            .clone()
+            .context("finding the history file, pass one with --history")
+    }
+
+    /// Conditions that end a test before the end of its text, given by
+    /// `-t`, `--chars`, `--stop-on-error`, `--min-accuracy`,
+    /// `--time-attack`, `--word-budget` and `--endless`.
+    pub fn end_conditions(&self) -> Vec<Box<dyn EndCondition>> {
+        let mut conditions: Vec<Box<dyn EndCondition>> = Vec::new();
+        if let Some(secs) = self.time {
+            conditions.push(Box::new(TimeLimit(Duration::from_secs(secs))));
+        }
+        if let Some(chars) = self.chars {
+            conditions.push(Box::new(CharCount(chars)));
+        }
+        if self.stop_on_error {
+            conditions.push(Box::new(FirstError));
+        }
+        if let Some(min_accuracy) = self.min_accuracy {
+            conditions.push(Box::new(MinAccuracy(min_accuracy)));
+        }
+        if self.time_attack.is_some() {
+            conditions.push(Box::new(TimeAttack));
+        }
+        if let Some(ms) = self.word_budget {
+            conditions.push(Box::new(WordBudget(Duration::from_millis(ms))));
+        }
+        if self.endless {
+            conditions.push(Box::new(Endless));
+        }
+        conditions
+    }
+
+    /// Resumes the book where it was left off, or starts it from the
+    /// chapter or line given by `--chapter` or `--from-line`.
+    fn start_book<T>(
+        &self,
+        mut selector: BookSelector<T>,
+        book_path: &str,
+    ) -> Result<Box<dyn WordSelector>>
+    where
+        T: Seek + Read + 'static,
+    {
+        // the position is saved next to the history
+        if let Ok(history_path) = self.history_path() {
+            let key = std::fs::canonicalize(book_path).unwrap_or_else(|_| PathBuf::from(book_path));
+            selector = selector
+                .with_bookmark(
+                    history_path.with_file_name("bookmarks"),
+                    key.display().to_string(),
+                )
+                .with_context(|| format!("resuming the book at '{}'", book_path))?;
+            if self.restart_book {
+                selector.reset()?;
+            }
+        }
+        if let Some(chapter) = self.chapter {
+            selector
+                .seek_to_chapter(chapter)
+                .with_context(|| format!("finding chapter {} of the book", chapter))?;
+        } else if let Some(line) = self.from_line {
+            selector
+                .seek_to_line(line)
+                .with_context(|| format!("finding line {} of the book", line))?;
+        }
+
+        Ok(Box::new(selector))
+    }
+
+    /// Wraps the word selector to prefer the letters and bigrams mistyped
+    /// most often, read from the history.
+    #[cfg(feature = "history")]
+    fn adaptive_selector(
+        &self,
+        word_selector: Box<dyn WordSelector>,
+    ) -> Result<Box<dyn WordSelector>> {
+        let history = History::load(&self.history_path()?)?;
+        Ok(Box::new(
+            AdaptiveSelector::from_word_selector(word_selector, history.keys)
+                .with_bigrams(history.bigrams),
+        ))
+    }
+
+    /// Returns an error, since the mistakes are read from the history,
+    /// which needs the `history` feature.
+    #[cfg(not(feature = "history"))]
+    fn adaptive_selector(
+        &self,
+        _word_selector: Box<dyn WordSelector>,
+    ) -> Result<Box<dyn WordSelector>> {
+        Err(ToipeError::from(
+            "cannot prefer the letters mistyped most often, toipe was built without the `history` feature"
+                .to_owned(),
+        ))?
+    }
+
+    /// Creates the word selector for the EPUB book given by `--book`.
+    #[cfg(feature = "epub")]
+    fn epub_selector(
+        &self,
+        book_path: &str,
+        sanitizer: Sanitizer,
+    ) -> Result<Box<dyn WordSelector>> {
+        let selector = BookSelector::from_epub_path(PathBuf::from(book_path), sanitizer)
+            .with_context(|| format!("reading the book from given path '{}'", book_path))?;
+        self.start_book(selector, book_path)
+    }
+
+    /// Returns an error, since EPUB books need the `epub` feature.
+    #[cfg(not(feature = "epub"))]
+    fn epub_selector(
+        &self,
+        book_path: &str,
+        _sanitizer: Sanitizer,
+    ) -> Result<Box<dyn WordSelector>> {
+        Err(ToipeError::from(format!(
+            "cannot read the EPUB book '{}', toipe was built without the `epub` feature",
+            book_path
+        )))?
+    }
+
+    /// Creates the word selector for the PDF file given by `--book`.
+    #[cfg(feature = "pdf")]
+    fn pdf_selector(&self, book_path: &str, sanitizer: Sanitizer) -> Result<Box<dyn WordSelector>> {
+        let selector = BookSelector::from_pdf_path(PathBuf::from(book_path), sanitizer)
+            .with_context(|| format!("reading the book from given path '{}'", book_path))?;
+        self.start_book(selector, book_path)
+    }
+
+    /// Returns an error, since PDF files need the `pdf` feature.
+    #[cfg(not(feature = "pdf"))]
+    fn pdf_selector(
+        &self,
+        book_path: &str,
+        _sanitizer: Sanitizer,
+    ) -> Result<Box<dyn WordSelector>> {
+        Err(ToipeError::from(format!(
+            "cannot read the PDF file '{}', toipe was built without the `pdf` feature",
+            book_path
+        )))?
+    }
+
+    /// Creates the word selector for the word list and options given
+    /// in this config.
+    pub fn word_selector(&self) -> Result<Box<dyn WordSelector>> {
+        let max_word_len = self.max_word_length.unwrap_or(DEFAULT_MAX_WORD_LEN);
+        let mut rng = ToipeRng::new(self.seed);
+        // each selector gets its own generator, derived from the seed
+        let mut new_rng = || rng.fork();
+        let mut word_selector: Box<dyn WordSelector> = if self.stdin_text.is_some() {
+            let mut text = String::new();
+            io::stdin()
+                .read_to_string(&mut text)
+                .context("reading the text from stdin")?;
+            let sanitizer = Sanitizer {
+                numbers: self.book_numbers,
+                untypeable: self.untypeable,
+            };
+            let words: Vec<String> = text
+                .lines()
+                .flat_map(|line| sanitizer.sanitize_line(line))
+                .collect();
+            Box::new(TextSelector::new(&words.join(" ")).context("reading the text from stdin")?)
+        } else if let Some(text) = &self.text {
+            Box::new(TextSelector::new(text).context("reading the text given by --text")?)
+        } else if self.quotes {
+            Box::new(
+                QuoteSelector::new(wordlists::quotes(), self.quote_length)?.with_rng(new_rng()),
+            )
+        } else if let Some(code_path) = self.code.clone() {
+            Box::new(
+                CodeSelector::from_path(PathBuf::from(code_path.clone()))
+                    .with_context(|| format!("reading the code from given path '{}'", code_path))?
+                    .with_rng(new_rng()),
+            )
+        } else if let Some(charset) = &self.charset {
+            Box::new(
+                CharsetSelector::new(charset, self.charset_length[0], self.charset_length[1])
+                    .context("creating the strings given by --charset")?
+                    .with_rng(new_rng()),
+            )
+        } else if let Some(book_path) = self.book.clone() {
+            let sanitizer = Sanitizer {
+                numbers: self.book_numbers,
+                untypeable: self.untypeable,
+            };
+            let extension = Path::new(&book_path)
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .map(str::to_ascii_lowercase);
+            match extension.as_deref() {
+                Some("epub") => self.epub_selector(&book_path, sanitizer)?,
+                Some("pdf") => self.pdf_selector(&book_path, sanitizer)?,
+                _ => {
+                    let selector =
+                        BookSelector::from_path(PathBuf::from(book_path.clone()), sanitizer)
+                            .and_then(|selector| {
+                                selector.with_markdown(MarkdownStripper::is_markdown_file(
+                                    Path::new(&book_path),
+                                ))
+                            })
+                            .with_context(|| {
+                                format!("reading the book from given path '{}'", book_path)
+                            })?;
+                    self.start_book(selector, &book_path)?
+                }
+            }
+        } else if let Some(markov_path) = self.markov.clone() {
+            Box::new(
+                MarkovSelector::from_path(
+                    PathBuf::from(markov_path.clone()),
+                    Sanitizer {
+                        numbers: self.book_numbers,
+                        untypeable: self.untypeable,
+                    },
+                )
+                .with_context(|| format!("reading the text from given path '{}'", markov_path))?
+                .with_rng(new_rng()),
+            )
+        } else if self
+            .wordlist_file
+            .iter()
+            .any(|path| StructuredWordList::is_structured_file(Path::new(path)))
+        {
+            if self.shuffle {
+                return Err(ToipeError::from(
+                    "--shuffle needs a plain word list, not a .json or .csv one".to_string(),
+                ))?;
+            }
+            let wordlist_path = self.wordlist_file.join("', '");
+            let mut list = StructuredWordList::default();
+            for path in &self.wordlist_file {
+                let other = if StructuredWordList::is_structured_file(Path::new(path)) {
+                    StructuredWordList::from_path(PathBuf::from(path))
+                } else {
+                    std::fs::read_to_string(path).map(|text| StructuredWordList::from_plain(&text))
+                }
+                .with_context(|| format!("reading the word list from given path '{}'", path))?;
+                list.merge(other);
+            }
+            list.words.retain(|entry| {
+                !entry.word.is_empty()
+                    && entry.word.chars().count() <= max_word_len
+                    && !entry.word.contains(char::is_whitespace)
+                    && (self.tags.is_empty()
+                        || entry.tags.iter().any(|tag| self.tags.contains(tag)))
+                    && self.max_difficulty.map_or(true, |max_difficulty| {
+                        entry
+                            .difficulty
+                            .is_some_and(|difficulty| difficulty <= max_difficulty)
+                    })
+            });
+            Box::new(
+                StructuredSelector::new(list)
+                    .with_context(|| {
+                        format!("selecting words from the word list at '{}'", wordlist_path)
+                    })?
+                    .with_rng(new_rng()),
+            )
+        } else if !self.tags.is_empty() || self.max_difficulty.is_some() {
+            return Err(ToipeError::from(
+                "--tags and --max-difficulty need a .json or .csv word list".to_string(),
+            ))?;
+        } else if let [wordlist_path] = self.wordlist_file.as_slice() {
+            Box::new(
+                RawWordSelector::from_path(PathBuf::from(wordlist_path.clone()))
+                    .with_context(|| {
+                        format!("reading the word list from given path '{}'", wordlist_path)
+                    })?
+                    .with_max_word_len(max_word_len)
+                    .with_shuffle(self.shuffle)
+                    .with_rng(new_rng()),
+            )
+        } else if !self.wordlist_file.is_empty() {
+            let wordlist_paths: Vec<PathBuf> =
+                self.wordlist_file.iter().map(PathBuf::from).collect();
+            Box::new(
+                RawWordSelector::from_paths(&wordlist_paths)
+                    .with_context(|| {
+                        format!(
+                            "reading the word lists from given paths '{}'",
+                            self.wordlist_file.join("', '")
+                        )
+                    })?
+                    .with_max_word_len(max_word_len)
+                    .with_shuffle(self.shuffle)
+                    .with_rng(new_rng()),
+            )
+        } else if let Some(word_list) = self.language.wordlist_contents(self.builtin_wordlist()) {
+            Box::new(
+                RawWordSelector::from_string(word_list.to_string())
+                    .with_context(|| {
+                        format!("reading the built-in word list {}", self.text_name())
+                    })?
+                    .with_max_word_len(max_word_len)
+                    .with_shuffle(self.shuffle)
+                    .with_rng(new_rng()),
+            )
+        } else if let BuiltInWordlist::OS = self.builtin_wordlist() {
+            let word_list_path = PathBuf::from(OS_WORDLIST_PATH);
+            let selector = match wordlists::os_wordlist_index_path() {
+                Some(cache_path) => {
+                    RawWordSelector::from_path_with_index_cache(word_list_path, &cache_path)
+                }
+                None => RawWordSelector::from_path(word_list_path),
+            };
+            Box::new(
+                selector.with_context(|| {
+                    format!(
+                        "reading from the OS wordlist at path '{}'. See https://en.wikipedia.org/wiki/Words_(Unix) for more info on this file and how it can be installed.",
+                        OS_WORDLIST_PATH
+                    )
+                })?
+                .with_max_word_len(max_word_len)
+                .with_shuffle(self.shuffle)
+                .with_rng(new_rng()),
+            )
+        } else {
+            return Err(ToipeError::from(format!(
+                "the word list {} is not available, see `toipe wordlist list` for the available ones",
+                self.text_name()
+            )))?;
+        };
+
+        if let Some(exclude_path) = &self.exclude_file {
+            let excluded = ExcludeSelector::read_excluded_words(Path::new(exclude_path))
+                .with_context(|| {
+                    format!(
+                        "reading the words to exclude from given path '{}'",
+                        exclude_path
+                    )
+                })?;
+            word_selector = Box::new(ExcludeSelector::from_word_selector(word_selector, excluded));
+        }
+
+        if let Some(digraphs) = self.drill_digraphs()? {
+            let ngrams = digraphs
+                .into_iter()
+                .map(|(first, second)| [first, second].iter().collect())
+                .collect();
+            word_selector = Box::new(
+                NgramSelector::from_word_selector(word_selector, ngrams)?.with_rng(new_rng()),
+            );
+        }
+
+        if let Some(letters) = &self.letters {
+            word_selector = Box::new(LettersSelector::from_word_selector(
+                word_selector,
+                &letters.to_lowercase(),
+            ));
+        }
+
+        if let Some(pattern) = &self.pattern {
+            let pattern = Regex::new(pattern).context("parsing the pattern given by --pattern")?;
+            word_selector = Box::new(PatternSelector::from_word_selector(word_selector, pattern));
+        }
+
+        if !self.ngram.is_empty() {
+            let ngrams = self
+                .ngram
+                .iter()
+                .map(|ngram| ngram.to_lowercase())
+                .collect();
+            word_selector = Box::new(
+                NgramSelector::from_word_selector(word_selector, ngrams)?.with_rng(new_rng()),
+            );
+        }
+
+        // an automatic drill practices the most mistyped letters too
+        let auto_drill = matches!(self.command, Some(ToipeCommand::Drill { auto: true, .. }));
+        if self.adaptive || auto_drill {
+            word_selector = self.adaptive_selector(word_selector)?;
+        }
+
+        if self.numbers {
+            word_selector = Box::new(
+                NumberedWordSelector::from_word_selector(word_selector, self.number_frequency)
+                    .with_rng(new_rng()),
+            )
+        }
+
+        if let Some(percentage) = self.capitalize {
+            word_selector = Box::new(
+                CapitalizedWordSelector::from_word_selector(word_selector, percentage / 100.0)
+                    .with_rng(new_rng()),
+            )
+        }
+
+        if self.punctuation {
+            word_selector = Box::new(
+                PunctuatedWordSelector::from_word_selector(word_selector, 0.15).with_rng(new_rng()),
+            )
+        }
+
+        Ok(word_selector)
+    }
+
+    /// Stream of the words of tests, from the word selector given by
+    /// the config (see [`ToipeConfig::word_selector`]).
+    pub fn word_stream(&self) -> Result<WordStream> {
+        Ok(self.word_stream_from(self.word_selector()?))
+    }
+
+    /// Stream of the words of tests from the given selector.
+    ///
+    /// Each text has [`ToipeConfig::num_words`] words, unless the
+    /// selector decides the length (see
+    /// [`WordSelector::next_text_len`]). Texts of passages (see
+    /// [`ToipeConfig::is_passage`]) go on to the end of their last
+    /// sentence. With [`ToipeConfig::unique_words`], words of a test are
+    /// not repeated, as long as the selector has enough distinct words.
+    pub fn word_stream_from(&self, word_selector: Box<dyn WordSelector>) -> WordStream {
+        WordStream::new(word_selector, self.num_words)
+            .with_unique_words(self.unique_words)
+            .with_sentences(self.is_passage())
+    }
+
+    /// Built-in word list given by `-w`/`--wordlist`, or the default one
+    /// of the language.
+    pub fn builtin_wordlist(&self) -> BuiltInWordlist {
+        self.wordlist
+            .unwrap_or_else(|| self.language.default_wordlist())
+    }
+
+    /// Rarity of the words of the word list given by `-f`/`--file`, if
+    /// it has the frequency of every word (see
+    /// [`WordRarities::from_frequencies`]).
+    ///
+    /// Used to find the difficulty of the text (see `--normalize`)
+    /// instead of the built-in English word lists.
+    pub fn word_rarities(&self) -> Option<WordRarities> {
+        let [wordlist_path] = self.wordlist_file.as_slice() else {
+            return None;
+        };
+        let contents = std::fs::read_to_string(wordlist_path).ok()?;
+        WordRarities::from_frequencies(&contents)
+    }
+
+    /// Name that the best normalized speed (see `--normalize`) of this
+    /// kind of test is kept under in the history.
+    ///
+    /// This is [`ToipeConfig::best_run_key`] without the name of the
+    /// text, so that tests typed with different texts are compared.
+    pub fn normalized_run_key(&self) -> String {
+        let best_run_key = self.best_run_key();
+        let modes = best_run_key
+            .strip_prefix(&self.text_name())
+            .unwrap_or_default();
+        format!("normalized{}", modes)
+    }
+
+    /// Whether the text is a passage that is read in order, like a quote
+    /// or a book, rather than random words.
+    pub fn is_passage(&self) -> bool {
+        self.quotes || self.text.is_some() || self.stdin_text.is_some() || self.book.is_some()
+    }
+
     /// Name of the text used for typing test
     pub fn text_name(&self) -> String {
-        if let Some(wordlist_file) = &self.wordlist_file {
+        if self.stdin_text.is_some() {
+            "text from stdin".to_string()
+        } else if self.text.is_some() {
+            "custom text".to_string()
+        } else if self.quotes {
+            match self.quote_length {
+                QuoteLength::Any => "a quote".to_string(),
+                _ => format!(
+                    "a {} quote",
+                    self.quote_length
+                        .to_possible_value()
+                        .map(|value| value.get_name())
+                        .unwrap_or("")
+                ),
+            }
+        } else if let Some(code) = &self.code {
+            format!("code `{}`", code)
+        } else if let Some(charset) = &self.charset {
+            format!("charset `{}`", charset)
+        } else if let Some(book) = &self.book {
+            format!("book `{}`", book)
+        } else if let Some(markov) = &self.markov {
+            format!("text made up from `{}`", markov)
+        } else if let [wordlist_file] = self.wordlist_file.as_slice() {
             format!("custom file `{}`", wordlist_file)
+        } else if !self.wordlist_file.is_empty() {
+            format!("custom files `{}`", self.wordlist_file.join("`, `"))
+        } else if let BuiltInWordlist::OS = self.builtin_wordlist() {
+            "os".to_string()
         } else {
-            if let Some(possible_value) = self.wordlist.to_possible_value() {
-                possible_value.get_name()
-            } else {
-                "unknown"
-            }
-            .to_string()
+            wordlists::wordlist_name(self.language, self.builtin_wordlist())
+        }
+    }
+
+    /// Name that the best test of this kind is kept under in the
+    /// history: the name of the text, along with the length of the test
+    /// and the options that make it easier or harder, so that only tests
+    /// that can be compared are raced against.
+    pub fn best_run_key(&self) -> String {
+        let mut parts = vec![self.text_name()];
+        let whole_text = self.quotes || self.text.is_some() || self.stdin_text.is_some();
+        match self.time {
+            Some(secs) => parts.push(format!("{}s", secs)),
+            None if self.time_attack.is_some() => parts.push("time attack".to_string()),
+            None if self.endless => parts.push("endless".to_string()),
+            None if whole_text => {}
+            None => parts.push(format!("{} words", self.num_words)),
+        }
+        let modes = [
+            (self.punctuation, "punctuation"),
+            (self.numbers, "numbers"),
+            (self.capitalize.is_some(), "capitalized"),
+            (self.confirm_words, "confirm words"),
+            (self.hardcore, "hardcore"),
+            (self.blind, "blind"),
+            (self.romaji, "romaji"),
+        ];
+        parts.extend(
+            modes
+                .iter()
+                .filter(|(enabled, _)| *enabled)
+                .map(|(_, mode)| mode.to_string()),
+        );
+        if let Some(ms) = self.word_budget {
+            parts.push(format!("{}ms per char", ms));
+        }
+        parts.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn config(args: &[&str]) -> ToipeConfig {
+        ToipeConfig::try_parse_from(["toipe"].iter().chain(args)).unwrap()
+    }
+
+    /// Writes a file named `name` in the temp dir, unique to this process.
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("toipe-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn unique_words_are_not_repeated_in_added_pages() {
+        let mut word_stream = config(&["--unique-words", "--seed", "42", "-n", "100"])
+            .word_stream()
+            .unwrap();
+
+        let mut all_words = word_stream.next_text().unwrap();
+        all_words.extend(word_stream.next_text().unwrap());
+        all_words.sort();
+        all_words.dedup();
+        assert_eq!(all_words.len(), 200);
+    }
+
+    #[test]
+    fn end_conditions_follow_the_options() {
+        assert!(config(&[]).end_conditions().is_empty());
+
+        let conditions = config(&["-t", "30", "--stop-on-error"]).end_conditions();
+        assert_eq!(conditions.len(), 2);
+        assert_eq!(conditions[0].time_limit(), Some(Duration::from_secs(30)));
+
+        let conditions = config(&["--endless"]).end_conditions();
+        assert!(conditions.iter().any(|condition| condition.is_endless()));
+    }
+
+    #[cfg(feature = "history")]
+    #[test]
+    fn auto_drill_practices_the_slowest_digraphs() {
+        let history = temp_file(
+            "drill",
+            "digraph\tqu\t10\t5000\ndigraph\tth\t10\t800\nkey\tz\t20\t10\n",
+        );
+        let mut selector = config(&["--history", history.to_str().unwrap(), "drill", "--auto"])
+            .word_selector()
+            .unwrap();
+        std::fs::remove_file(&history).unwrap();
+
+        for word in selector.new_words(20).unwrap() {
+            assert!(word.contains("qu") || word.contains("th"), "{}", word);
+        }
+    }
+
+    #[cfg(feature = "structured-wordlist")]
+    #[test]
+    fn structured_word_list_is_filtered() {
+        let word_list = temp_file(
+            "words.csv",
+            "word,difficulty,tags\ncat,1,pets\ndog,3,pets\nowl,1,birds\n",
+        );
+        let words = config(&[
+            "-f",
+            word_list.to_str().unwrap(),
+            "--tags",
+            "pets",
+            "--max-difficulty",
+            "2",
+        ])
+        .word_selector()
+        .unwrap()
+        .new_words(20)
+        .unwrap();
+        std::fs::remove_file(&word_list).unwrap();
+
+        assert!(words.iter().all(|word| word == "cat"), "{:?}", words);
+
+        // tags need a structured word list
+        assert!(config(&["-f", "words.txt", "--tags", "pets"])
+            .word_selector()
+            .is_err());
+    }
+
+    /// Word selector reading the given lists, with the other args.
+    fn word_selector(lists: &[&Path], args: &[&str]) -> Result<Box<dyn WordSelector>> {
+        let mut all_args = vec![];
+        for list in lists {
+            all_args.extend(["-f", list.to_str().unwrap()]);
         }
+        all_args.extend(args);
+        config(&all_args).word_selector()
+    }
+
+    #[test]
+    fn word_lists_are_merged() {
+        let common = temp_file("common.txt", "cat\ndog\n");
+        let technical = temp_file("technical.txt", "kernel\ncat\n");
+
+        let words: BTreeSet<_> = word_selector(&[&common, &technical], &[])
+            .unwrap()
+            .new_words(100)
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(
+            words.iter().map(String::as_str).collect::<Vec<_>>(),
+            ["cat", "dog", "kernel"]
+        );
+
+        // plain lists are merged into a structured one, without tags
+        #[cfg(feature = "structured-wordlist")]
+        {
+            let tagged = temp_file("tagged.csv", "word,tags\nmutex,code\nowl,birds\n");
+            let words = word_selector(&[&technical, &tagged], &["--tags", "code"])
+                .unwrap()
+                .new_words(20)
+                .unwrap();
+            assert!(words.iter().all(|word| word == "mutex"), "{:?}", words);
+            std::fs::remove_file(tagged).unwrap();
+        }
+
+        for list in [common, technical] {
+            std::fs::remove_file(list).unwrap();
+        }
+    }
+
+    #[test]
+    fn excluded_words_are_not_selected() {
+        let word_list = temp_file("list.txt", "ant\nbear\ncamel\n");
+        let excluded = temp_file("excluded.txt", "Bear\ncamel\n");
+        let words = word_selector(
+            &[&word_list],
+            &[
+                "--exclude-file",
+                excluded.to_str().unwrap(),
+                "--punctuation",
+            ],
+        )
+        .unwrap()
+        .new_words(50)
+        .unwrap();
+        std::fs::remove_file(&word_list).unwrap();
+        std::fs::remove_file(&excluded).unwrap();
+
+        assert!(
+            words.iter().all(|word| word.to_lowercase().contains("ant")),
+            "{:?}",
+            words
+        );
+    }
+
+    #[test]
+    fn personal_bests_are_kept_per_length_and_mode() {
+        let key = |args: &[&str]| config(args).best_run_key();
+
+        assert_eq!(key(&[]), key(&["--theme", "dark"]));
+        assert_ne!(key(&["-t", "30"]), key(&["-t", "60"]));
+        assert_ne!(key(&["-t", "30"]), key(&["-t", "30", "--punctuation"]));
+        assert_ne!(key(&[]), key(&["--hardcore"]));
+        assert_ne!(key(&[]), key(&["-n", "50"]));
+    }
+
+    #[test]
+    fn normalized_bests_are_kept_across_texts() {
+        let key = |args: &[&str]| config(args).normalized_run_key();
+
+        assert_eq!(key(&["-t", "30"]), "normalized, 30s");
+        assert_eq!(key(&["-t", "30"]), key(&["-t", "30", "-w", "top10000"]));
+        assert_ne!(key(&["-t", "30"]), key(&["-t", "60"]));
+        assert_ne!(key(&[]), key(&["--punctuation"]));
     }
 }
@@ -0,0 +1,67 @@
+//! Debug logging to a file.
+//!
+//! The terminal is taken over by the TUI during the typing test, so logs
+//! are written to a file instead. See [`init`].
+
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::ToipeError;
+
+/// Logger that appends every log record to a file.
+struct FileLogger {
+    file: Mutex<File>,
+    started_at: Instant,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        if let Ok(mut file) = self.file.lock() {
+            // errors are ignored since there is nowhere to report them
+            let _ = writeln!(
+                file,
+                "[{:>10.4}s {:<5} {}] {}",
+                self.started_at.elapsed().as_secs_f64(),
+                record.level(),
+                record.target(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// Starts writing all logs (including trace logs) to the file at given
+/// path.
+///
+/// The file is truncated if it exists. Can only be called once.
+pub fn init(path: &Path) -> Result<()> {
+    let file = File::create(path)
+        .with_context(|| format!("creating the debug log file '{}'", path.display()))?;
+
+    // the logger lives until the end of the program
+    let logger = Box::leak(Box::new(FileLogger {
+        file: Mutex::new(file),
+        started_at: Instant::now(),
+    }));
+    log::set_logger(logger)
+        .map_err(|_| ToipeError::from("debug log was already initialized".to_owned()))?;
+    log::set_max_level(LevelFilter::Trace);
+
+    Ok(())
+}
@@ -0,0 +1,192 @@
+//! Difficulty of typing a text, for comparing speeds across word lists.
+//!
+//! See [`text_difficulty`] and [`ToipeResults::normalized_wpm`].
+
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+#[cfg(doc)]
+use crate::results::ToipeResults;
+#[cfg(doc)]
+use crate::textgen::RawWordSelector;
+use crate::wordlists::BuiltInWordlist;
+
+/// Average length of the words in the `top250` word list, which is the
+/// reference with a difficulty of 1.
+///
+/// Checked against the word list by the `calibration` test.
+pub const REFERENCE_WORD_LEN: f64 = 4.128;
+
+/// Increase in difficulty when the words are twice as long as the
+/// reference.
+const LENGTH_WEIGHT: f64 = 0.1;
+
+/// Increase in difficulty for each tier of rarity (see
+/// [`word_rarity`]).
+const RARITY_WEIGHT: f64 = 0.05;
+
+/// English word lists from the most common words to the least common,
+/// which the rarity of a word is measured with.
+const RARITY_TIERS: [BuiltInWordlist; 4] = [
+    BuiltInWordlist::Top250,
+    BuiltInWordlist::Top1000,
+    BuiltInWordlist::Top5000,
+    BuiltInWordlist::Top25000,
+];
+
+/// Words of each of [`RARITY_TIERS`], read once.
+fn rarity_tiers() -> &'static [HashSet<&'static str>] {
+    static TIERS: OnceLock<Vec<HashSet<&'static str>>> = OnceLock::new();
+    TIERS.get_or_init(|| {
+        RARITY_TIERS
+            .iter()
+            .map(|wordlist| wordlist.contents().unwrap_or_default().lines().collect())
+            .collect()
+    })
+}
+
+/// Number of words in each of [`RARITY_TIERS`], for ranking the words of
+/// a word list with frequencies the same way.
+const RARITY_TIER_SIZES: [usize; 4] = [250, 1000, 5000, 25000];
+
+/// Returns the word with case and punctuation around it removed.
+fn bare_word(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase()
+}
+
+/// How rare an English word is, from 0 for the 250 most common words up
+/// to 4 for words that are in none of the built-in word lists.
+///
+/// Case and punctuation around the word are ignored.
+pub fn word_rarity(word: &str) -> usize {
+    let word = bare_word(word);
+    rarity_tiers()
+        .iter()
+        .position(|tier| tier.contains(word.as_str()))
+        .unwrap_or(RARITY_TIERS.len())
+}
+
+/// Rarity of the words of a word list that gives the frequency of every
+/// word (see [`RawWordSelector#assumptions`]).
+///
+/// Words are ranked from the most frequent and are given the rarity of
+/// the built-in word list that the rank would fall in, so the 250 most
+/// frequent words have a rarity of 0 as in [`word_rarity`].
+#[derive(Clone, Debug)]
+pub struct WordRarities {
+    /// rarity of each word, keyed by the word without case and
+    /// punctuation
+    rarities: HashMap<String, usize>,
+}
+
+impl WordRarities {
+    /// Ranks the words of a word list by their frequencies. Returns
+    /// `None` if a word has no frequency.
+    pub fn from_frequencies(contents: &str) -> Option<Self> {
+        let mut words = contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let (word, frequency) = line.split_once('\t')?;
+                Some((bare_word(word), frequency.trim().parse::<u64>().ok()?))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        words.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        let mut rarities = HashMap::new();
+        for (rank, (word, _)) in words.into_iter().enumerate() {
+            let rarity = RARITY_TIER_SIZES
+                .iter()
+                .position(|&size| rank < size)
+                .unwrap_or(RARITY_TIERS.len());
+            rarities.entry(word).or_insert(rarity);
+        }
+        Some(Self { rarities })
+    }
+
+    /// How rare the word is, or its [`word_rarity`] if it is not in the
+    /// word list.
+    pub fn rarity(&self, word: &str) -> usize {
+        self.rarities
+            .get(&bare_word(word))
+            .copied()
+            .unwrap_or_else(|| word_rarity(word))
+    }
+}
+
+/// Difficulty of typing the given words, from how long and how rare
+/// they are.
+///
+/// The most common English words (the `top250` word list) have a
+/// difficulty of about 1, and harder texts have a higher difficulty.
+/// Returns 1 if there are no words.
+pub fn text_difficulty<S: AsRef<str>>(words: &[S]) -> f64 {
+    text_difficulty_with(words, word_rarity)
+}
+
+/// Same as [`text_difficulty`], but with the rarity of each word given by
+/// `rarity`, like from [`WordRarities::rarity`].
+pub fn text_difficulty_with<S: AsRef<str>>(words: &[S], rarity: impl Fn(&str) -> usize) -> f64 {
+    let words: Vec<&str> = words
+        .iter()
+        .flat_map(|word| word.as_ref().split_whitespace())
+        .collect();
+    if words.is_empty() {
+        return 1.0;
+    }
+
+    let num_words = words.len() as f64;
+    let average_len =
+        words.iter().map(|word| word.chars().count()).sum::<usize>() as f64 / num_words;
+    let average_rarity = words.iter().map(|word| rarity(word)).sum::<usize>() as f64 / num_words;
+
+    1.0 + LENGTH_WEIGHT * (average_len - REFERENCE_WORD_LEN) / REFERENCE_WORD_LEN
+        + RARITY_WEIGHT * average_rarity
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wordlist_difficulty(wordlist: BuiltInWordlist) -> f64 {
+        let words: Vec<&str> = wordlist.contents().unwrap().lines().collect();
+        text_difficulty(&words)
+    }
+
+    #[test]
+    fn calibration() {
+        assert!((wordlist_difficulty(BuiltInWordlist::Top250) - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn rarer_words_are_harder() {
+        assert_eq!(word_rarity("The,"), 0);
+        assert_eq!(word_rarity("aasvogel"), 3);
+        assert_eq!(word_rarity("toipe"), 4);
+
+        let top1000 = wordlist_difficulty(BuiltInWordlist::Top1000);
+        let top25000 = wordlist_difficulty(BuiltInWordlist::Top25000);
+        assert!(1.0 < top1000 && top1000 < top25000);
+        assert_eq!(text_difficulty::<&str>(&[]), 1.0);
+    }
+
+    #[test]
+    fn rarities_from_word_frequencies() {
+        let contents: String = (0..300)
+            .map(|i| format!("word{}\t{}\n", i, 1000 - i))
+            .collect();
+        let rarities = WordRarities::from_frequencies(&contents).unwrap();
+        assert_eq!(rarities.rarity("Word0."), 0);
+        assert_eq!(rarities.rarity("word249"), 0);
+        assert_eq!(rarities.rarity("word250"), 1);
+        assert_eq!(rarities.rarity("the"), 0);
+
+        let rarity = |word: &str| rarities.rarity(word);
+        assert!(
+            text_difficulty_with(&["word0"], rarity) < text_difficulty_with(&["word299"], rarity)
+        );
+
+        assert!(WordRarities::from_frequencies("the\t5\nof\n").is_none());
+    }
+}
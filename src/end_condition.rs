@@ -0,0 +1,290 @@
+//! Conditions that end a typing test before the end of its text.
+//!
+//! See [`EndCondition`].
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How far a typing test has gone, for deciding whether it should end.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub struct TestProgress {
+    /// number of words finished by typing the space after them
+    pub words_typed: usize,
+    /// number of chars of the text typed so far, not counting those
+    /// that were deleted
+    pub chars_typed: usize,
+    /// number of chars typed, including those that were deleted
+    pub total_chars_typed: usize,
+    /// number of wrong chars typed, including those that were deleted
+    pub errors: usize,
+    /// number of chars of the text passed by the pace caret, if it is
+    /// displayed
+    pub pace_chars: Option<usize>,
+}
+
+/// Decides when a typing test ends.
+///
+/// A test ends when the end of its text is typed, when it is quit, or
+/// when any of its end conditions is met, whichever comes first. The
+/// results are then shown like for a test that was typed till the end.
+///
+/// Conditions are checked after every key press, and also every
+/// [`EndCondition::check_interval`] for conditions that can be met
+/// without a key being pressed.
+pub trait EndCondition {
+    /// Whether the test should end, given how far it has gone.
+    fn is_met(&self, progress: &TestProgress) -> bool;
+
+    /// Time after the start of the test at which it should end, if any.
+    ///
+    /// Time during which the test was paused is not counted. A test with
+    /// a time limit gets more words as they are typed, so that the text
+    /// does not run out before the time does.
+    fn time_limit(&self) -> Option<Duration> {
+        None
+    }
+
+    /// How often to check the condition when no key is pressed, if it
+    /// can be met without a key being pressed.
+    fn check_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Whether the test keeps getting more words as they are typed until
+    /// it is ended, by this condition or with ctrl-c, which shows the
+    /// results of everything typed.
+    fn is_endless(&self) -> bool {
+        false
+    }
+
+    /// Whether a test ended by this condition is shown as given up on,
+    /// with why it ended, instead of with its results.
+    fn aborts(&self) -> bool {
+        false
+    }
+
+    /// Time in which a word of `word_len` chars, including the space
+    /// after it, has to be typed, if any.
+    ///
+    /// A word that is not typed in time is marked as failed and skipped,
+    /// without ending the test.
+    fn word_time_limit(&self, _word_len: usize) -> Option<Duration> {
+        None
+    }
+}
+
+/// Ends the test once a number of words are typed.
+#[derive(Copy, Clone, Debug)]
+pub struct WordCount(pub usize);
+
+impl EndCondition for WordCount {
+    fn is_met(&self, progress: &TestProgress) -> bool {
+        progress.words_typed >= self.0
+    }
+}
+
+/// Ends the test after some time (see `-t`).
+#[derive(Copy, Clone, Debug)]
+pub struct TimeLimit(pub Duration);
+
+impl EndCondition for TimeLimit {
+    fn is_met(&self, _progress: &TestProgress) -> bool {
+        false
+    }
+
+    fn time_limit(&self) -> Option<Duration> {
+        Some(self.0)
+    }
+}
+
+/// Ends the test once a number of chars are typed (see `--chars`).
+#[derive(Copy, Clone, Debug)]
+pub struct CharCount(pub usize);
+
+impl EndCondition for CharCount {
+    fn is_met(&self, progress: &TestProgress) -> bool {
+        progress.chars_typed >= self.0
+    }
+}
+
+/// Ends the test at the first wrong char (see `--stop-on-error`).
+#[derive(Copy, Clone, Debug)]
+pub struct FirstError;
+
+impl EndCondition for FirstError {
+    fn is_met(&self, progress: &TestProgress) -> bool {
+        progress.errors > 0
+    }
+}
+
+/// Never ends the test, which keeps getting more words until ctrl-c is
+/// pressed (see `--endless`).
+#[derive(Copy, Clone, Debug)]
+pub struct Endless;
+
+impl EndCondition for Endless {
+    fn is_met(&self, _progress: &TestProgress) -> bool {
+        false
+    }
+
+    fn is_endless(&self) -> bool {
+        true
+    }
+}
+
+/// Number of chars to be typed before a test can be ended for low
+/// accuracy by [`MinAccuracy`], so that a mistake in the first few chars
+/// does not end it.
+const MIN_ACCURACY_CHARS: usize = 10;
+
+/// Gives up on the test if the accuracy drops below a percentage (see
+/// `--min-accuracy`).
+#[derive(Copy, Clone, Debug)]
+pub struct MinAccuracy(pub f64);
+
+impl EndCondition for MinAccuracy {
+    fn is_met(&self, progress: &TestProgress) -> bool {
+        let typed = progress.total_chars_typed;
+        let accuracy = typed.saturating_sub(progress.errors) as f64 / typed.max(1) as f64;
+        typed >= MIN_ACCURACY_CHARS && accuracy * 100.0 < self.0
+    }
+
+    fn aborts(&self) -> bool {
+        true
+    }
+}
+
+/// Ends the test once the pace caret passes the char to be typed, adding
+/// words until then (see `--time-attack`, which also makes the caret
+/// speed up).
+#[derive(Copy, Clone, Debug)]
+pub struct TimeAttack;
+
+impl EndCondition for TimeAttack {
+    fn is_met(&self, progress: &TestProgress) -> bool {
+        progress
+            .pace_chars
+            .is_some_and(|pace_chars| pace_chars > progress.chars_typed)
+    }
+
+    fn is_endless(&self) -> bool {
+        true
+    }
+}
+
+/// Gives each word a number of milliseconds for each of its chars,
+/// skipping the words that are not typed in time (see `--word-budget`).
+#[derive(Copy, Clone, Debug)]
+pub struct WordBudget(pub Duration);
+
+impl EndCondition for WordBudget {
+    fn is_met(&self, _progress: &TestProgress) -> bool {
+        false
+    }
+
+    fn word_time_limit(&self, word_len: usize) -> Option<Duration> {
+        Some(self.0 * word_len as u32)
+    }
+}
+
+/// How often a [`Signal`] is checked when no key is pressed.
+const SIGNAL_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Ends the test when a flag is set, like from another thread.
+///
+/// ```
+/// use std::sync::atomic::{AtomicBool, Ordering};
+/// use std::sync::Arc;
+/// use toipe::end_condition::Signal;
+///
+/// let flag = Arc::new(AtomicBool::new(false));
+/// let signal = Signal(Arc::clone(&flag));
+/// // give `signal` to the test, then end it with:
+/// flag.store(true, Ordering::Relaxed);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Signal(pub Arc<AtomicBool>);
+
+impl EndCondition for Signal {
+    fn is_met(&self, _progress: &TestProgress) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn check_interval(&self) -> Option<Duration> {
+        Some(SIGNAL_CHECK_INTERVAL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_conditions() {
+        let progress = TestProgress {
+            words_typed: 3,
+            chars_typed: 17,
+            total_chars_typed: 20,
+            errors: 0,
+            pace_chars: None,
+        };
+
+        assert!(WordCount(3).is_met(&progress));
+        assert!(!WordCount(4).is_met(&progress));
+        assert!(CharCount(17).is_met(&progress));
+        assert!(!CharCount(18).is_met(&progress));
+        assert!(!FirstError.is_met(&progress));
+        assert!(FirstError.is_met(&TestProgress {
+            errors: 1,
+            ..progress
+        }));
+        assert!(!TimeLimit(Duration::from_secs(1)).is_met(&progress));
+        assert!(!Endless.is_met(&progress));
+        assert!(!WordBudget(Duration::from_millis(100)).is_met(&progress));
+        assert_eq!(
+            WordBudget(Duration::from_millis(100)).word_time_limit(6),
+            Some(Duration::from_millis(600))
+        );
+
+        let flag = Arc::new(AtomicBool::new(false));
+        let signal = Signal(Arc::clone(&flag));
+        assert!(!signal.is_met(&progress));
+        flag.store(true, Ordering::Relaxed);
+        assert!(signal.is_met(&progress));
+    }
+
+    #[test]
+    fn min_accuracy_waits_for_a_few_chars() {
+        let progress = TestProgress {
+            total_chars_typed: MIN_ACCURACY_CHARS - 1,
+            errors: 3,
+            ..TestProgress::default()
+        };
+        assert!(!MinAccuracy(90.0).is_met(&progress));
+
+        let progress = TestProgress {
+            total_chars_typed: 20,
+            ..progress
+        };
+        assert!(MinAccuracy(90.0).is_met(&progress));
+        assert!(!MinAccuracy(80.0).is_met(&progress));
+    }
+
+    #[test]
+    fn time_attack_ends_when_the_caret_passes_the_user() {
+        let progress = TestProgress {
+            chars_typed: 12,
+            ..TestProgress::default()
+        };
+        assert!(!TimeAttack.is_met(&progress));
+        assert!(!TimeAttack.is_met(&TestProgress {
+            pace_chars: Some(12),
+            ..progress
+        }));
+        assert!(TimeAttack.is_met(&TestProgress {
+            pace_chars: Some(13),
+            ..progress
+        }));
+    }
+}
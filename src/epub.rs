@@ -0,0 +1,314 @@
+//! Reading the text of EPUB books, for typing them with `--book`.
+//!
+//! See [`read_text`].
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use regex::{Captures, Regex};
+use zip::ZipArchive;
+
+/// Text of an EPUB book, as read by [`read_text`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EpubText {
+    /// paragraphs of the book in reading order, separated by blank lines
+    pub text: String,
+    /// byte offset in the text where each chapter starts
+    pub chapter_offsets: Vec<u64>,
+}
+
+/// Reads the text of the EPUB book at the path, in reading order.
+///
+/// Each document of the book that has text is a chapter. The tags of
+/// the documents are left out, with paragraphs, headings and other
+/// blocks on lines of their own.
+pub fn read_text(path: &Path) -> Result<EpubText, io::Error> {
+    let mut archive = ZipArchive::new(File::open(path)?).map_err(invalid_book)?;
+
+    let container = read_file(&mut archive, "META-INF/container.xml")?;
+    let package_path = Regex::new(r#"<rootfile\b[^>]*\bfull-path\s*=\s*["']([^"']+)["']"#)
+        .expect("pattern is valid")
+        .captures(&container)
+        .map(|captures| captures[1].to_string())
+        .ok_or_else(|| invalid_book("container.xml has no rootfile"))?;
+    let package = read_file(&mut archive, &package_path)?;
+    let package_dir = package_path
+        .rfind('/')
+        .map_or("", |slash| &package_path[..=slash]);
+
+    let mut text = String::new();
+    let mut chapter_offsets = Vec::new();
+    for href in spine_hrefs(&package) {
+        let document = read_file(&mut archive, &resolve_href(package_dir, &href))?;
+        let paragraphs = document_paragraphs(&document);
+        if paragraphs.is_empty() {
+            continue;
+        }
+
+        chapter_offsets.push(text.len() as u64);
+        for paragraph in paragraphs {
+            text.push_str(&paragraph);
+            text.push_str("\n\n");
+        }
+    }
+
+    if chapter_offsets.is_empty() {
+        return Err(invalid_book("book has no text"));
+    }
+
+    Ok(EpubText {
+        text,
+        chapter_offsets,
+    })
+}
+
+fn invalid_book<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+fn read_file(archive: &mut ZipArchive<File>, name: &str) -> Result<String, io::Error> {
+    let mut file = archive.by_name(name).map_err(|error| {
+        invalid_book(format!("could not read '{}' in the book: {}", name, error))
+    })?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+/// Attributes of each tag with the given name in the XML, in order.
+fn tag_attributes<'a>(xml: &'a str, tag: &str) -> Vec<Vec<(&'a str, String)>> {
+    let tags = Regex::new(&format!(r"<{}\b[^>]*>", tag)).expect("pattern is valid");
+    let attributes =
+        Regex::new(r#"([\w:-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).expect("pattern is valid");
+
+    tags.find_iter(xml)
+        .map(|tag| {
+            attributes
+                .captures_iter(tag.as_str())
+                .map(|captures| {
+                    let name = captures.get(1).expect("name is matched").as_str();
+                    let value = captures.get(2).or_else(|| captures.get(3));
+                    (
+                        name,
+                        decode_entities(value.map_or("", |value| value.as_str())),
+                    )
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Paths of the documents of the book in reading order, as given by
+/// the spine of its package document, relative to the package.
+fn spine_hrefs(package: &str) -> Vec<String> {
+    let attribute = |attributes: &[(&str, String)], name: &str| {
+        attributes
+            .iter()
+            .find(|(attribute, _)| *attribute == name)
+            .map(|(_, value)| value.clone())
+    };
+
+    let items: Vec<(String, String)> = tag_attributes(package, "item")
+        .iter()
+        .filter_map(|item| Some((attribute(item, "id")?, attribute(item, "href")?)))
+        .collect();
+    tag_attributes(package, "itemref")
+        .iter()
+        .filter_map(|itemref| attribute(itemref, "idref"))
+        .filter_map(|idref| {
+            items
+                .iter()
+                .find(|(id, _)| *id == idref)
+                .map(|(_, href)| href.clone())
+        })
+        .collect()
+}
+
+/// Path in the archive of a document linked from the package document
+/// in the given directory.
+fn resolve_href(package_dir: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or_default();
+    let mut parts: Vec<String> = Vec::new();
+    for part in format!("{}{}", package_dir, href).split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(percent_decode(part)),
+        }
+    }
+    parts.join("/")
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Paragraphs of text in an XHTML document of the book, without tags.
+fn document_paragraphs(document: &str) -> Vec<String> {
+    let regex = |pattern| Regex::new(pattern).expect("pattern is valid");
+    let body = regex(r"(?is)<body\b[^>]*>(.*)</body>")
+        .captures(document)
+        .and_then(|captures| captures.get(1))
+        .map_or(document, |body| body.as_str());
+
+    let text = regex(r"(?is)<script\b.*?</script>").replace_all(body, "");
+    let text = regex(r"(?is)<style\b.*?</style>").replace_all(&text, "");
+    let text = regex(r"\s+").replace_all(&text, " ");
+    let text = regex(r"(?i)</?(p|div|h[1-6]|li|br|hr|tr|blockquote|section|pre)\b[^>]*>")
+        .replace_all(&text, "\n");
+    let text = regex(r"<[^>]*>").replace_all(&text, "");
+
+    decode_entities(&text)
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Replaces the character references in XML text, like `&amp;` and
+/// `&#8217;`, by the chars they stand for.
+fn decode_entities(text: &str) -> String {
+    Regex::new(r"&(#[xX][0-9a-fA-F]+|#[0-9]+|[a-zA-Z]+);")
+        .expect("pattern is valid")
+        .replace_all(text, |captures: &Captures| {
+            let name = &captures[1];
+            let code = if let Some(hex) = name.strip_prefix("#x").or(name.strip_prefix("#X")) {
+                u32::from_str_radix(hex, 16).ok()
+            } else if let Some(decimal) = name.strip_prefix('#') {
+                decimal.parse().ok()
+            } else {
+                match name {
+                    "amp" => Some('&' as u32),
+                    "lt" => Some('<' as u32),
+                    "gt" => Some('>' as u32),
+                    "quot" => Some('"' as u32),
+                    "apos" => Some('\'' as u32),
+                    "nbsp" => Some(' ' as u32),
+                    _ => None,
+                }
+            };
+            match code.and_then(char::from_u32) {
+                Some(c) => c.to_string(),
+                None => captures[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::textgen::{BookSelector, Sanitizer, WordSelector};
+    use std::io::Write;
+    use std::num::NonZeroUsize;
+    use zip::write::{FileOptions, ZipWriter};
+
+    const CONTAINER: &str = r#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>"#;
+
+    const PACKAGE: &str = r#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+  <manifest>
+    <item href="text/chapter%202.xhtml" id="two" media-type="application/xhtml+xml"/>
+    <item id="cover" href="cover.xhtml" media-type="application/xhtml+xml"/>
+    <item id="one" href="text/chapter1.xhtml" media-type="application/xhtml+xml"/>
+  </manifest>
+  <spine>
+    <itemref idref="cover"/>
+    <itemref idref="one"/>
+    <itemref idref='two'/>
+  </spine>
+</package>"#;
+
+    fn write_book(path: &Path) {
+        let mut book = ZipWriter::new(File::create(path).unwrap());
+        let files = [
+            ("mimetype", "application/epub+zip"),
+            ("META-INF/container.xml", CONTAINER),
+            ("OEBPS/content.opf", PACKAGE),
+            (
+                "OEBPS/cover.xhtml",
+                "<html><body><img src=\"cover.png\"/></body></html>",
+            ),
+            (
+                "OEBPS/text/chapter1.xhtml",
+                "<html><head><title>One</title><style>p { margin: 0 }</style></head>\n\
+                 <body><h1>Chapter 1</h1>\n<p>Call me\n  <em>Ishmael</em>.</p>\
+                 <p>Tom &amp; Jerry&#8217;s</p></body></html>",
+            ),
+            (
+                "OEBPS/text/chapter 2.xhtml",
+                "<html><body><h2>Chapter 2</h2><p>The end.</p></body></html>",
+            ),
+        ];
+        for (name, contents) in files {
+            book.start_file(name, FileOptions::default()).unwrap();
+            book.write_all(contents.as_bytes()).unwrap();
+        }
+        book.finish().unwrap();
+    }
+
+    #[test]
+    fn reads_text_in_reading_order() {
+        let dir = std::env::temp_dir().join(format!("toipe-epub-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("book.epub");
+        write_book(&path);
+
+        let book = read_text(&path).unwrap();
+        assert_eq!(
+            book.text,
+            "Chapter 1\n\nCall me Ishmael.\n\nTom & Jerry\u{2019}s\n\nChapter 2\n\nThe end.\n\n"
+        );
+        let chapters: Vec<&str> = book
+            .chapter_offsets
+            .iter()
+            .map(|&offset| &book.text[offset as usize..][..9])
+            .collect();
+        assert_eq!(chapters, ["Chapter 1", "Chapter 2"]);
+
+        // each document is a chapter of the book
+        let mut selector =
+            BookSelector::from_epub_path(path.clone(), Sanitizer::default()).unwrap();
+        selector
+            .seek_to_chapter(NonZeroUsize::new(2).unwrap())
+            .unwrap();
+        assert_eq!(selector.new_words(3).unwrap(), ["Chapter", "2", "The"]);
+        assert!(selector
+            .seek_to_chapter(NonZeroUsize::new(3).unwrap())
+            .is_err());
+
+        assert!(read_text(&dir.join("missing.epub")).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,305 @@
+//! Results exported to JSON with `--export`, with hashes to check them
+//! by with `toipe verify`.
+//!
+//! See [`ExportedResults`].
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::ToipeConfig;
+use crate::results::{ToipeResults, WordRecord};
+
+/// Char in [`ExportedResults::text`] for a char of the text that no key
+/// was pressed for, like those of a word skipped when its time ran out.
+pub const UNKNOWN_CHAR: char = '\u{FFFD}';
+
+/// Results of a test as written to the file given by `--export`.
+///
+/// Two hashes make the results checkable by others:
+///
+/// - [`reproducibility_hash`](ExportedResults::reproducibility_hash)
+///   identifies the seed, the word list and the options the text was
+///   selected with, so that the text can be selected again with the
+///   same [`args`](ExportedResults::args).
+/// - [`checksum`](ExportedResults::checksum) is the SHA-256 hash of all
+///   the other fields, which catches files that were corrupted or
+///   partly changed.
+///
+/// The checksum is not keyed, so anyone who edits the results can
+/// compute it again: the speed and accuracy cannot be verified, only
+/// checked against the counts they are computed from.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExportedResults {
+    /// version of toipe the test was typed with
+    pub toipe_version: String,
+    pub wpm: f64,
+    /// between 0 and 1
+    pub accuracy: f64,
+    pub duration_secs: f64,
+    pub total_chars_typed: usize,
+    pub total_char_errors: usize,
+    pub final_chars_typed_correctly: usize,
+    pub final_uncorrected_errors: usize,
+    /// text of the test up to the last char typed, with pages
+    /// separated by a space
+    pub text: String,
+    /// how each word of the text was typed, in order
+    pub word_records: Vec<ExportedWordRecord>,
+    pub seed: Option<u64>,
+    /// name of the word list or text the test was typed from
+    pub wordlist: String,
+    /// length of the longest word that could be selected
+    pub max_word_length: usize,
+    /// arguments toipe was run with, to select the text again
+    pub args: Vec<String>,
+    pub reproducibility_hash: String,
+    pub checksum: String,
+}
+
+/// How one word of the text was typed, as in [`WordRecord`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExportedWordRecord {
+    pub word: String,
+    pub duration_secs: f64,
+    pub errors: usize,
+    pub corrected: bool,
+}
+
+impl From<WordRecord> for ExportedWordRecord {
+    fn from(record: WordRecord) -> Self {
+        Self {
+            word: record.word,
+            duration_secs: record.duration.as_secs_f64(),
+            errors: record.errors,
+            corrected: record.corrected,
+        }
+    }
+}
+
+impl ExportedResults {
+    /// Exports the results of a test typed with the given config, which
+    /// was parsed from `args` (without the name of the program).
+    ///
+    /// The config is the one used for the test (see
+    /// [`Toipe::config`](crate::Toipe::config)), since toipe fills in
+    /// some options, like the length of the longest word, when a test
+    /// is started.
+    pub fn new(results: &ToipeResults, config: &ToipeConfig, args: &[String]) -> Self {
+        let pages = if results.pages.is_empty() {
+            std::slice::from_ref(results)
+        } else {
+            &results.pages
+        };
+        let text = pages
+            .iter()
+            .map(|page| {
+                page.char_records()
+                    .iter()
+                    .map(|record| record.expected.unwrap_or(UNKNOWN_CHAR))
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let word_records = pages
+            .iter()
+            .flat_map(|page| page.word_records())
+            .map(ExportedWordRecord::from)
+            .collect();
+        let max_word_length = config
+            .max_word_length
+            .unwrap_or(crate::textgen::DEFAULT_MAX_WORD_LEN);
+
+        let mut exported = Self {
+            toipe_version: env!("CARGO_PKG_VERSION").to_string(),
+            wpm: results.wpm(),
+            accuracy: results.accuracy(),
+            duration_secs: results.duration().as_secs_f64(),
+            total_chars_typed: results.total_chars_typed,
+            total_char_errors: results.total_char_errors,
+            final_chars_typed_correctly: results.final_chars_typed_correctly,
+            final_uncorrected_errors: results.final_uncorrected_errors,
+            text,
+            word_records,
+            seed: config.seed,
+            wordlist: config.text_name(),
+            max_word_length,
+            args: args.to_vec(),
+            reproducibility_hash: reproducibility_hash(config, max_word_length, args),
+            checksum: String::new(),
+        };
+        exported.checksum = exported.expected_checksum();
+        exported
+    }
+
+    /// Reads results from a file written by [`ExportedResults::save`].
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("reading the results from {}", path.display()))?;
+        serde_json::from_str(&json)
+            .with_context(|| format!("reading the results from {}", path.display()))
+    }
+
+    /// Writes the results to a JSON file.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).expect("results can be serialized");
+        fs::write(path, json + "\n")
+            .with_context(|| format!("writing the results to {}", path.display()))
+    }
+
+    /// Hash of all the fields but the checksum, which is what the
+    /// checksum should be if the results were not edited.
+    pub fn expected_checksum(&self) -> String {
+        let unchecked = Self {
+            checksum: String::new(),
+            ..self.clone()
+        };
+        sha256_hex(
+            serde_json::to_string(&unchecked)
+                .expect("results can be serialized")
+                .as_bytes(),
+        )
+    }
+
+    /// Whether the speed and accuracy are the ones the counts and the
+    /// duration give.
+    pub fn stats_are_consistent(&self) -> bool {
+        let minutes = self.duration_secs / 60.0;
+        let wpm = (self.final_chars_typed_correctly as f64 / 5.0
+            - self.final_uncorrected_errors as f64)
+            .max(0.0)
+            / minutes;
+        let accuracy = if self.total_chars_typed == 0 {
+            0.0
+        } else {
+            (self.total_chars_typed as isize - self.total_char_errors as isize) as f64
+                / self.total_chars_typed as f64
+        };
+        (wpm - self.wpm).abs() < 1e-6 && (accuracy - self.accuracy).abs() < 1e-9
+    }
+
+    /// Whether the text is the start of `text`, ignoring chars that
+    /// were not typed (see [`UNKNOWN_CHAR`]).
+    pub fn text_is_start_of(&self, text: &str) -> bool {
+        let mut chars = text.chars();
+        self.text.chars().all(|expected| {
+            chars
+                .next()
+                .is_some_and(|c| expected == UNKNOWN_CHAR || expected == c)
+        })
+    }
+}
+
+/// The arguments without `--export` and its path, which do not change
+/// the text, for [`ExportedResults::args`].
+pub fn args_without_export(args: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut args = args.into_iter();
+    let mut kept = Vec::new();
+    while let Some(arg) = args.next() {
+        if arg == "--export" {
+            args.next();
+        } else if !arg.starts_with("--export=") {
+            kept.push(arg);
+        }
+    }
+    kept
+}
+
+/// Hash of the seed, the word list and the options that the text of a
+/// test was selected with.
+///
+/// The word list is identified by its name, the version of toipe for
+/// built-in ones and the contents of the files it was read from.
+pub fn reproducibility_hash(
+    config: &ToipeConfig,
+    max_word_length: usize,
+    args: &[String],
+) -> String {
+    let files = config
+        .wordlist_file
+        .iter()
+        .chain(&config.book)
+        .chain(&config.markov)
+        .chain(&config.code);
+    let file_hashes: Vec<String> = files
+        .map(|file| match fs::read(file) {
+            Ok(contents) => sha256_hex(&contents),
+            Err(_) => "unreadable".to_string(),
+        })
+        .collect();
+
+    let identity = serde_json::json!({
+        "seed": config.seed,
+        "wordlist": config.text_name(),
+        "toipe_version": env!("CARGO_PKG_VERSION"),
+        "files": file_hashes,
+        "max_word_length": max_word_length,
+        "args": args,
+    });
+    sha256_hex(identity.to_string().as_bytes())
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_path_is_left_out_of_args() {
+        let args = [
+            "--seed",
+            "4",
+            "--export",
+            "a.json",
+            "-n",
+            "5",
+            "--export=b.json",
+        ];
+        assert_eq!(
+            args_without_export(args.map(String::from)),
+            ["--seed", "4", "-n", "5"]
+        );
+    }
+
+    #[test]
+    fn checksums_survive_reading_the_results_back() {
+        let mut exported = ExportedResults {
+            toipe_version: "0.0.0".to_string(),
+            wpm: 0.1 + 0.2,
+            accuracy: 2.0 / 3.0,
+            duration_secs: 12.345678901234567,
+            total_chars_typed: 3,
+            total_char_errors: 1,
+            final_chars_typed_correctly: 2,
+            final_uncorrected_errors: 0,
+            text: "abc".to_string(),
+            word_records: vec![ExportedWordRecord {
+                word: "abc".to_string(),
+                duration_secs: 0.7,
+                errors: 1,
+                corrected: false,
+            }],
+            seed: Some(1),
+            wordlist: "test".to_string(),
+            max_word_length: 5,
+            args: Vec::new(),
+            reproducibility_hash: String::new(),
+            checksum: String::new(),
+        };
+        exported.checksum = exported.expected_checksum();
+
+        let json = serde_json::to_string_pretty(&exported).unwrap();
+        let read: ExportedResults = serde_json::from_str(&json).unwrap();
+        assert_eq!(read, exported);
+        assert_eq!(read.expected_checksum(), exported.checksum);
+    }
+}
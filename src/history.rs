@@ -0,0 +1,518 @@
+//! Stats of past typing tests, stored in a file.
+//!
+//! See [`History`] and [`default_path`].
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use crate::results::ToipeResults;
+use crate::ToipeError;
+
+/// Number of times a digraph must have been typed for its speed to be
+/// reported, so that a single slow key press does not stand out.
+pub const MIN_DIGRAPH_COUNT: u64 = 3;
+
+/// Time taken to type a digraph (two consecutive letters), over all the
+/// times it was typed.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub struct DigraphStats {
+    /// number of times the digraph was typed
+    pub count: u64,
+    /// time taken to type the second letter after the first, added up
+    pub total: Duration,
+}
+
+impl DigraphStats {
+    /// Average time taken to type the second letter after the first.
+    pub fn average(&self) -> Duration {
+        self.total / self.count.max(1) as u32
+    }
+}
+
+/// Number of times a letter must have been typed for its error rate to
+/// be used, so that a single mistake does not stand out.
+pub const MIN_KEY_COUNT: u64 = 10;
+
+/// Number of times a bigram must have been typed for its error rate to
+/// be used, like [`MIN_KEY_COUNT`] for letters.
+pub const MIN_BIGRAM_COUNT: u64 = 5;
+
+/// Mistakes made when typing a letter, over all the times it was typed.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub struct KeyStats {
+    /// number of times a key was pressed for the letter
+    pub count: u64,
+    /// number of times the wrong key was pressed
+    pub errors: u64,
+}
+
+impl KeyStats {
+    /// Counts a key press for the letter.
+    pub fn record(&mut self, correct: bool) {
+        self.count += 1;
+        self.errors += u64::from(!correct);
+    }
+
+    /// Fraction of the key presses that were wrong, between 0 and 1.
+    pub fn error_rate(&self) -> f64 {
+        self.errors as f64 / self.count.max(1) as f64
+    }
+}
+
+/// The fastest test typed with some text, to race against.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BestRun {
+    /// speed of the test
+    pub wpm: f64,
+    /// see [`ToipeResults::progress`]
+    pub progress: Vec<Duration>,
+}
+
+/// Stats of past typing tests.
+///
+/// Stored as a text file with one record on each line. Fields are
+/// separated by tabs and the first field is the kind of record.
+///
+/// Digraph records have the two letters, the number of times they were
+/// typed and the total time taken in microseconds, like
+/// `digraph th 42 4200000` with tabs between the fields.
+///
+/// Key records have the letter, the number of times a key was pressed
+/// for it and the number of those that were wrong, like `key e 120 4`.
+///
+/// Best run records have the name of the text (like the word list),
+/// the speed and the progress in milliseconds separated by commas, like
+/// `best top250 62.5 0,180,350`. The best speed adjusted for the
+/// difficulty of the text (see `--normalize`) is kept the same way, with
+/// a name starting with `normalized` in place of the name of the text.
+///
+/// Bigram records are the same as key records, with the two letters of
+/// the bigram and the mistakes made for its second letter, like
+/// `bigram th 40 2`.
+///
+/// Stats of a test still being typed (see [`History::record_partial`])
+/// are stored as `partial-digraph`, `partial-key` and `partial-bigram`
+/// records, with the same fields as digraph, key and bigram records. They are only there if
+/// toipe stopped before the test ended, like after a crash, and are
+/// added to the other stats when the history is loaded.
+#[derive(Clone, Default, Debug)]
+pub struct History {
+    /// stats of each digraph, keyed by its two letters
+    pub digraphs: HashMap<(char, char), DigraphStats>,
+    /// mistakes made for each letter
+    pub keys: HashMap<char, KeyStats>,
+    /// mistakes made for the second letter of each bigram, keyed by its
+    /// two letters
+    pub bigrams: HashMap<(char, char), KeyStats>,
+    /// fastest test for each text, keyed by the name of the text
+    pub best_runs: HashMap<String, BestRun>,
+    /// stats of the test being typed so far, if any, which are replaced
+    /// by the stats of the whole test once it is recorded
+    pub partial: Option<Box<History>>,
+}
+
+/// Path of the history file when none is given.
+///
+/// This is `toipe/history` under `$XDG_DATA_HOME`, or under
+/// `~/.local/share` if that is not set. Returns `None` if neither
+/// variable is set.
+pub fn default_path() -> Option<PathBuf> {
+    let data_dir = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("HOME")
+                .filter(|home| !home.is_empty())
+                .map(|home| PathBuf::from(home).join(".local/share"))
+        })?;
+
+    Some(data_dir.join("toipe").join("history"))
+}
+
+impl History {
+    /// Reads the history from the file at given path.
+    ///
+    /// Returns an empty history if the file does not exist.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("reading the history file '{}'", path.display()))
+            }
+        };
+
+        Self::parse(&contents)
+            .map_err(|err| err.with_context(&format!("in history file '{}': ", path.display())))
+            .map_err(Into::into)
+    }
+
+    /// Writes the history to the file at given path, creating its
+    /// directory if needed.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("creating the directory '{}'", dir.display()))?;
+        }
+        fs::write(path, self.to_string())
+            .with_context(|| format!("writing the history file '{}'", path.display()))
+    }
+
+    /// Adds the stats of a test to the history, replacing the partial
+    /// stats of the test if any were recorded.
+    pub fn record(&mut self, results: &ToipeResults) {
+        self.partial = None;
+        for (digraph, latency) in results.digraph_latencies() {
+            let stats = self.digraphs.entry(digraph).or_default();
+            stats.count += 1;
+            stats.total += latency;
+        }
+        for (letter, correct) in results.letter_presses() {
+            self.keys.entry(letter).or_default().record(correct);
+        }
+        for (bigram, correct) in results.bigram_presses() {
+            self.bigrams.entry(bigram).or_default().record(correct);
+        }
+    }
+
+    /// Keeps the stats of a test that is still being typed, replacing
+    /// those kept before, so that they can be saved in case toipe stops
+    /// before the test ends.
+    ///
+    /// They are only added to the other stats when the history is loaded
+    /// again, or replaced by the stats of the whole test when it ends
+    /// (see [`History::record`]).
+    pub fn record_partial(&mut self, results: &ToipeResults) {
+        let mut partial = History::default();
+        partial.record(results);
+        self.partial = Some(Box::new(partial));
+    }
+
+    /// Records the test as the best run for the text with given name if
+    /// it is faster than the previous best.
+    ///
+    /// Returns whether it was recorded.
+    pub fn record_best_run(&mut self, text_name: &str, results: &ToipeResults) -> bool {
+        self.insert_best_run(text_name, results.wpm(), results)
+    }
+
+    /// Records the test as the best normalized run (see
+    /// [`ToipeResults::normalized_wpm`]) under the given key if its
+    /// normalized speed is faster than the previous best.
+    ///
+    /// Returns whether it was recorded.
+    pub fn record_best_normalized_run(
+        &mut self,
+        key: &str,
+        results: &ToipeResults,
+        difficulty: f64,
+    ) -> bool {
+        self.insert_best_run(key, results.normalized_wpm(difficulty), results)
+    }
+
+    fn insert_best_run(&mut self, text_name: &str, wpm: f64, results: &ToipeResults) -> bool {
+        // tabs and newlines separate records and fields
+        let text_name = text_name.replace(['\t', '\n'], " ");
+        if self
+            .best_runs
+            .get(&text_name)
+            .is_some_and(|best_run| best_run.wpm >= wpm)
+        {
+            return false;
+        }
+
+        let progress = results.progress();
+        self.best_runs.insert(text_name, BestRun { wpm, progress });
+        true
+    }
+
+    /// Returns up to `n` digraphs with the longest average time, slowest
+    /// first.
+    ///
+    /// Only digraphs typed at least `min_count` times are included (see
+    /// [`MIN_DIGRAPH_COUNT`]).
+    pub fn slowest_digraphs(&self, n: usize, min_count: u64) -> Vec<((char, char), DigraphStats)> {
+        let mut digraphs: Vec<_> = self
+            .digraphs
+            .iter()
+            .filter(|(_, stats)| stats.count >= min_count)
+            .map(|(&digraph, &stats)| (digraph, stats))
+            .collect();
+        // ties broken alphabetically
+        digraphs.sort_by(|(digraph_a, stats_a), (digraph_b, stats_b)| {
+            stats_b
+                .average()
+                .cmp(&stats_a.average())
+                .then_with(|| digraph_a.cmp(digraph_b))
+        });
+        digraphs.truncate(n);
+        digraphs
+    }
+
+    fn parse(contents: &str) -> Result<Self, ToipeError> {
+        let mut history = Self::default();
+
+        for (line_number, line) in contents.lines().enumerate() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let invalid =
+                || ToipeError::from(format!("invalid record on line {}", line_number + 1));
+            match fields.as_slice() {
+                // stats of a test that did not end are kept as they are
+                ["digraph" | "partial-digraph", digraph, count, total] => {
+                    let mut chars = digraph.chars();
+                    let digraph = match (chars.next(), chars.next(), chars.next()) {
+                        (Some(a), Some(b), None) => (a, b),
+                        _ => return Err(invalid()),
+                    };
+                    let stats = DigraphStats {
+                        count: count.parse().map_err(|_| invalid())?,
+                        total: Duration::from_micros(total.parse().map_err(|_| invalid())?),
+                    };
+                    let total_stats = history.digraphs.entry(digraph).or_default();
+                    total_stats.count += stats.count;
+                    total_stats.total += stats.total;
+                }
+                ["digraph" | "partial-digraph", ..] => return Err(invalid()),
+                ["key" | "partial-key", letter, count, errors] => {
+                    let mut chars = letter.chars();
+                    let letter = match (chars.next(), chars.next()) {
+                        (Some(letter), None) => letter,
+                        _ => return Err(invalid()),
+                    };
+                    let stats = KeyStats {
+                        count: count.parse().map_err(|_| invalid())?,
+                        errors: errors.parse().map_err(|_| invalid())?,
+                    };
+                    let total_stats = history.keys.entry(letter).or_default();
+                    total_stats.count += stats.count;
+                    total_stats.errors += stats.errors;
+                }
+                ["key" | "partial-key", ..] => return Err(invalid()),
+                ["bigram" | "partial-bigram", bigram, count, errors] => {
+                    let mut chars = bigram.chars();
+                    let bigram = match (chars.next(), chars.next(), chars.next()) {
+                        (Some(a), Some(b), None) => (a, b),
+                        _ => return Err(invalid()),
+                    };
+                    let stats = KeyStats {
+                        count: count.parse().map_err(|_| invalid())?,
+                        errors: errors.parse().map_err(|_| invalid())?,
+                    };
+                    let total_stats = history.bigrams.entry(bigram).or_default();
+                    total_stats.count += stats.count;
+                    total_stats.errors += stats.errors;
+                }
+                ["bigram" | "partial-bigram", ..] => return Err(invalid()),
+                ["best", text_name, wpm, progress] => {
+                    let progress = progress
+                        .split(',')
+                        .filter(|ms| !ms.is_empty())
+                        .map(|ms| ms.parse().map(Duration::from_millis))
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| invalid())?;
+                    let best_run = BestRun {
+                        wpm: wpm.parse().map_err(|_| invalid())?,
+                        progress,
+                    };
+                    history.best_runs.insert(text_name.to_string(), best_run);
+                }
+                ["best", ..] => return Err(invalid()),
+                // empty lines and records added by newer versions
+                _ => {}
+            }
+        }
+
+        Ok(history)
+    }
+}
+
+impl History {
+    /// Writes the digraph, key and bigram records, with kinds starting
+    /// with the given prefix.
+    fn fmt_stats(&self, f: &mut std::fmt::Formatter<'_>, prefix: &str) -> std::fmt::Result {
+        let mut digraphs: Vec<_> = self.digraphs.iter().collect();
+        // stable order so that the file does not change needlessly
+        digraphs.sort_by_key(|(&digraph, _)| digraph);
+        for ((a, b), stats) in digraphs {
+            writeln!(
+                f,
+                "{}digraph\t{}{}\t{}\t{}",
+                prefix,
+                a,
+                b,
+                stats.count,
+                stats.total.as_micros()
+            )?;
+        }
+
+        let mut keys: Vec<_> = self.keys.iter().collect();
+        keys.sort_by_key(|(&letter, _)| letter);
+        for (letter, stats) in keys {
+            writeln!(
+                f,
+                "{}key\t{}\t{}\t{}",
+                prefix, letter, stats.count, stats.errors
+            )?;
+        }
+
+        let mut bigrams: Vec<_> = self.bigrams.iter().collect();
+        bigrams.sort_by_key(|(&bigram, _)| bigram);
+        for ((a, b), stats) in bigrams {
+            writeln!(
+                f,
+                "{}bigram\t{}{}\t{}\t{}",
+                prefix, a, b, stats.count, stats.errors
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for History {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_stats(f, "")?;
+
+        let mut best_runs: Vec<_> = self.best_runs.iter().collect();
+        best_runs.sort_by_key(|(text_name, _)| text_name.as_str());
+        for (text_name, best_run) in best_runs {
+            let progress: Vec<String> = best_run
+                .progress
+                .iter()
+                .map(|at| at.as_millis().to_string())
+                .collect();
+            writeln!(
+                f,
+                "best\t{}\t{}\t{}",
+                text_name,
+                best_run.wpm,
+                progress.join(",")
+            )?;
+        }
+
+        if let Some(partial) = &self.partial {
+            partial.fmt_stats(f, "partial-")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(count: u64, total_ms: u64) -> DigraphStats {
+        DigraphStats {
+            count,
+            total: Duration::from_millis(total_ms),
+        }
+    }
+
+    #[test]
+    fn save_and_load() {
+        let mut history = History::default();
+        history.digraphs.insert(('t', 'h'), stats(3, 300));
+        history.digraphs.insert(('é', 'a'), stats(1, 250));
+        history.keys.insert(
+            'e',
+            KeyStats {
+                count: 120,
+                errors: 4,
+            },
+        );
+        history.bigrams.insert(
+            ('t', 'h'),
+            KeyStats {
+                count: 40,
+                errors: 2,
+            },
+        );
+        history.best_runs.insert(
+            "top250".to_string(),
+            BestRun {
+                wpm: 62.5,
+                progress: vec![Duration::from_millis(0), Duration::from_millis(180)],
+            },
+        );
+
+        let path = env::temp_dir()
+            .join(format!("toipe-history-{}", std::process::id()))
+            .join("history");
+        history.save(&path).unwrap();
+        let loaded = History::load(&path).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert_eq!(loaded.digraphs, history.digraphs);
+        assert_eq!(loaded.keys, history.keys);
+        assert_eq!(loaded.bigrams, history.bigrams);
+        assert_eq!(loaded.best_runs, history.best_runs);
+        assert!(History::load(&path).unwrap().digraphs.is_empty());
+    }
+
+    #[test]
+    fn partial_stats() {
+        let mut history = History::default();
+        history.digraphs.insert(('t', 'h'), stats(3, 300));
+        let mut partial = History::default();
+        partial.digraphs.insert(('t', 'h'), stats(1, 50));
+        partial.keys.insert(
+            'e',
+            KeyStats {
+                count: 2,
+                errors: 1,
+            },
+        );
+        history.partial = Some(Box::new(partial));
+
+        let saved = history.to_string();
+        assert!(
+            saved.contains("partial-digraph\tth\t1\t50000\n"),
+            "{}",
+            saved
+        );
+
+        // added to the other stats when loaded, like after a crash
+        let loaded = History::parse(&saved).unwrap();
+        assert_eq!(loaded.digraphs[&('t', 'h')], stats(4, 350));
+        assert_eq!(loaded.keys[&'e'].errors, 1);
+        assert!(loaded.partial.is_none());
+    }
+
+    #[test]
+    fn invalid_records() {
+        assert!(History::parse("digraph\tth\t3\n").is_err());
+        assert!(History::parse("digraph\tthe\t3\t300\n").is_err());
+        assert!(History::parse("digraph\tth\tmany\t300\n").is_err());
+        assert!(History::parse("key\tee\t10\t1\n").is_err());
+        assert!(History::parse("key\te\t10\n").is_err());
+        assert!(History::parse("bigram\tt\t10\t1\n").is_err());
+        assert!(History::parse("best\ttop250\tfast\t0,100\n").is_err());
+        assert!(History::parse("best\ttop250\t62.5\t0,1s\n").is_err());
+        assert!(History::parse("\nsomething\telse\n").is_ok());
+    }
+
+    #[test]
+    fn slowest_digraphs() {
+        let mut history = History::default();
+        history.digraphs.insert(('t', 'h'), stats(10, 1000));
+        history.digraphs.insert(('q', 'z'), stats(1, 900));
+        history.digraphs.insert(('e', 'r'), stats(5, 1000));
+        history.digraphs.insert(('a', 'b'), stats(4, 800));
+        history.digraphs.insert(('i', 'n'), stats(20, 1000));
+
+        assert_eq!(
+            history.slowest_digraphs(3, 2),
+            vec![
+                (('a', 'b'), stats(4, 800)),
+                (('e', 'r'), stats(5, 1000)),
+                (('t', 'h'), stats(10, 1000)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,279 @@
+//! Reading key presses for the typing test.
+//!
+//! See [`KeyEvents`].
+
+use std::io::{self, Read};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Instant;
+
+use termion::event::Key;
+use termion::input::TermRead;
+
+/// A keyboard shortcut and what it does, for listing the shortcuts of
+/// each screen.
+#[derive(Copy, Clone, Debug)]
+pub struct Keybinding {
+    /// keys to press, like `ctrl-r`
+    pub keys: &'static str,
+    /// what pressing the keys does
+    pub action: &'static str,
+    /// what pressing the keys does in a word or two, if the shortcut is
+    /// shown in the hints of its screen, like `restart`
+    pub hint: Option<&'static str>,
+}
+
+/// Shortcuts while the text of a test is previewed, before it starts.
+pub const PREVIEW_KEYBINDINGS: &[Keybinding] = &[
+    Keybinding {
+        keys: "enter",
+        action: "start the test",
+        hint: Some("begin"),
+    },
+    Keybinding {
+        keys: "ctrl-r",
+        action: "restart with new words",
+        hint: None,
+    },
+    Keybinding {
+        keys: "ctrl-p",
+        action: "restart with the same words",
+        hint: None,
+    },
+    Keybinding {
+        keys: "ctrl-c",
+        action: "quit",
+        hint: Some("quit"),
+    },
+    Keybinding {
+        keys: "?",
+        action: "show keyboard shortcuts",
+        hint: None,
+    },
+];
+
+/// Shortcuts during a test.
+///
+/// `?` is typed like any other char during a test, so the shortcuts are
+/// listed from the preview and the results instead.
+pub const TEST_KEYBINDINGS: &[Keybinding] = &[
+    Keybinding {
+        keys: "ctrl-r",
+        action: "restart with new words",
+        hint: Some("restart"),
+    },
+    Keybinding {
+        keys: "ctrl-p",
+        action: "restart with the same words",
+        hint: None,
+    },
+    Keybinding {
+        keys: "ctrl-w",
+        action: "delete last word",
+        hint: None,
+    },
+    Keybinding {
+        keys: "esc",
+        action: "pause, any key resumes",
+        hint: Some("pause"),
+    },
+    Keybinding {
+        keys: "ctrl-z",
+        action: "pause and suspend toipe",
+        hint: None,
+    },
+    Keybinding {
+        keys: "ctrl-c",
+        action: "quit, or finish an endless test",
+        hint: Some("quit"),
+    },
+];
+
+/// Shortcuts on the results screen.
+pub const RESULTS_KEYBINDINGS: &[Keybinding] = &[
+    Keybinding {
+        keys: "d",
+        action: "switch between summary and details",
+        hint: Some("see details"),
+    },
+    Keybinding {
+        keys: "m",
+        action: "switch between summary and mistakes",
+        hint: Some("see mistakes"),
+    },
+    Keybinding {
+        keys: "c",
+        action: "copy the summary",
+        hint: Some("copy"),
+    },
+    Keybinding {
+        keys: "ctrl-r",
+        action: "start a test with new words",
+        hint: Some("restart"),
+    },
+    Keybinding {
+        keys: "ctrl-p",
+        action: "start a test with the same words",
+        hint: Some("repeat"),
+    },
+    Keybinding {
+        keys: "ctrl-c",
+        action: "quit",
+        hint: Some("quit"),
+    },
+    Keybinding {
+        keys: "?",
+        action: "show keyboard shortcuts",
+        hint: None,
+    },
+];
+
+/// Shortcuts on the summary shown after the last of the rounds typed
+/// with `--rounds`.
+pub const ROUNDS_SUMMARY_KEYBINDINGS: &[Keybinding] = &[
+    Keybinding {
+        keys: "ctrl-r",
+        action: "start the rounds again",
+        hint: Some("start again"),
+    },
+    Keybinding {
+        keys: "ctrl-c",
+        action: "quit",
+        hint: Some("quit"),
+    },
+    Keybinding {
+        keys: "?",
+        action: "show keyboard shortcuts",
+        hint: None,
+    },
+];
+
+/// Stream of key presses.
+///
+/// Keys are read on a background thread so that waiting for a key can
+/// be given a deadline, for things that need to happen even when no key
+/// is pressed (like ending a timed test).
+///
+/// Keys are queued as they are read, so none are lost when they come in
+/// faster than they are handled, like when the screen is slow to
+/// update. Each key keeps the time at which it was read (see
+/// [`KeyEvents::last_key_at`]).
+pub struct KeyEvents {
+    receiver: Receiver<(Instant, io::Result<Key>)>,
+    last_key_at: Option<Instant>,
+}
+
+impl KeyEvents {
+    /// Reads keys from the given reader.
+    ///
+    /// The reader should be in raw mode for keys to be received as soon
+    /// as they are pressed.
+    pub fn from_reader<R>(reader: R) -> Self
+    where
+        R: Read + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            for key in reader.keys() {
+                if sender.send((Instant::now(), key)).is_err() {
+                    // nobody is listening anymore
+                    break;
+                }
+            }
+        });
+
+        Self {
+            receiver,
+            last_key_at: None,
+        }
+    }
+
+    /// Takes keys from the iterator, like keys pressed by another
+    /// program.
+    ///
+    /// Keys are taken on a background thread as soon as the iterator
+    /// returns them, so an iterator that waits before returning each key
+    /// types like a person would.
+    pub fn from_keys<I>(keys: I) -> Self
+    where
+        I: IntoIterator<Item = Key>,
+        I::IntoIter: Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        let keys = keys.into_iter();
+
+        thread::spawn(move || {
+            for key in keys {
+                if sender.send((Instant::now(), Ok(key))).is_err() {
+                    // nobody is listening anymore
+                    break;
+                }
+            }
+        });
+
+        Self {
+            receiver,
+            last_key_at: None,
+        }
+    }
+
+    /// Reads keys from the standard input.
+    pub fn from_stdin() -> Self {
+        Self::from_reader(io::stdin())
+    }
+
+    /// Reads keys from the terminal (`/dev/tty`), for when the
+    /// standard input is used for something else, like a pipe.
+    pub fn from_tty() -> io::Result<Self> {
+        Ok(Self::from_reader(termion::get_tty()?))
+    }
+
+    /// Waits for the next key press.
+    ///
+    /// Returns an error if there are no more keys to be read.
+    pub fn next_key(&mut self) -> io::Result<Key> {
+        match self.receiver.recv() {
+            Ok((read_at, key)) => {
+                self.last_key_at = Some(read_at);
+                key
+            }
+            Err(_) => Err(Self::closed_error()),
+        }
+    }
+
+    /// Waits for the next key press until the deadline, if any.
+    ///
+    /// Returns `None` if the deadline passes before a key is pressed and
+    /// an error if there are no more keys to be read.
+    pub fn next_key_before(&mut self, deadline: Option<Instant>) -> io::Result<Option<Key>> {
+        let deadline = match deadline {
+            Some(deadline) => deadline,
+            None => return self.next_key().map(Some),
+        };
+
+        match self
+            .receiver
+            .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        {
+            Ok((read_at, key)) => {
+                self.last_key_at = Some(read_at);
+                key.map(Some)
+            }
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(Self::closed_error()),
+        }
+    }
+
+    /// Time at which the key returned last was read, which is before it
+    /// was returned if keys came in faster than they were handled.
+    ///
+    /// `None` if no key was returned yet.
+    pub fn last_key_at(&self) -> Option<Instant> {
+        self.last_key_at
+    }
+
+    fn closed_error() -> io::Error {
+        io::Error::new(io::ErrorKind::UnexpectedEof, "no more keys to read")
+    }
+}
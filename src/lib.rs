@@ -7,36 +7,225 @@
 //! library. This documentation describes the API and algorithms used
 //! internally.
 //!
-//! See [`RawWordSelector`] if you're looking for the word selection
+//! See [`session::ToipeSession`] for running typing tests from another
+//! application, and `examples/embed.rs` for one that uses its own words
+//! and keys. The terminal is given back when the typing test is
+//! dropped, or with [`Toipe::shutdown`] to handle errors in doing so.
+//!
+//! [`FixedWordSelector`](textgen::FixedWordSelector) gives the same
+//! words in every run, for reproducible tests.
+//!
+//! See [`RawWordSelector`](textgen::RawWordSelector) if you're looking for the word selection
 //! algorithm.
+//!
+//! # Features
+//!
+//! - `tui`: the terminal UI ([`tui`], [`theme`]) and key input
+//!   ([`input`]).
+//! - `cli` (default): the typing test ([`Toipe`]), its configuration
+//!   ([`config`]), [`session`], [`commands`], practice reminders
+//!   ([`remind`]) and certificates ([`certificate`]). Enables `tui`.
+//! - `history` (default): stats of past tests and personal bests kept
+//!   in a file ([`history`]), for `--adaptive`, `--ghost`, `--pb-pace`,
+//!   `drill --auto` and `toipe stats`.
+//! - `export` (default): exporting results with `--export` and checking
+//!   them with `toipe verify` ([`export`]). Enables `cli`.
+//! - `structured-wordlist` (default): reading word lists in JSON and
+//!   CSV (see [`StructuredWordList`](textgen::StructuredWordList)).
+//! - `net` (default): publishing the state of the test on a UNIX socket
+//!   with `--status-socket` ([`status`]). Enables `cli`.
+//! - `clipboard`: copying the results to the system clipboard (see
+//!   [`clipboard`]). Enables `cli`.
+//! - `epub`: typing EPUB books with `--book` (see the `epub` module).
+//! - `pdf`: typing the text of PDF files with `--book` (see the `pdf`
+//!   module).
+//!
+//! The `toipe` binary needs `cli`, `history`, `export` and `net`.
+//!
+//! With no features, only word selection ([`textgen`], [`wordlists`]),
+//! romaji input ([`romaji`]) and results ([`results`], [`difficulty`])
+//! are available, without depending on `clap`, `termion` or `serde`.
+//! Word selection still depends on `regex` for cleaning up texts:
+//!
+//! ```toml
+//! toipe = { version = "0.5", default-features = false }
+//! ```
 
+#[cfg(feature = "cli")]
+pub mod certificate;
+#[cfg(feature = "cli")]
+pub mod clipboard;
+#[cfg(feature = "cli")]
+pub mod commands;
+#[cfg(feature = "cli")]
 pub mod config;
+pub mod debuglog;
+pub mod difficulty;
+pub mod end_condition;
+#[cfg(feature = "epub")]
+pub mod epub;
+#[cfg(feature = "export")]
+pub mod export;
+#[cfg(feature = "history")]
+pub mod history;
+#[cfg(feature = "tui")]
+pub mod input;
+#[cfg(feature = "cli")]
+mod page;
+#[cfg(feature = "pdf")]
+pub mod pdf;
+#[cfg(feature = "cli")]
+pub mod remind;
 pub mod results;
+pub mod rng;
+pub mod romaji;
+#[cfg(feature = "cli")]
+pub mod session;
+#[cfg(feature = "net")]
+pub mod status;
+#[cfg(feature = "tui")]
+pub mod term_caps;
 pub mod textgen;
+#[cfg(feature = "tui")]
+pub mod theme;
+#[cfg(feature = "tui")]
 pub mod tui;
 pub mod wordlists;
 
-use std::io::StdinLock;
-use std::path::PathBuf;
-use std::time::Instant;
+#[cfg(feature = "cli")]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "cli")]
+use clipboard::Clipboard;
+#[cfg(feature = "cli")]
+use config::{ToipeCommand, ToipeConfig};
+#[cfg(feature = "cli")]
+use end_condition::EndCondition;
+#[cfg(all(feature = "cli", feature = "history"))]
+use history::History;
+#[cfg(feature = "cli")]
+use input::{
+    KeyEvents, Keybinding, PREVIEW_KEYBINDINGS, RESULTS_KEYBINDINGS, ROUNDS_SUMMARY_KEYBINDINGS,
+    TEST_KEYBINDINGS,
+};
+#[cfg(feature = "cli")]
+use page::TestStatus;
+#[cfg(feature = "cli")]
+use results::{CharRecord, ToipeResults};
+#[cfg(feature = "net")]
+use status::{Status, StatusServer};
+#[cfg(feature = "cli")]
+use termion::event::Key;
+#[cfg(feature = "cli")]
+use textgen::{WordSelector, WordStream};
+#[cfg(feature = "cli")]
+use theme::Theme;
+#[cfg(feature = "cli")]
+use tui::{TerminalTooSmall, Text, ToipeTui};
+
+#[cfg(feature = "net")]
+use anyhow::Context;
+#[cfg(feature = "cli")]
+use anyhow::Result;
+
+/// How often the size of the terminal is checked while waiting for it to
+/// be resized.
+#[cfg(feature = "cli")]
+const RESIZE_CHECK_INTERVAL: Duration = Duration::from_millis(100);
 
-use config::ToipeConfig;
-use results::ToipeResults;
-use termion::input::Keys;
-use termion::{color, event::Key, input::TermRead};
-use textgen::{PunctuatedWordSelector, RawWordSelector, WordSelector};
-use tui::{Text, ToipeTui};
-use wordlists::{BuiltInWordlist, OS_WORDLIST_PATH};
+/// How fast the deadline of a time attack speeds up, in words per
+/// minute for every second (see [`ToipeConfig::time_attack`]).
+#[cfg(feature = "cli")]
+const TIME_ATTACK_WPM_PER_SEC: f64 = 0.5;
 
-use anyhow::{Context, Result};
+/// Number of buckets in the histogram of the typing rhythm.
+#[cfg(feature = "cli")]
+const RHYTHM_BUCKETS: usize = 8;
+
+/// Length of the longest bar in the histogram of the typing rhythm.
+#[cfg(feature = "cli")]
+const RHYTHM_BAR_LEN: usize = 30;
 
 /// Typing test terminal UI and logic.
+#[cfg(feature = "cli")]
 pub struct Toipe {
     tui: ToipeTui,
     text: Vec<Text>,
     words: Vec<String>,
-    word_selector: Box<dyn WordSelector>,
+    /// words split into pages that each fit on the screen
+    pages: Vec<Vec<String>>,
+    current_page: usize,
+    word_stream: WordStream,
     config: ToipeConfig,
+    /// whether the next test has the same words as the last one
+    repeat: bool,
+    /// results of the rounds completed so far, when tests are typed in
+    /// rounds (see [`ToipeConfig::rounds`])
+    rounds: Vec<ToipeResults>,
+    /// how the pace caret moves, if it is displayed
+    pace: Option<Pace>,
+    /// speed of the best test of the text, if being on pace for it is
+    /// shown (see [`ToipeConfig::pb_pace`])
+    personal_best: Option<f64>,
+    clipboard: Clipboard,
+    /// results that were to be copied when there was no clipboard, to
+    /// be printed instead (see [`Toipe::take_uncopied_results`])
+    uncopied_results: Vec<String>,
+    /// conditions that end a test before the end of its text
+    end_conditions: Vec<Box<dyn EndCondition>>,
+    /// serves the status of the user to other programs, if a socket was
+    /// given (see [`ToipeConfig::status_socket`])
+    #[cfg(feature = "net")]
+    status_server: Option<StatusServer>,
+    /// how often to invoke the checkpoint hook, and the hook, if any
+    /// (see [`Toipe::on_checkpoint`])
+    checkpoint: Option<(Duration, CheckpointHook)>,
+}
+
+/// Function invoked with the results of a test so far while it is typed.
+#[cfg(feature = "cli")]
+type CheckpointHook = Box<dyn FnMut(&ToipeResults) -> Result<()>>;
+
+/// How the pace caret moves (see [`ToipeConfig::pace`] and
+/// [`ToipeConfig::ghost`]).
+#[cfg(feature = "cli")]
+enum Pace {
+    // moves at a fixed number of chars per second
+    Speed(f64),
+    // moves like in a past test, see `ToipeResults::progress`
+    Ghost(Vec<Duration>),
+    // starts at a number of chars per second and speeds up by a number of
+    // chars per second every second
+    Accelerating(f64, f64),
+}
+
+#[cfg(feature = "cli")]
+impl Pace {
+    /// Number of chars passed by the caret at the given time since the
+    /// start of the test.
+    fn chars_at(&self, elapsed: Duration) -> usize {
+        match self {
+            Pace::Speed(chars_per_sec) => (elapsed.as_secs_f64() * chars_per_sec) as usize,
+            Pace::Ghost(progress) => progress.partition_point(|&at| at <= elapsed),
+            Pace::Accelerating(chars_per_sec, acceleration) => {
+                let secs = elapsed.as_secs_f64();
+                (secs * chars_per_sec + acceleration * secs * secs / 2.0) as usize
+            }
+        }
+    }
+
+    /// Speed of the caret in words per minute at the given time since the
+    /// start of the test, if it moves at a known speed.
+    fn wpm_at(&self, elapsed: Duration) -> Option<f64> {
+        let chars_per_sec = match self {
+            Pace::Speed(chars_per_sec) => *chars_per_sec,
+            Pace::Ghost(_) => return None,
+            Pace::Accelerating(chars_per_sec, acceleration) => {
+                chars_per_sec + acceleration * elapsed.as_secs_f64()
+            }
+        };
+        Some(chars_per_sec * 60.0 / 5.0)
+    }
 }
 
 /// Represents any error caught in Toipe.
@@ -68,56 +257,249 @@ impl std::fmt::Display for ToipeError {
 
 impl std::error::Error for ToipeError {}
 
-impl<'a> Toipe {
+/// Pages of the results screen (see [`Toipe::display_results`]).
+#[cfg(feature = "cli")]
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum ResultsPage {
+    Summary,
+    Details,
+    Mistakes,
+}
+
+/// Text to display for a char on the mistakes page of the results.
+///
+/// Chars are shaded by how often they were typed wrong: more intense for
+/// more mistakes, and underlined if they were still wrong at the end.
+#[cfg(feature = "cli")]
+fn mistake_text(record: &CharRecord, theme: &Theme) -> Text {
+    let text = Text::from(match record.expected {
+        Some(c) if c.is_whitespace() => ' ',
+        Some(c) => c,
+        None => '_',
+    });
+    match record {
+        CharRecord { expected: None, .. } => theme.dim(text),
+        CharRecord {
+            correct: Some(false),
+            ..
+        } => text.with_underline().with_color(theme.incorrect),
+        CharRecord { errors: 0, .. } => text.with_color(theme.correct),
+        CharRecord { errors: 1, .. } => text.with_color(theme.incorrect).with_faint(),
+        _ => text.with_color(theme.incorrect),
+    }
+}
+
+/// Speed and progress (see [`ToipeResults::progress`]) of the best test
+/// of the text in the history, if any. Without it, the test goes on as
+/// if there were none.
+#[cfg(all(feature = "cli", feature = "history"))]
+fn best_run(config: &ToipeConfig) -> Option<(f64, Vec<Duration>)> {
+    match config.history_path().and_then(|path| History::load(&path)) {
+        Ok(mut history) => history
+            .best_runs
+            .remove(&config.best_run_key())
+            .map(|best_run| (best_run.wpm, best_run.progress)),
+        Err(error) => {
+            log::warn!("could not read the personal best: {:#}", error);
+            None
+        }
+    }
+}
+
+/// Returns `None`, since the best tests are kept in the history, which
+/// needs the `history` feature.
+#[cfg(all(feature = "cli", not(feature = "history")))]
+fn best_run(_config: &ToipeConfig) -> Option<(f64, Vec<Duration>)> {
+    log::warn!("could not read the personal best, toipe was built without the `history` feature");
+    None
+}
+
+/// Hints for the keyboard shortcuts of a screen, like `ctrl-r to
+/// restart, ctrl-c to quit `.
+///
+/// `hint` gives the hint of each shortcut in the current mode, or `None`
+/// to leave the shortcut out. It is usually [`Keybinding::hint`], but
+/// can differ, like for a shortcut that does something else in some
+/// modes.
+#[cfg(feature = "cli")]
+fn keybinding_hints(
+    keybindings: &[Keybinding],
+    theme: &Theme,
+    hint: impl Fn(&Keybinding) -> Option<&'static str>,
+) -> Vec<Text> {
+    let hints: Vec<(&str, &str)> = keybindings
+        .iter()
+        .filter_map(|keybinding| Some((keybinding.keys, hint(keybinding)?)))
+        .collect();
+    let mut line = Vec::new();
+    for (i, (keys, hint)) in hints.iter().enumerate() {
+        let separator = if i + 1 == hints.len() { " " } else { ", " };
+        line.extend([
+            Text::from(*keys).with_color(theme.accent),
+            theme.dim(Text::from(format!(" to {}{}", hint, separator))),
+        ]);
+    }
+    line
+}
+
+/// Hints displayed during a test: whether the test is on pace for the
+/// personal best, the live accuracy if it is enabled (see
+/// [`ToipeConfig::live_accuracy`]), the round if there are rounds and
+/// the keyboard shortcuts.
+///
+/// `rounds_done` is the number of rounds completed before this test.
+/// `accuracy` is between 0 and 1.
+#[cfg(feature = "cli")]
+fn test_hud_line(
+    config: &ToipeConfig,
+    theme: &Theme,
+    rounds_done: usize,
+    accuracy: f64,
+    on_pb_pace: bool,
+    endless: bool,
+) -> Vec<Text> {
+    let mut line = Vec::new();
+    if on_pb_pace {
+        line.extend([
+            Text::from("PB pace").with_color(theme.correct),
+            theme.dim(Text::from(", ")),
+        ]);
+    }
+    if config.live_accuracy {
+        let good = config
+            .accuracy_thresholds
+            .iter()
+            .cloned()
+            .fold(0.0, f64::max);
+        let fair = config
+            .accuracy_thresholds
+            .iter()
+            .cloned()
+            .fold(100.0, f64::min);
+        let percentage = accuracy * 100.0;
+        let color = if percentage >= good {
+            theme.correct
+        } else if percentage >= fair {
+            theme.warning
+        } else {
+            theme.incorrect
+        };
+        line.extend([
+            theme.dim(Text::from("accuracy ")),
+            // same width for any accuracy so that the hints do not move
+            Text::from(format!("{:>5.1}%", percentage)).with_color(color),
+            theme.dim(Text::from(", ")),
+        ]);
+    }
+    if let Some(rounds) = config.rounds {
+        line.push(theme.dim(Text::from(format!(
+            "round {} of {}, ",
+            rounds_done + 1,
+            rounds
+        ))));
+    }
+    line.extend(keybinding_hints(
+        TEST_KEYBINDINGS,
+        theme,
+        |keybinding| match keybinding.keys {
+            "ctrl-c" if endless => Some("finish"),
+            _ => keybinding.hint,
+        },
+    ));
+    line
+}
+
+#[cfg(feature = "cli")]
+impl Toipe {
     /// Initializes a new typing test on the standard output.
     ///
     /// See [`ToipeConfig`] for configuration options.
     ///
-    /// Initializes the word selector.
+    /// Initializes the word selector using [`ToipeConfig::word_selector`].
     /// Also invokes [`Toipe::restart()`].
     pub fn new(config: ToipeConfig) -> Result<Self> {
-        let mut word_selector: Box<dyn WordSelector> = if let Some(wordlist_path) =
-            config.wordlist_file.clone()
-        {
-            Box::new(
-                RawWordSelector::from_path(PathBuf::from(wordlist_path.clone())).with_context(
-                    || format!("reading the word list from given path '{}'", wordlist_path),
-                )?,
-            )
-        } else if let Some(word_list) = config.wordlist.contents() {
-            Box::new(
-                RawWordSelector::from_string(word_list.to_string()).with_context(|| {
-                    format!("reading the built-in word list {:?}", config.wordlist)
-                })?,
-            )
-        } else if let BuiltInWordlist::OS = config.wordlist {
-            Box::new(
-                RawWordSelector::from_path(PathBuf::from(OS_WORDLIST_PATH)).with_context(|| {
-                    format!(
-                        "reading from the OS wordlist at path '{}'. See https://en.wikipedia.org/wiki/Words_(Unix) for more info on this file and how it can be installed.",
-                        OS_WORDLIST_PATH
-                    )
-                })?,
-            )
-        } else {
-            // this should never happen!
-            // TODO: somehow enforce this at compile time?
-            return Err(ToipeError::from("Undefined word list or path.".to_owned()))?;
-        };
+        Self::with_tui(config, ToipeTui::new())
+    }
 
-        if config.punctuation {
-            word_selector = Box::new(PunctuatedWordSelector::from_word_selector(
-                word_selector,
-                0.15,
-            ))
+    /// Initializes a new typing test on the given terminal UI, like a
+    /// [`FakeTerminal`](tui::FakeTerminal).
+    ///
+    /// See [`Toipe::new`].
+    pub fn with_tui(mut config: ToipeConfig, tui: ToipeTui) -> Result<Self> {
+        let tui = tui.with_max_columns(config.max_columns);
+        // words longer than a line do not fit on the screen
+        config
+            .max_word_length
+            .get_or_insert(tui.max_word_len()?.min(textgen::DEFAULT_MAX_WORD_LEN));
+        // a certificate is for a timed test of the standard length
+        if let Some(ToipeCommand::Certify { duration, .. }) = &config.command {
+            config.time = Some(*duration);
+        }
+        let word_stream = config.word_stream()?;
+        let end_conditions = config.end_conditions();
+        #[cfg(feature = "net")]
+        let status_server = config
+            .status_socket
+            .clone()
+            .map(|path| {
+                StatusServer::bind(path.clone())
+                    .with_context(|| format!("creating the status socket at {}", path.display()))
+            })
+            .transpose()?;
+        #[cfg(feature = "net")]
+        if let Some(status_server) = &status_server {
+            status_server.publish(&Status::Idle);
+        }
+        #[cfg(not(feature = "net"))]
+        if config.status_socket.is_some() {
+            return Err(ToipeError::from(
+                "cannot create the status socket, toipe was built without the `net` feature"
+                    .to_owned(),
+            ))?;
         }
+        let best_run = || best_run(&config);
+        let pace = if let Some(wpm) = config.pace {
+            Some(Pace::Speed(wpm * 5.0 / 60.0))
+        } else if let Some(wpm) = config.time_attack {
+            Some(Pace::Accelerating(
+                wpm * 5.0 / 60.0,
+                TIME_ATTACK_WPM_PER_SEC * 5.0 / 60.0,
+            ))
+        } else if config.ghost {
+            best_run().map(|(_, progress)| Pace::Ghost(progress))
+        } else {
+            None
+        };
+        let personal_best = if config.pb_pace {
+            best_run().map(|(wpm, _)| wpm)
+        } else {
+            None
+        };
 
         let mut toipe = Toipe {
-            tui: ToipeTui::new(),
+            // the theme is detected in raw mode and before keys are read
+            tui: tui
+                .with_ascii(config.ascii)
+                .with_hud(config.hud)
+                .with_wrap_markers(config.is_passage())
+                .with_streamer(config.streamer)
+                .with_theme(config.theme.theme()),
             words: Vec::new(),
+            pages: Vec::new(),
+            current_page: 0,
             text: Vec::new(),
-            word_selector,
+            word_stream,
             config,
+            repeat: false,
+            rounds: Vec::new(),
+            pace,
+            personal_best,
+            clipboard: Clipboard::default(),
+            uncopied_results: Vec::new(),
+            end_conditions,
+            #[cfg(feature = "net")]
+            status_server,
+            checkpoint: None,
         };
 
         toipe.restart()?;
@@ -125,32 +507,331 @@ impl<'a> Toipe {
         Ok(toipe)
     }
 
+    /// Adds a condition that ends each test before the end of its text,
+    /// along with those given in the config (see
+    /// [`ToipeConfig::end_conditions`]).
+    ///
+    /// ```no_run
+    /// use clap::StructOpt;
+    /// use toipe::config::ToipeConfig;
+    /// use toipe::end_condition::WordCount;
+    /// use toipe::Toipe;
+    ///
+    /// let toipe = Toipe::new(ToipeConfig::parse())?.with_end_condition(WordCount(10));
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn with_end_condition<C>(mut self, condition: C) -> Self
+    where
+        C: EndCondition + 'static,
+    {
+        self.end_conditions.push(Box::new(condition));
+        self
+    }
+
+    /// Whether each test keeps getting more words until it is ended (see
+    /// [`EndCondition::is_endless`]).
+    fn is_endless(&self) -> bool {
+        self.end_conditions
+            .iter()
+            .any(|condition| condition.is_endless())
+    }
+
+    /// Adds a function to be invoked with the results of each test so
+    /// far while it is typed, like for saving them in case toipe stops
+    /// before a long test ends.
+    ///
+    /// The function is invoked after a key is pressed, if at least
+    /// `interval` has passed since the test started or since it was last
+    /// invoked. The results are not
+    /// [`completed`](ToipeResults::completed) and have the chars typed
+    /// so far, including those of the page being typed. An error
+    /// returned by the function is logged, and the test goes on.
+    pub fn on_checkpoint<F>(mut self, interval: Duration, hook: F) -> Self
+    where
+        F: FnMut(&ToipeResults) -> Result<()> + 'static,
+    {
+        self.checkpoint = Some((interval, Box::new(hook)));
+        self
+    }
+
+    /// Selects the words of the tests using the given selector instead
+    /// of the one made from the config (see
+    /// [`ToipeConfig::word_selector`]), and displays a test with its
+    /// words.
+    ///
+    /// ```no_run
+    /// use toipe::config::ToipeConfig;
+    /// use toipe::textgen::RawWordSelector;
+    /// use toipe::Toipe;
+    ///
+    /// let words = RawWordSelector::from_string("apple\nbanana\ncherry\n".to_string())?;
+    /// let toipe = Toipe::new(ToipeConfig::default())?.with_word_selector(Box::new(words))?;
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn with_word_selector(mut self, word_selector: Box<dyn WordSelector>) -> Result<Self> {
+        self.word_stream = self.config.word_stream_from(word_selector);
+        self.repeat = false;
+        self.restart()?;
+        Ok(self)
+    }
+
     /// Make the terminal ready for the next typing test.
     ///
     /// Clears the screen, generates new words and displays them on the
     /// UI.
+    ///
+    /// If the words do not fit on the screen, they are split into pages
+    /// and the first page is displayed.
+    ///
+    /// If the last test was ended with ctrl-p, its words are displayed
+    /// again instead.
     pub fn restart(&mut self) -> Result<()> {
+        self.next_words()?;
+        self.show_test()
+    }
+
+    /// Same as [`Toipe::restart`], but if the terminal is too small for
+    /// the test, asks for it to be resized and waits until it is.
+    ///
+    /// Returns `false` if the user quit while waiting.
+    pub fn restart_when_fits(&mut self, keys: &mut KeyEvents) -> Result<bool> {
+        self.next_words()?;
+
+        let mut size = self.tui.size()?;
+        loop {
+            match self
+                .show_test()
+                .map_err(|err| err.downcast::<TerminalTooSmall>())
+            {
+                Ok(()) => return Ok(true),
+                Err(Ok(too_small)) => self.display_resize_prompt(&too_small.0.msg)?,
+                Err(Err(err)) => return Err(err),
+            }
+
+            // try again once the terminal is resized
+            while self.tui.size()? == size {
+                let deadline = Instant::now() + RESIZE_CHECK_INTERVAL;
+                if let Some(Key::Ctrl('c')) = keys.next_key_before(Some(deadline))? {
+                    return Ok(false);
+                }
+            }
+            size = self.tui.size()?;
+        }
+    }
+
+    /// Generates the words for the next test, unless the last test is
+    /// to be repeated.
+    fn next_words(&mut self) -> Result<()> {
+        if !std::mem::take(&mut self.repeat) {
+            self.word_stream.start_text();
+            self.words = self.word_stream.next_text()?;
+        }
+        Ok(())
+    }
+
+    /// Clears the screen and displays the first page of the words.
+    fn show_test(&mut self) -> Result<()> {
         self.tui.reset_screen()?;
+        self.display_test_hud()?;
 
-        self.words = self.word_selector.new_words(self.config.num_words)?;
+        self.pages = self.paginate(&self.words)?;
+        self.current_page = 0;
 
-        self.tui.display_lines_bottom(&[&[
-            Text::from("ctrl-r").with_color(color::Blue),
-            Text::from(" to restart, ").with_faint(),
-            Text::from("ctrl-c").with_color(color::Blue),
-            Text::from(" to quit ").with_faint(),
-        ]])?;
+        self.show_words()?;
 
+        Ok(())
+    }
+
+    /// Displays the keyboard shortcuts available during the test.
+    fn display_test_hud(&mut self) -> Result<()> {
+        let theme = self.tui.theme();
+        // nothing is wrong before the first key
+        let line = test_hud_line(
+            &self.config,
+            &theme,
+            self.rounds.len(),
+            1.0,
+            false,
+            self.is_endless(),
+        );
+        self.tui.display_hud(&[line])?;
+        Ok(())
+    }
+
+    /// Displays the page before the test starts, until Enter is pressed
+    /// (see [`ToipeConfig::preview`]).
+    ///
+    /// Returns the key that ended the preview if it was not Enter, like
+    /// ctrl-c.
+    fn preview(&mut self, keys: &mut KeyEvents) -> Result<Option<Key>> {
+        let theme = self.tui.theme();
+        self.tui.reset_screen()?;
+        self.tui.display_hud(&[keybinding_hints(
+            PREVIEW_KEYBINDINGS,
+            &theme,
+            |keybinding| keybinding.hint,
+        )])?;
         self.show_words()?;
+        self.tui.hide_cursor()?;
 
+        let key = loop {
+            match keys.next_key()? {
+                Key::Char('\n') => break None,
+                key @ Key::Ctrl('c' | 'r' | 'p') => break Some(key),
+                Key::Char('?') => {
+                    self.display_keybindings(
+                        &[
+                            ("Before the test", PREVIEW_KEYBINDINGS),
+                            ("During the test", TEST_KEYBINDINGS),
+                        ],
+                        keys,
+                        None,
+                    )?;
+                }
+                _ => {}
+            }
+        };
+
+        self.tui.reset_screen()?;
+        self.display_test_hud()?;
+        self.show_words()?;
+        self.tui.show_cursor()?;
+
+        Ok(key)
+    }
+
+    /// Displays the keyboard shortcuts of each section over the screen,
+    /// until any key is pressed or the deadline passes.
+    ///
+    /// Returns `false` if the deadline passed. The key that was pressed
+    /// does nothing else.
+    fn display_keybindings(
+        &mut self,
+        sections: &[(&str, &[Keybinding])],
+        keys: &mut KeyEvents,
+        deadline: Option<Instant>,
+    ) -> Result<bool> {
+        let theme = self.tui.theme();
+        let keys_width = sections
+            .iter()
+            .flat_map(|(_, keybindings)| keybindings.iter())
+            .map(|keybinding| keybinding.keys.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let mut lines: Vec<Vec<Text>> = Vec::new();
+        for (heading, keybindings) in sections {
+            if !lines.is_empty() {
+                lines.push(vec![]);
+            }
+            lines.push(vec![Text::from(*heading)]);
+            for keybinding in keybindings.iter() {
+                lines.push(vec![
+                    Text::from(format!("{:>width$}", keybinding.keys, width = keys_width))
+                        .with_color(theme.accent),
+                    theme.dim(Text::from(format!("  {}", keybinding.action))),
+                ]);
+            }
+        }
+
+        self.display_notice(&lines, keys, deadline)
+    }
+
+    /// Displays lines over the screen until any key is pressed or the
+    /// deadline passes.
+    ///
+    /// Returns `false` if the deadline passed. The key that was pressed
+    /// does nothing else.
+    fn display_notice(
+        &mut self,
+        lines: &[Vec<Text>],
+        keys: &mut KeyEvents,
+        deadline: Option<Instant>,
+    ) -> Result<bool> {
+        self.tui.display_overlay(lines)?;
+        let key = keys.next_key_before(deadline)?;
+        self.tui.hide_overlay()?;
+
+        Ok(key.is_some())
+    }
+
+    /// Displays why the terminal is too small and asks for it to be
+    /// resized.
+    fn display_resize_prompt(&mut self, problem: &str) -> Result<()> {
+        let theme = self.tui.theme();
+        self.tui.reset_screen()?;
+
+        self.tui.display_lines(&[
+            vec![Text::from(problem)],
+            vec![
+                theme.dim(Text::from("Resize the terminal to continue, ")),
+                Text::from("ctrl-c").with_color(theme.accent),
+                theme.dim(Text::from(" to quit")),
+            ],
+        ])?;
+
+        Ok(())
+    }
+
+    /// Takes more words from the stream and adds them as pages after the
+    /// existing ones.
+    fn add_pages(&mut self) -> Result<()> {
+        let words = self.word_stream.next_text()?;
+        let pages = self.paginate(&words)?;
+        self.pages.extend(pages);
+        self.words.extend(words);
         Ok(())
     }
 
+    /// Splits words, or lines in code mode, into pages that fit on the
+    /// screen. Pages of passages, like books, end at the end of a
+    /// sentence where they can.
+    fn paginate(&self, words: &[String]) -> Result<Vec<Vec<String>>> {
+        if self.config.code.is_some() {
+            self.tui.paginate_code(words)
+        } else if self.config.is_passage() {
+            self.tui.paginate_sentences(words)
+        } else {
+            self.tui.paginate_words(words)
+        }
+    }
+
     fn show_words(&mut self) -> Result<()> {
-        self.text = self.tui.display_words(&self.words)?;
+        let page = &self.pages[self.current_page];
+        self.text = if self.config.code.is_some() {
+            self.tui.display_code(page)?
+        } else {
+            self.tui.display_words(page)?
+        };
         Ok(())
     }
 
+    /// Counts down `secs` seconds above the text before the test starts
+    /// (see [`ToipeConfig::countdown`]).
+    ///
+    /// Returns the key that ended the countdown early, like ctrl-c. Other
+    /// keys pressed during the countdown are ignored.
+    fn countdown(&mut self, keys: &mut KeyEvents, secs: u64) -> Result<Option<Key>> {
+        self.tui.hide_cursor()?;
+
+        let mut key = None;
+        'countdown: for secs_left in (1..=secs).rev() {
+            self.tui.display_countdown(Some(secs_left))?;
+            let deadline = Instant::now() + Duration::from_secs(1);
+            while let Some(next_key) = keys.next_key_before(Some(deadline))? {
+                if matches!(next_key, Key::Ctrl('c' | 'r' | 'p')) {
+                    key = Some(next_key);
+                    break 'countdown;
+                }
+            }
+        }
+
+        self.tui.display_countdown(None)?;
+        self.tui.show_cursor()?;
+
+        Ok(key)
+    }
+
     /// Start typing test by monitoring input keys.
     ///
     /// Must only be invoked after [`Toipe::restart()`].
@@ -158,204 +839,600 @@ impl<'a> Toipe {
     /// If the test completes successfully, returns a boolean indicating
     /// whether the user wants to do another test and the
     /// [`ToipeResults`] for this test.
-    pub fn test(&mut self, stdin: StdinLock<'a>) -> Result<(bool, ToipeResults)> {
-        let mut input = Vec::<char>::new();
-        let original_text = self
-            .text
-            .iter()
-            .fold(Vec::<char>::new(), |mut chars, text| {
-                chars.extend(text.text().chars());
-                chars
-            });
-        let mut num_errors = 0;
-        let mut num_chars_typed = 0;
-
-        enum TestStatus {
-            // last key press did not quit/restart - more keys to be entered
-            NotDone,
-            // last letter was typed
-            Done,
-            // user wants to quit test
-            Quit,
-            // user wants to restart test
-            Restart,
-        }
+    ///
+    /// If the text was split into multiple pages, each page is displayed
+    /// once the previous one is completed. The results of all pages are
+    /// combined into one [`ToipeResults`].
+    ///
+    /// In a timed test (see [`ToipeConfig::time`]), new pages of words
+    /// keep being added until the time is up. In an endless test (see
+    /// [`ToipeConfig::endless`]), they keep being added until ctrl-c is
+    /// pressed, which ends the test.
+    pub fn test(&mut self, keys: &mut KeyEvents) -> Result<(bool, ToipeResults)> {
+        let mut page_results: Vec<ToipeResults> = Vec::new();
+        let mut started_at = None;
+        let mut last_checkpoint_at = None;
 
-        impl TestStatus {
-            fn to_process_more_keys(&self) -> bool {
-                matches!(self, TestStatus::NotDone)
-            }
+        let status = loop {
+            let (status, results) = self.test_page(
+                keys,
+                &mut started_at,
+                &mut last_checkpoint_at,
+                &page_results,
+            )?;
+            page_results.push(results);
 
-            fn to_display_results(&self) -> bool {
-                matches!(self, TestStatus::Done)
+            if !matches!(status, TestStatus::Done) {
+                break status;
             }
 
-            fn to_restart(&self) -> bool {
-                matches!(self, TestStatus::Restart)
+            if self.current_page + 1 >= self.pages.len() {
+                let timed = self
+                    .end_conditions
+                    .iter()
+                    .any(|condition| condition.time_limit().is_some());
+                if !timed && !self.is_endless() {
+                    break status;
+                }
+                self.add_pages()?;
             }
-        }
 
-        let mut process_key = |key: Key| -> Result<TestStatus> {
-            match key {
-                Key::Ctrl('c') => {
-                    return Ok(TestStatus::Quit);
-                }
-                Key::Ctrl('r') => {
-                    return Ok(TestStatus::Restart);
-                }
-                Key::Ctrl('w') => {
-                    // delete last word
-                    while !matches!(input.last(), Some(' ') | None) {
-                        if input.pop().is_some() {
-                            self.tui.replace_text(
-                                Text::from(original_text[input.len()]).with_faint(),
-                            )?;
-                        }
-                    }
+            self.current_page += 1;
+            self.show_words()?;
+        };
+
+        let results = ToipeResults::from_pages(page_results);
+        self.repeat = matches!(status, TestStatus::Repeat);
+        if let Some((p50, p95)) = results.render_latency() {
+            log::debug!(
+                "screen updated after a key press in {}us (median), {}us (95th percentile)",
+                p50.as_micros(),
+                p95.as_micros()
+            );
+        }
+        if results.completed {
+            self.word_stream.selector_mut().record_results(&results);
+        }
+        #[cfg(feature = "net")]
+        if let Some(status_server) = &self.status_server {
+            status_server.publish(&if results.completed {
+                Status::Done {
+                    wpm: results.wpm(),
+                    accuracy: results.accuracy() * 100.0,
                 }
-                Key::Char(c) => {
-                    input.push(c);
+            } else {
+                Status::Idle
+            });
+        }
 
-                    if input.len() >= original_text.len() {
-                        return Ok(TestStatus::Done);
-                    }
+        let to_restart = if status.to_display_results() && self.config.rounds.is_some() {
+            self.end_round(results.clone(), keys)?
+        } else if status.to_display_results() {
+            self.display_results(results.clone(), keys)?
+        } else if matches!(status, TestStatus::Aborted) {
+            self.display_aborted(&results, keys)?
+        } else {
+            status.to_restart()
+        };
 
-                    num_chars_typed += 1;
+        Ok((to_restart, results))
+    }
 
-                    if original_text[input.len() - 1] == c {
-                        self.tui
-                            .display_raw_text(&Text::from(c).with_color(color::LightGreen))?;
-                        self.tui.move_to_next_char()?;
+    /// Drops the first `count` keys pressed in a test (see
+    /// [`ToipeConfig::ignore_first_keys`]).
+    ///
+    /// Returns the key that ended this early, like ctrl-c, which is not
+    /// dropped.
+    fn ignore_first_keys(&mut self, keys: &mut KeyEvents, count: usize) -> Result<Option<Key>> {
+        for _ in 0..count {
+            match keys.next_key()? {
+                key @ Key::Ctrl('c' | 'r' | 'p') => return Ok(Some(key)),
+                key => log::debug!("ignored key {:?} at the start of the test", key),
+            }
+        }
+        Ok(None)
+    }
+
+    fn display_results(&mut self, results: ToipeResults, keys: &mut KeyEvents) -> Result<bool> {
+        let summary_lines = self.results_summary_lines(&results);
+        let details_lines = self.results_details_lines(&results);
+        let mistakes_lines = self.results_mistakes_lines(&results)?;
+        let mut page = ResultsPage::Summary;
+        self.display_results_page(&summary_lines, page)?;
+
+        let deadline = self
+            .config
+            .auto_restart
+            .map(|secs| Instant::now() + Duration::from_secs(secs));
+
+        // TODO: make this a bit more general
+        // perhaps use a `known_keys_pressed` flag?
+        let mut to_restart: Option<bool> = None;
+        while to_restart.is_none() {
+            match keys.next_key_before(deadline)? {
+                // press ctrl + 'r' to restart
+                Some(Key::Ctrl('r')) => to_restart = Some(true),
+                // press ctrl + 'p' to restart with the same words
+                Some(Key::Ctrl('p')) => {
+                    self.repeat = true;
+                    to_restart = Some(true);
+                }
+                // press ctrl + 'c' to quit
+                Some(Key::Ctrl('c')) => to_restart = Some(false),
+                // press 'd' to switch between the summary and details,
+                // and 'm' between the summary and mistakes
+                Some(Key::Char(c @ ('d' | 'm'))) => {
+                    let other_page = if c == 'd' {
+                        ResultsPage::Details
                     } else {
-                        self.tui.display_raw_text(
-                            &Text::from(original_text[input.len() - 1])
-                                .with_underline()
-                                .with_color(color::Red),
-                        )?;
-                        self.tui.move_to_next_char()?;
-                        num_errors += 1;
+                        ResultsPage::Mistakes
+                    };
+                    page = if page == other_page {
+                        ResultsPage::Summary
+                    } else {
+                        other_page
+                    };
+                    let lines = match page {
+                        ResultsPage::Summary => &summary_lines,
+                        ResultsPage::Details => &details_lines,
+                        ResultsPage::Mistakes => &mistakes_lines,
+                    };
+                    self.display_results_page(lines, page)?;
+                }
+                // press 'c' to copy the summary
+                Some(Key::Char('c')) => {
+                    let dismissed = self.copy_results(&summary_lines, keys, deadline)?;
+                    if !dismissed {
+                        to_restart = Some(true);
                     }
                 }
-                Key::Backspace | Key::Ctrl('h') => {
-                    if input.pop().is_some() {
-                        self.tui
-                            .replace_text(Text::from(original_text[input.len()]).with_faint())?;
+                // press '?' to list the keyboard shortcuts
+                Some(Key::Char('?')) => {
+                    let dismissed = self.display_keybindings(
+                        &[("Keyboard shortcuts", RESULTS_KEYBINDINGS)],
+                        keys,
+                        deadline,
+                    )?;
+                    if !dismissed {
+                        to_restart = Some(true);
                     }
                 }
+                // start the next test once the auto restart time is up
+                None => to_restart = Some(true),
                 _ => {}
             }
+        }
+
+        self.tui.show_cursor()?;
 
-            self.tui.flush()?;
+        Ok(to_restart.unwrap_or(false))
+    }
 
-            Ok(TestStatus::NotDone)
+    /// Copies the lines of the results to the clipboard, or keeps them
+    /// to be printed later if there is no clipboard, and says which
+    /// happened until any key is pressed.
+    ///
+    /// Returns `false` if the deadline passed, like
+    /// [`Toipe::display_notice`].
+    fn copy_results(
+        &mut self,
+        lines: &[Vec<Text>],
+        keys: &mut KeyEvents,
+        deadline: Option<Instant>,
+    ) -> Result<bool> {
+        let text: String = lines
+            .iter()
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let line: String = line.iter().map(|text| text.text().as_str()).collect();
+                line + "\n"
+            })
+            .collect();
+
+        let notice = if self.clipboard.copy(&text) {
+            "Copied the results to the clipboard"
+        } else {
+            self.uncopied_results.push(text);
+            "No clipboard available, the results will be printed when you quit"
         };
+        self.display_notice(&[vec![Text::from(notice)]], keys, deadline)
+    }
+
+    /// The config of the tests, with the options that are filled in
+    /// when the test starts, like the length of the longest word.
+    pub fn config(&self) -> &ToipeConfig {
+        &self.config
+    }
 
-        let mut keys = stdin.keys();
+    /// Results that were to be copied when there was no clipboard,
+    /// removing them.
+    ///
+    /// Each is a few lines of text, to be printed after the terminal is
+    /// restored.
+    pub fn take_uncopied_results(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.uncopied_results)
+    }
+
+    /// Resets the terminal and gives it back to the shell, returning any
+    /// error in doing so (see [`ToipeTui::close`]).
+    ///
+    /// The terminal is otherwise reset when this is dropped, ignoring
+    /// errors. No tests should be run after this.
+    pub fn shutdown(&mut self) -> Result<()> {
+        self.tui.close()
+    }
 
-        // read first key
-        let key = keys.next().unwrap()?;
-        // start the timer
-        let started_at = Instant::now();
-        // process first key
-        let mut status = process_key(key)?;
+    /// Displays why the test was ended early for low accuracy (see
+    /// [`ToipeConfig::min_accuracy`]).
+    ///
+    /// Returns whether another test should be started, like
+    /// [`Toipe::display_results`].
+    fn display_aborted(&mut self, results: &ToipeResults, keys: &mut KeyEvents) -> Result<bool> {
+        let theme = self.tui.theme();
+        self.tui.reset_screen()?;
+        self.tui.display_lines(&[
+            vec![Text::from(format!(
+                "Test ended: accuracy dropped to {:.1}%, below the minimum of {}%",
+                results.accuracy() * 100.0,
+                self.config.min_accuracy.unwrap_or_default(),
+            ))
+            .with_color(theme.incorrect)],
+            vec![],
+            vec![theme.dim(Text::from("Slow down and type each character carefully"))],
+        ])?;
+        // only the shortcuts that leave the results apply here
+        self.tui.display_hud(&[keybinding_hints(
+            RESULTS_KEYBINDINGS,
+            &theme,
+            |keybinding| {
+                keybinding
+                    .hint
+                    .filter(|_| keybinding.keys.starts_with("ctrl-"))
+            },
+        )])?;
+        self.tui.hide_cursor()?;
 
-        if status.to_process_more_keys() {
-            for key in &mut keys {
-                status = process_key(key?)?;
-                if !status.to_process_more_keys() {
-                    break;
+        let to_restart = loop {
+            match keys.next_key()? {
+                Key::Ctrl('r') => break true,
+                Key::Ctrl('p') => {
+                    self.repeat = true;
+                    break true;
                 }
+                Key::Ctrl('c') => break false,
+                _ => {}
             }
-        }
+        };
 
-        // stop the timer
-        let ended_at = Instant::now();
+        self.tui.show_cursor()?;
 
-        let (final_chars_typed_correctly, final_uncorrected_errors) =
-            input.iter().zip(original_text.iter()).fold(
-                (0, 0),
-                |(total_chars_typed_correctly, total_uncorrected_errors),
-                 (typed_char, orig_char)| {
-                    if typed_char == orig_char {
-                        (total_chars_typed_correctly + 1, total_uncorrected_errors)
-                    } else {
-                        (total_chars_typed_correctly, total_uncorrected_errors + 1)
-                    }
-                },
-            );
+        Ok(to_restart)
+    }
 
-        let results = ToipeResults {
-            total_words: self.words.len(),
-            total_chars_typed: num_chars_typed,
-            total_chars_in_text: input.len(),
-            total_char_errors: num_errors,
-            final_chars_typed_correctly,
-            final_uncorrected_errors,
-            started_at,
-            ended_at,
-        };
+    /// Records the results of a round. The next round starts right away,
+    /// and after the last round the summary of all the rounds is
+    /// displayed.
+    ///
+    /// Returns whether another test should be started, like
+    /// [`Toipe::display_results`].
+    fn end_round(&mut self, results: ToipeResults, keys: &mut KeyEvents) -> Result<bool> {
+        self.rounds.push(results);
+        let num_rounds = self.config.rounds.map_or(1, |rounds| rounds.get());
+        if self.rounds.len() < num_rounds {
+            return Ok(true);
+        }
 
-        let to_restart = if status.to_display_results() {
-            self.display_results(results.clone(), keys)?
-        } else {
-            status.to_restart()
+        let rounds = std::mem::take(&mut self.rounds);
+        let theme = self.tui.theme();
+        self.tui.reset_screen()?;
+        self.tui
+            .display_lines(&self.rounds_summary_lines(&rounds))?;
+        self.tui.display_hud(&[keybinding_hints(
+            ROUNDS_SUMMARY_KEYBINDINGS,
+            &theme,
+            |keybinding| keybinding.hint,
+        )])?;
+        // no cursor on results page
+        self.tui.hide_cursor()?;
+
+        let to_restart = loop {
+            match keys.next_key()? {
+                Key::Ctrl('r') => break true,
+                Key::Ctrl('c') => break false,
+                Key::Char('?') => {
+                    self.display_keybindings(
+                        &[("Keyboard shortcuts", ROUNDS_SUMMARY_KEYBINDINGS)],
+                        keys,
+                        None,
+                    )?;
+                }
+                _ => {}
+            }
         };
 
-        Ok((to_restart, results))
+        self.tui.show_cursor()?;
+
+        Ok(to_restart)
     }
 
-    fn display_results(
-        &mut self,
-        results: ToipeResults,
-        mut keys: Keys<StdinLock>,
-    ) -> Result<bool> {
+    /// Lines of the summary of all the rounds, like the average speed
+    /// and the speed of each round.
+    fn rounds_summary_lines(&self, rounds: &[ToipeResults]) -> Vec<Vec<Text>> {
+        let theme = self.tui.theme();
+        let wpms: Vec<f64> = rounds.iter().map(|results| results.wpm()).collect();
+        let average_wpm = wpms.iter().sum::<f64>() / wpms.len().max(1) as f64;
+        let best = (0..wpms.len()).max_by(|&a, &b| wpms[a].total_cmp(&wpms[b]));
+        let worst = (0..wpms.len()).min_by(|&a, &b| wpms[a].total_cmp(&wpms[b]));
+
+        let mut lines = vec![
+            vec![Text::from(format!(
+                "Average of {} rounds: {:.1} wpm, {:.1}% accuracy",
+                rounds.len(),
+                average_wpm,
+                ToipeResults::combined_accuracy(rounds) * 100.0,
+            ))
+            .with_color(theme.accent)],
+            vec![],
+        ];
+        for (i, results) in rounds.iter().enumerate() {
+            let mark = if Some(i) == best {
+                Text::from(" (best)").with_color(theme.correct)
+            } else if Some(i) == worst {
+                Text::from(" (worst)").with_color(theme.incorrect)
+            } else {
+                Text::from("")
+            };
+            lines.push(vec![
+                theme.dim(Text::from(format!("Round {}: ", i + 1))),
+                Text::from(format!(
+                    "{:.1} wpm, {:.1}% accuracy",
+                    results.wpm(),
+                    results.accuracy() * 100.0
+                )),
+                mark,
+            ]);
+        }
+        lines
+    }
+
+    /// Displays lines of results along with the keyboard shortcuts.
+    fn display_results_page(&mut self, lines: &[Vec<Text>], page: ResultsPage) -> Result<()> {
+        let theme = self.tui.theme();
         self.tui.reset_screen()?;
+        self.tui.display_lines(lines)?;
+
+        let mut hints = Vec::new();
+        if let Some(auto_restart) = self.config.auto_restart {
+            hints.push(theme.dim(Text::from(format!("next test in {}s, ", auto_restart))));
+        }
+        // 'd' and 'm' go back to the summary from their pages
+        hints.extend(keybinding_hints(
+            RESULTS_KEYBINDINGS,
+            &theme,
+            |keybinding| match (keybinding.keys, page) {
+                ("d", ResultsPage::Details) | ("m", ResultsPage::Mistakes) => Some("see summary"),
+                _ => keybinding.hint,
+            },
+        ));
+        self.tui.display_hud(&[hints])?;
+        // no cursor on results page
+        self.tui.hide_cursor()?;
+
+        Ok(())
+    }
+
+    /// Lines of the details of the results, like the typing rhythm.
+    fn results_details_lines(&self, results: &ToipeResults) -> Vec<Vec<Text>> {
+        let theme = self.tui.theme();
+        let rhythm = match results.rhythm(RHYTHM_BUCKETS) {
+            Some(rhythm) => rhythm,
+            None => return vec![vec![Text::from("Not enough keys pressed to show details")]],
+        };
+
+        let mut lines = vec![
+            vec![Text::from("Time between key presses").with_color(theme.accent)],
+            vec![theme.dim(Text::from(format!(
+                "median: {}ms, 95th percentile: {}ms",
+                rhythm.p50.as_millis(),
+                rhythm.p95.as_millis(),
+            )))],
+            vec![],
+        ];
+        lines.extend(
+            rhythm
+                .plot(RHYTHM_BAR_LEN)
+                .into_iter()
+                .map(|line| vec![Text::from(line)]),
+        );
+        if let Some((p50, p95)) = results.render_latency() {
+            lines.extend([
+                vec![],
+                vec![Text::from("Time to update the screen after a key press")
+                    .with_color(theme.accent)],
+                vec![theme.dim(Text::from(format!(
+                    "median: {:.1}ms, 95th percentile: {:.1}ms",
+                    p50.as_secs_f64() * 1000.0,
+                    p95.as_secs_f64() * 1000.0,
+                )))],
+            ]);
+        }
+        lines
+    }
+
+    /// Lines of the text typed in the test, with each char shaded by how
+    /// often it was typed wrong (see [`mistake_text`]).
+    ///
+    /// Lines that do not fit the terminal are left out.
+    fn results_mistakes_lines(&self, results: &ToipeResults) -> Result<Vec<Vec<Text>>> {
+        let theme = self.tui.theme();
+        let records = results.char_records();
+        if records.is_empty() {
+            return Ok(vec![vec![Text::from("Nothing typed to show mistakes in")]]);
+        }
+
+        let mut lines = vec![
+            vec![Text::from("Mistakes in the text").with_color(theme.accent)],
+            vec![
+                Text::from("wrong once")
+                    .with_color(theme.incorrect)
+                    .with_faint(),
+                theme.dim(Text::from(", ")),
+                Text::from("wrong more than once").with_color(theme.incorrect),
+                theme.dim(Text::from(", ")),
+                Text::from("still wrong")
+                    .with_underline()
+                    .with_color(theme.incorrect),
+            ],
+            vec![],
+        ];
+        let header_len = lines.len();
 
-        self.tui.display_lines::<&[Text], _>(&[
-            &[Text::from(format!(
+        let max_width = self.tui.max_word_len()?;
+        let mut line: Vec<Text> = Vec::new();
+        for word in
+            records.split_inclusive(|record| record.expected.is_some_and(char::is_whitespace))
+        {
+            if !line.is_empty() && line.len() + word.len() > max_width {
+                lines.push(std::mem::take(&mut line));
+            }
+            line.extend(word.iter().map(|record| mistake_text(record, &theme)));
+        }
+        lines.push(line);
+
+        // leave room for the hints and a line saying how many are left out
+        let (_, terminal_height) = self.tui.size()?;
+        let max_lines = (terminal_height as usize)
+            .saturating_sub(4)
+            .max(header_len + 1);
+        if lines.len() > max_lines {
+            let left_out = lines.len() - (max_lines - 1);
+            lines.truncate(max_lines - 1);
+            lines.push(vec![
+                theme.dim(Text::from(format!("and {} more lines", left_out)))
+            ]);
+        }
+
+        Ok(lines)
+    }
+
+    /// Lines of the summary of the results, like speed and accuracy.
+    fn results_summary_lines(&self, results: &ToipeResults) -> Vec<Vec<Text>> {
+        let theme = self.tui.theme();
+
+        let summary = if let Some(time) = self.config.time {
+            format!(
+                "Typed {} words of {} in a {}s test",
+                results.total_words,
+                self.config.text_name(),
+                time,
+            )
+        } else {
+            format!(
                 "Took {}s for {} words of {}",
                 results.duration().as_secs(),
                 results.total_words,
                 self.config.text_name(),
-            ))],
-            &[
+            )
+        };
+
+        let mut lines: Vec<Vec<Text>> = vec![
+            vec![Text::from(summary)],
+            vec![
                 Text::from(format!("Accuracy: {:.1}%", results.accuracy() * 100.0))
-                    .with_color(color::Blue),
+                    .with_color(theme.accent),
             ],
-            &[Text::from(format!(
-                "Mistakes: {} out of {} characters",
-                results.total_char_errors, results.total_chars_in_text
-            ))],
-            &[
+            vec![Text::from(if results.total_number_errors > 0 {
+                format!(
+                    "Mistakes: {} out of {} characters ({} in numbers)",
+                    results.total_char_errors,
+                    results.total_chars_in_text,
+                    results.total_number_errors,
+                )
+            } else {
+                format!(
+                    "Mistakes: {} out of {} characters",
+                    results.total_char_errors, results.total_chars_in_text
+                )
+            })],
+            vec![
                 Text::from("Speed: "),
-                Text::from(format!("{:.1} wpm", results.wpm())).with_color(color::Green),
+                Text::from(format!("{:.1} wpm", results.wpm())).with_color(theme.speed),
                 Text::from(" (words per minute)"),
             ],
-        ])?;
-        self.tui.display_lines_bottom(&[&[
-            Text::from("ctrl-r").with_color(color::Blue),
-            Text::from(" to restart, ").with_faint(),
-            Text::from("ctrl-c").with_color(color::Blue),
-            Text::from(" to quit ").with_faint(),
-        ]])?;
-        // no cursor on results page
-        self.tui.hide_cursor()?;
-
-        // TODO: make this a bit more general
-        // perhaps use a `known_keys_pressed` flag?
-        let mut to_restart: Option<bool> = None;
-        while to_restart.is_none() {
-            match keys.next().unwrap()? {
-                // press ctrl + 'r' to restart
-                Key::Ctrl('r') => to_restart = Some(true),
-                // press ctrl + 'c' to quit
-                Key::Ctrl('c') => to_restart = Some(false),
-                _ => {}
+        ];
+        if let Some(wpm) = self
+            .pace
+            .as_ref()
+            .filter(|_| self.config.time_attack.is_some())
+            .and_then(|pace| pace.wpm_at(results.duration()))
+        {
+            lines.push(vec![
+                Text::from("Time attack: ").with_color(theme.accent),
+                Text::from("kept up with the deadline until "),
+                Text::from(format!("{:.1} wpm", wpm)).with_color(theme.speed),
+            ]);
+        }
+        if self.config.word_budget.is_some() {
+            lines.push(vec![Text::from(format!(
+                "Words failed: {} (ran out of time)",
+                results.failed_words
+            ))]);
+        }
+        if self.config.hardcore {
+            lines.push(vec![
+                Text::from("Hardcore").with_color(theme.incorrect),
+                Text::from(format!(
+                    ": {} corrections attempted",
+                    results.attempted_corrections
+                )),
+            ]);
+        }
+        if let Some(goal_wpm) = self.config.goal_wpm {
+            let difference = results.wpm() - goal_wpm;
+            lines.push(if difference >= 0.0 {
+                vec![Text::from(format!(
+                    "Goal of {} wpm beaten by {:.1} wpm",
+                    goal_wpm, difference
+                ))
+                .with_color(theme.correct)]
+            } else {
+                vec![Text::from(format!(
+                    "Goal of {} wpm missed by {:.1} wpm",
+                    goal_wpm, -difference
+                ))
+                .with_color(theme.incorrect)]
+            });
+        }
+        if results.pages.len() > 1 {
+            let page_speeds = results
+                .pages
+                .iter()
+                .map(|page| format!("{:.1}", page.wpm()))
+                .collect::<Vec<_>>()
+                .join(", ");
+            lines.push(vec![
+                theme.dim(Text::from(format!("Speed per page: {} wpm", page_speeds)))
+            ]);
+        }
+        if self.config.normalize {
+            let difficulty = results.text_difficulty(self.config.word_rarities().as_ref());
+            lines.push(vec![
+                Text::from("Normalized speed: "),
+                Text::from(format!("{:.1} wpm", results.normalized_wpm(difficulty)))
+                    .with_color(theme.speed),
+                Text::from(format!(" (text difficulty {:.2})", difficulty)),
+            ]);
+        }
+        if let Some(warmup) = self.config.warmup {
+            if let Some(settled_wpm) = results.settled_wpm(Duration::from_secs(warmup)) {
+                lines.push(vec![
+                    Text::from("Settled speed: "),
+                    Text::from(format!("{:.1} wpm", settled_wpm)).with_color(theme.speed),
+                    Text::from(format!(" (excluding first {}s)", warmup)),
+                ]);
             }
         }
 
-        self.tui.show_cursor()?;
-
-        Ok(to_restart.unwrap_or(false))
+        lines
     }
 }
@@ -1,24 +1,166 @@
 use anyhow::Result;
 use clap::StructOpt;
 
-use std::io::stdin;
-use toipe::config::ToipeConfig;
-use toipe::Toipe;
+use std::cell::RefCell;
+use std::io::stdout;
+use std::process::ExitCode;
+use std::rc::Rc;
+use std::time::Duration;
+use toipe::config::{ToipeCommand, ToipeConfig};
+use toipe::export::{self, ExportedResults};
+use toipe::history::History;
+use toipe::input::KeyEvents;
+use toipe::session::ToipeSession;
+use toipe::{commands, debuglog};
 
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
     let config = ToipeConfig::parse();
 
-    let mut toipe = Toipe::new(config)?;
+    if let Some(debug_log) = &config.debug_log {
+        debuglog::init(debug_log)?;
+    }
+
+    // drills and certifications are typing tests
+    let command = config
+        .command
+        .as_ref()
+        .filter(|command| !command.is_typing_test());
+    if let Some(command) = command {
+        commands::run(command, &config, &mut stdout().lock())?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if config.print_text {
+        commands::print_text(&config, &mut stdout().lock())?;
+        return Ok(ExitCode::SUCCESS);
+    }
 
-    let stdin = stdin();
+    let certify = match &config.command {
+        Some(ToipeCommand::Certify { name, output, .. }) => Some((name.clone(), output.clone())),
+        _ => None,
+    };
+    let export_path = config.export.clone();
+    let print_summary = !config.no_summary;
+    let goal_wpm = config.goal_wpm;
+    // stats are not saved if there is nowhere to save them
+    let history_path = config.history_path().ok();
+    let best_run_key = config.best_run_key();
+    let text_name = config.text_name();
+    // the best normalized speed is kept across texts
+    let normalized = config
+        .normalize
+        .then(|| (config.normalized_run_key(), config.word_rarities()));
+    // problems with the history are printed once the terminal is restored
+    let mut warnings = Vec::new();
+    // tests are not recorded if the history cannot be read, so that it
+    // is not overwritten
+    let history = history_path
+        .as_deref()
+        .and_then(|path| match History::load(path) {
+            Ok(history) => Some(history),
+            Err(error) => {
+                warnings.push(format!("{:#}, so the tests are not recorded", error));
+                None
+            }
+        });
+    // shared with the hooks that record tests as they are typed
+    let history = Rc::new(RefCell::new(history));
+    let checkpoint_interval = Duration::from_secs(config.checkpoint_interval);
+    // stdin is taken by the text in this case
+    let keys_from_tty = config.stdin_text.is_some();
 
-    loop {
-        let stdin = stdin.lock();
-        if let Ok((true, _)) = toipe.test(stdin) {
-            toipe.restart()?;
-        } else {
-            break;
+    let mut session = ToipeSession::new(config)?.on_test_end({
+        let history = Rc::clone(&history);
+        move |results| {
+            if let Some(history) = history.borrow_mut().as_mut() {
+                history.record(results);
+                history.record_best_run(&best_run_key, results);
+                if let Some((normalized_run_key, rarities)) = &normalized {
+                    let difficulty = results.text_difficulty(rarities.as_ref());
+                    history.record_best_normalized_run(normalized_run_key, results, difficulty);
+                }
+            }
+            Ok(())
         }
+    });
+    if let Some(history_path) = history_path
+        .clone()
+        .filter(|_| !checkpoint_interval.is_zero())
+    {
+        let history = Rc::clone(&history);
+        session = session.on_checkpoint(checkpoint_interval, move |results| {
+            if let Some(history) = history.borrow_mut().as_mut() {
+                history.record_partial(results);
+                history.save(&history_path)?;
+            }
+            Ok(())
+        });
+    }
+    // keys are read only after the session has set up the terminal
+    let mut keys = if keys_from_tty {
+        KeyEvents::from_tty()?
+    } else {
+        KeyEvents::from_stdin()
+    };
+    session.run(&mut keys)?;
+    let summary = session.summary();
+    // the goal has to be beaten in the last test
+    let goal_beaten = goal_wpm.map(|goal_wpm| {
+        session
+            .results()
+            .last()
+            .is_some_and(|results| results.wpm() >= goal_wpm)
+    });
+    let last_results = session.results().last().cloned();
+    // only the text of the first test can be selected again from the seed
+    let first_results = session.results().first().cloned();
+    if let (Some(path), Some(results)) = (&export_path, &first_results) {
+        let args = export::args_without_export(std::env::args().skip(1));
+        ExportedResults::new(results, session.toipe().config(), &args).save(path)?;
+    }
+    // saved before restoring the terminal, which can fail
+    if let (Some(history), Some(history_path)) = (history.borrow_mut().as_mut(), history_path) {
+        // tests that were not completed are not recorded
+        history.partial = None;
+        if let Err(error) = history.save(&history_path) {
+            warnings.push(format!("{:#}", error));
+        }
+    }
+    let uncopied_results = session.toipe().take_uncopied_results();
+    // restore the terminal before printing
+    session.toipe().shutdown()?;
+    drop(session);
+
+    for warning in warnings {
+        eprintln!("toipe: warning: {}", warning);
     }
-    Ok(())
+
+    for results in uncopied_results {
+        print!("{}", results);
+    }
+
+    if let Some(summary) = summary.filter(|_| print_summary) {
+        let goal = match goal_beaten {
+            Some(true) => ", goal beaten",
+            Some(false) => ", goal missed",
+            None => "",
+        };
+        println!("toipe: {}{}", summary, goal);
+    }
+
+    if let Some((name, output)) = certify {
+        commands::certify(
+            &name,
+            &text_name,
+            last_results.as_ref(),
+            output.as_deref(),
+            &mut stdout().lock(),
+        )?;
+    }
+
+    if goal_beaten == Some(false) {
+        return Ok(ExitCode::FAILURE);
+    }
+
+    Ok(ExitCode::SUCCESS)
 }
@@ -0,0 +1,1423 @@
+//! Typing a page of a test.
+//!
+//! [`Toipe::test_page`] reads the keys and the other events of the page
+//! being typed (see [`TestEvent`]) until it is done, and hands each of
+//! them to a method. What the methods share, like the chars typed so far
+//! and the deadlines, is kept in a [`PageState`].
+
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use termion::event::Key;
+
+use crate::end_condition::{EndCondition, TestProgress};
+use crate::input::KeyEvents;
+use crate::results::{Keystroke, ToipeResults};
+use crate::romaji::{self, RomajiInput};
+#[cfg(feature = "net")]
+use crate::status::Status;
+use crate::textgen;
+use crate::theme::Theme;
+use crate::tui::{HasLength, Text};
+use crate::{test_hud_line, Toipe};
+
+/// How often the pace caret is moved when no key is pressed (see
+/// [`ToipeConfig::pace`](crate::config::ToipeConfig::pace)).
+const PACE_REDRAW_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How often the test checks whether the program was stopped from
+/// outside and continued, to pause the test and redisplay it.
+const CONTINUE_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Stands for the chars of a word that were skipped because its time
+/// budget ran out (see
+/// [`ToipeConfig::word_budget`](crate::config::ToipeConfig::word_budget)),
+/// in place of the chars typed.
+const SKIPPED_CHAR: char = '\0';
+
+/// Number of chars to type before the live speed is compared to the
+/// personal best (see [`ToipeConfig::pb_pace`](crate::config::ToipeConfig::pb_pace)),
+/// since it varies a lot at the start of a test.
+const MIN_PB_PACE_CHARS: usize = 20;
+
+/// Fraction of the personal best that the live speed has to reach to be
+/// on PB pace.
+const PB_PACE_MARGIN: f64 = 0.03;
+
+/// Something that happened during a typing test.
+enum TestEvent {
+    // a key was pressed
+    Key(Key),
+    // the pace caret reached the char at this index of the page
+    Pace(usize),
+    // a key was pressed while the test was paused
+    Resume,
+    // the test was paused with ctrl-z and the program is to be suspended
+    Suspend,
+    // the time budget of the word being typed ran out
+    WordTimeUp,
+    // the end conditions are to be checked without a key being pressed
+    CheckEnd,
+    // whether the program was stopped from outside and continued is to
+    // be checked
+    CheckContinued,
+    // the results so far are to be passed to the checkpoint hook
+    Checkpoint,
+}
+
+/// State of a typing test after processing a key.
+pub(crate) enum TestStatus {
+    // last key press did not quit/restart - more keys to be entered
+    NotDone,
+    // last letter was typed
+    Done,
+    // time limit of the test was reached
+    TimeUp,
+    // user wants to quit test
+    Quit,
+    // user wants to restart test
+    Restart,
+    // user wants to restart test with the same words
+    Repeat,
+    // user ended an endless test
+    Stop,
+    // user paused the test - more keys to be entered after resuming
+    Paused,
+    // user paused the test with ctrl-z to suspend the program
+    Suspended,
+    // the program was stopped from outside and continued, and the test
+    // is paused since it was stopped
+    Stopped,
+    // accuracy dropped below the minimum
+    Aborted,
+    // an end condition was met
+    Ended,
+}
+
+impl TestStatus {
+    pub(crate) fn to_process_more_keys(&self) -> bool {
+        matches!(
+            self,
+            TestStatus::NotDone | TestStatus::Paused | TestStatus::Suspended | TestStatus::Stopped
+        )
+    }
+
+    pub(crate) fn to_display_results(&self) -> bool {
+        matches!(
+            self,
+            TestStatus::Done | TestStatus::TimeUp | TestStatus::Stop | TestStatus::Ended
+        )
+    }
+
+    pub(crate) fn to_restart(&self) -> bool {
+        matches!(self, TestStatus::Restart | TestStatus::Repeat)
+    }
+}
+
+/// Counts kept while a page of a test is typed.
+#[derive(Clone, Copy, Default)]
+struct PageCounts {
+    chars_typed: usize,
+    errors: usize,
+    number_errors: usize,
+    attempted_corrections: usize,
+    failed_words: usize,
+}
+
+/// Totals of the pages of a test typed before the current one.
+#[derive(Clone, Copy, Default)]
+struct PreviousPages {
+    /// chars of their text
+    chars: usize,
+    /// chars typed, including the ones deleted later
+    chars_typed: usize,
+    errors: usize,
+    chars_typed_correctly: usize,
+    uncorrected_errors: usize,
+    words: usize,
+}
+
+impl PreviousPages {
+    fn new(results: &[ToipeResults], words: usize) -> Self {
+        let sum = |count: fn(&ToipeResults) -> usize| results.iter().map(count).sum();
+        Self {
+            chars: sum(|r| r.total_chars_in_text),
+            chars_typed: sum(|r| r.total_chars_typed),
+            errors: sum(|r| r.total_char_errors),
+            chars_typed_correctly: sum(|r| r.final_chars_typed_correctly),
+            uncorrected_errors: sum(|r| r.final_uncorrected_errors),
+            words,
+        }
+    }
+}
+
+/// State of the page of a test being typed.
+struct PageState {
+    /// text as displayed, with a space at the end of each line
+    displayed_text: Vec<char>,
+    /// text to be typed, where lines of code end with a newline
+    original_text: Vec<char>,
+    input: Vec<char>,
+    counts: PageCounts,
+    keystrokes: Vec<Keystroke>,
+    before: PreviousPages,
+    /// chars before this were skipped or come before skipped chars, and
+    /// cannot be deleted
+    num_locked_chars: usize,
+    /// time at which the event being processed happened, which is
+    /// earlier than now for a key if keys came in faster than they were
+    /// displayed
+    event_at: Instant,
+    /// time of the first key on this page
+    started_at: Instant,
+    /// start of the test, moved later by the time that it was paused
+    test_started_at: Instant,
+    /// time by which the page has to be typed, if the test has a time
+    /// limit
+    deadline: Option<Instant>,
+    /// time by which the word being typed has to be typed, if words have
+    /// a time budget
+    word_deadline: Option<Instant>,
+    /// start of the furthest word that the time budget was started for
+    timed_word: Option<usize>,
+    pauses: Vec<Range<Instant>>,
+    /// annotations of the words on this page, like their meanings
+    annotations: Vec<Option<String>>,
+    /// index of the word whose annotation is displayed
+    annotated_word: Option<usize>,
+    /// index of the char on this page that the pace caret is on
+    pace_index: Option<usize>,
+    /// number of chars of the test passed by the pace caret
+    pace_chars: Option<usize>,
+    /// romaji typed for the kana of the text, if it is typed in romaji
+    romaji: Option<RomajiInput>,
+    /// letters displayed above the text until they spell a kana
+    shown_romaji: String,
+    /// whether the hints show that the test is on PB pace
+    shown_pb_pace: bool,
+    /// chars of this page typed, typed correctly and wrong without being
+    /// corrected, kept up to date for the status socket, the readout and
+    /// the PB pace
+    live_chars: (usize, usize, usize),
+    /// time from reading each key to updating the screen for it, only
+    /// measured for the debug log
+    render_latencies: Vec<Duration>,
+}
+
+impl PageState {
+    /// State of a page with the given lines of text before any key is
+    /// typed. In code, the lines end with a newline to be typed instead
+    /// of the space displayed.
+    fn new(lines: &[Text], code: bool, before: PreviousPages) -> Self {
+        let displayed_text: Vec<char> = lines.iter().flat_map(|line| line.text().chars()).collect();
+        let mut original_text = displayed_text.clone();
+        if code {
+            let mut line_end = 0;
+            for line in &lines[..lines.len().saturating_sub(1)] {
+                line_end += line.length();
+                original_text[line_end - 1] = '\n';
+            }
+        }
+
+        let now = Instant::now();
+        Self {
+            displayed_text,
+            original_text,
+            input: Vec::new(),
+            counts: PageCounts::default(),
+            keystrokes: Vec::new(),
+            before,
+            num_locked_chars: 0,
+            event_at: now,
+            started_at: now,
+            test_started_at: now,
+            deadline: None,
+            word_deadline: None,
+            timed_word: None,
+            pauses: Vec::new(),
+            annotations: Vec::new(),
+            annotated_word: None,
+            pace_index: None,
+            pace_chars: None,
+            romaji: None,
+            shown_romaji: String::new(),
+            shown_pb_pace: false,
+            live_chars: (0, 0, 0),
+            render_latencies: Vec::new(),
+        }
+    }
+
+    /// Start and end of the word containing the char at `position`.
+    fn word_bounds(&self, position: usize) -> (usize, usize) {
+        let start = self.original_text[..position]
+            .iter()
+            .rposition(|&c| c == ' ')
+            .map_or(0, |i| i + 1);
+        let end = self.original_text[start..]
+            .iter()
+            .position(|&c| c == ' ')
+            .map_or(self.original_text.len(), |i| start + i);
+        (start, end)
+    }
+
+    /// Index on this page of the word containing the char to be typed
+    /// next.
+    fn current_word(&self) -> usize {
+        self.original_text[..self.input.len()]
+            .iter()
+            .filter(|&&c| c == ' ')
+            .count()
+    }
+
+    /// Whether the last char of the page was typed.
+    fn is_done(&self) -> bool {
+        self.input.len() >= self.original_text.len()
+    }
+
+    /// Records a key that typed `typed` at `position`, or deleted the
+    /// char there if `typed` is `None`.
+    fn push_keystroke(&mut self, position: usize, expected: char, typed: Option<char>) {
+        self.keystrokes.push(Keystroke {
+            at: self.event_at,
+            position,
+            expected,
+            typed,
+            correct: typed == Some(expected),
+        });
+    }
+
+    /// Counts a mistake made in typing `expected`.
+    fn count_error(&mut self, expected: char) {
+        self.counts.errors += 1;
+        if expected.is_ascii_digit() {
+            self.counts.number_errors += 1;
+        }
+    }
+
+    /// Spaces typed by a tab in code, up to the next tab stop.
+    fn tab_spaces(&self) -> Vec<char> {
+        let column = self.input.len()
+            - self.original_text[..self.input.len()]
+                .iter()
+                .rposition(|&c| c == '\n')
+                .map_or(0, |i| i + 1);
+        vec![' '; textgen::TAB_WIDTH - column % textgen::TAB_WIDTH]
+    }
+
+    /// Kana typed by a letter when the text is typed in romaji, matched
+    /// to the ones of the text where they can be typed either way, or
+    /// else the letter itself.
+    fn romaji_kana(&mut self, c: char) -> Vec<char> {
+        let Some(romaji) = &mut self.romaji else {
+            return vec![c];
+        };
+        let mut kana: Vec<char> = romaji.push(c).chars().collect();
+        // a lone n would wait for a letter after the text
+        if self.input.len() + kana.len() + 1 == self.original_text.len()
+            && matches!(self.original_text.last(), Some('ん' | 'ン'))
+        {
+            kana.extend(romaji.finish_n());
+        }
+        kana.iter()
+            .zip(&self.original_text[self.input.len().min(self.original_text.len())..])
+            .map(|(&typed, &expected)| romaji::matching_kana(typed, expected))
+            .collect()
+    }
+
+    /// Whether backspace deletes the last char typed: words that were
+    /// confirmed and chars that were locked cannot be changed.
+    fn can_delete(&self, confirm_words: bool) -> bool {
+        !(confirm_words && matches!(self.input.last(), Some(' ') | None))
+            && self.input.len() > self.num_locked_chars
+    }
+
+    /// Starts the time budget of the word being typed, if it is further
+    /// than the words that it was started for.
+    fn start_word_time(&mut self, end_conditions: &[Box<dyn EndCondition>]) {
+        let (word_start, word_end) = self.word_bounds(self.input.len());
+        // going back to a previous word does not restart its time
+        if self
+            .timed_word
+            .map_or(true, |timed_word| word_start > timed_word)
+        {
+            self.timed_word = Some(word_start);
+            // the space after the word counts towards its length
+            let word_len = word_end - word_start + 1;
+            self.word_deadline = end_conditions
+                .iter()
+                .filter_map(|condition| condition.word_time_limit(word_len))
+                .min()
+                .map(|word_time_limit| self.event_at + word_time_limit);
+        }
+    }
+
+    /// Moves the start of the test and the deadlines by the time that it
+    /// was paused, so that they do not run while paused.
+    fn resume(&mut self, pause: Range<Instant>) {
+        let paused_for = pause.end - pause.start;
+        self.test_started_at += paused_for;
+        self.deadline = self.deadline.map(|deadline| deadline + paused_for);
+        self.word_deadline = self.word_deadline.map(|deadline| deadline + paused_for);
+        self.pauses.push(pause);
+    }
+
+    /// Time from the start of the test to the event being processed.
+    fn elapsed(&self) -> Duration {
+        self.event_at
+            .saturating_duration_since(self.test_started_at)
+    }
+
+    /// Chars typed in the test so far, including the ones deleted later.
+    fn chars_typed(&self) -> usize {
+        self.before.chars_typed + self.counts.chars_typed
+    }
+
+    /// Fraction of the chars typed in the test so far that were typed
+    /// correctly.
+    fn accuracy(&self) -> f64 {
+        let chars_typed = self.chars_typed();
+        let errors = self.before.errors + self.counts.errors;
+        if chars_typed == 0 {
+            1.0
+        } else {
+            chars_typed.saturating_sub(errors) as f64 / chars_typed as f64
+        }
+    }
+
+    /// Counts the chars of this page typed so far (see
+    /// [`PageState::live_chars`]).
+    fn update_live_chars(&mut self) {
+        let correct = self
+            .input
+            .iter()
+            .zip(&self.original_text)
+            .filter(|(typed, original)| typed == original)
+            .count();
+        self.live_chars = (self.input.len(), correct, self.input.len() - correct);
+    }
+
+    /// Speed of the test after `elapsed`, from the chars last counted by
+    /// [`PageState::update_live_chars`].
+    fn live_wpm(&self, elapsed: Duration) -> f64 {
+        let (_, correct, uncorrected) = self.live_chars;
+        live_wpm(
+            self.before.chars_typed_correctly + correct,
+            self.before.uncorrected_errors + uncorrected,
+            elapsed,
+        )
+    }
+
+    /// Whether the test is typed fast enough to beat `personal_best`.
+    fn is_on_pb_pace(&self, personal_best: f64) -> bool {
+        self.chars_typed() >= MIN_PB_PACE_CHARS
+            && self.live_wpm(self.elapsed()) >= personal_best * (1.0 - PB_PACE_MARGIN)
+    }
+
+    /// Progress of the test, for its end conditions.
+    fn progress(&self) -> TestProgress {
+        TestProgress {
+            words_typed: self.before.words + self.current_word(),
+            chars_typed: self.before.chars + self.input.len(),
+            total_chars_typed: self.chars_typed(),
+            errors: self.before.errors + self.counts.errors,
+            pace_chars: self.pace_chars,
+        }
+    }
+
+    /// Results of the page from the chars typed on it so far, which are
+    /// not [`completed`](ToipeResults::completed).
+    fn results(&self, time: Range<Instant>) -> ToipeResults {
+        let (final_chars_typed_correctly, final_uncorrected_errors) =
+            self.input.iter().zip(self.original_text.iter()).fold(
+                (0, 0),
+                |(total_chars_typed_correctly, total_uncorrected_errors),
+                 (typed_char, orig_char)| {
+                    if typed_char == orig_char {
+                        (total_chars_typed_correctly + 1, total_uncorrected_errors)
+                    } else {
+                        (total_chars_typed_correctly, total_uncorrected_errors + 1)
+                    }
+                },
+            );
+
+        // words that were typed at least partially
+        let total_words = self.original_text[..self.input.len()]
+            .iter()
+            .collect::<String>()
+            .split_whitespace()
+            .count();
+
+        ToipeResults {
+            total_words,
+            total_chars_typed: self.counts.chars_typed,
+            total_chars_in_text: self.input.len(),
+            total_char_errors: self.counts.errors,
+            total_number_errors: self.counts.number_errors,
+            final_chars_typed_correctly,
+            final_uncorrected_errors,
+            attempted_corrections: self.counts.attempted_corrections,
+            failed_words: self.counts.failed_words,
+            started_at: time.start,
+            ended_at: time.end,
+            keystrokes: self.keystrokes.clone(),
+            pages: Vec::new(),
+            completed: false,
+            pauses: self.pauses.clone(),
+            render_latencies: Vec::new(),
+        }
+    }
+
+    /// Records the time from reading `key` to updating the screen for
+    /// it, if it is measured for the debug log.
+    fn record_latency(&mut self, key: Key) {
+        if log::log_enabled!(log::Level::Trace) {
+            let latency = self.event_at.elapsed();
+            log::trace!(
+                "displayed key {:?} {}us after it was read",
+                key,
+                latency.as_micros()
+            );
+            self.render_latencies.push(latency);
+        }
+    }
+}
+
+/// Speed in words per minute of a test being typed, counted like
+/// [`ToipeResults::wpm`] for the chars typed so far.
+fn live_wpm(chars_typed_correctly: usize, uncorrected_errors: usize, elapsed: Duration) -> f64 {
+    let minutes = elapsed.as_secs_f64() / 60.0;
+    if minutes == 0.0 {
+        return 0.0;
+    }
+    (chars_typed_correctly as f64 / 5.0 - uncorrected_errors as f64).max(0.0) / minutes
+}
+
+/// Texts to display for the chars of a word when words are confirmed
+/// (see [`ToipeConfig::confirm_words`](crate::config::ToipeConfig::confirm_words)).
+///
+/// Typed chars are marked as correct or incorrect and the rest are
+/// faint. If `mistake` is true, the whole word is marked as incorrect.
+fn confirmed_word_texts(typed: &[char], word: &[char], mistake: bool, theme: &Theme) -> Vec<Text> {
+    word.iter()
+        .enumerate()
+        .map(|(i, &c)| match typed.get(i) {
+            _ if mistake => Text::from(c)
+                .with_underline()
+                .with_color(theme.incorrect)
+                .with_marker('x'),
+            Some(&typed_c) if typed_c == c => Text::from(c).with_color(theme.correct),
+            Some(_) => Text::from(c)
+                .with_underline()
+                .with_color(theme.incorrect)
+                .with_marker('x'),
+            None => theme.dim(Text::from(c)),
+        })
+        .collect()
+}
+
+impl Toipe {
+    /// Runs the typing test on the page currently displayed.
+    ///
+    /// `test_started_at` is the time at which the first key of the test
+    /// was pressed. It is set when the first key is pressed on the first
+    /// page and moved later by the time that the test is paused, so that
+    /// the time limit and the pace caret do not run while paused.
+    /// `previous_pages` are the results of the previous pages, whose
+    /// chars the pace caret has to pass before reaching this page and
+    /// which count towards the live accuracy.
+    pub(crate) fn test_page(
+        &mut self,
+        keys: &mut KeyEvents,
+        test_started_at: &mut Option<Instant>,
+        last_checkpoint_at: &mut Option<Instant>,
+        previous_pages: &[ToipeResults],
+    ) -> Result<(TestStatus, ToipeResults)> {
+        let (first_key, countdown_ended_at) = self.wait_for_start(keys, *test_started_at)?;
+        if countdown_ended_at.is_some() {
+            *test_started_at = countdown_ended_at;
+        }
+        let mut page = self.new_page_state(*test_started_at, previous_pages)?;
+
+        let time_limit = self
+            .end_conditions
+            .iter()
+            .filter_map(|condition| condition.time_limit())
+            .min();
+        let check_interval = self
+            .end_conditions
+            .iter()
+            .filter_map(|condition| condition.check_interval())
+            .min();
+        let deadline = |test_started_at: Option<Instant>| {
+            test_started_at
+                .zip(time_limit)
+                .map(|(started_at, time_limit)| started_at + time_limit)
+        };
+
+        // read first key
+        let key = match first_key {
+            Some(key) => Some(key),
+            None => keys.next_key_before(deadline(*test_started_at))?,
+        };
+        if key.is_some() {
+            page.event_at = keys.last_key_at().unwrap_or_else(Instant::now);
+        }
+        // start the timer
+        page.started_at = countdown_ended_at.unwrap_or(page.event_at);
+        page.test_started_at = *test_started_at.get_or_insert(page.started_at);
+        page.deadline = deadline(Some(page.test_started_at));
+        // process first key
+        let mut status = match key {
+            Some(key) => {
+                let status = self.process_event(&mut page, TestEvent::Key(key), previous_pages)?;
+                page.record_latency(key);
+                #[cfg(feature = "net")]
+                self.publish_typing_status(&page, time_limit);
+                status
+            }
+            None => TestStatus::TimeUp,
+        };
+
+        let mut next_pace_at = Instant::now();
+        let mut next_check_at = check_interval.map(|interval| Instant::now() + interval);
+        let mut next_continue_check_at = Instant::now() + CONTINUE_CHECK_INTERVAL;
+        // last time that the program was known to be running
+        let mut running_at = Instant::now();
+        while status.to_process_more_keys() {
+            if let TestStatus::Paused | TestStatus::Suspended | TestStatus::Stopped = status {
+                status = self.wait_while_paused(keys, &mut page, status, running_at)?;
+                *test_started_at = Some(page.test_started_at);
+                continue;
+            }
+            // wake up to move the pace caret even if keys keep being pressed
+            let next_deadline = page
+                .deadline
+                .into_iter()
+                .chain(self.pace.as_ref().map(|_| next_pace_at))
+                .chain(page.word_deadline)
+                .chain(next_check_at)
+                .chain(Some(next_continue_check_at))
+                .min();
+            let time_up = |deadline: Option<Instant>| deadline.is_some_and(|d| Instant::now() >= d);
+            running_at = Instant::now();
+            let key = keys.next_key_before(next_deadline)?;
+            page.event_at = match key {
+                Some(_) => keys.last_key_at().unwrap_or_else(Instant::now),
+                None => Instant::now(),
+            };
+            if time_up(Some(next_continue_check_at)) {
+                next_continue_check_at = Instant::now() + CONTINUE_CHECK_INTERVAL;
+                // a key pressed to continue the program is not typed
+                status =
+                    self.process_event(&mut page, TestEvent::CheckContinued, previous_pages)?;
+                if let TestStatus::Stopped = status {
+                    continue;
+                }
+                running_at = Instant::now();
+            }
+            status = match key {
+                Some(key) => {
+                    let status =
+                        self.process_event(&mut page, TestEvent::Key(key), previous_pages)?;
+                    page.record_latency(key);
+                    #[cfg(feature = "net")]
+                    self.publish_typing_status(&page, time_limit);
+                    status
+                }
+                None if time_up(page.deadline) => TestStatus::TimeUp,
+                None if time_up(page.word_deadline) => {
+                    self.process_event(&mut page, TestEvent::WordTimeUp, previous_pages)?
+                }
+                None if time_up(next_check_at) => {
+                    next_check_at = check_interval.map(|interval| Instant::now() + interval);
+                    self.process_event(&mut page, TestEvent::CheckEnd, previous_pages)?
+                }
+                // only woke up to check whether the program was continued
+                None if self.pace.is_none() => TestStatus::NotDone,
+                None => {
+                    next_pace_at = Instant::now() + PACE_REDRAW_INTERVAL;
+                    let event = self.pace_event(&page);
+                    self.process_event(&mut page, event, previous_pages)?
+                }
+            };
+
+            let next_checkpoint_at = self.checkpoint.as_ref().map(|(interval, _)| {
+                last_checkpoint_at.unwrap_or(page.test_started_at) + *interval
+            });
+            if matches!(status, TestStatus::NotDone) && time_up(next_checkpoint_at) {
+                *last_checkpoint_at = Some(Instant::now());
+                status = self.process_event(&mut page, TestEvent::Checkpoint, previous_pages)?;
+            }
+        }
+
+        // stop the timer
+        let ended_at = match (&status, page.deadline) {
+            (TestStatus::TimeUp, Some(deadline)) => deadline,
+            (TestStatus::Done | TestStatus::Ended, _) => page.event_at,
+            _ => Instant::now(),
+        };
+        // time may have been up even before the first key on this page
+        let started_at = page.started_at.min(ended_at);
+
+        let mut results = page.results(started_at..ended_at);
+        results.completed = status.to_display_results();
+        results.render_latencies = page.render_latencies;
+
+        Ok((status, results))
+    }
+
+    /// Drops the first keys, shows the preview and counts down before
+    /// the first page of a test, if they are enabled.
+    ///
+    /// Returns the key that ended this early, like ctrl-c, and the time
+    /// at which the countdown ended, if it did.
+    fn wait_for_start(
+        &mut self,
+        keys: &mut KeyEvents,
+        test_started_at: Option<Instant>,
+    ) -> Result<(Option<Key>, Option<Instant>)> {
+        // the timer starts after the preview of the first page
+        let mut first_key = None;
+        let first_page = self.current_page == 0 && test_started_at.is_none();
+        if self.config.ignore_first_keys > 0 && first_page {
+            first_key = self.ignore_first_keys(keys, self.config.ignore_first_keys)?;
+        }
+        if self.config.preview && first_page && first_key.is_none() {
+            first_key = self.preview(keys)?;
+        }
+        // or when the countdown ends, if there is one
+        let mut countdown_ended_at = None;
+        if let Some(secs) = self.config.countdown.filter(|_| first_page) {
+            if first_key.is_none() {
+                first_key = self.countdown(keys, secs)?;
+            }
+            if first_key.is_none() {
+                countdown_ended_at = Some(Instant::now());
+            }
+        }
+        Ok((first_key, countdown_ended_at))
+    }
+
+    /// State of the page currently displayed before its first key,
+    /// displaying the readout and the first annotation if there are any.
+    fn new_page_state(
+        &mut self,
+        test_started_at: Option<Instant>,
+        previous_pages: &[ToipeResults],
+    ) -> Result<PageState> {
+        let code = self.config.code.is_some();
+        let words_before = self.pages[..self.current_page].iter().map(Vec::len).sum();
+        let mut page = PageState::new(
+            &self.text,
+            code,
+            PreviousPages::new(previous_pages, words_before),
+        );
+        if let Some(test_started_at) = test_started_at {
+            page.test_started_at = test_started_at;
+        }
+        page.romaji = self.config.romaji.then(RomajiInput::default);
+
+        if self.config.streamer {
+            let elapsed = test_started_at.map_or(Duration::ZERO, |started_at| started_at.elapsed());
+            self.tui
+                .display_readout(page.live_wpm(elapsed), page.accuracy() * 100.0)?;
+        }
+
+        if !code {
+            page.annotations = self.pages[self.current_page]
+                .iter()
+                .map(|word| self.word_stream.selector().annotation(word))
+                .collect();
+        }
+        if page.annotations.iter().any(Option::is_some) {
+            self.tui
+                .display_annotation(page.annotations[0].as_deref())?;
+            page.annotated_word = Some(0);
+        }
+
+        Ok(page)
+    }
+
+    /// Waits for the key that resumes a test that was paused, suspended
+    /// or stopped from outside, and moves the deadlines by the time that
+    /// it was paused.
+    ///
+    /// `running_at` is the last time that the program was known to be
+    /// running, since a program stopped from outside was stopped at some
+    /// point after it.
+    fn wait_while_paused(
+        &mut self,
+        keys: &mut KeyEvents,
+        page: &mut PageState,
+        status: TestStatus,
+        running_at: Instant,
+    ) -> Result<TestStatus> {
+        if let TestStatus::Stopped = status {
+            // displayed like a test paused with esc
+            self.process_event(page, TestEvent::Key(Key::Esc), &[])?;
+        }
+        let paused_at = match status {
+            TestStatus::Stopped => running_at,
+            _ => Instant::now(),
+        };
+        if let TestStatus::Suspended = status {
+            // still paused once continued, so that the time until the
+            // user is back counts as paused too
+            self.process_event(page, TestEvent::Suspend, &[])?;
+        }
+        // the key that resumes the test is not typed
+        let key = keys.next_key()?;
+        page.resume(paused_at..Instant::now());
+        match key {
+            // quits like it does while typing
+            Key::Ctrl('c') => self.process_event(page, TestEvent::Key(key), &[]),
+            _ => self.process_event(page, TestEvent::Resume, &[]),
+        }
+    }
+
+    /// Processes an event of the page being typed.
+    ///
+    /// `previous_pages` are the results of the previous pages, which are
+    /// passed to the checkpoint hook with the page so far.
+    fn process_event(
+        &mut self,
+        page: &mut PageState,
+        event: TestEvent,
+        previous_pages: &[ToipeResults],
+    ) -> Result<TestStatus> {
+        let key = match event {
+            TestEvent::Suspend => {
+                self.tui.suspend()?;
+                return Ok(TestStatus::Paused);
+            }
+            TestEvent::Key(Key::Esc | Key::Ctrl('z')) | TestEvent::Resume => {
+                let paused = !matches!(event, TestEvent::Resume);
+                self.redisplay_page(page, paused)?;
+                return Ok(match event {
+                    TestEvent::Resume => TestStatus::NotDone,
+                    TestEvent::Key(Key::Ctrl('z')) => TestStatus::Suspended,
+                    _ => TestStatus::Paused,
+                });
+            }
+            TestEvent::WordTimeUp => {
+                if self.skip_timed_word(page)? {
+                    return Ok(TestStatus::Done);
+                }
+                // the rest is done like for a key that does nothing
+                Key::Null
+            }
+            TestEvent::CheckEnd => Key::Null,
+            TestEvent::CheckContinued => {
+                return Ok(if self.tui.take_continued()? {
+                    TestStatus::Stopped
+                } else {
+                    TestStatus::NotDone
+                });
+            }
+            TestEvent::Checkpoint => {
+                self.save_checkpoint(page, previous_pages);
+                return Ok(TestStatus::NotDone);
+            }
+            TestEvent::Key(key) => key,
+            TestEvent::Pace(index) => {
+                self.move_pace_caret(page, index)?;
+                // the rest is done like for a key that does nothing,
+                // since the caret may have caught up with the user
+                Key::Null
+            }
+        };
+
+        if let Some(status) = self.process_key(page, key)? {
+            return Ok(status);
+        }
+        self.update_after_key(page, key)
+    }
+
+    /// Text to display for a char that was typed: in a blind test, typed
+    /// chars look like the ones not typed yet.
+    fn typed_text(&self, text: Text) -> Text {
+        if self.config.blind {
+            self.tui.theme().dim(Text::from(text.text().as_str()))
+        } else {
+            text
+        }
+    }
+
+    /// Displays the whole page again, dimmed while the test is paused.
+    fn redisplay_page(&mut self, page: &PageState, paused: bool) -> Result<()> {
+        let theme = self.tui.theme();
+        for (i, &c) in page.displayed_text.iter().enumerate() {
+            let text = match page.input.get(i) {
+                _ if paused => theme.dim(Text::from(c)),
+                Some(&typed_c) if typed_c == page.original_text[i] => {
+                    self.typed_text(Text::from(c).with_color(theme.correct))
+                }
+                Some(_) => self.typed_text(
+                    Text::from(c)
+                        .with_underline()
+                        .with_color(theme.incorrect)
+                        .with_marker('x'),
+                ),
+                None => theme.dim(Text::from(c)),
+            };
+            self.tui.display_char_at(i, &text)?;
+        }
+        if paused {
+            self.tui.display_paused(true)?;
+        } else {
+            self.tui.display_annotation(
+                page.annotated_word
+                    .and_then(|word| page.annotations.get(word).cloned().flatten())
+                    .as_deref(),
+            )?;
+        }
+        self.tui.flush()?;
+        Ok(())
+    }
+
+    /// Skips the rest of the word being typed, whose time budget ran out,
+    /// and the space after it, and locks them (see
+    /// [`PageState::num_locked_chars`]).
+    ///
+    /// Returns whether this skipped to the end of the page.
+    fn skip_timed_word(&mut self, page: &mut PageState) -> Result<bool> {
+        let theme = self.tui.theme();
+        let (_, word_end) = page.word_bounds(page.input.len());
+        while page.input.len() < word_end {
+            self.tui.display_char(
+                &self.typed_text(
+                    Text::from(page.displayed_text[page.input.len()])
+                        .with_color(theme.incorrect)
+                        .with_marker('x'),
+                ),
+            )?;
+            page.input.push(SKIPPED_CHAR);
+        }
+        page.counts.failed_words += 1;
+        // the space after the word is skipped too
+        if let Some(&c) = page.original_text.get(page.input.len()) {
+            page.input.push(c);
+            self.tui.display_char(&self.typed_text(
+                Text::from(page.displayed_text[page.input.len() - 1]).with_color(theme.correct),
+            ))?;
+        }
+        if page.is_done() {
+            return Ok(true);
+        }
+        page.num_locked_chars = page.input.len();
+        Ok(false)
+    }
+
+    /// Passes the results of the test so far to the checkpoint hook, if
+    /// there is one (see [`Toipe::on_checkpoint`]).
+    fn save_checkpoint(&mut self, page: &PageState, previous_pages: &[ToipeResults]) {
+        if let Some((_, hook)) = &mut self.checkpoint {
+            let mut pages = previous_pages.to_vec();
+            pages.push(page.results(page.started_at..page.event_at));
+            let mut results = ToipeResults::from_pages(pages);
+            results.completed = false;
+            // the test goes on, since only the saved copy is lost
+            if let Err(error) = hook(&results) {
+                log::warn!("could not save the test typed so far: {:#}", error);
+            }
+        }
+    }
+
+    /// Event of the pace caret reaching the char of the page that it is
+    /// on now.
+    fn pace_event(&self, page: &PageState) -> TestEvent {
+        let elapsed = page.test_started_at.elapsed();
+        let pace_chars = self.pace.as_ref().map_or(0, |pace| pace.chars_at(elapsed));
+        TestEvent::Pace(pace_chars.saturating_sub(page.before.chars))
+    }
+
+    /// Moves the pace caret to the char at `index` of the page, or to the
+    /// last char if it is past the page.
+    fn move_pace_caret(&mut self, page: &mut PageState, index: usize) -> Result<()> {
+        let theme = self.tui.theme();
+        page.pace_chars = Some(page.before.chars + index);
+        let index = index.min(page.displayed_text.len().saturating_sub(1));
+        // chars that were typed already are left as they are
+        if let Some(prev_index) = page
+            .pace_index
+            .filter(|&i| i != index && i >= page.input.len())
+        {
+            self.tui.display_char_at(
+                prev_index,
+                &theme.dim(Text::from(page.displayed_text[prev_index])),
+            )?;
+        }
+        if index >= page.input.len() {
+            self.tui.display_char_at(
+                index,
+                &theme
+                    .dim(Text::from(page.displayed_text[index]))
+                    .with_underline()
+                    .with_marker('*'),
+            )?;
+        }
+        page.pace_index = Some(index);
+        self.tui.flush()?;
+        Ok(())
+    }
+
+    /// Types, deletes or otherwise handles a key pressed while typing.
+    ///
+    /// Returns the status of the test if the key ends the page.
+    fn process_key(&mut self, page: &mut PageState, key: Key) -> Result<Option<TestStatus>> {
+        match key {
+            Key::Ctrl('c') if self.is_endless() => return Ok(Some(TestStatus::Stop)),
+            Key::Ctrl('c') => return Ok(Some(TestStatus::Quit)),
+            Key::Ctrl('r') => return Ok(Some(TestStatus::Restart)),
+            Key::Ctrl('p') => return Ok(Some(TestStatus::Repeat)),
+            // romaji that do not spell a kana yet are not typed chars
+            Key::Backspace | Key::Ctrl('h')
+                if page.romaji.as_mut().is_some_and(|romaji| romaji.pop()) => {}
+            Key::Backspace | Key::Ctrl('h' | 'w') if self.config.hardcore => {
+                page.counts.attempted_corrections += 1;
+            }
+            Key::Ctrl('w') => {
+                // delete last word
+                while !matches!(page.input.last(), Some(' ' | '\n') | None) {
+                    self.delete_char(page)?;
+                }
+            }
+            Key::Char(c) => {
+                let done = if self.config.confirm_words {
+                    self.type_confirmed_char(page, c)?
+                } else {
+                    self.type_chars(page, c)?
+                };
+                if done {
+                    return Ok(Some(TestStatus::Done));
+                }
+            }
+            // confirmed words cannot be changed
+            Key::Backspace | Key::Ctrl('h') if page.can_delete(self.config.confirm_words) => {
+                self.delete_char(page)?;
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    /// Deletes the last char typed.
+    fn delete_char(&mut self, page: &mut PageState) -> Result<()> {
+        if page.input.pop().is_some() {
+            let position = page.input.len();
+            page.push_keystroke(position, page.original_text[position], None);
+            let text = self
+                .tui
+                .theme()
+                .dim(Text::from(page.displayed_text[position]));
+            self.tui.replace_text(text)?;
+        }
+        Ok(())
+    }
+
+    /// Types a char when words are confirmed with a space (see
+    /// [`ToipeConfig::confirm_words`](crate::config::ToipeConfig::confirm_words)).
+    ///
+    /// Returns whether this confirmed the last word of the page.
+    fn type_confirmed_char(&mut self, page: &mut PageState, c: char) -> Result<bool> {
+        let theme = self.tui.theme();
+        let (word_start, word_end) = page.word_bounds(page.input.len());
+        let word = page.original_text[word_start..word_end].to_vec();
+
+        if c == ' ' {
+            let correct = page.input[word_start..] == *word;
+            page.keystrokes.push(Keystroke {
+                at: page.event_at,
+                position: page.input.len(),
+                expected: ' ',
+                typed: Some(c),
+                correct,
+            });
+
+            if !correct {
+                page.counts.errors += 1;
+                if word.iter().any(char::is_ascii_digit) {
+                    page.counts.number_errors += 1;
+                }
+                self.tui.redisplay_chars(
+                    page.input.len() - word_start,
+                    &confirmed_word_texts(&page.input[word_start..], &word, true, &theme),
+                )?;
+            } else if word_end >= page.original_text.len() {
+                return Ok(true);
+            } else {
+                page.input.push(c);
+                page.counts.chars_typed += 1;
+                self.tui
+                    .display_char(&Text::from(c).with_color(theme.correct))?;
+            }
+        } else if page.input.len() < word_end {
+            page.input.push(c);
+            page.counts.chars_typed += 1;
+
+            let position = page.input.len() - 1;
+            let expected = page.original_text[position];
+            page.push_keystroke(position, expected, Some(c));
+            if expected != c {
+                page.count_error(expected);
+            }
+
+            self.tui.redisplay_chars(
+                position - word_start,
+                &confirmed_word_texts(&page.input[word_start..], &word, false, &theme),
+            )?;
+            self.tui.move_to_next_char()?;
+        }
+        Ok(false)
+    }
+
+    /// Types the chars of a key: the spaces of a tab in code, the kana
+    /// spelled by a letter typed in romaji, or else the char of the key.
+    ///
+    /// Returns whether this typed the last char of the page.
+    fn type_chars(&mut self, page: &mut PageState, c: char) -> Result<bool> {
+        let theme = self.tui.theme();
+        let typed = if self.config.code.is_some() && c == '\t' {
+            page.tab_spaces()
+        } else {
+            page.romaji_kana(c)
+        };
+
+        for c in typed {
+            page.input.push(c);
+
+            let position = page.input.len() - 1;
+            let expected = page.original_text[position];
+            page.push_keystroke(position, expected, Some(c));
+
+            if page.is_done() {
+                return Ok(true);
+            }
+
+            page.counts.chars_typed += 1;
+
+            let text = Text::from(page.displayed_text[position]);
+            if expected == c {
+                self.tui
+                    .display_char(&self.typed_text(text.with_color(theme.correct)))?;
+            } else {
+                self.tui.display_char(
+                    &self.typed_text(
+                        text.with_underline()
+                            .with_color(theme.incorrect)
+                            .with_marker('x'),
+                    ),
+                )?;
+                page.count_error(expected);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Updates the screen around the text after a key was handled, and
+    /// checks the end conditions.
+    fn update_after_key(&mut self, page: &mut PageState, key: Key) -> Result<TestStatus> {
+        if self.config.confirm_words && matches!(key, Key::Backspace | Key::Ctrl('h' | 'w')) {
+            // clear the whole word being marked as a mistake
+            let (word_start, word_end) = page.word_bounds(page.input.len());
+            self.tui.redisplay_chars(
+                page.input.len() - word_start,
+                &confirmed_word_texts(
+                    &page.input[word_start..],
+                    &page.original_text[word_start..word_end],
+                    false,
+                    &self.tui.theme(),
+                ),
+            )?;
+        }
+
+        self.update_annotation(page)?;
+
+        let has_word_time_limit = self
+            .end_conditions
+            .iter()
+            .any(|condition| condition.word_time_limit(1).is_some());
+        if has_word_time_limit {
+            page.start_word_time(&self.end_conditions);
+        }
+
+        self.update_hud(page)?;
+
+        let progress = page.progress();
+        let met_conditions = || {
+            self.end_conditions
+                .iter()
+                .filter(|condition| condition.is_met(&progress))
+        };
+        if met_conditions().any(|condition| condition.aborts()) {
+            return Ok(TestStatus::Aborted);
+        }
+        if met_conditions().next().is_some() {
+            return Ok(TestStatus::Ended);
+        }
+
+        self.tui.flush()?;
+
+        Ok(TestStatus::NotDone)
+    }
+
+    /// Displays the annotation of the word being typed, or the romaji
+    /// typed that do not spell a kana yet.
+    fn update_annotation(&mut self, page: &mut PageState) -> Result<()> {
+        if page.annotations.iter().any(Option::is_some) {
+            let word = page.current_word();
+            if page.annotated_word != Some(word) {
+                page.annotated_word = Some(word);
+                self.tui
+                    .display_annotation(page.annotations.get(word).cloned().flatten().as_deref())?;
+            }
+        }
+
+        if let Some(romaji) = &page.romaji {
+            if romaji.pending() != page.shown_romaji {
+                page.shown_romaji = romaji.pending().to_string();
+                let annotation = page
+                    .annotated_word
+                    .and_then(|word| page.annotations.get(word).cloned().flatten());
+                self.tui.display_annotation(
+                    Some(romaji.pending())
+                        .filter(|pending| !pending.is_empty())
+                        .or(annotation.as_deref()),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Displays the hints, if they show the live accuracy or whether the
+    /// test is on PB pace changed, and the readout, if it is shown.
+    fn update_hud(&mut self, page: &mut PageState) -> Result<()> {
+        #[cfg(feature = "net")]
+        let publish_status = self.status_server.is_some();
+        #[cfg(not(feature = "net"))]
+        let publish_status = false;
+        if publish_status || self.config.streamer || self.personal_best.is_some() {
+            page.update_live_chars();
+        }
+
+        let theme = self.tui.theme();
+        let accuracy = page.accuracy();
+        let on_pb_pace = self
+            .personal_best
+            .is_some_and(|personal_best| page.is_on_pb_pace(personal_best));
+        if self.config.live_accuracy || on_pb_pace != page.shown_pb_pace {
+            page.shown_pb_pace = on_pb_pace;
+            self.tui.display_hud(&[test_hud_line(
+                &self.config,
+                &theme,
+                self.rounds.len(),
+                accuracy,
+                on_pb_pace,
+                self.is_endless(),
+            )])?;
+            self.tui.move_to_cur_pos()?;
+        }
+        if self.config.streamer {
+            self.tui
+                .display_readout(page.live_wpm(page.elapsed()), accuracy * 100.0)?;
+            self.tui.move_to_cur_pos()?;
+        }
+        Ok(())
+    }
+
+    /// Publishes the speed and the progress of the test on the status
+    /// socket, if there is one.
+    #[cfg(feature = "net")]
+    fn publish_typing_status(&self, page: &PageState, time_limit: Option<Duration>) {
+        let Some(status_server) = &self.status_server else {
+            return;
+        };
+        // the last word of each page is followed by the first of the next
+        let test_chars = self
+            .pages
+            .iter()
+            .flatten()
+            .map(|word| word.chars().count() + 1)
+            .sum::<usize>()
+            .saturating_sub(1);
+        let (typed, _, _) = page.live_chars;
+        let elapsed = page.elapsed();
+        let progress = match time_limit {
+            Some(time_limit) => Some(elapsed.as_secs_f64() / time_limit.as_secs_f64()),
+            None if self.is_endless() => None,
+            None => Some((page.before.chars + typed) as f64 / test_chars.max(1) as f64),
+        };
+        status_server.publish(&Status::Typing {
+            wpm: page.live_wpm(elapsed),
+            progress: progress.map(|progress| progress.min(1.0) * 100.0),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::end_condition::WordBudget;
+
+    fn new_page(lines: &[&str], code: bool) -> PageState {
+        let lines: Vec<Text> = lines.iter().map(|&line| Text::from(line)).collect();
+        PageState::new(&lines, code, PreviousPages::default())
+    }
+
+    fn type_str(page: &mut PageState, typed: &str) {
+        for c in typed.chars() {
+            let position = page.input.len();
+            let expected = page.original_text[position];
+            page.input.push(c);
+            page.counts.chars_typed += 1;
+            page.push_keystroke(position, expected, Some(c));
+            if expected != c {
+                page.count_error(expected);
+            }
+        }
+    }
+
+    #[test]
+    fn lines_of_code_end_with_newlines() {
+        let page = new_page(&["fn main() { ", "} "], true);
+        assert_eq!(
+            page.displayed_text.iter().collect::<String>(),
+            "fn main() { } "
+        );
+        assert_eq!(
+            page.original_text.iter().collect::<String>(),
+            "fn main() {\n} "
+        );
+
+        let page = new_page(&["two words ", "more "], false);
+        assert_eq!(page.original_text, page.displayed_text);
+    }
+
+    #[test]
+    fn finds_the_word_being_typed() {
+        let mut page = new_page(&["one two three"], false);
+        assert_eq!(page.word_bounds(0), (0, 3));
+        assert_eq!(page.word_bounds(4), (4, 7));
+        assert_eq!(page.word_bounds(9), (8, 13));
+
+        type_str(&mut page, "one tw");
+        assert_eq!(page.current_word(), 1);
+        assert!(!page.is_done());
+        type_str(&mut page, "o three");
+        assert!(page.is_done());
+    }
+
+    #[test]
+    fn tab_types_spaces_up_to_the_next_tab_stop() {
+        let mut page = new_page(&["if x { ", "        y "], true);
+        type_str(&mut page, "if x {\n");
+        assert_eq!(page.tab_spaces().len(), textgen::TAB_WIDTH);
+        type_str(&mut page, "  ");
+        assert_eq!(page.tab_spaces().len(), textgen::TAB_WIDTH - 2);
+    }
+
+    #[test]
+    fn counts_mistakes() {
+        let mut page = new_page(&["ab 12"], false);
+        type_str(&mut page, "ax 13");
+        assert_eq!(page.counts.chars_typed, 5);
+        assert_eq!(page.counts.errors, 2);
+        assert_eq!(page.counts.number_errors, 1);
+        assert_eq!(page.accuracy(), 3.0 / 5.0);
+
+        let progress = page.progress();
+        assert_eq!(progress.words_typed, 1);
+        assert_eq!(progress.chars_typed, 5);
+        assert_eq!(progress.errors, 2);
+
+        let correct: Vec<bool> = page.keystrokes.iter().map(|k| k.correct).collect();
+        assert_eq!(correct, [true, false, true, true, false]);
+    }
+
+    #[test]
+    fn locked_chars_and_confirmed_words_cannot_be_deleted() {
+        let mut page = new_page(&["ab cd"], false);
+        assert!(!page.can_delete(false));
+        type_str(&mut page, "ab ");
+        assert!(page.can_delete(false));
+        assert!(!page.can_delete(true));
+
+        page.num_locked_chars = 3;
+        assert!(!page.can_delete(false));
+        type_str(&mut page, "c");
+        assert!(page.can_delete(false));
+    }
+
+    #[test]
+    fn counts_the_previous_pages() {
+        let mut page = new_page(&["abcde"], false);
+        page.before = PreviousPages {
+            chars: 10,
+            chars_typed: 10,
+            errors: 5,
+            chars_typed_correctly: 10,
+            uncorrected_errors: 0,
+            words: 2,
+        };
+        type_str(&mut page, "abcde");
+        assert_eq!(page.accuracy(), 10.0 / 15.0);
+        assert_eq!(page.progress().words_typed, 2);
+        assert_eq!(page.progress().chars_typed, 15);
+
+        page.update_live_chars();
+        assert_eq!(page.live_chars, (5, 5, 0));
+        // 15 chars typed correctly are 3 words
+        assert_eq!(page.live_wpm(Duration::from_secs(60)), 3.0);
+        assert_eq!(page.live_wpm(Duration::ZERO), 0.0);
+    }
+
+    #[test]
+    fn word_time_starts_once_per_word() {
+        let mut page = new_page(&["one two"], false);
+        let conditions: Vec<Box<dyn EndCondition>> =
+            vec![Box::new(WordBudget(Duration::from_secs(1)))];
+        page.start_word_time(&conditions);
+        let first_deadline = page.word_deadline;
+        assert!(first_deadline.is_some());
+
+        type_str(&mut page, "on");
+        page.event_at += Duration::from_secs(1);
+        page.start_word_time(&conditions);
+        assert_eq!(page.word_deadline, first_deadline);
+
+        type_str(&mut page, "e ");
+        page.start_word_time(&conditions);
+        assert!(page.word_deadline > first_deadline);
+    }
+
+    #[test]
+    fn resuming_moves_the_deadlines() {
+        let mut page = new_page(&["abc"], false);
+        let start = page.test_started_at;
+        page.deadline = Some(start + Duration::from_secs(30));
+        page.word_deadline = Some(start + Duration::from_secs(2));
+        let paused_at = start + Duration::from_secs(1);
+        page.resume(paused_at..paused_at + Duration::from_secs(5));
+
+        assert_eq!(page.test_started_at, start + Duration::from_secs(5));
+        assert_eq!(page.deadline, Some(start + Duration::from_secs(35)));
+        assert_eq!(page.word_deadline, Some(start + Duration::from_secs(7)));
+        assert_eq!(page.pauses.len(), 1);
+    }
+
+    #[test]
+    fn results_count_the_chars_typed_so_far() {
+        let mut page = new_page(&["one two three"], false);
+        type_str(&mut page, "one tx");
+        let started_at = page.started_at;
+        let results = page.results(started_at..started_at + Duration::from_secs(6));
+
+        assert_eq!(results.total_words, 2);
+        assert_eq!(results.total_chars_in_text, 6);
+        assert_eq!(results.final_chars_typed_correctly, 5);
+        assert_eq!(results.final_uncorrected_errors, 1);
+        assert_eq!(results.keystrokes.len(), 6);
+        assert!(!results.completed);
+    }
+}
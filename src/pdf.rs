@@ -0,0 +1,151 @@
+//! Reading the text of PDF files, for typing them with `--book`.
+//!
+//! See [`read_text`].
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+/// Reads the text of the PDF file at the path, in order.
+///
+/// The text is cleaned up with [`clean_pages`], since it is laid out
+/// for printing.
+pub fn read_text(path: &Path) -> Result<String, io::Error> {
+    let pages = pdf_extract::extract_text_by_pages(path)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+    Ok(clean_pages(&pages))
+}
+
+/// Joins the text of the pages of a document, leaving out what is only
+/// there because of how it was printed:
+///
+/// - Headers and footers: the first or last line of a page, if it is
+///   the first or last line of at least half of the pages (and of more
+///   than one), ignoring digits (like page numbers). Lines that are only
+///   a number are left out there as well.
+/// - Hyphens that break a word across lines: `exam-` followed by `ple`
+///   on the next line is `example`.
+///
+/// ```
+/// use toipe::pdf::clean_pages;
+///
+/// let pages = ["A Book\nThe exam-\nple works.\n1", "A Book\nFine.\n2"];
+/// assert_eq!(clean_pages(&pages), "The example works.\nFine.\n");
+/// ```
+pub fn clean_pages<S: AsRef<str>>(pages: &[S]) -> String {
+    let pages: Vec<Vec<&str>> = pages
+        .iter()
+        .map(|page| page.as_ref().lines().map(str::trim).collect())
+        .collect();
+    // first and last lines of each page with text
+    let edges: Vec<Option<(usize, usize)>> = pages
+        .iter()
+        .map(|lines| {
+            let first = lines.iter().position(|line| !line.is_empty())?;
+            let last = lines.iter().rposition(|line| !line.is_empty())?;
+            Some((first, last))
+        })
+        .collect();
+
+    // how many pages each line starts or ends, with its digits ignored
+    let mut first_lines: HashMap<String, usize> = HashMap::new();
+    let mut last_lines: HashMap<String, usize> = HashMap::new();
+    for (lines, (first, last)) in pages
+        .iter()
+        .zip(&edges)
+        .filter_map(|(l, e)| Some((l, (*e)?)))
+    {
+        *first_lines.entry(without_digits(lines[first])).or_default() += 1;
+        *last_lines.entry(without_digits(lines[last])).or_default() += 1;
+    }
+    let is_edge_line = |counts: &HashMap<String, usize>, line: &str| {
+        let is_page_number = line.chars().all(|c| c.is_ascii_digit());
+        let count = counts[&without_digits(line)];
+        is_page_number || (count > 1 && count * 2 >= pages.len())
+    };
+
+    let mut text = String::new();
+    let mut paragraph_break = false;
+    for (lines, edges) in pages.iter().zip(edges) {
+        let (mut start, mut end) = match edges {
+            Some((first, last)) => (first, last + 1),
+            None => continue,
+        };
+        if is_edge_line(&first_lines, lines[start]) {
+            start += 1;
+        }
+        if start < end && is_edge_line(&last_lines, lines[end - 1]) {
+            end -= 1;
+        }
+
+        for line in &lines[start..end] {
+            if line.is_empty() {
+                paragraph_break = true;
+                continue;
+            }
+            // paragraphs are separated by one blank line
+            if paragraph_break && !text.is_empty() {
+                text.push('\n');
+            }
+            paragraph_break = false;
+
+            let continues_word = line.starts_with(|c: char| c.is_lowercase());
+            let before_hyphen = text
+                .strip_suffix("-\n")
+                .filter(|text| continues_word && text.ends_with(|c: char| c.is_alphabetic()));
+            if let Some(before_hyphen) = before_hyphen {
+                text.truncate(before_hyphen.len());
+            }
+            text.push_str(line);
+            text.push('\n');
+        }
+    }
+
+    text
+}
+
+/// The line with its digits replaced, so that headers and footers with
+/// page numbers are the same on every page.
+fn without_digits(line: &str) -> String {
+    line.chars()
+        .map(|c| if c.is_ascii_digit() { '#' } else { c })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headers_and_footers_are_left_out() {
+        let pages = [
+            "Moby Dick\n\nCHAPTER 1\n\nCall me Ishmael.\n\nPage 1 of 3",
+            "Moby Dick\nSome years ago, never\nmind how long.\nPage 2 of 3\n",
+            "\n  Moby Dick  \nThe end.\n\n\n\nPage 3 of 3",
+        ];
+        assert_eq!(
+            clean_pages(&pages),
+            "CHAPTER 1\n\nCall me Ishmael.\n\nSome years ago, never\nmind how long.\nThe end.\n"
+        );
+
+        // a line on a single page is text, unless it is a page number
+        assert_eq!(
+            clean_pages(&["Moby Dick\nThe end.\n7"]),
+            "Moby Dick\nThe end.\n"
+        );
+        assert_eq!(clean_pages(&["", "\n\n"]), "");
+    }
+
+    #[test]
+    fn hyphenated_words_are_joined() {
+        let pages = [
+            "Header\nThe exam-\nple of a sea-\nSerpent, ninety-\n9 feet long,",
+            "Header\nand hor-\nrible.\nFooter",
+            "Header\nThe end-\n\nof it.\nFooter",
+        ];
+        assert_eq!(
+            clean_pages(&pages),
+            "The example of a sea-\nSerpent, ninety-\n9 feet long,\nand horrible.\nThe end-\n\nof it.\n"
+        );
+    }
+}
@@ -0,0 +1,285 @@
+//! Reminders to practice typing, shown as desktop notifications.
+//!
+//! Reminders are installed as a user-level systemd timer on Linux and as
+//! a launchd agent on Mac OS. See [`install`] and [`uninstall`].
+
+use std::env;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::{Context, Result};
+use clap::ArgEnum;
+
+use crate::ToipeError;
+
+/// How often to be reminded.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ArgEnum, Debug)]
+pub enum ReminderInterval {
+    /// At the start of every hour.
+    Hourly,
+    /// Every day at 6 PM.
+    Daily,
+}
+
+/// Name of the systemd units.
+const SYSTEMD_UNIT_NAME: &str = "toipe-remind";
+
+/// Label of the launchd agent.
+const LAUNCHD_LABEL: &str = "io.github.samyak2.toipe.remind";
+
+/// Text of the notification.
+const MESSAGE: &str = "Time to practice typing! Run toipe to start.";
+
+/// A file that is written to install a reminder.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ReminderFile {
+    pub path: PathBuf,
+    pub contents: String,
+}
+
+/// Directory with the user's systemd units.
+fn systemd_user_dir() -> Result<PathBuf> {
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("HOME")
+                .filter(|home| !home.is_empty())
+                .map(|home| PathBuf::from(home).join(".config"))
+        })
+        .context("finding the config directory, HOME is not set")?;
+
+    Ok(config_dir.join("systemd").join("user"))
+}
+
+/// Directory with the user's launchd agents.
+fn launchd_agents_dir() -> Result<PathBuf> {
+    let home = env::var_os("HOME")
+        .filter(|home| !home.is_empty())
+        .context("finding the home directory, HOME is not set")?;
+
+    Ok(PathBuf::from(home).join("Library").join("LaunchAgents"))
+}
+
+/// Service and timer units that show a notification with
+/// `notify-send`.
+fn systemd_units(interval: ReminderInterval, dir: PathBuf) -> Vec<ReminderFile> {
+    let on_calendar = match interval {
+        ReminderInterval::Hourly => "hourly",
+        ReminderInterval::Daily => "*-*-* 18:00:00",
+    };
+
+    vec![
+        ReminderFile {
+            path: dir.join(format!("{}.service", SYSTEMD_UNIT_NAME)),
+            contents: format!(
+                "[Unit]\n\
+                 Description=Reminder to practice typing with toipe\n\
+                 \n\
+                 [Service]\n\
+                 Type=oneshot\n\
+                 ExecStart=notify-send toipe \"{}\"\n",
+                MESSAGE
+            ),
+        },
+        ReminderFile {
+            path: dir.join(format!("{}.timer", SYSTEMD_UNIT_NAME)),
+            contents: format!(
+                "[Unit]\n\
+                 Description=Reminder to practice typing with toipe\n\
+                 \n\
+                 [Timer]\n\
+                 OnCalendar={}\n\
+                 \n\
+                 [Install]\n\
+                 WantedBy=timers.target\n",
+                on_calendar
+            ),
+        },
+    ]
+}
+
+/// Agent that shows a notification with `osascript`.
+fn launchd_plist(interval: ReminderInterval, dir: PathBuf) -> ReminderFile {
+    let calendar_interval = match interval {
+        ReminderInterval::Hourly => "<key>Minute</key><integer>0</integer>",
+        ReminderInterval::Daily => {
+            "<key>Hour</key><integer>18</integer><key>Minute</key><integer>0</integer>"
+        }
+    };
+
+    ReminderFile {
+        path: dir.join(format!("{}.plist", LAUNCHD_LABEL)),
+        contents: format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+             <plist version=\"1.0\">\n\
+             <dict>\n\
+             \x20   <key>Label</key>\n\
+             \x20   <string>{}</string>\n\
+             \x20   <key>ProgramArguments</key>\n\
+             \x20   <array>\n\
+             \x20       <string>osascript</string>\n\
+             \x20       <string>-e</string>\n\
+             \x20       <string>display notification \"{}\" with title \"toipe\"</string>\n\
+             \x20   </array>\n\
+             \x20   <key>StartCalendarInterval</key>\n\
+             \x20   <dict>{}</dict>\n\
+             </dict>\n\
+             </plist>\n",
+            LAUNCHD_LABEL, MESSAGE, calendar_interval
+        ),
+    }
+}
+
+/// Files that install a reminder on this platform, along with the
+/// command that enables it.
+fn reminder_files(interval: ReminderInterval) -> Result<(Vec<ReminderFile>, String)> {
+    if cfg!(target_os = "macos") {
+        let plist = launchd_plist(interval, launchd_agents_dir()?);
+        let command = format!("launchctl load {}", plist.path.display());
+        Ok((vec![plist], command))
+    } else if cfg!(target_os = "linux") {
+        let command = format!("systemctl --user enable --now {}.timer", SYSTEMD_UNIT_NAME);
+        Ok((systemd_units(interval, systemd_user_dir()?), command))
+    } else {
+        Err(ToipeError::from(
+            "reminders are only supported on Linux and Mac OS".to_owned(),
+        ))?
+    }
+}
+
+/// Command that stops the reminder on this platform and disables it, so
+/// that it does not run again once its files are removed.
+fn disable_command() -> Command {
+    if cfg!(target_os = "macos") {
+        let mut command = Command::new("launchctl");
+        command.args(["remove", LAUNCHD_LABEL]);
+        command
+    } else {
+        let mut command = Command::new("systemctl");
+        command.args(["--user", "disable", "--now"]);
+        command.arg(format!("{}.timer", SYSTEMD_UNIT_NAME));
+        command
+    }
+}
+
+/// Writes the files of a reminder for this platform and prints how to
+/// enable it.
+///
+/// The reminder is not enabled, so that nothing runs without the user
+/// knowing.
+pub fn install(interval: ReminderInterval, out: &mut impl Write) -> Result<()> {
+    let (files, command) = reminder_files(interval)?;
+
+    for file in files {
+        if let Some(dir) = file.path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("creating the directory '{}'", dir.display()))?;
+        }
+        fs::write(&file.path, &file.contents)
+            .with_context(|| format!("writing '{}'", file.path.display()))?;
+        writeln!(out, "wrote {}", file.path.display())?;
+    }
+    writeln!(out, "to enable the reminder, run: {}", command)?;
+
+    Ok(())
+}
+
+/// Stops and disables the reminder for this platform, then removes its
+/// files, if they exist.
+///
+/// On Linux, the files include the link made when the timer was
+/// enabled. If the reminder could not be disabled, like when
+/// `systemctl` is not available, the command to stop it is printed.
+pub fn uninstall(out: &mut impl Write) -> Result<()> {
+    // the interval does not change the paths
+    let (files, _) = reminder_files(ReminderInterval::Daily)?;
+    let mut paths: Vec<PathBuf> = files.into_iter().map(|file| file.path).collect();
+
+    // disabled first, since an enabled timer whose files are removed is
+    // left behind
+    let mut disabled = false;
+    if paths.iter().any(|path| path.exists()) {
+        let mut command = disable_command();
+        match command.output() {
+            Ok(output) if output.status.success() => {
+                disabled = true;
+                writeln!(out, "disabled the reminder")?;
+            }
+            Ok(output) => log::warn!(
+                "could not disable the reminder: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Err(err) => log::warn!("could not disable the reminder: {}", err),
+        }
+    }
+
+    if cfg!(target_os = "linux") {
+        let timer_link = systemd_user_dir()?
+            .join("timers.target.wants")
+            .join(format!("{}.timer", SYSTEMD_UNIT_NAME));
+        paths.push(timer_link);
+    }
+
+    let mut removed = false;
+    for path in paths {
+        match fs::remove_file(&path) {
+            Ok(()) => {
+                removed = true;
+                writeln!(out, "removed {}", path.display())?;
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(err).with_context(|| format!("removing '{}'", path.display())),
+        }
+    }
+
+    if !removed {
+        writeln!(out, "no reminder was installed")?;
+    } else if !disabled {
+        let command = if cfg!(target_os = "macos") {
+            format!("launchctl remove {}", LAUNCHD_LABEL)
+        } else {
+            format!("systemctl --user stop {}.timer", SYSTEMD_UNIT_NAME)
+        };
+        writeln!(out, "if the reminder was enabled, also run: {}", command)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn systemd_timer() {
+        let units = systemd_units(ReminderInterval::Hourly, PathBuf::from("/units"));
+
+        assert_eq!(units[0].path, PathBuf::from("/units/toipe-remind.service"));
+        assert!(units[0].contents.contains("ExecStart=notify-send toipe"));
+        assert_eq!(units[1].path, PathBuf::from("/units/toipe-remind.timer"));
+        assert!(units[1].contents.contains("OnCalendar=hourly\n"));
+
+        let units = systemd_units(ReminderInterval::Daily, PathBuf::from("/units"));
+        assert!(units[1].contents.contains("OnCalendar=*-*-* 18:00:00\n"));
+    }
+
+    #[test]
+    fn launchd_agent() {
+        let plist = launchd_plist(ReminderInterval::Daily, PathBuf::from("/agents"));
+
+        assert_eq!(
+            plist.path,
+            PathBuf::from("/agents/io.github.samyak2.toipe.remind.plist")
+        );
+        assert!(plist
+            .contents
+            .contains("<string>io.github.samyak2.toipe.remind</string>"));
+        assert!(plist
+            .contents
+            .contains("<key>Hour</key><integer>18</integer>"));
+    }
+}
@@ -1,5 +1,129 @@
+use std::collections::HashMap;
+use std::ops::Range;
 use std::time::{Duration, Instant};
 
+use crate::difficulty::{text_difficulty, text_difficulty_with, WordRarities};
+
+/// A single key press recorded during a typing test.
+#[derive(Clone, Debug)]
+pub struct Keystroke {
+    /// time at which the key was pressed
+    pub at: Instant,
+    /// index of the char in the text that this key press applied to
+    pub position: usize,
+    /// char in the text at `position`
+    pub expected: char,
+    /// char that was typed, or `None` if the key deleted the char
+    pub typed: Option<char>,
+    /// whether the typed char matches the char in the text
+    pub correct: bool,
+}
+
+/// Histogram of the time between consecutive key presses in a test.
+///
+/// See [`ToipeResults::rhythm`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RhythmHistogram {
+    /// range of intervals in each bucket
+    pub bucket_width: Duration,
+    /// number of intervals in each bucket
+    ///
+    /// Bucket `i` has the intervals from `i * bucket_width` up to
+    /// `(i + 1) * bucket_width`. The last bucket also has all the longer
+    /// intervals.
+    pub counts: Vec<usize>,
+    /// median interval
+    pub p50: Duration,
+    /// interval that 95% of the intervals are not longer than
+    pub p95: Duration,
+}
+
+impl RhythmHistogram {
+    /// Plots the histogram as one line of text for each bucket, with a
+    /// bar of up to `max_bar_len` `#`s and the count.
+    ///
+    /// All the lines have the same length, so that their bars line up
+    /// when the lines are centered.
+    pub fn plot(&self, max_bar_len: usize) -> Vec<String> {
+        let width_ms = self.bucket_width.as_millis();
+        let labels: Vec<String> = (0..self.counts.len() as u128)
+            .map(|i| {
+                if i + 1 == self.counts.len() as u128 {
+                    format!("{}ms+", i * width_ms)
+                } else {
+                    format!("{}-{}ms", i * width_ms, (i + 1) * width_ms)
+                }
+            })
+            .collect();
+        let label_len = labels.iter().map(String::len).max().unwrap_or(0);
+        let max_count = self.counts.iter().copied().max().unwrap_or(0).max(1);
+        let count_len = max_count.to_string().len();
+
+        labels
+            .iter()
+            .zip(&self.counts)
+            .map(|(label, &count)| {
+                // round up so that every non-empty bucket has a bar
+                let bar_len = (count * max_bar_len).div_ceil(max_count);
+                format!(
+                    "{:>label_len$} {:<max_bar_len$} {:>count_len$}",
+                    label,
+                    "#".repeat(bar_len),
+                    count,
+                )
+            })
+            .collect()
+    }
+}
+
+/// How one word of the text was typed.
+///
+/// See [`ToipeResults::word_records`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WordRecord {
+    /// the word as in the text
+    pub word: String,
+    /// time from the key press before the word to the last key press
+    /// for it, or from its first key press for the first word
+    pub duration: Duration,
+    /// number of wrong chars typed for the word, including those that
+    /// were deleted later
+    pub errors: usize,
+    /// whether a char of the word was deleted
+    pub corrected: bool,
+}
+
+/// How a char of the text was typed, as listed by
+/// [`ToipeResults::char_records`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CharRecord {
+    /// the char in the text, or `None` if no key was pressed for it,
+    /// like for the chars of a word skipped when its time ran out
+    pub expected: Option<char>,
+    /// number of wrong chars typed for it, including those that were
+    /// deleted later
+    pub errors: usize,
+    /// whether the char was right at the end of the test, or `None` if
+    /// it was deleted and not typed again
+    pub correct: Option<bool>,
+}
+
+/// Lowercases a letter that has a single char lowercase form.
+fn lowercase(c: char) -> char {
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(lower), None) => lower,
+        _ => c,
+    }
+}
+
+/// Returns the value that `fraction` of the sorted values are not more
+/// than, using the nearest rank.
+fn percentile(sorted: &[Duration], fraction: f64) -> Duration {
+    let rank = (fraction * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
 /// Stores stats from a typing test.
 #[derive(Clone)]
 pub struct ToipeResults {
@@ -13,21 +137,114 @@ pub struct ToipeResults {
     /// number of wrongly typed characters including those that were cleared
     /// (by backspace or ctrl-w)
     pub total_char_errors: usize,
+    /// number of wrongly typed characters where the text has a digit,
+    /// included in `total_char_errors`
+    pub total_number_errors: usize,
     /// number of chars in given text that were correctly typed at the end of the test
     pub final_chars_typed_correctly: usize,
     /// number of chars in given text that were wrongly typed at the end of the test
     pub final_uncorrected_errors: usize,
+    /// number of times Backspace or ctrl-w was pressed and ignored, when
+    /// corrections are not allowed (see `--hardcore`)
+    pub attempted_corrections: usize,
+    /// number of words that were skipped because their time ran out,
+    /// when each word has a time budget (see `--word-budget`)
+    pub failed_words: usize,
     pub started_at: Instant,
     pub ended_at: Instant,
+    /// every key press that typed or deleted a char, in order
+    pub keystrokes: Vec<Keystroke>,
+    /// results of each page, if the text was split into multiple pages
+    pub pages: Vec<ToipeResults>,
+    /// whether the test ran till the end, as opposed to being quit or
+    /// restarted midway
+    pub completed: bool,
+    /// times during which the test was paused, left out of its duration
+    pub pauses: Vec<Range<Instant>>,
+    /// time from when each key was read to when the screen was updated
+    /// for it, only measured when trace logs are enabled (see
+    /// `--debug-log`)
+    pub render_latencies: Vec<Duration>,
 }
 
 impl ToipeResults {
+    /// Combines the results of consecutive pages of a test into one.
+    ///
+    /// Counts are added up and the test is considered to span from the
+    /// start of the first page to the end of the last. Positions of
+    /// keystrokes are offset to be relative to the whole text.
+    ///
+    /// The results of each page are available in
+    /// [`ToipeResults::pages`]. Results of a single page are returned
+    /// as-is.
+    ///
+    /// Panics if `pages` is empty.
+    pub fn from_pages(mut pages: Vec<ToipeResults>) -> Self {
+        if pages.len() == 1 {
+            return pages.pop().unwrap();
+        }
+
+        let mut keystrokes = Vec::new();
+        let mut position_offset = 0;
+        for page in &pages {
+            keystrokes.extend(page.keystrokes.iter().map(|keystroke| Keystroke {
+                position: keystroke.position + position_offset,
+                ..keystroke.clone()
+            }));
+            position_offset += page.total_chars_in_text;
+        }
+
+        let sum = |count: fn(&ToipeResults) -> usize| pages.iter().map(count).sum();
+
+        Self {
+            total_words: sum(|page| page.total_words),
+            total_chars_typed: sum(|page| page.total_chars_typed),
+            total_chars_in_text: sum(|page| page.total_chars_in_text),
+            total_char_errors: sum(|page| page.total_char_errors),
+            total_number_errors: sum(|page| page.total_number_errors),
+            final_chars_typed_correctly: sum(|page| page.final_chars_typed_correctly),
+            final_uncorrected_errors: sum(|page| page.final_uncorrected_errors),
+            attempted_corrections: sum(|page| page.attempted_corrections),
+            failed_words: sum(|page| page.failed_words),
+            started_at: pages.first().expect("no pages given").started_at,
+            ended_at: pages.last().expect("no pages given").ended_at,
+            completed: pages.last().expect("no pages given").completed,
+            pauses: pages.iter().flat_map(|page| page.pauses.clone()).collect(),
+            render_latencies: pages
+                .iter()
+                .flat_map(|page| page.render_latencies.clone())
+                .collect(),
+            keystrokes,
+            pages,
+        }
+    }
+
     /// Duration of the test.
     ///
     /// i.e., the time between the user pressing the first key and them
-    /// typing the last letter.
+    /// typing the last letter, except when the test was paused.
     pub fn duration(&self) -> Duration {
-        self.ended_at.duration_since(self.started_at)
+        self.elapsed_at(self.ended_at)
+    }
+
+    /// Time from the start of the test until `at`, except when the test
+    /// was paused.
+    fn elapsed_at(&self, at: Instant) -> Duration {
+        at.saturating_duration_since(self.started_at)
+            .saturating_sub(self.paused_between(self.started_at, at))
+    }
+
+    /// Time that the test was paused between `from` and `to`.
+    fn paused_between(&self, from: Instant, to: Instant) -> Duration {
+        self.pauses
+            .iter()
+            .map(|pause| {
+                pause
+                    .end
+                    .min(to)
+                    .saturating_duration_since(pause.start.max(from))
+            })
+            .sum()
     }
 
     /// Percentage of letters that were typed correctly.
@@ -40,6 +257,18 @@ impl ToipeResults {
             / self.total_chars_typed as f64
     }
 
+    /// Percentage of letters that were typed correctly across all the
+    /// given tests.
+    pub fn combined_accuracy(all_results: &[ToipeResults]) -> f64 {
+        let chars_typed: usize = all_results.iter().map(|r| r.total_chars_typed).sum();
+        let char_errors: usize = all_results.iter().map(|r| r.total_char_errors).sum();
+        if chars_typed == 0 {
+            return 0.0;
+        }
+
+        chars_typed.saturating_sub(char_errors) as f64 / chars_typed as f64
+    }
+
     /// Speed in (correctly typed) words per minute.
     ///
     /// Measured as (number of correctly typed chars / 5 - number of uncorrected errors) / minute
@@ -54,6 +283,337 @@ impl ToipeResults {
             .max(0.0)
             / (self.duration().as_secs_f64() / 60.0)
     }
+
+    /// Speed in words per minute, adjusted for the difficulty of the text
+    /// (see [`text_difficulty`]).
+    ///
+    /// This is the speed the test would have been typed at if the text
+    /// was as easy as the most common English words, so that tests with
+    /// different word lists can be compared.
+    pub fn normalized_wpm(&self, difficulty: f64) -> f64 {
+        self.wpm() * difficulty
+    }
+
+    /// Difficulty of the words that were typed (see
+    /// [`text_difficulty`]), leaving
+    /// out the words of the text that were not reached.
+    ///
+    /// The rarity of the words is taken from `rarities` if given, and
+    /// from the built-in English word lists otherwise.
+    pub fn text_difficulty(&self, rarities: Option<&WordRarities>) -> f64 {
+        let words: Vec<String> = self
+            .word_records()
+            .into_iter()
+            .map(|record| record.word)
+            .collect();
+        match rarities {
+            Some(rarities) => text_difficulty_with(&words, |word| rarities.rarity(word)),
+            None => text_difficulty(&words),
+        }
+    }
+
+    /// Time from the start of the test at which each char of the text was
+    /// typed for the first time, in order.
+    ///
+    /// The time at index `i` is when `i + 1` chars had been typed,
+    /// whether correctly or not.
+    pub fn progress(&self) -> Vec<Duration> {
+        let mut progress = Vec::new();
+        for keystroke in &self.keystrokes {
+            if keystroke.typed.is_some() && keystroke.position >= progress.len() {
+                let at = self.elapsed_at(keystroke.at);
+                progress.resize(keystroke.position + 1, at);
+            }
+        }
+        progress
+    }
+
+    /// Time between each key press and the one before it, except when
+    /// the test was paused.
+    pub fn keystroke_intervals(&self) -> Vec<Duration> {
+        self.keystrokes
+            .windows(2)
+            .map(|pair| self.time_between(&pair[0], &pair[1]))
+            .collect()
+    }
+
+    /// Time between two key presses, except when the test was paused.
+    fn time_between(&self, first: &Keystroke, second: &Keystroke) -> Duration {
+        second
+            .at
+            .saturating_duration_since(first.at)
+            .saturating_sub(self.paused_between(first.at, second.at))
+    }
+
+    /// Time taken to move from one letter to the next, for each pair of
+    /// consecutive letters that were both typed correctly.
+    ///
+    /// Letters are lowercased. Pairs that span a deleted char or that
+    /// have anything other than a letter are left out.
+    pub fn digraph_latencies(&self) -> Vec<((char, char), Duration)> {
+        self.keystrokes
+            .windows(2)
+            .filter_map(|pair| {
+                let (first, second) = (&pair[0], &pair[1]);
+                let (a, b) = (first.typed?, second.typed?);
+                let adjacent = second.position == first.position + 1;
+                let letters = a.is_alphabetic() && b.is_alphabetic();
+                if !(first.correct && second.correct && adjacent && letters) {
+                    return None;
+                }
+                let latency = self.time_between(first, second);
+                Some(((lowercase(a), lowercase(b)), latency))
+            })
+            .collect()
+    }
+
+    /// Letters that keys were pressed for, with whether the right key
+    /// was pressed, in order.
+    ///
+    /// Letters are lowercased. Key presses that deleted a char or that
+    /// were for anything other than a letter are left out.
+    pub fn letter_presses(&self) -> Vec<(char, bool)> {
+        self.keystrokes
+            .iter()
+            .filter(|keystroke| keystroke.typed.is_some() && keystroke.expected.is_alphabetic())
+            .map(|keystroke| (lowercase(keystroke.expected), keystroke.correct))
+            .collect()
+    }
+
+    /// Bigrams (two consecutive letters of the text) whose second letter
+    /// a key was pressed for, with whether the right key was pressed,
+    /// in order.
+    ///
+    /// Letters are lowercased. Key presses are left out as in
+    /// [`ToipeResults::letter_presses`], and so are those for a letter
+    /// that does not follow another letter.
+    pub fn bigram_presses(&self) -> Vec<((char, char), bool)> {
+        let text: HashMap<usize, char> = self
+            .keystrokes
+            .iter()
+            .map(|keystroke| (keystroke.position, keystroke.expected))
+            .collect();
+        self.keystrokes
+            .iter()
+            .filter(|keystroke| keystroke.typed.is_some() && keystroke.expected.is_alphabetic())
+            .filter_map(|keystroke| {
+                let previous = *text.get(&keystroke.position.checked_sub(1)?)?;
+                previous.is_alphabetic().then(|| {
+                    let bigram = (lowercase(previous), lowercase(keystroke.expected));
+                    (bigram, keystroke.correct)
+                })
+            })
+            .collect()
+    }
+
+    /// How each word of the text that keys were pressed for was typed,
+    /// in order.
+    ///
+    /// Words are found from the chars that keys were pressed for and
+    /// are separated by whitespace. Chars that no key was pressed for,
+    /// like those of a word skipped when its time ran out, also
+    /// separate words.
+    pub fn word_records(&self) -> Vec<WordRecord> {
+        let text_len = self
+            .keystrokes
+            .iter()
+            .map(|keystroke| keystroke.position + 1)
+            .max()
+            .unwrap_or(0);
+        let mut text: Vec<Option<char>> = vec![None; text_len];
+        for keystroke in &self.keystrokes {
+            text[keystroke.position] = Some(keystroke.expected);
+        }
+        let in_word = |position: usize| text[position].is_some_and(|c| !c.is_whitespace());
+
+        let mut records = Vec::new();
+        let mut position = 0;
+        while position < text_len {
+            if !in_word(position) {
+                position += 1;
+                continue;
+            }
+            let start = position;
+            while position < text_len && in_word(position) {
+                position += 1;
+            }
+            let word = start..position;
+
+            let in_range = |keystroke: &Keystroke| word.contains(&keystroke.position);
+            let first = self.keystrokes.iter().position(in_range);
+            let last = self.keystrokes.iter().rposition(in_range);
+            let (first, last) = (
+                first.expect("word was typed"),
+                last.expect("word was typed"),
+            );
+            let keystrokes = || {
+                self.keystrokes[first..=last]
+                    .iter()
+                    .filter(|keystroke| in_range(keystroke))
+            };
+            records.push(WordRecord {
+                word: text[word.clone()].iter().flatten().collect(),
+                duration: self.time_between(
+                    &self.keystrokes[first.saturating_sub(1)],
+                    &self.keystrokes[last],
+                ),
+                errors: keystrokes()
+                    .filter(|keystroke| keystroke.typed.is_some() && !keystroke.correct)
+                    .count(),
+                corrected: keystrokes().any(|keystroke| keystroke.typed.is_none()),
+            });
+        }
+
+        records
+    }
+
+    /// How each char of the text up to the last one that a key was
+    /// pressed for was typed, in order.
+    pub fn char_records(&self) -> Vec<CharRecord> {
+        let text_len = self
+            .keystrokes
+            .iter()
+            .map(|keystroke| keystroke.position + 1)
+            .max()
+            .unwrap_or(0);
+        let mut records = vec![
+            CharRecord {
+                expected: None,
+                errors: 0,
+                correct: None,
+            };
+            text_len
+        ];
+        for keystroke in &self.keystrokes {
+            let record = &mut records[keystroke.position];
+            record.expected = Some(keystroke.expected);
+            record.correct = keystroke.typed.map(|_| keystroke.correct);
+            if keystroke.typed.is_some() && !keystroke.correct {
+                record.errors += 1;
+            }
+        }
+        records
+    }
+
+    /// Median and 95th percentile of
+    /// [`ToipeResults::render_latencies`], or `None` if they were not
+    /// measured.
+    pub fn render_latency(&self) -> Option<(Duration, Duration)> {
+        if self.render_latencies.is_empty() {
+            return None;
+        }
+        let mut latencies = self.render_latencies.clone();
+        latencies.sort();
+
+        Some((percentile(&latencies, 0.5), percentile(&latencies, 0.95)))
+    }
+
+    /// Histogram of [`ToipeResults::keystroke_intervals`] with
+    /// `num_buckets` buckets, for seeing how even the typing rhythm is.
+    ///
+    /// The buckets are sized so that the intervals up to the 95th
+    /// percentile are spread across them. Returns `None` if fewer than
+    /// two keys were pressed.
+    pub fn rhythm(&self, num_buckets: usize) -> Option<RhythmHistogram> {
+        let mut intervals = self.keystroke_intervals();
+        if intervals.is_empty() || num_buckets == 0 {
+            return None;
+        }
+        intervals.sort();
+
+        let p50 = percentile(&intervals, 0.5);
+        let p95 = percentile(&intervals, 0.95);
+
+        // multiples of 10ms make for readable labels
+        let bucket_width_ms = (p95.as_millis() as u64)
+            .div_ceil(num_buckets as u64 * 10)
+            .max(1)
+            * 10;
+        let bucket_width = Duration::from_millis(bucket_width_ms);
+
+        let mut counts = vec![0; num_buckets];
+        for interval in &intervals {
+            let bucket = (interval.as_millis() / bucket_width.as_millis()) as usize;
+            counts[bucket.min(num_buckets - 1)] += 1;
+        }
+
+        Some(RhythmHistogram {
+            bucket_width,
+            counts,
+            p50,
+            p95,
+        })
+    }
+
+    /// Speed in words per minute, excluding the first `warmup` duration
+    /// of the test.
+    ///
+    /// Calculated the same way as [`ToipeResults::wpm`], but only
+    /// considering the chars (present at the end of the test) that were
+    /// typed after the warm-up. This keeps the reaction time before the
+    /// first few words from dragging down the speed of short tests.
+    ///
+    /// Returns `None` if the test was not longer than the warm-up.
+    pub fn settled_wpm(&self, warmup: Duration) -> Option<f64> {
+        let settled_duration = self.duration().checked_sub(warmup)?;
+        if settled_duration.is_zero() {
+            return None;
+        }
+
+        // last keystroke that typed the char at each position
+        let mut final_keystrokes: Vec<Option<&Keystroke>> = Vec::new();
+        for keystroke in &self.keystrokes {
+            if keystroke.position >= final_keystrokes.len() {
+                final_keystrokes.resize(keystroke.position + 1, None);
+            }
+            final_keystrokes[keystroke.position] = keystroke.typed.map(|_| keystroke);
+        }
+
+        let (chars_typed_correctly, uncorrected_errors) = final_keystrokes
+            .iter()
+            .flatten()
+            .filter(|keystroke| self.elapsed_at(keystroke.at) >= warmup)
+            .fold((0, 0), |(correct, errors), keystroke| {
+                if keystroke.correct {
+                    (correct + 1, errors)
+                } else {
+                    (correct, errors + 1)
+                }
+            });
+
+        Some(
+            (chars_typed_correctly as f64 / 5.0 - uncorrected_errors as f64).max(0.0)
+                / (settled_duration.as_secs_f64() / 60.0),
+        )
+    }
+}
+
+/// Results of a completed test with the given key presses, whose counts
+/// are all 0, for tests to fill in.
+#[cfg(test)]
+pub(crate) fn results_with(
+    started_at: Instant,
+    ended_at: Instant,
+    keystrokes: Vec<Keystroke>,
+) -> ToipeResults {
+    ToipeResults {
+        total_words: 0,
+        total_chars_typed: 0,
+        total_chars_in_text: 0,
+        total_char_errors: 0,
+        total_number_errors: 0,
+        final_chars_typed_correctly: 0,
+        final_uncorrected_errors: 0,
+        attempted_corrections: 0,
+        failed_words: 0,
+        started_at,
+        ended_at,
+        keystrokes,
+        pages: Vec::new(),
+        completed: true,
+        pauses: Vec::new(),
+        render_latencies: Vec::new(),
+    }
 }
 
 #[cfg(test)]
@@ -66,14 +626,12 @@ mod tests {
         let started_at = Instant::now();
         let ended_at = started_at + Duration::new(10, 0);
         let results = ToipeResults {
-            total_words: 0,
             total_chars_typed: 100,
             total_chars_in_text: 120,
             total_char_errors: 10,
             final_chars_typed_correctly: 80,
             final_uncorrected_errors: 2,
-            started_at,
-            ended_at,
+            ..results_with(started_at, ended_at, Vec::new())
         };
 
         assert_eq!(results.duration(), Duration::new(10, 0));
@@ -86,14 +644,9 @@ mod tests {
     fn accuracy() {
         fn get_toipe_results(total_chars_typed: usize, total_char_errors: usize) -> ToipeResults {
             ToipeResults {
-                total_words: 0,
                 total_chars_typed,
-                total_chars_in_text: 0,
                 total_char_errors,
-                final_chars_typed_correctly: 0,
-                final_uncorrected_errors: 0,
-                started_at: Instant::now(),
-                ended_at: Instant::now(),
+                ..results_with(Instant::now(), Instant::now(), Vec::new())
             }
         }
 
@@ -139,14 +692,9 @@ mod tests {
             let nanoseconds = (duration - seconds) * 1_000_000_000.0;
             let ended_at = started_at + Duration::new(seconds as u64, nanoseconds as u32);
             ToipeResults {
-                total_words: 0,
-                total_chars_typed: 0,
-                total_chars_in_text: 0,
-                total_char_errors: 0,
                 final_chars_typed_correctly,
                 final_uncorrected_errors,
-                started_at,
-                ended_at,
+                ..results_with(started_at, ended_at, Vec::new())
             }
         }
 
@@ -201,4 +749,338 @@ mod tests {
         );
         // we don't consider the case of duration = 0 because that seems impossible
     }
+
+    #[test]
+    fn settled_wpm() {
+        let started_at = Instant::now();
+        let ended_at = started_at + Duration::new(40, 0);
+
+        let keystroke =
+            |secs: u64, position: usize, typed: Option<char>, correct: bool| Keystroke {
+                at: started_at + Duration::new(secs, 0),
+                position,
+                expected: 'a',
+                typed,
+                correct,
+            };
+
+        let mut keystrokes = vec![
+            // typed during warm-up, not counted
+            keystroke(0, 0, Some('a'), true),
+            keystroke(5, 1, Some('b'), true),
+            // error during warm-up, corrected after it
+            keystroke(8, 2, Some('x'), false),
+            keystroke(12, 2, None, false),
+            keystroke(13, 2, Some('c'), true),
+        ];
+        // 50 correct chars after warm-up
+        keystrokes.extend((3..53).map(|i| keystroke(20, i, Some('d'), true)));
+        // an uncorrected error
+        keystrokes.push(keystroke(30, 53, Some('y'), false));
+        // a char that was typed and then deleted
+        keystrokes.push(keystroke(31, 54, Some('e'), true));
+        keystrokes.push(keystroke(32, 54, None, false));
+
+        let results = results_with(started_at, ended_at, keystrokes);
+
+        // (51 chars / 5 - 1 error) / 0.5 min
+        assert_ulps_eq!(
+            results.settled_wpm(Duration::new(10, 0)).unwrap(),
+            18.4,
+            max_ulps = 1
+        );
+        // no warm-up counts every char
+        assert_ulps_eq!(
+            results.settled_wpm(Duration::ZERO).unwrap(),
+            (53.0 / 5.0 - 1.0) / (40.0 / 60.0),
+            max_ulps = 1
+        );
+        // test was shorter than the warm-up
+        assert!(results.settled_wpm(Duration::new(40, 0)).is_none());
+        assert!(results.settled_wpm(Duration::new(50, 0)).is_none());
+    }
+
+    #[test]
+    fn digraph_latencies() {
+        let started_at = Instant::now();
+        let keystroke = |ms, position, expected, typed: Option<char>| Keystroke {
+            at: started_at + Duration::from_millis(ms),
+            position,
+            expected,
+            typed,
+            correct: typed == Some(expected),
+        };
+        // "Th" typed, "e" typed wrong and deleted, then "e ", "a" typed
+        let results = results_with(
+            started_at,
+            started_at + Duration::from_millis(1000),
+            vec![
+                keystroke(0, 0, 'T', Some('T')),
+                keystroke(100, 1, 'h', Some('h')),
+                keystroke(250, 2, 'e', Some('w')),
+                keystroke(300, 2, 'e', None),
+                keystroke(400, 2, 'e', Some('e')),
+                keystroke(500, 3, ' ', Some(' ')),
+                keystroke(650, 4, 'a', Some('a')),
+            ],
+        );
+
+        assert_eq!(
+            results.digraph_latencies(),
+            vec![(('t', 'h'), Duration::from_millis(100))]
+        );
+        assert_eq!(
+            results.letter_presses(),
+            vec![
+                ('t', true),
+                ('h', true),
+                ('e', false),
+                ('e', true),
+                ('a', true)
+            ]
+        );
+        assert_eq!(
+            results.bigram_presses(),
+            vec![(('t', 'h'), true), (('h', 'e'), false), (('h', 'e'), true)]
+        );
+        assert_eq!(
+            results.word_records(),
+            vec![
+                WordRecord {
+                    word: "The".to_string(),
+                    duration: Duration::from_millis(400),
+                    errors: 1,
+                    corrected: true,
+                },
+                WordRecord {
+                    word: "a".to_string(),
+                    duration: Duration::from_millis(150),
+                    errors: 0,
+                    corrected: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn char_records() {
+        let started_at = Instant::now();
+        let keystroke = |position, expected, typed: Option<char>| Keystroke {
+            at: started_at,
+            position,
+            expected,
+            typed,
+            correct: typed == Some(expected),
+        };
+        // "a" typed wrong twice, "b" typed wrong, "c" skipped, "d" typed
+        // and deleted
+        let results = results_with(
+            started_at,
+            started_at,
+            vec![
+                keystroke(0, 'a', Some('x')),
+                keystroke(0, 'a', None),
+                keystroke(0, 'a', Some('y')),
+                keystroke(0, 'a', None),
+                keystroke(0, 'a', Some('a')),
+                keystroke(1, 'b', Some('x')),
+                keystroke(3, 'd', Some('d')),
+                keystroke(3, 'd', None),
+            ],
+        );
+
+        let record = |expected, errors, correct| CharRecord {
+            expected,
+            errors,
+            correct,
+        };
+        assert_eq!(
+            results.char_records(),
+            vec![
+                record(Some('a'), 2, Some(true)),
+                record(Some('b'), 1, Some(false)),
+                record(None, 0, None),
+                record(Some('d'), 0, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn progress() {
+        let started_at = Instant::now();
+        let keystroke = |ms, position, typed| Keystroke {
+            at: started_at + Duration::from_millis(ms),
+            position,
+            expected: 'a',
+            typed,
+            correct: true,
+        };
+        // second char typed, deleted and typed again
+        let results = results_with(
+            started_at,
+            started_at + Duration::from_millis(1000),
+            vec![
+                keystroke(0, 0, Some('a')),
+                keystroke(100, 1, Some('x')),
+                keystroke(200, 1, None),
+                keystroke(300, 1, Some('b')),
+                keystroke(400, 2, Some('c')),
+            ],
+        );
+
+        assert_eq!(
+            results.progress(),
+            vec![
+                Duration::from_millis(0),
+                Duration::from_millis(100),
+                Duration::from_millis(400),
+            ]
+        );
+    }
+
+    #[test]
+    fn rhythm() {
+        let started_at = Instant::now();
+        let mut at = started_at;
+        let mut keystrokes = Vec::new();
+        for (position, interval_ms) in [0, 100, 100, 120, 150, 90, 110, 400, 100, 130, 105]
+            .into_iter()
+            .enumerate()
+        {
+            at += Duration::from_millis(interval_ms);
+            keystrokes.push(Keystroke {
+                at,
+                position,
+                expected: 'a',
+                typed: Some('a'),
+                correct: true,
+            });
+        }
+        let results = ToipeResults {
+            render_latencies: [3, 1, 2, 40].map(Duration::from_millis).to_vec(),
+            ..results_with(started_at, at, keystrokes)
+        };
+
+        assert_eq!(results.keystroke_intervals().len(), 10);
+
+        assert_eq!(
+            results.render_latency(),
+            Some((Duration::from_millis(2), Duration::from_millis(40)))
+        );
+
+        let rhythm = results.rhythm(4).unwrap();
+        assert_eq!(rhythm.p50, Duration::from_millis(105));
+        assert_eq!(rhythm.p95, Duration::from_millis(400));
+        assert_eq!(rhythm.bucket_width, Duration::from_millis(100));
+        assert_eq!(rhythm.counts, vec![1, 8, 0, 1]);
+        assert_eq!(
+            rhythm.plot(8),
+            vec![
+                "  0-100ms #        1",
+                "100-200ms ######## 8",
+                "200-300ms          0",
+                "   300ms+ #        1",
+            ]
+        );
+
+        // no intervals with a single key press
+        let results = ToipeResults {
+            keystrokes: results.keystrokes[..1].to_vec(),
+            ..results
+        };
+        assert!(results.rhythm(4).is_none());
+    }
+
+    #[test]
+    fn pauses() {
+        let started_at = Instant::now();
+        let at = |ms: u64| started_at + Duration::from_millis(ms);
+        let keystroke = |ms: u64, position: usize, typed: char| Keystroke {
+            at: at(ms),
+            position,
+            expected: typed,
+            typed: Some(typed),
+            correct: true,
+        };
+        let results = ToipeResults {
+            total_words: 1,
+            total_chars_typed: 3,
+            total_chars_in_text: 3,
+            final_chars_typed_correctly: 3,
+            pauses: vec![at(150)..at(5150)],
+            ..results_with(
+                started_at,
+                at(5300),
+                vec![
+                    keystroke(0, 0, 't'),
+                    keystroke(100, 1, 'h'),
+                    keystroke(5200, 2, 'e'),
+                ],
+            )
+        };
+
+        assert_eq!(results.duration(), Duration::from_millis(300));
+        assert_eq!(
+            results.keystroke_intervals(),
+            vec![Duration::from_millis(100), Duration::from_millis(100)]
+        );
+        assert_eq!(
+            results.progress(),
+            vec![
+                Duration::from_millis(0),
+                Duration::from_millis(100),
+                Duration::from_millis(200)
+            ]
+        );
+    }
+
+    #[test]
+    fn from_pages() {
+        let started_at = Instant::now();
+        let page = |start: u64, end: u64, correct: usize, errors: usize| ToipeResults {
+            total_words: 10,
+            total_chars_typed: correct + errors,
+            total_chars_in_text: correct + errors,
+            total_char_errors: errors,
+            total_number_errors: errors / 2,
+            final_chars_typed_correctly: correct,
+            final_uncorrected_errors: errors,
+            ..results_with(
+                started_at + Duration::new(start, 0),
+                started_at + Duration::new(end, 0),
+                vec![Keystroke {
+                    at: started_at + Duration::new(start, 0),
+                    position: 0,
+                    expected: 'a',
+                    typed: Some('a'),
+                    correct: true,
+                }],
+            )
+        };
+
+        let results = ToipeResults::from_pages(vec![page(0, 20, 50, 0), page(20, 30, 48, 2)]);
+
+        assert_eq!(results.total_words, 20);
+        assert_eq!(results.total_chars_typed, 100);
+        assert_eq!(results.total_chars_in_text, 100);
+        assert_eq!(results.total_char_errors, 2);
+        assert_eq!(results.total_number_errors, 1);
+        assert_eq!(results.final_chars_typed_correctly, 98);
+        assert_eq!(results.final_uncorrected_errors, 2);
+        assert_eq!(results.duration(), Duration::new(30, 0));
+        assert_eq!(
+            results
+                .keystrokes
+                .iter()
+                .map(|keystroke| keystroke.position)
+                .collect::<Vec<_>>(),
+            vec![0, 50]
+        );
+        assert_eq!(results.pages.len(), 2);
+
+        // a single page is returned as-is
+        let results = ToipeResults::from_pages(vec![page(0, 20, 50, 0)]);
+        assert!(results.pages.is_empty());
+        assert_eq!(results.total_words, 10);
+    }
 }
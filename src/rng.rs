@@ -0,0 +1,112 @@
+//! Random numbers for everything that is chosen at random, like the
+//! words of a test.
+//!
+//! See [`ToipeRng`].
+
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+
+/// Random number generator given to the parts of toipe that choose
+/// things at random, like word selectors.
+///
+/// A generator made from a seed gives the same numbers every time, so
+/// tests with the same seed have the same words (see `--seed`). Each
+/// part gets its own generator from [`ToipeRng::fork`], so that one
+/// part drawing more numbers does not change what the others draw.
+///
+/// ```
+/// use rand::Rng;
+/// use toipe::rng::ToipeRng;
+///
+/// let mut rng = ToipeRng::from_seed(42);
+/// let mut words_rng = rng.fork();
+/// let index = words_rng.gen_range(0..100);
+///
+/// let mut words_rng = ToipeRng::from_seed(42).fork();
+/// assert_eq!(words_rng.gen_range(0..100), index);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ToipeRng(StdRng);
+
+impl ToipeRng {
+    /// Creates a generator that gives the same numbers for the same
+    /// seed.
+    pub fn from_seed(seed: u64) -> Self {
+        Self(StdRng::seed_from_u64(seed))
+    }
+
+    /// Creates a generator seeded from the operating system, which
+    /// gives different numbers every time.
+    pub fn from_entropy() -> Self {
+        Self(StdRng::from_entropy())
+    }
+
+    /// Creates a generator from the seed, if any, or from the operating
+    /// system otherwise.
+    pub fn new(seed: Option<u64>) -> Self {
+        match seed {
+            Some(seed) => Self::from_seed(seed),
+            None => Self::from_entropy(),
+        }
+    }
+
+    /// Creates a new generator seeded from this one, for a part of
+    /// toipe that draws numbers on its own.
+    pub fn fork(&mut self) -> Self {
+        Self::from_seed(self.0.gen())
+    }
+}
+
+impl Default for ToipeRng {
+    fn default() -> Self {
+        Self::from_entropy()
+    }
+}
+
+impl RngCore for ToipeRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draw(rng: &mut ToipeRng) -> Vec<u32> {
+        (0..10).map(|_| rng.gen_range(0..1000)).collect()
+    }
+
+    #[test]
+    fn seeded_generators_are_reproducible() {
+        let mut rng = ToipeRng::from_seed(7);
+        let mut first = rng.fork();
+        let mut second = rng.fork();
+        let first_numbers = draw(&mut first);
+        assert_ne!(first_numbers, draw(&mut second));
+
+        // forks do not depend on how much the other forks drew
+        let mut rng = ToipeRng::from_seed(7);
+        let mut first = rng.fork();
+        let mut second_again = rng.fork();
+        draw(&mut second_again);
+        assert_eq!(draw(&mut first), first_numbers);
+
+        assert_ne!(
+            draw(&mut ToipeRng::from_seed(7)),
+            draw(&mut ToipeRng::from_seed(8))
+        );
+    }
+}
@@ -0,0 +1,290 @@
+//! Typing Japanese kana as romaji, without an input method (see
+//! `--romaji`).
+//!
+//! See [`RomajiInput`].
+
+/// Romaji spellings of the hiragana that they are typed as.
+///
+/// Katakana are typed the same way as the hiragana for the same sound
+/// (see [`matching_kana`]).
+const ROMAJI: &[(&str, &str)] = &[
+    ("a", "あ"),
+    ("i", "い"),
+    ("u", "う"),
+    ("e", "え"),
+    ("o", "お"),
+    ("ka", "か"),
+    ("ki", "き"),
+    ("ku", "く"),
+    ("ke", "け"),
+    ("ko", "こ"),
+    ("sa", "さ"),
+    ("si", "し"),
+    ("shi", "し"),
+    ("su", "す"),
+    ("se", "せ"),
+    ("so", "そ"),
+    ("ta", "た"),
+    ("ti", "ち"),
+    ("chi", "ち"),
+    ("tu", "つ"),
+    ("tsu", "つ"),
+    ("te", "て"),
+    ("to", "と"),
+    ("na", "な"),
+    ("ni", "に"),
+    ("nu", "ぬ"),
+    ("ne", "ね"),
+    ("no", "の"),
+    ("nn", "ん"),
+    ("n'", "ん"),
+    ("ha", "は"),
+    ("hi", "ひ"),
+    ("hu", "ふ"),
+    ("fu", "ふ"),
+    ("he", "へ"),
+    ("ho", "ほ"),
+    ("ma", "ま"),
+    ("mi", "み"),
+    ("mu", "む"),
+    ("me", "め"),
+    ("mo", "も"),
+    ("ya", "や"),
+    ("yu", "ゆ"),
+    ("yo", "よ"),
+    ("ra", "ら"),
+    ("ri", "り"),
+    ("ru", "る"),
+    ("re", "れ"),
+    ("ro", "ろ"),
+    ("wa", "わ"),
+    ("wo", "を"),
+    ("ga", "が"),
+    ("gi", "ぎ"),
+    ("gu", "ぐ"),
+    ("ge", "げ"),
+    ("go", "ご"),
+    ("za", "ざ"),
+    ("zi", "じ"),
+    ("ji", "じ"),
+    ("zu", "ず"),
+    ("ze", "ぜ"),
+    ("zo", "ぞ"),
+    ("da", "だ"),
+    ("di", "ぢ"),
+    ("du", "づ"),
+    ("de", "で"),
+    ("do", "ど"),
+    ("ba", "ば"),
+    ("bi", "び"),
+    ("bu", "ぶ"),
+    ("be", "べ"),
+    ("bo", "ぼ"),
+    ("pa", "ぱ"),
+    ("pi", "ぴ"),
+    ("pu", "ぷ"),
+    ("pe", "ぺ"),
+    ("po", "ぽ"),
+    ("kya", "きゃ"),
+    ("kyu", "きゅ"),
+    ("kyo", "きょ"),
+    ("sya", "しゃ"),
+    ("syu", "しゅ"),
+    ("syo", "しょ"),
+    ("sha", "しゃ"),
+    ("shu", "しゅ"),
+    ("she", "しぇ"),
+    ("sho", "しょ"),
+    ("tya", "ちゃ"),
+    ("tyu", "ちゅ"),
+    ("tyo", "ちょ"),
+    ("cha", "ちゃ"),
+    ("chu", "ちゅ"),
+    ("che", "ちぇ"),
+    ("cho", "ちょ"),
+    ("nya", "にゃ"),
+    ("nyu", "にゅ"),
+    ("nyo", "にょ"),
+    ("hya", "ひゃ"),
+    ("hyu", "ひゅ"),
+    ("hyo", "ひょ"),
+    ("fa", "ふぁ"),
+    ("fi", "ふぃ"),
+    ("fe", "ふぇ"),
+    ("fo", "ふぉ"),
+    ("mya", "みゃ"),
+    ("myu", "みゅ"),
+    ("myo", "みょ"),
+    ("rya", "りゃ"),
+    ("ryu", "りゅ"),
+    ("ryo", "りょ"),
+    ("gya", "ぎゃ"),
+    ("gyu", "ぎゅ"),
+    ("gyo", "ぎょ"),
+    ("zya", "じゃ"),
+    ("zyu", "じゅ"),
+    ("zyo", "じょ"),
+    ("ja", "じゃ"),
+    ("ju", "じゅ"),
+    ("je", "じぇ"),
+    ("jo", "じょ"),
+    ("jya", "じゃ"),
+    ("jyu", "じゅ"),
+    ("jyo", "じょ"),
+    ("bya", "びゃ"),
+    ("byu", "びゅ"),
+    ("byo", "びょ"),
+    ("pya", "ぴゃ"),
+    ("pyu", "ぴゅ"),
+    ("pyo", "ぴょ"),
+    ("xa", "ぁ"),
+    ("xi", "ぃ"),
+    ("xu", "ぅ"),
+    ("xe", "ぇ"),
+    ("xo", "ぉ"),
+    ("la", "ぁ"),
+    ("li", "ぃ"),
+    ("lu", "ぅ"),
+    ("le", "ぇ"),
+    ("lo", "ぉ"),
+    ("xya", "ゃ"),
+    ("xyu", "ゅ"),
+    ("xyo", "ょ"),
+    ("lya", "ゃ"),
+    ("lyu", "ゅ"),
+    ("lyo", "ょ"),
+    ("xtu", "っ"),
+    ("ltu", "っ"),
+    ("xtsu", "っ"),
+    ("-", "ー"),
+    (",", "、"),
+    (".", "。"),
+];
+
+/// Converts romaji to hiragana as they are typed.
+///
+/// Letters are kept pending until they spell a kana, so several keys
+/// make one kana, and some spellings make two (`kya` is `きゃ`). Like
+/// in input methods, a doubled consonant makes a small `っ` (`kka` is
+/// `っか`) and `n` makes `ん` when it is not followed by a vowel or `y`
+/// (`nn` or `n'` spell it before those). Letters that cannot spell a
+/// kana are passed through as they are, so that they count as mistakes.
+///
+/// ```
+/// use toipe::romaji::RomajiInput;
+///
+/// let mut input = RomajiInput::default();
+/// let kana: String = "kyouha".chars().map(|c| input.push(c)).collect();
+/// assert_eq!(kana, "きょうは");
+/// ```
+#[derive(Clone, Default, Debug)]
+pub struct RomajiInput {
+    /// letters typed that do not spell a kana yet
+    pending: String,
+}
+
+impl RomajiInput {
+    /// Adds a typed char, returning the kana that it completes, if any.
+    pub fn push(&mut self, c: char) -> String {
+        self.pending.push(c.to_ascii_lowercase());
+
+        let mut kana = String::new();
+        while !self.pending.is_empty() {
+            if let Some((_, spelled)) = ROMAJI.iter().find(|(romaji, _)| *romaji == self.pending) {
+                kana.push_str(spelled);
+                self.pending.clear();
+                break;
+            }
+            if ROMAJI
+                .iter()
+                .any(|(romaji, _)| romaji.starts_with(self.pending.as_str()))
+            {
+                break;
+            }
+
+            // the first letter cannot be part of a kana with the rest
+            let mut letters = self.pending.chars();
+            let first = letters.next().unwrap_or_default();
+            let second = letters.next();
+            if first == 'n' {
+                kana.push('ん');
+            } else if second == Some(first) && is_consonant(first) {
+                kana.push('っ');
+            } else {
+                kana.push(first);
+            }
+            self.pending.remove(0);
+        }
+
+        kana
+    }
+
+    /// Letters typed that do not spell a kana yet.
+    pub fn pending(&self) -> &str {
+        &self.pending
+    }
+
+    /// Removes the last pending letter, returning whether there was one.
+    pub fn pop(&mut self) -> bool {
+        self.pending.pop().is_some()
+    }
+
+    /// Returns `ん` if the pending letters are a lone `n`, which would
+    /// otherwise wait for the next letter, like at the end of the text.
+    pub fn finish_n(&mut self) -> Option<char> {
+        if self.pending != "n" {
+            return None;
+        }
+        self.pending.clear();
+        Some('ん')
+    }
+}
+
+/// Whether the char is a romaji consonant that is doubled to make a
+/// small `っ`.
+fn is_consonant(c: char) -> bool {
+    c.is_ascii_lowercase() && !"aiueon".contains(c)
+}
+
+/// Returns the kana typed as the katakana for the same sound if the
+/// expected kana is a katakana, or as it is otherwise.
+pub fn matching_kana(typed: char, expected: char) -> char {
+    let is_katakana = ('\u{30a1}'..='\u{30f6}').contains(&expected);
+    if is_katakana && ('\u{3041}'..='\u{3096}').contains(&typed) {
+        char::from_u32(typed as u32 + 0x60).unwrap_or(typed)
+    } else {
+        typed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(romaji: &str) -> String {
+        let mut input = RomajiInput::default();
+        romaji.chars().map(|c| input.push(c)).collect()
+    }
+
+    #[test]
+    fn romaji_to_kana() {
+        assert_eq!(convert("arigatou"), "ありがとう");
+        assert_eq!(convert("shinbun"), "しんぶ");
+        assert_eq!(convert("konnnichiha"), "こんにちは");
+        assert_eq!(convert("kitte"), "きって");
+        assert_eq!(convert("kan'i"), "かんい");
+        assert_eq!(convert("q a"), "q あ");
+        assert_eq!(convert("Sushi"), "すし");
+
+        // a trailing n waits for the next letter
+        let mut input = RomajiInput::default();
+        assert_eq!(input.push('n'), "");
+        assert_eq!(input.pending(), "n");
+        assert_eq!(input.finish_n(), Some('ん'));
+        assert!(!input.pop());
+
+        assert_eq!(matching_kana('ね', 'ネ'), 'ネ');
+        assert_eq!(matching_kana('ね', 'ね'), 'ね');
+        assert_eq!(matching_kana('ー', 'ー'), 'ー');
+    }
+}
@@ -0,0 +1,163 @@
+//! Running multiple typing tests in one terminal session.
+//!
+//! See [`ToipeSession`].
+
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::config::ToipeConfig;
+use crate::end_condition::EndCondition;
+use crate::input::KeyEvents;
+use crate::results::ToipeResults;
+use crate::Toipe;
+
+/// Function invoked after each completed test.
+type TestEndHook = Box<dyn FnMut(&ToipeResults) -> Result<()>>;
+
+/// Runs typing tests one after the other until the user quits.
+///
+/// The terminal is set up once for the whole session and the word
+/// selector is reused across tests. This is what the `toipe` binary
+/// uses.
+///
+/// ```no_run
+/// use clap::StructOpt;
+/// use toipe::config::ToipeConfig;
+/// use toipe::input::KeyEvents;
+/// use toipe::session::ToipeSession;
+///
+/// let mut session = ToipeSession::new(ToipeConfig::parse())?
+///     .on_test_end(|results| {
+///         eprintln!("{:.1} wpm", results.wpm());
+///         Ok(())
+///     });
+/// session.run(&mut KeyEvents::from_stdin())?;
+///
+/// println!("completed {} tests", session.results().len());
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct ToipeSession {
+    toipe: Toipe,
+    results: Vec<ToipeResults>,
+    test_end_hooks: Vec<TestEndHook>,
+}
+
+impl ToipeSession {
+    /// Initializes the terminal and displays the first test.
+    ///
+    /// See [`Toipe::new`].
+    pub fn new(config: ToipeConfig) -> Result<Self> {
+        Ok(Self::from_toipe(Toipe::new(config)?))
+    }
+
+    /// Runs the tests of the given typing test, like one displayed on a
+    /// [`FakeTerminal`](crate::tui::FakeTerminal) or with words from
+    /// another selector (see [`Toipe::with_word_selector`]).
+    pub fn from_toipe(toipe: Toipe) -> Self {
+        Self {
+            toipe,
+            results: Vec::new(),
+            test_end_hooks: Vec::new(),
+        }
+    }
+
+    /// Adds a function to be invoked with the results of each completed
+    /// test, before the next test starts.
+    ///
+    /// Hooks are invoked in the order they were added. An error
+    /// returned by a hook ends the session.
+    pub fn on_test_end<F>(mut self, hook: F) -> Self
+    where
+        F: FnMut(&ToipeResults) -> Result<()> + 'static,
+    {
+        self.test_end_hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Adds a function to be invoked with the results of each test so
+    /// far while it is typed.
+    ///
+    /// See [`Toipe::on_checkpoint`].
+    pub fn on_checkpoint<F>(mut self, interval: Duration, hook: F) -> Self
+    where
+        F: FnMut(&ToipeResults) -> Result<()> + 'static,
+    {
+        self.toipe = self.toipe.on_checkpoint(interval, hook);
+        self
+    }
+
+    /// Adds a condition that ends each test before the end of its text.
+    ///
+    /// See [`Toipe::with_end_condition`].
+    pub fn with_end_condition<C>(mut self, condition: C) -> Self
+    where
+        C: EndCondition + 'static,
+    {
+        self.toipe = self.toipe.with_end_condition(condition);
+        self
+    }
+
+    /// Runs tests until the user quits.
+    pub fn run(&mut self, keys: &mut KeyEvents) -> Result<()> {
+        loop {
+            let (to_restart, results) = self.toipe.test(keys)?;
+
+            if results.completed {
+                for hook in &mut self.test_end_hooks {
+                    hook(&results)?;
+                }
+                self.results.push(results);
+            }
+
+            if !to_restart {
+                return Ok(());
+            }
+
+            // the terminal may have been resized since the last test
+            if !self.toipe.restart_when_fits(keys)? {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Results of all the tests completed in this session, in order.
+    pub fn results(&self) -> &[ToipeResults] {
+        &self.results
+    }
+
+    /// One line summary of the tests completed in this session, or
+    /// `None` if no test was completed.
+    ///
+    /// Describes the last test if only one test was completed, and all
+    /// the tests otherwise.
+    pub fn summary(&self) -> Option<String> {
+        match self.results.as_slice() {
+            [] => None,
+            [results] => Some(format!(
+                "{:.1} wpm, {:.1}% accuracy, {} words in {}s",
+                results.wpm(),
+                results.accuracy() * 100.0,
+                results.total_words,
+                results.duration().as_secs(),
+            )),
+            all_results => {
+                let wpms: Vec<f64> = all_results.iter().map(|results| results.wpm()).collect();
+                let accuracy = ToipeResults::combined_accuracy(all_results);
+
+                Some(format!(
+                    "{} tests, {:.1} wpm on average, {:.1} wpm best, {:.1}% accuracy",
+                    all_results.len(),
+                    wpms.iter().sum::<f64>() / wpms.len() as f64,
+                    wpms.iter().cloned().fold(0.0, f64::max),
+                    accuracy * 100.0,
+                ))
+            }
+        }
+    }
+
+    /// The typing test used by this session.
+    pub fn toipe(&mut self) -> &mut Toipe {
+        &mut self.toipe
+    }
+}
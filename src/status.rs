@@ -0,0 +1,353 @@
+//! Reporting the progress of typing tests to other programs, like
+//! status bars or stream overlays, over a UNIX socket.
+//!
+//! See [`StatusServer`].
+
+use std::fmt;
+use std::io::{self, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// How often the socket is checked for new connections, and for
+/// programs that can take the rest of the lines sent to them.
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// What the user is doing, as sent to the programs connected to a
+/// [`StatusServer`].
+///
+/// Each status is sent as one line of space-separated fields, the first
+/// of which is the state:
+///
+/// ```text
+/// idle
+/// typing wpm=52.3 progress=41.0
+/// done wpm=55.1 accuracy=97.2
+/// ```
+///
+/// `progress` is the percentage of the test done so far, by time for a
+/// timed test or by chars otherwise. It is left out for tests that go
+/// on until they are stopped.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Status {
+    /// No test is being typed.
+    Idle,
+    /// A test is being typed.
+    Typing {
+        /// speed so far in words per minute
+        wpm: f64,
+        /// percentage of the test done so far, if it has a known end
+        progress: Option<f64>,
+    },
+    /// A test was completed.
+    Done {
+        /// speed in words per minute
+        wpm: f64,
+        /// percentage of chars typed correctly
+        accuracy: f64,
+    },
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Idle => write!(f, "idle"),
+            Status::Typing { wpm, progress } => {
+                write!(f, "typing wpm={:.1}", wpm)?;
+                if let Some(progress) = progress {
+                    write!(f, " progress={:.1}", progress)?;
+                }
+                Ok(())
+            }
+            Status::Done { wpm, accuracy } => {
+                write!(f, "done wpm={:.1} accuracy={:.1}", wpm, accuracy)
+            }
+        }
+    }
+}
+
+/// A program connected to the socket.
+struct Client {
+    stream: UnixStream,
+    /// bytes of the lines sent that could not be written without
+    /// blocking, written before the next line so that lines are never
+    /// cut
+    unsent: Vec<u8>,
+}
+
+impl Client {
+    fn new(stream: UnixStream) -> Self {
+        Self {
+            stream,
+            unsent: Vec::new(),
+        }
+    }
+
+    /// Writes as much of the line as the program takes without
+    /// blocking, after what is left of the previous ones.
+    ///
+    /// A program that reads slower than lines are sent skips the lines
+    /// it has not started reading, so that at most the rest of one line
+    /// is kept along with the new one. Returns false if the program
+    /// disconnected.
+    fn send(&mut self, line: &str) -> bool {
+        if let Some(end) = self.unsent.iter().position(|&byte| byte == b'\n') {
+            self.unsent.truncate(end + 1);
+        }
+        self.unsent.extend_from_slice(line.as_bytes());
+        self.flush()
+    }
+
+    /// Writes as much of the unsent bytes as the program takes without
+    /// blocking. Returns false if the program disconnected.
+    fn flush(&mut self) -> bool {
+        while !self.unsent.is_empty() {
+            match self.stream.write(&self.unsent) {
+                Ok(0) => return false,
+                Ok(written) => {
+                    self.unsent.drain(..written);
+                }
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(_) => return false,
+            }
+        }
+        true
+    }
+}
+
+/// Programs connected to the socket, along with the last status line
+/// sent, which is sent to programs as soon as they connect.
+#[derive(Default)]
+struct Clients {
+    connected: Vec<Client>,
+    last_line: Option<String>,
+}
+
+impl Clients {
+    /// Sends the line to every program, forgetting those that
+    /// disconnected.
+    fn send(&mut self, line: &str) {
+        self.connected.retain_mut(|client| client.send(line));
+    }
+
+    /// Writes what is left of the lines sent to the programs that did
+    /// not take all of them yet.
+    fn flush(&mut self) {
+        self.connected.retain_mut(Client::flush);
+    }
+
+    /// Adds a program, sending it the last status line.
+    fn connect(&mut self, stream: UnixStream) {
+        let mut client = Client::new(stream);
+        let connected = match &self.last_line {
+            Some(line) => client.send(line),
+            None => true,
+        };
+        if connected {
+            self.connected.push(client);
+        }
+    }
+}
+
+/// Serves the [`Status`] of the user over a UNIX socket (see
+/// `--status-socket`).
+///
+/// Programs connect to the socket and read one line for every change,
+/// starting with the current status. Connections are accepted on a
+/// background thread, which is stopped and the socket removed when the
+/// server is dropped.
+pub struct StatusServer {
+    path: PathBuf,
+    clients: Arc<Mutex<Clients>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl StatusServer {
+    /// Creates the socket at the given path and starts accepting
+    /// connections.
+    ///
+    /// A socket left at the path by a program that is no longer running
+    /// is replaced. Returns an error if another program is listening
+    /// on it.
+    pub fn bind(path: PathBuf) -> io::Result<Self> {
+        if is_stale_socket(&path) {
+            std::fs::remove_file(&path)?;
+        }
+        let listener = UnixListener::bind(&path)?;
+        listener.set_nonblocking(true)?;
+
+        let clients = Arc::new(Mutex::new(Clients::default()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let clients = Arc::clone(&clients);
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || accept_clients(listener, &clients, &stop))
+        };
+
+        Ok(Self {
+            path,
+            clients,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    /// Sends the status to every connected program.
+    pub fn publish(&self, status: &Status) {
+        let line = format!("{}\n", status);
+        let mut clients = self.clients.lock().expect("no thread panics with the lock");
+        clients.send(&line);
+        clients.last_line = Some(line);
+    }
+}
+
+impl Drop for StatusServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Whether there is a socket at the path that nothing listens on.
+fn is_stale_socket(path: &Path) -> bool {
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket())
+        && UnixStream::connect(path).is_err()
+}
+
+/// Accepts connections until `stop` is set, sending the last status to
+/// each new program, and writes the rest of the lines that programs
+/// did not take when they were sent.
+fn accept_clients(listener: UnixListener, clients: &Mutex<Clients>, stop: &AtomicBool) {
+    while !stop.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                // a program that does not read should not block the test
+                if stream.set_nonblocking(true).is_err() {
+                    continue;
+                }
+                clients
+                    .lock()
+                    .expect("no thread panics with the lock")
+                    .connect(stream);
+            }
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                clients
+                    .lock()
+                    .expect("no thread panics with the lock")
+                    .flush();
+                thread::sleep(ACCEPT_INTERVAL)
+            }
+            Err(error) => {
+                log::warn!("stopped accepting status socket connections: {}", error);
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+
+    fn read_status_line(reader: &mut BufReader<UnixStream>) -> io::Result<String> {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        Ok(line.trim_end().to_string())
+    }
+
+    #[test]
+    fn status_lines() {
+        assert_eq!(Status::Idle.to_string(), "idle");
+        assert_eq!(
+            Status::Typing {
+                wpm: 52.34,
+                progress: Some(41.0)
+            }
+            .to_string(),
+            "typing wpm=52.3 progress=41.0"
+        );
+        assert_eq!(
+            Status::Typing {
+                wpm: 52.34,
+                progress: None
+            }
+            .to_string(),
+            "typing wpm=52.3"
+        );
+        assert_eq!(
+            Status::Done {
+                wpm: 55.06,
+                accuracy: 97.24
+            }
+            .to_string(),
+            "done wpm=55.1 accuracy=97.2"
+        );
+    }
+
+    #[test]
+    fn serves_status_to_connected_programs() {
+        let path = std::env::temp_dir().join(format!("toipe-status-{}.sock", std::process::id()));
+        let server = StatusServer::bind(path.clone()).unwrap();
+        server.publish(&Status::Idle);
+
+        // the current status is sent on connecting
+        let mut reader = BufReader::new(UnixStream::connect(&path).unwrap());
+        assert_eq!(read_status_line(&mut reader).unwrap(), "idle");
+
+        server.publish(&Status::Typing {
+            wpm: 40.0,
+            progress: None,
+        });
+        assert_eq!(read_status_line(&mut reader).unwrap(), "typing wpm=40.0");
+
+        // a second server cannot take over the socket
+        assert!(StatusServer::bind(path.clone()).is_err());
+
+        drop(server);
+        assert!(!path.exists());
+        assert_eq!(read_status_line(&mut reader).unwrap(), "");
+    }
+
+    #[test]
+    fn slow_programs_get_whole_lines() {
+        let path = std::env::temp_dir().join(format!("toipe-slow-{}.sock", std::process::id()));
+        let server = StatusServer::bind(path.clone()).unwrap();
+        server.publish(&Status::Idle);
+        let mut reader = BufReader::new(UnixStream::connect(&path).unwrap());
+        assert_eq!(read_status_line(&mut reader).unwrap(), "idle");
+
+        // more than the socket takes before the program reads any
+        for i in 0..100_000 {
+            server.publish(&Status::Typing {
+                wpm: f64::from(i),
+                progress: None,
+            });
+        }
+        server.publish(&Status::Done {
+            wpm: 50.0,
+            accuracy: 100.0,
+        });
+
+        let mut num_lines = 0;
+        loop {
+            let line = read_status_line(&mut reader).unwrap();
+            if line.starts_with("done ") {
+                break;
+            }
+            let wpm: f64 = line.strip_prefix("typing wpm=").unwrap().parse().unwrap();
+            assert_eq!(wpm.fract(), 0.0, "{}", line);
+            num_lines += 1;
+        }
+        assert!(num_lines < 100_000);
+    }
+}
@@ -0,0 +1,167 @@
+//! Detecting what the terminal is like.
+//!
+//! See [`detect_background`].
+
+use std::env;
+use std::io::{self, Write};
+use std::os::unix::io::AsRawFd;
+
+/// Brightness of the background color of the terminal.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Background {
+    Dark,
+    Light,
+}
+
+/// Max number of bytes read from the reply to the background color
+/// query.
+const MAX_REPLY_LEN: usize = 64;
+
+/// Detects whether the terminal has a light or a dark background.
+///
+/// Uses the `COLORFGBG` environment variable if it is set. Otherwise,
+/// asks the terminal for its background color (OSC 11) and waits for
+/// up to 100ms for a reply. Returns `None` if neither works.
+///
+/// NOTE: must be invoked with the terminal in raw mode and before keys
+/// are read (see [`crate::input::KeyEvents`]), since the reply of the
+/// terminal is read from it.
+pub fn detect_background() -> Option<Background> {
+    if let Some(background) = env::var("COLORFGBG")
+        .ok()
+        .and_then(|value| parse_colorfgbg(&value))
+    {
+        return Some(background);
+    }
+
+    query_background().ok().flatten()
+}
+
+/// Parses `COLORFGBG`, which is set by some terminals to the foreground
+/// and background color numbers, like `15;0`.
+fn parse_colorfgbg(value: &str) -> Option<Background> {
+    let background: u8 = value.rsplit(';').next()?.parse().ok()?;
+    match background {
+        // black, dark colors and dark gray
+        0..=6 | 8 => Some(Background::Dark),
+        // light gray, light colors and white
+        7 | 9..=15 => Some(Background::Light),
+        _ => None,
+    }
+}
+
+/// Parses the reply to the background color query, like
+/// `\x1b]11;rgb:ffff/ffff/ffff\x07`.
+fn parse_osc11_reply(reply: &[u8]) -> Option<Background> {
+    let reply = std::str::from_utf8(reply).ok()?;
+    let rgb = reply.split("rgb:").nth(1)?;
+    let rgb = rgb.trim_end_matches(['\x07', '\x1b', '\\']);
+
+    let mut components = rgb.split('/').map(|component| {
+        let max = 16f64.powi(component.len() as i32) - 1.0;
+        u16::from_str_radix(component, 16)
+            .ok()
+            .map(|value| value as f64 / max)
+    });
+    let (r, g, b) = (
+        components.next()??,
+        components.next()??,
+        components.next()??,
+    );
+
+    // relative luminance
+    if 0.2126 * r + 0.7152 * g + 0.0722 * b > 0.5 {
+        Some(Background::Light)
+    } else {
+        Some(Background::Dark)
+    }
+}
+
+/// Asks the terminal for its background color.
+///
+/// Returns `Ok(None)` if the terminal did not reply in time.
+fn query_background() -> io::Result<Option<Background>> {
+    // not stdin, which may be a pipe
+    let tty = termion::get_tty()?;
+    let fd = tty.as_raw_fd();
+
+    // SAFETY: termios is a plain C struct that is filled by tcgetattr.
+    let mut termios: libc::termios = unsafe { std::mem::zeroed() };
+    // SAFETY: termios is a valid pointer for the duration of the call.
+    if unsafe { libc::tcgetattr(fd, &mut termios) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let original = termios;
+
+    // make reads return after 100ms even when nothing was read
+    termios.c_cc[libc::VMIN] = 0;
+    termios.c_cc[libc::VTIME] = 1;
+    // SAFETY: termios is a valid pointer for the duration of the call.
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &termios) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let reply = read_osc11_reply(fd);
+
+    // SAFETY: original is a valid pointer for the duration of the call.
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &original) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(parse_osc11_reply(&reply?))
+}
+
+/// Sends the background color query and reads the reply, which ends
+/// with BEL or ST.
+fn read_osc11_reply(fd: libc::c_int) -> io::Result<Vec<u8>> {
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]11;?\x07")?;
+    stdout.flush()?;
+
+    let mut reply = Vec::new();
+    while reply.len() < MAX_REPLY_LEN && !reply.ends_with(b"\x07") && !reply.ends_with(b"\x1b\\") {
+        let mut byte = 0u8;
+        // SAFETY: byte is a valid buffer of length 1.
+        let read = unsafe { libc::read(fd, &mut byte as *mut u8 as *mut libc::c_void, 1) };
+        match read {
+            1 => reply.push(byte),
+            // timed out
+            0 => break,
+            _ => return Err(io::Error::last_os_error()),
+        }
+    }
+
+    Ok(reply)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colorfgbg() {
+        assert_eq!(parse_colorfgbg("15;0"), Some(Background::Dark));
+        assert_eq!(parse_colorfgbg("0;15"), Some(Background::Light));
+        assert_eq!(parse_colorfgbg("0;default;7"), Some(Background::Light));
+        assert_eq!(parse_colorfgbg("15;default"), None);
+        assert_eq!(parse_colorfgbg(""), None);
+    }
+
+    #[test]
+    fn osc11_reply() {
+        assert_eq!(
+            parse_osc11_reply(b"\x1b]11;rgb:ffff/ffff/ffff\x07"),
+            Some(Background::Light)
+        );
+        assert_eq!(
+            parse_osc11_reply(b"\x1b]11;rgb:2828/2c2c/3434\x1b\\"),
+            Some(Background::Dark)
+        );
+        assert_eq!(
+            parse_osc11_reply(b"\x1b]11;rgb:fd/f6/e3\x07"),
+            Some(Background::Light)
+        );
+        assert_eq!(parse_osc11_reply(b""), None);
+        assert_eq!(parse_osc11_reply(b"\x1b]11;rgb:ffff\x07"), None);
+    }
+}
@@ -1,16 +1,28 @@
 //! Utilities for generating/selecting new (random) words for the typing
 //! test.
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::File;
 use std::io::{self, BufRead, BufReader, Cursor, Seek, SeekFrom};
-use std::path::PathBuf;
+use std::num::NonZeroUsize;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 
+use rand::distributions::{Distribution, WeightedIndex};
 use rand::seq::SliceRandom;
 use rand::Rng;
 
 use bisection::bisect_right;
-use rand::prelude::ThreadRng;
+use regex::Regex;
+#[cfg(feature = "structured-wordlist")]
+use serde::Deserialize;
+
+#[cfg(feature = "history")]
+use crate::history::{KeyStats, MIN_BIGRAM_COUNT, MIN_KEY_COUNT};
+use crate::results::ToipeResults;
+use crate::rng::ToipeRng;
+#[cfg(feature = "cli")]
+use clap::ArgEnum;
 
 /// Efficient selector of words from a word list.
 ///
@@ -20,32 +32,57 @@ use rand::prelude::ThreadRng;
 ///
 /// The word list is assumed to:
 /// - Have a list of words separated by newline.
+///     - Each word can be followed by a tab and an annotation, like its
+///       meaning in a vocabulary list (`bonjour\thello`). See
+///       [`WordSelector::annotation`].
+///     - Or, each word can be followed by a tab and how frequent it is,
+///       as a whole number (`the\t23135851162`). Words are then selected
+///       in proportion to their frequency. This format is detected when
+///       every word has a frequency; see [`RawWordSelector#algorithm`].
 /// - Use only English alphabet and **ASCII**.
-/// - Be **sorted alphabetically**.
+/// - Be **sorted alphabetically**, for words to be read from the file
+///   as they are selected.
 ///     - In case-insensitive manner.
 ///     - For example, both "Apple" and "apple" must appear before words
 ///       started with "b".
+///     - Only the first letter matters: words starting with each letter
+///       must be next to each other.
+///     - A list that is not sorted is loaded in memory instead, with its
+///       words sorted by their first letter.
 /// - Be a file that is **not modified** while the object is alive.
-/// - Have no empty lines except at the end of the file.
 ///
-/// Note: only words between length 2 and 8, inclusive, are considered.
-/// Having no words matching the criteria may lead to an infinite loop.
+/// The list does not need to contain words for every letter of the
+/// alphabet. Letters with no words are never selected. Lines that do
+/// not start with a letter (including empty lines) are ignored.
+///
+/// Note: only words of letters alone, between length 2 and 8 (see
+/// [`RawWordSelector::with_max_word_len`]), inclusive, are selected.
+/// Selecting words returns an error if no word matches the criteria.
 ///
 /// ### Algorithm
 ///
+/// In the format with frequencies, everything below is the same, except
+/// that each word counts as many times as its frequency: the index has
+/// the cumulative frequency of the words of each letter, and the words
+/// of a letter are read until their frequencies add up to the number.
+///
 /// During initialization, the [`RawWordSelector`] iterates through all
 /// the words in the list and builds an index mapping each letter (of
-/// the alphabet) to its byte position in the file and the cumulative
-/// number of words present starting with it.
+/// the alphabet) to the byte position of its first word in the file and
+/// the number of words of each length starting with it. From this, the
+/// cumulative number of words starting with each letter that match the
+/// criteria above is found, so that words that do not are never drawn.
 ///
 /// To select a (pesudo-)random word, a random number between 0
-/// (inclusive) and number of lines (exclusive) is generated. Using
-/// binary search, the index of where this number lies in the cumulative
-/// no. of words list is found. Using this index, the byte offset of the
-/// corresponding letter is found. The file is then read starting from
-/// this byte offset and read line-by-line until the correct word (at
-/// line `number - cumulative num. words` from the starting of this
-/// letter).
+/// (inclusive) and number of matching words (exclusive) is generated.
+/// Using binary search, the letter in whose range this number lies in
+/// the cumulative no. of words list is found. Letters without any
+/// matching words have an empty range and are skipped by the search.
+/// The file is then read starting from the byte offset of this letter,
+/// line-by-line, counting only the matching words, until the correct
+/// word (at `number - cumulative num. words` from the first word of
+/// this letter). Every matching word is thus equally likely, however
+/// many words of each letter do not match.
 ///
 /// ### Time complexity
 ///
@@ -55,12 +92,74 @@ use rand::prelude::ThreadRng;
 ///
 /// ### Space complexity
 ///
-/// `O(1)` (only needs fixed length arrays).
+/// `O(l)` for the longest word length `l` in the index, along with the
+/// annotations of the words selected so far. `O(n)` for a list that is
+/// not sorted.
 #[derive(Debug)]
 pub struct RawWordSelector<T> {
     reader: BufReader<T>,
-    letter_pos: [u64; 26],
-    letter_lines_sum: [u64; 27],
+    index: WordListIndex,
+    /// lines of the word list sorted by their first letter, if it is
+    /// not sorted and was loaded in memory
+    sorted_lines: Option<Vec<String>>,
+    /// number of words that can be selected starting with the letters
+    /// before each letter
+    word_lines_sum: [u64; 27],
+    /// total frequency of the words that can be selected starting with
+    /// the letters before each letter, if the word list has frequencies
+    word_weights_sum: Option<[u64; 27]>,
+    /// annotations of the words selected so far, by lowercase word
+    annotations: HashMap<String, String>,
+    /// longest word that is selected
+    max_word_len: usize,
+    /// order in which the words left are dealt, if each word is to be
+    /// selected once before any is repeated
+    deck: Option<IndexSampler>,
+    rng: ToipeRng,
+}
+
+/// Length of the longest word selected by [`RawWordSelector`] by
+/// default.
+pub const DEFAULT_MAX_WORD_LEN: usize = 8;
+
+/// Returns the index of the letter (0 for 'a', 25 for 'z') that a line
+/// starts with, ignoring case.
+fn letter_index_of(line: &str) -> Option<usize> {
+    match line.bytes().next() {
+        Some(first_char) if first_char.is_ascii_alphabetic() => {
+            Some((first_char.to_ascii_lowercase() - b'a') as usize)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the frequency after the word on a line of a word list, if it
+/// has one (see [`RawWordSelector#assumptions`]).
+fn frequency_of(line: &str) -> Option<u64> {
+    let (_, frequency) = line.split_once('\t')?;
+    frequency.trim().parse().ok()
+}
+
+/// Returns the length of the word on a line of a word list if it has
+/// only letters, as only such words are selected.
+fn word_len_of(line: &str) -> Option<usize> {
+    let word = line
+        .split('\t')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches(['\n', '\r']);
+    word.bytes()
+        .all(|c| c.is_ascii_alphabetic())
+        .then_some(word.len())
+}
+
+/// Returns the cumulative sums of the numbers, starting from 0.
+fn cumulative_sums(numbers: &[u64; 26]) -> [u64; 27] {
+    let mut sums = [0u64; 27];
+    for (i, number) in numbers.iter().enumerate() {
+        sums[i + 1] = sums[i] + number;
+    }
+    sums
 }
 
 impl<T: Seek + io::Read> RawWordSelector<T> {
@@ -68,18 +167,21 @@ impl<T: Seek + io::Read> RawWordSelector<T> {
     ///
     /// Please ensure that assumptions defined at
     /// [`RawWordSelector#assumptions`] are valid for the contents.
+    ///
+    /// Returns an error if the word list has no words.
     pub fn new(mut reader: BufReader<T>) -> Result<Self, io::Error> {
         let mut letter_pos = [0u64; 26];
         let mut letter_lines = [0u64; 26];
-        let mut num_lines = 0;
-        let mut cur_letter = b'a' - 1;
-        let mut cur_pos = 0;
+        let mut length_lines: Vec<[u64; 26]> = Vec::new();
+        let mut length_weights: Vec<[u64; 26]> = Vec::new();
+        // whether every word has a frequency so far
+        let mut has_frequencies = true;
+        // whether words starting with each letter are next to each other
+        let mut is_sorted = true;
+        let mut last_letter_index = None;
+        let mut cur_pos = reader.stream_position()?;
         let mut buffer = String::new();
 
-        fn is_letter(char: u8) -> bool {
-            char.is_ascii_lowercase()
-        }
-
         loop {
             buffer.clear();
             let len = reader.read_line(&mut buffer)?;
@@ -88,112 +190,360 @@ impl<T: Seek + io::Read> RawWordSelector<T> {
                 break;
             }
 
-            let line = buffer.to_ascii_lowercase();
-            num_lines += 1;
-            let first_char = line.bytes().next().unwrap();
-
-            if !is_letter(first_char) {
-                continue;
-            }
-
-            if cur_letter != first_char {
-                letter_pos[cur_pos] = reader.stream_position()?;
+            let line_pos = cur_pos;
+            cur_pos += len as u64;
 
-                letter_lines[cur_pos] = num_lines;
-                num_lines = 0;
-
-                // println!(
-                //     "{}, {}, {}, {}",
-                //     char::from(first_char),
-                //     cur_pos,
-                //     letter_pos[cur_pos],
-                //     letter_lines[cur_pos],
-                // );
-
-                cur_pos += 1;
-                cur_letter = first_char;
+            if let Some(letter_index) = letter_index_of(&buffer) {
+                if letter_lines[letter_index] == 0 {
+                    letter_pos[letter_index] = line_pos;
+                } else if last_letter_index != Some(letter_index) {
+                    is_sorted = false;
+                }
+                last_letter_index = Some(letter_index);
+                letter_lines[letter_index] += 1;
 
-                if cur_pos >= 26 {
-                    break;
+                let frequency = frequency_of(&buffer);
+                has_frequencies &= frequency.is_some();
+                if let Some(word_len) = word_len_of(&buffer) {
+                    if length_lines.len() <= word_len {
+                        length_lines.resize(word_len + 1, [0; 26]);
+                        length_weights.resize(word_len + 1, [0; 26]);
+                    }
+                    length_lines[word_len][letter_index] += 1;
+                    length_weights[word_len][letter_index] = length_weights[word_len][letter_index]
+                        .saturating_add(frequency.unwrap_or(0));
                 }
             }
         }
 
-        letter_lines.rotate_left(1);
-        letter_lines[25] = num_lines;
-        let letter_lines_sum: [u64; 26] = letter_lines
-            .into_iter()
-            .scan(0, |acc, x| {
-                *acc += x;
+        let letter_lines_sum = cumulative_sums(&letter_lines);
+        let length_weights =
+            (has_frequencies && letter_lines_sum[26] > 0).then_some(length_weights);
 
-                Some(*acc)
-            })
-            .collect::<Vec<u64>>()
-            .try_into()
-            .unwrap();
-        let mut letter_lines_sum_ = [0u64; 27];
-        letter_lines_sum_[1..].copy_from_slice(&letter_lines_sum[..]);
-        let letter_lines_sum = letter_lines_sum_;
+        let has_no_weights = length_weights
+            .as_ref()
+            .is_some_and(|weights| weights.iter().flatten().all(|&weight| weight == 0));
+        if letter_lines_sum[26] == 0 || has_no_weights {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "word list does not have any words",
+            ));
+        }
 
-        // println!("{:?}", letter_lines);
-        // println!("{:?}", letter_lines_sum);
+        let sorted_lines = if is_sorted {
+            None
+        } else {
+            log::debug!("word list is not sorted, loading it in memory");
+            reader.seek(SeekFrom::Start(0))?;
+            let mut lines = Vec::new();
+            for line in (&mut reader).lines() {
+                let line = line?;
+                if letter_index_of(&line).is_some() {
+                    lines.push(line.trim_end_matches('\r').to_string());
+                }
+            }
+            // stable, so that the words of each letter keep their order
+            lines.sort_by_key(|line| letter_index_of(line));
+            Some(lines)
+        };
 
-        let word_selector = Self {
-            reader,
+        let index = WordListIndex {
             letter_pos,
             letter_lines_sum,
+            length_lines,
+            length_weights,
         };
+        let mut word_selector = Self::with_index(reader, index);
+        word_selector.sorted_lines = sorted_lines;
 
         Ok(word_selector)
     }
 
+    /// Whether the word on a line of the word list can be selected: if
+    /// it has only letters and its length is within the limits.
+    fn is_selectable(&self, line: &str) -> bool {
+        word_len_of(line).is_some_and(|len| (2..=self.max_word_len).contains(&len))
+    }
+
+    /// Counts the words that can be selected from the index, after the
+    /// limits on their length change.
+    fn update_word_sums(&mut self) {
+        self.word_lines_sum = self.index.word_lines_sum(self.max_word_len);
+        self.word_weights_sum = self.index.word_weights_sum(self.max_word_len);
+        if let Some(deck) = &mut self.deck {
+            *deck = IndexSampler::new(self.word_lines_sum[26]);
+        }
+    }
+
+    /// Error for a word list without any word that can be selected.
+    fn no_words_error(&self) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "word list does not have any words of 2 to {} letters",
+                self.max_word_len
+            ),
+        )
+    }
+
+    /// Returns the word of the given letter at the given offset, counting
+    /// each word as many times as its frequency if `weighted` is true, or
+    /// once otherwise.
     fn word_at_letter_offset(
         &mut self,
         letter_index: usize,
-        line_offset: u64,
+        mut offset: u64,
+        weighted: bool,
     ) -> Result<String, io::Error> {
+        if let Some(lines) = &self.sorted_lines {
+            let start = self.index.letter_lines_sum[letter_index] as usize;
+            let end = self.index.letter_lines_sum[letter_index + 1] as usize;
+            for line in &lines[start..end] {
+                if !self.is_selectable(line) {
+                    continue;
+                }
+                let count = if weighted {
+                    frequency_of(line).unwrap_or(0)
+                } else {
+                    1
+                };
+                if offset < count {
+                    return Ok(line.clone());
+                }
+                offset -= count;
+            }
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "offset is past the words of the letter",
+            ));
+        }
+
         self.reader
-            .seek(SeekFrom::Start(self.letter_pos[letter_index]))?;
+            .seek(SeekFrom::Start(self.index.letter_pos[letter_index]))?;
 
         let mut buffer = String::new();
-        let mut line_no = 0;
 
         loop {
             buffer.clear();
-            self.reader.read_line(&mut buffer)?;
+            if self.reader.read_line(&mut buffer)? == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "word list changed after it was indexed",
+                ));
+            }
+
+            // skip lines that do not belong to this letter, or whose
+            // words cannot be selected
+            if letter_index_of(&buffer) != Some(letter_index) || !self.is_selectable(&buffer) {
+                continue;
+            }
 
-            if line_no == line_offset {
+            let count = if weighted {
+                frequency_of(&buffer).unwrap_or(0)
+            } else {
+                1
+            };
+            if offset < count {
                 break;
             }
 
-            line_no += 1
+            offset -= count;
         }
 
         // remove trailing newline
-        buffer.truncate(buffer.len() - 1);
+        let len = buffer.trim_end_matches(&['\n', '\r'][..]).len();
+        buffer.truncate(len);
 
         Ok(buffer)
     }
 
-    fn new_word_raw(&mut self, rng: &mut ThreadRng) -> Result<String, io::Error> {
-        let line_index = rng.gen_range(self.letter_lines_sum[0]..self.letter_lines_sum[26]);
-        // let line_index = 0;
+    /// Returns the word at given index among the words in the list that
+    /// can be selected.
+    fn word_at_index(&mut self, line_index: u64) -> Result<String, io::Error> {
+        let letter_index = bisect_right(&self.word_lines_sum, &line_index) - 1;
+
+        let line_offset = line_index - self.word_lines_sum[letter_index];
+
+        self.word_at_letter_offset(letter_index, line_offset, false)
+    }
+
+    /// Returns the word at given index among the words in the list that
+    /// can be selected, with each word counted as many times as its
+    /// frequency.
+    fn word_at_weight(&mut self, weights_sum: [u64; 27], index: u64) -> Result<String, io::Error> {
+        let letter_index = bisect_right(&weights_sum, &index) - 1;
+
+        self.word_at_letter_offset(letter_index, index - weights_sum[letter_index], true)
+    }
+
+    fn new_word_raw(&mut self) -> Result<String, io::Error> {
+        let num_words = self.word_weights_sum.unwrap_or(self.word_lines_sum)[26];
+        if num_words == 0 {
+            return Err(self.no_words_error());
+        }
+
+        if let Some(deck) = &mut self.deck {
+            let line_index = match deck.next(&mut self.rng) {
+                Some(line_index) => line_index,
+                None => {
+                    // every word was dealt, shuffle them again
+                    *deck = IndexSampler::new(self.word_lines_sum[26]);
+                    deck.next(&mut self.rng).expect("word list has words")
+                }
+            };
+            return self.draw_word(line_index);
+        }
+
+        if let Some(weights_sum) = self.word_weights_sum {
+            let index = self.rng.gen_range(0..weights_sum[26]);
+            let word = self.word_at_weight(weights_sum, index)?;
+            let word = self.strip_field(word);
+            log::trace!("drew weight_index={} word={:?}", index, word);
+            return Ok(word);
+        }
+
+        let line_index = self.rng.gen_range(0..self.word_lines_sum[26]);
+
+        self.draw_word(line_index)
+    }
+
+    /// Removes the annotation or frequency after the word, recording the
+    /// annotation.
+    fn strip_field(&mut self, mut word: String) -> String {
+        if let Some((word_part, field)) = word.split_once('\t') {
+            let annotation = field.trim();
+            if self.index.length_weights.is_none() && !annotation.is_empty() {
+                self.annotations
+                    .insert(word_part.to_ascii_lowercase(), annotation.to_string());
+            }
+            word.truncate(word_part.len());
+        }
+        word
+    }
+
+    /// Same as [`RawWordSelector::word_at_index`], but also logs the
+    /// draw for debugging the distribution of words.
+    ///
+    /// The annotation after the word, if any, is removed and recorded
+    /// (see [`RawWordSelector::strip_field`]).
+    fn draw_word(&mut self, line_index: u64) -> Result<String, io::Error> {
+        let word = self.word_at_index(line_index)?;
+        let word = self.strip_field(word);
+
+        if log::log_enabled!(log::Level::Trace) {
+            let letter_index = bisect_right(&self.word_lines_sum, &line_index) - 1;
+            log::trace!(
+                "drew line_index={} letter={} offset={} word={:?}",
+                line_index,
+                char::from(b'a' + letter_index as u8),
+                line_index - self.word_lines_sum[letter_index],
+                word,
+            );
+        }
+
+        Ok(word)
+    }
+
+    /// Draws `num_words` distinct words at the line indexes given by the
+    /// sampler. If the sampler runs out of lines, it starts over and
+    /// words are repeated.
+    fn draw_unique_lines(
+        &mut self,
+        sampler: &mut IndexSampler,
+        num_words: usize,
+    ) -> Result<Vec<String>, io::Error> {
+        let mut seen = HashSet::new();
+        let mut words = Vec::with_capacity(num_words);
+
+        while words.len() < num_words {
+            let line_index = match sampler.next(&mut self.rng) {
+                Some(line_index) => line_index,
+                None => {
+                    *sampler = IndexSampler::new(self.word_lines_sum[26]);
+                    seen.clear();
+                    continue;
+                }
+            };
+
+            let mut word = self.draw_word(line_index)?;
+            word.make_ascii_lowercase();
+            if seen.insert(word.clone()) {
+                words.push(word);
+            }
+        }
+
+        Ok(words)
+    }
 
-        let letter_lines_sum_index = bisect_right(&self.letter_lines_sum, &line_index);
+    /// Draws up to `num_words` distinct words without replacement, each
+    /// with a chance proportional to its frequency, by going through the
+    /// list once (see [`PrioritySample`]).
+    fn sample_weighted_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        let mut sample = PrioritySample::new(num_words);
+        let word_of = |line: &str| {
+            line.split('\t')
+                .next()
+                .unwrap_or_default()
+                .trim_end_matches(['\n', '\r'])
+                .to_ascii_lowercase()
+        };
+
+        if let Some(lines) = &self.sorted_lines {
+            for line in lines {
+                if self.is_selectable(line) {
+                    let weight = frequency_of(line).unwrap_or(0);
+                    sample.offer(&word_of(line), weight, &mut self.rng);
+                }
+            }
+            return Ok(sample.into_words());
+        }
 
-        let line_offset = self.letter_lines_sum[letter_lines_sum_index] - line_index;
+        self.reader.seek(SeekFrom::Start(0))?;
+        let mut buffer = String::new();
+        loop {
+            buffer.clear();
+            if self.reader.read_line(&mut buffer)? == 0 {
+                break;
+            }
+            if self.is_selectable(&buffer) {
+                let weight = frequency_of(&buffer).unwrap_or(0);
+                sample.offer(&word_of(&buffer), weight, &mut self.rng);
+            }
+        }
 
-        let letter_index = letter_lines_sum_index - 1;
+        Ok(sample.into_words())
+    }
+}
 
-        // println!(
-        //     "{}, {}, {}, {}",
-        //     line_index,
-        //     letter_lines_sum_index,
-        //     self.letter_lines_sum[letter_lines_sum_index],
-        //     line_offset
-        // );
+impl<T: Seek + io::Read> RawWordSelector<T> {
+    /// Create from a [`BufReader`] and an index built earlier from the
+    /// same contents, without reading them.
+    pub fn with_index(reader: BufReader<T>, index: WordListIndex) -> Self {
+        let mut word_selector = Self {
+            reader,
+            index,
+            sorted_lines: None,
+            word_lines_sum: [0; 27],
+            word_weights_sum: None,
+            annotations: HashMap::new(),
+            max_word_len: DEFAULT_MAX_WORD_LEN,
+            deck: None,
+            rng: ToipeRng::from_entropy(),
+        };
+        word_selector.update_word_sums();
+        word_selector
+    }
 
-        self.word_at_letter_offset(letter_index, line_offset)
+    /// Returns the index built from the word list, to be given to
+    /// [`RawWordSelector::with_index`] later.
+    ///
+    /// Returns `None` if the word list is not sorted, since it is then
+    /// loaded in memory instead.
+    pub fn index(&self) -> Option<WordListIndex> {
+        if self.sorted_lines.is_some() {
+            return None;
+        }
+        Some(self.index.clone())
     }
 }
 
@@ -209,6 +559,232 @@ impl RawWordSelector<File> {
 
         Self::new(reader)
     }
+
+    /// Same as [`RawWordSelector::from_path`], but the index of the
+    /// file is saved at `cache_path` and read from there the next time,
+    /// as long as the size and modification time of the file are the
+    /// same.
+    ///
+    /// A cache that cannot be read is ignored, and one that cannot be
+    /// written is only logged, since it only makes starting faster.
+    pub fn from_path_with_index_cache(
+        word_list_path: PathBuf,
+        cache_path: &Path,
+    ) -> Result<Self, io::Error> {
+        let file = File::open(word_list_path)?;
+        let stamp = FileStamp::of(&file)?;
+
+        let cached = std::fs::read_to_string(cache_path)
+            .ok()
+            .and_then(|contents| WordListIndex::parse_cache(&contents, &stamp));
+        if let Some(index) = cached {
+            log::debug!("read word list index from {}", cache_path.display());
+            return Ok(Self::with_index(BufReader::new(file), index));
+        }
+
+        let selector = Self::new(BufReader::new(file))?;
+        if let Some(index) = selector.index() {
+            if let Err(error) = write_index_cache(cache_path, &index, &stamp) {
+                log::warn!(
+                    "could not save word list index to {}: {}",
+                    cache_path.display(),
+                    error
+                );
+            }
+        }
+
+        Ok(selector)
+    }
+}
+
+/// Where each letter's words are in a sorted word list, and how many
+/// there are of each length, as built by [`RawWordSelector::new`].
+///
+/// It can be saved with [`RawWordSelector::from_path_with_index_cache`]
+/// so that large word lists do not have to be read on every start.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WordListIndex {
+    /// byte position of the first word starting with each letter
+    letter_pos: [u64; 26],
+    /// number of lines starting with the letters before each letter
+    letter_lines_sum: [u64; 27],
+    /// number of words of only letters starting with each letter, by
+    /// their length
+    length_lines: Vec<[u64; 26]>,
+    /// total frequency of those words, if the word list has frequencies
+    length_weights: Option<Vec<[u64; 26]>>,
+}
+
+/// Returns the cumulative sums over the letters of the counts by word
+/// length, for the lengths from 2 to `max_word_len`.
+fn length_letter_sums(counts: &[[u64; 26]], max_word_len: usize) -> [u64; 27] {
+    let mut letter_counts = [0u64; 26];
+    for counts in counts.iter().take(max_word_len.saturating_add(1)).skip(2) {
+        for (total, count) in letter_counts.iter_mut().zip(counts) {
+            *total = total.saturating_add(*count);
+        }
+    }
+    cumulative_sums(&letter_counts)
+}
+
+/// Version of the index cache format, written on its first line.
+const INDEX_CACHE_VERSION: &str = "toipe-index 2";
+
+/// Size and modification time of a word list file, to tell if the
+/// cached index of it is stale.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct FileStamp {
+    size: u64,
+    modified_nanos: u128,
+}
+
+impl FileStamp {
+    fn of(file: &File) -> Result<Self, io::Error> {
+        let metadata = file.metadata()?;
+        let modified_nanos = metadata
+            .modified()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|since| since.as_nanos())
+            .unwrap_or(0);
+        Ok(Self {
+            size: metadata.len(),
+            modified_nanos,
+        })
+    }
+}
+
+impl WordListIndex {
+    /// Number of words of 2 to `max_word_len` letters starting with the
+    /// letters before each letter.
+    fn word_lines_sum(&self, max_word_len: usize) -> [u64; 27] {
+        length_letter_sums(&self.length_lines, max_word_len)
+    }
+
+    /// Total frequency of the words of 2 to `max_word_len` letters
+    /// starting with the letters before each letter, if the word list
+    /// has frequencies.
+    fn word_weights_sum(&self, max_word_len: usize) -> Option<[u64; 27]> {
+        self.length_weights
+            .as_ref()
+            .map(|weights| length_letter_sums(weights, max_word_len))
+    }
+
+    /// Formats the index as a cache file for a word list with the given
+    /// stamp, one line per field with its name first:
+    ///
+    /// ```text
+    /// toipe-index 2
+    /// size 985084
+    /// modified 1698148800000000000
+    /// pos 0 17125 ...
+    /// lines 0 8023 ...
+    /// length 0 0 0 ...
+    /// length 1 1 0 ...
+    /// weights 1 23135 0 ...
+    /// ```
+    ///
+    /// Each `length` line has the length followed by the number of words
+    /// of that length for each letter. `weights` lines are only there
+    /// for word lists with frequencies, in the same format.
+    fn to_cache(&self, stamp: &FileStamp) -> String {
+        let join = |numbers: &[u64]| {
+            numbers
+                .iter()
+                .map(u64::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let mut cache = format!(
+            "{}\nsize {}\nmodified {}\npos {}\nlines {}\n",
+            INDEX_CACHE_VERSION,
+            stamp.size,
+            stamp.modified_nanos,
+            join(&self.letter_pos),
+            join(&self.letter_lines_sum),
+        );
+        for (len, counts) in self.length_lines.iter().enumerate() {
+            cache.push_str(&format!("length {} {}\n", len, join(counts)));
+        }
+        for (len, weights) in self.length_weights.iter().flatten().enumerate() {
+            cache.push_str(&format!("weights {} {}\n", len, join(weights)));
+        }
+        cache
+    }
+
+    /// Parses a cache file written by [`WordListIndex::to_cache`],
+    /// returning `None` if it is malformed, of another version or for
+    /// a word list with another stamp.
+    fn parse_cache(contents: &str, stamp: &FileStamp) -> Option<Self> {
+        fn numbers<const N: usize>(value: &str) -> Option<[u64; N]> {
+            let numbers = value
+                .split(' ')
+                .map(|number| number.parse().ok())
+                .collect::<Option<Vec<u64>>>()?;
+            numbers.try_into().ok()
+        }
+
+        let mut lines = contents.lines();
+        if lines.next()? != INDEX_CACHE_VERSION {
+            return None;
+        }
+
+        let mut size = None;
+        let mut modified_nanos = None;
+        let mut letter_pos = None;
+        let mut letter_lines_sum = None;
+        let mut length_lines = Vec::new();
+        let mut length_weights = None;
+        for line in lines {
+            let (field, value) = line.split_once(' ')?;
+            match field {
+                "size" => size = Some(value.parse().ok()?),
+                "modified" => modified_nanos = Some(value.parse().ok()?),
+                "pos" => letter_pos = Some(numbers(value)?),
+                "lines" => letter_lines_sum = Some(numbers(value)?),
+                "length" | "weights" => {
+                    let (len, value) = value.split_once(' ')?;
+                    let counts = if field == "length" {
+                        &mut length_lines
+                    } else {
+                        length_weights.get_or_insert_with(Vec::new)
+                    };
+                    // lengths are written in order, from 0
+                    if len.parse::<usize>().ok()? != counts.len() {
+                        return None;
+                    }
+                    counts.push(numbers(value)?);
+                }
+                _ => return None,
+            }
+        }
+
+        let cached_stamp = FileStamp {
+            size: size?,
+            modified_nanos: modified_nanos?,
+        };
+        if cached_stamp != *stamp {
+            return None;
+        }
+
+        Some(Self {
+            letter_pos: letter_pos?,
+            letter_lines_sum: letter_lines_sum?,
+            length_lines,
+            length_weights,
+        })
+    }
+}
+
+/// Writes the index cache, creating its directory if needed.
+fn write_index_cache(
+    cache_path: &Path,
+    index: &WordListIndex,
+    stamp: &FileStamp,
+) -> Result<(), io::Error> {
+    if let Some(dir) = cache_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(cache_path, index.to_cache(stamp))
 }
 
 impl RawWordSelector<Cursor<String>> {
@@ -222,91 +798,659 @@ impl RawWordSelector<Cursor<String>> {
 
         RawWordSelector::new(reader)
     }
-}
 
-/// Describes a thing that provides new words.
-pub trait WordSelector {
-    /// Returns a new word.
-    fn new_word(&mut self) -> Result<String, io::Error>;
+    /// Create from the words of the files at the given paths, merged
+    /// into one list in memory.
+    ///
+    /// A word that is in more than one file is only kept the first time,
+    /// along with what follows it on its line. The merged list is rarely
+    /// sorted, so it is loaded like an unsorted list.
+    pub fn from_paths(word_list_paths: &[PathBuf]) -> Result<Self, io::Error> {
+        let mut seen = HashSet::new();
+        let mut word_list = String::new();
+        for path in word_list_paths {
+            for line in std::fs::read_to_string(path)?.lines() {
+                let word = line.split('\t').next().unwrap_or_default().trim();
+                if !word.is_empty() && seen.insert(word.to_string()) {
+                    word_list.push_str(line);
+                    word_list.push('\n');
+                }
+            }
+        }
 
-    /// Returns a [`Vec`] containing `num_words` words.
-    fn new_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
-        (0..num_words).map(|_| self.new_word()).collect()
+        Self::from_string(word_list)
     }
 }
 
-impl<T: Seek + io::Read> WordSelector for RawWordSelector<T> {
-    fn new_word(&mut self) -> Result<String, io::Error> {
-        let mut rng = rand::thread_rng();
+/// Draws numbers from `0..len` in random order without replacement.
+///
+/// This is a lazily evaluated Fisher-Yates shuffle: only the positions
+/// that were swapped are stored. Drawing `k` numbers takes `O(k)` time
+/// and space, irrespective of `len`.
+#[derive(Debug)]
+struct IndexSampler {
+    len: u64,
+    num_drawn: u64,
+    /// values at the positions that were swapped
+    swapped: HashMap<u64, u64>,
+}
 
-        let mut word = "-".to_string();
+impl IndexSampler {
+    fn new(len: u64) -> Self {
+        Self {
+            len,
+            num_drawn: 0,
+            swapped: HashMap::new(),
+        }
+    }
 
-        while word.len() < 2 || word.len() > 8 || !word.chars().all(|c| c.is_ascii_alphabetic()) {
-            word = self.new_word_raw(&mut rng)?;
+    /// Returns the next number, or `None` if all numbers were drawn.
+    fn next(&mut self, rng: &mut impl Rng) -> Option<u64> {
+        if self.num_drawn >= self.len {
+            return None;
         }
 
-        word.make_ascii_lowercase();
+        let i = self.num_drawn;
+        let j = rng.gen_range(i..self.len);
+        let value_at_i = self.swapped.remove(&i).unwrap_or(i);
+        let value_at_j = if i == j {
+            value_at_i
+        } else {
+            self.swapped.insert(j, value_at_i).unwrap_or(j)
+        };
 
-        Ok(word)
+        self.num_drawn += 1;
+
+        Some(value_at_j)
     }
 }
 
-/// Wraps another word selector, taking words from it and adding punctuation to the end of or
-/// around words with a configurable chance. Will capitalize the next word when an end-of-sentence
-/// punctuation mark is used.
-pub struct PunctuatedWordSelector {
-    selector: Box<dyn WordSelector>,
-    next_is_capital: bool,
-    punctuation_chance: f64,
+/// Keeps the `len` distinct words with the highest priority keys among
+/// the words offered, to draw words without replacement with a chance
+/// proportional to their weights.
+///
+/// Each word offered gets the key `ln(u) / weight` for a random `u` in
+/// `[0, 1)` (Efraimidis and Spirakis). A word offered more than once
+/// keeps its highest key, which is distributed like the key of a single
+/// word with the weights added up, so repeated lines of a word list are
+/// counted together.
+#[derive(Debug)]
+struct PrioritySample {
+    len: usize,
+    /// words with the highest keys so far, in no particular order
+    words: Vec<(f64, String)>,
+    /// lowest key among the words, once there are `len` of them
+    min_key: f64,
 }
 
-enum PunctuationType {
-    Capitaizing(char),
-    Ending(char),
-    Surrounding(char, char),
+impl PrioritySample {
+    fn new(len: usize) -> Self {
+        Self {
+            len,
+            words: Vec::with_capacity(len),
+            min_key: f64::NEG_INFINITY,
+        }
+    }
+
+    fn offer(&mut self, word: &str, weight: u64, rng: &mut impl Rng) {
+        if weight == 0 || self.len == 0 {
+            return;
+        }
+
+        let key = rng.gen::<f64>().ln() / weight as f64;
+        // a word with a key this low is not kept, and neither is a higher
+        // key for the same word
+        if self.words.len() == self.len && key <= self.min_key {
+            return;
+        }
+
+        if let Some(entry) = self.words.iter_mut().find(|(_, kept)| kept == word) {
+            entry.0 = entry.0.max(key);
+        } else if self.words.len() < self.len {
+            self.words.push((key, word.to_string()));
+        } else {
+            let lowest = self
+                .words
+                .iter_mut()
+                .min_by(|(a, _), (b, _)| a.total_cmp(b))
+                .expect("sample has words");
+            *lowest = (key, word.to_string());
+        }
+
+        if self.words.len() == self.len {
+            self.min_key = self
+                .words
+                .iter()
+                .map(|&(key, _)| key)
+                .fold(f64::INFINITY, f64::min);
+        }
+    }
+
+    /// The words kept, from the highest key to the lowest, which is a
+    /// random order weighted like the draws.
+    fn into_words(mut self) -> Vec<String> {
+        self.words.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        self.words.into_iter().map(|(_, word)| word).collect()
+    }
 }
 
-const PUNCTUATION: [PunctuationType; 12] = [
-    PunctuationType::Capitaizing('!'),
-    PunctuationType::Capitaizing('?'),
-    PunctuationType::Capitaizing('.'),
-    PunctuationType::Ending(','),
-    PunctuationType::Ending(':'),
-    PunctuationType::Ending(';'),
-    PunctuationType::Surrounding('\'', '\''),
-    PunctuationType::Surrounding('"', '"'),
-    PunctuationType::Surrounding('(', ')'),
-    PunctuationType::Surrounding('{', '}'),
-    PunctuationType::Surrounding('<', '>'),
-    PunctuationType::Surrounding('[', ']'),
-];
+/// Number of words drawn per word requested before
+/// [`WordSelector::new_unique_words`] gives up on finding distinct words.
+const MAX_DRAWS_PER_UNIQUE_WORD: usize = 10;
 
-impl PunctuatedWordSelector {
-    /// Creates a PunctuatedWordSelector from another WordSelector, allowing the selection of the
-    /// chance of punctuation.
-    pub fn from_word_selector(
-        word_selector: Box<dyn WordSelector>,
-        punctuation_chance: f64,
-    ) -> Self {
-        Self {
-            selector: word_selector,
-            next_is_capital: true,
-            punctuation_chance,
+/// Returns `num_words` words from `new_word`, discarding repeated words
+/// and falling back to allowing repeats if too many words were
+/// discarded.
+fn discard_repeated_words(
+    mut new_word: impl FnMut() -> Result<String, io::Error>,
+    num_words: usize,
+) -> Result<Vec<String>, io::Error> {
+    let mut seen = HashSet::new();
+    let mut words = Vec::with_capacity(num_words);
+    let mut num_draws = 0;
+
+    while words.len() < num_words {
+        let word = new_word()?;
+        num_draws += 1;
+
+        if seen.insert(word.clone()) || num_draws > num_words * MAX_DRAWS_PER_UNIQUE_WORD {
+            words.push(word);
         }
     }
+
+    Ok(words)
 }
 
-impl WordSelector for PunctuatedWordSelector {
-    fn new_word(&mut self) -> Result<String, io::Error> {
-        let mut rng = rand::thread_rng();
+/// Describes a thing that provides new words.
+pub trait WordSelector {
+    /// Returns a new word.
+    fn new_word(&mut self) -> Result<String, io::Error>;
 
-        let mut word = self.selector.new_word()?;
+    /// Returns a [`Vec`] containing `num_words` words.
+    fn new_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        (0..num_words).map(|_| self.new_word()).collect()
+    }
 
-        let will_punctuate = rng.gen_bool(self.punctuation_chance);
-        if will_punctuate || self.next_is_capital {
-            let mut chars: VecDeque<char> = word.chars().collect();
-            if self.next_is_capital {
-                // some unicode chars map to multiple chars when uppercased.
+    /// Returns a [`Vec`] containing `num_words` words where no word is
+    /// repeated, as long as there are enough distinct words.
+    ///
+    /// Selectors that pick words from a list draw them without
+    /// replacement. The default implementation, for selectors that make
+    /// up their words, like [`MarkovSelector`], or take them from a text
+    /// in order, discards repeated words, falling back to allowing
+    /// repeats if too many words were discarded.
+    fn new_unique_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        discard_repeated_words(|| self.new_word(), num_words)
+    }
+
+    /// The selector that this selector takes its words from, for
+    /// selectors that wrap another one, like [`PunctuatedWordSelector`].
+    ///
+    /// The hooks below default to the ones of the wrapped selector, so a
+    /// wrapper only overrides how it changes the words. The default
+    /// implementation returns `None`.
+    fn wrapped(&self) -> Option<&dyn WordSelector> {
+        None
+    }
+
+    /// Same as [`WordSelector::wrapped`], but mutable.
+    fn wrapped_mut(&mut self) -> Option<&mut Box<dyn WordSelector>> {
+        None
+    }
+
+    /// Number of words that the next test should have, if it is decided
+    /// by the selector rather than the configured number of words.
+    ///
+    /// For example, [`QuoteSelector`] returns the number of words left
+    /// in the current quote so that each test is one whole quote.
+    fn next_text_len(&mut self) -> Option<usize> {
+        self.wrapped_mut()?.next_text_len()
+    }
+
+    /// Annotation of a word returned by this selector, like its meaning
+    /// in a vocabulary list, to be shown while it is typed.
+    fn annotation(&self, word: &str) -> Option<String> {
+        self.wrapped()?.annotation(word)
+    }
+
+    /// Called with the results of each test that was completed, for
+    /// selectors that adapt to how the user types, like
+    /// [`AdaptiveSelector`].
+    fn record_results(&mut self, results: &ToipeResults) {
+        if let Some(selector) = self.wrapped_mut() {
+            selector.record_results(results)
+        }
+    }
+
+    /// Returns the next `num_words` words that
+    /// [`WordSelector::new_words`] would return, without selecting them.
+    ///
+    /// Only selectors that return the words of a text in order, like
+    /// [`TextSelector`] and [`BookSelector`], know their next words, and
+    /// so do the selectors that wrap them (see [`peek_wrapped`]). The
+    /// default implementation returns an error of kind
+    /// [`io::ErrorKind::Unsupported`] for other selectors.
+    fn peek_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        if self.wrapped().is_some() {
+            return peek_wrapped(self, num_words);
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "words are chosen at random and cannot be known in advance",
+        ))
+    }
+
+    /// Starts again from the beginning of the text, for selectors that
+    /// return the words of a text in order.
+    ///
+    /// The default implementation does nothing.
+    fn reset(&mut self) -> Result<(), io::Error> {
+        self.wrapped_mut()
+            .map_or(Ok(()), |selector| selector.reset())
+    }
+}
+
+/// Words peeked from a wrapped selector, put in its place while the
+/// selector wrapping it selects its next words (see [`peek_wrapped`]).
+struct PeekedWords(VecDeque<String>);
+
+impl WordSelector for PeekedWords {
+    fn new_word(&mut self) -> Result<String, io::Error> {
+        self.0
+            .pop_front()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "ran out of peeked words"))
+    }
+}
+
+/// Returns the next `num_words` words of `wrapper`, a selector that
+/// wraps another one (see [`WordSelector::wrapped`]), without selecting
+/// them from the wrapped selector.
+///
+/// The wrapper selects its words from words peeked from the wrapped
+/// selector, which is put back afterwards. More words are peeked until
+/// they are enough, which ends since wrappers draw a limited number of
+/// words for each of their words. Wrappers that choose at random have to
+/// put their random number generator back too.
+fn peek_wrapped<S: WordSelector + ?Sized>(
+    wrapper: &mut S,
+    num_words: usize,
+) -> Result<Vec<String>, io::Error> {
+    // most words are taken in the first few draws
+    let mut num_peeked = num_words;
+    loop {
+        let wrapped = wrapper.wrapped_mut().expect("only wrappers are peeked");
+        let peeked = wrapped.peek_words(num_peeked)?;
+        let all_peeked = peeked.len() < num_peeked;
+        let selector = std::mem::replace(wrapped, Box::new(PeekedWords(VecDeque::from(peeked))));
+        let words = wrapper.new_words(num_words);
+        *wrapper.wrapped_mut().expect("only wrappers are peeked") = selector;
+        match words {
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof && !all_peeked => {
+                num_peeked *= 4;
+            }
+            words => return words,
+        }
+    }
+}
+
+/// Returns `num_words` words of `wrapper`, a selector that only selects
+/// the words of the selector it wraps that `accept` returns true for,
+/// with no word repeated as long as there are enough accepted words.
+///
+/// The words are filtered from one sample of distinct words of the
+/// wrapped selector (see [`WordSelector::new_unique_words`]), so they are
+/// drawn without replacement too. The sample is drawn again, four times
+/// larger, until it has enough accepted words, it has every word of the
+/// wrapped selector, or it has `max_draws` words for each word. The
+/// words still missing then are taken from [`WordSelector::new_word`] of
+/// the wrapper, and can be repeated.
+fn filter_unique_words<S: WordSelector>(
+    wrapper: &mut S,
+    num_words: usize,
+    max_draws: usize,
+    accept: fn(&S, &str) -> bool,
+) -> Result<Vec<String>, io::Error> {
+    let max_sample_len = num_words.saturating_mul(max_draws);
+    let mut sample_len = num_words;
+    let mut words = loop {
+        let sample = wrapper
+            .wrapped_mut()
+            .expect("only wrappers filter words")
+            .new_unique_words(sample_len)?;
+        let mut distinct = HashSet::new();
+        let words: Vec<String> = sample
+            .into_iter()
+            .filter(|word| distinct.insert(word.clone()))
+            .filter(|word| accept(wrapper, word))
+            .take(num_words)
+            .collect();
+        // a repeated word means that every word is in the sample
+        let has_every_word = distinct.len() < sample_len;
+        if words.len() == num_words || has_every_word || sample_len >= max_sample_len {
+            break words;
+        }
+        sample_len = sample_len.saturating_mul(4).min(max_sample_len);
+    };
+
+    while words.len() < num_words {
+        words.push(wrapper.new_word()?);
+    }
+    Ok(words)
+}
+
+/// Number of the most recent words of a text that are not repeated in
+/// it (see [`WordStream::with_unique_words`]).
+const MAX_RECENT_WORDS: usize = 1000;
+
+/// Number of words drawn after the length of a text to get to the end
+/// of its last sentence before ending it anyway (see
+/// [`WordStream::with_sentences`]).
+const MAX_WORDS_TO_SENTENCE_END: usize = 100;
+
+/// Words from a [`WordSelector`], taken as they are needed.
+///
+/// Tests take the words of each page from the stream, so tests that go
+/// on until time is up or until they are stopped, like timed and endless
+/// tests, get more words without deciding how many up front. Words can
+/// also be taken one at a time, since the stream is an endless
+/// [`Iterator`].
+///
+/// Words are drawn from the selector a text at a time, of the length
+/// given to [`WordStream::new`] or decided by the selector (see
+/// [`WordSelector::next_text_len`]). So selectors that treat the words of
+/// a text together, like [`QuoteSelector`] that returns whole quotes,
+/// work the same as when the text is drawn at once.
+///
+/// ```
+/// use toipe::textgen::{RawWordSelector, WordStream};
+///
+/// let selector = RawWordSelector::from_string("hello\nworld\n".to_string())?;
+/// let mut words = WordStream::new(Box::new(selector), 30);
+/// for word in words.by_ref().take(100) {
+///     assert!(["hello", "world"].contains(&word?.as_str()));
+/// }
+/// // the rest of the fourth text of 30 words
+/// assert_eq!(words.next_text()?.len(), 20);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct WordStream {
+    selector: Box<dyn WordSelector>,
+    text_len: usize,
+    unique_words: bool,
+    sentences: bool,
+    /// most recent words drawn since the text was started, oldest first,
+    /// when words are unique
+    recent: VecDeque<String>,
+    /// words drawn from the selector that were not streamed yet
+    pending: VecDeque<String>,
+}
+
+impl WordStream {
+    /// Streams words from the selector, drawing `text_len` words at a
+    /// time unless the selector decides the length.
+    pub fn new(selector: Box<dyn WordSelector>, text_len: usize) -> Self {
+        Self {
+            selector,
+            text_len,
+            unique_words: false,
+            sentences: false,
+            recent: VecDeque::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Sets whether to avoid streaming a word again until the next text
+    /// is started (see [`WordStream::start_text`]), as long as the
+    /// selector has enough distinct words.
+    ///
+    /// Only the last [`MAX_RECENT_WORDS`] words are remembered, so that
+    /// drawing more words for a long text takes the same time.
+    pub fn with_unique_words(mut self, unique_words: bool) -> Self {
+        self.unique_words = unique_words;
+        self
+    }
+
+    /// Sets whether texts go on to the end of their last sentence (see
+    /// [`ends_sentence`]), for selectors that give words in order, like
+    /// books. Texts whose length the selector decides, like quotes, are
+    /// left as they are.
+    pub fn with_sentences(mut self, sentences: bool) -> Self {
+        self.sentences = sentences;
+        self
+    }
+
+    /// The selector that words are drawn from.
+    pub fn selector(&self) -> &dyn WordSelector {
+        self.selector.as_ref()
+    }
+
+    /// The selector that words are drawn from, like for recording the
+    /// results of a test (see [`WordSelector::record_results`]).
+    pub fn selector_mut(&mut self) -> &mut dyn WordSelector {
+        self.selector.as_mut()
+    }
+
+    /// Starts a new text, like for a new test.
+    ///
+    /// Words of the previous texts can be streamed again, and words that
+    /// were drawn but not streamed yet are dropped, so that the text
+    /// starts with a new text of the selector, like a new quote.
+    pub fn start_text(&mut self) {
+        self.recent.clear();
+        self.pending.clear();
+    }
+
+    /// The words drawn but not streamed yet, or the next text drawn from
+    /// the selector if there are none.
+    pub fn next_text(&mut self) -> Result<Vec<String>, io::Error> {
+        if self.pending.is_empty() {
+            self.draw_text()?;
+        }
+        Ok(self.pending.drain(..).collect())
+    }
+
+    /// Draws the next text from the selector.
+    fn draw_text(&mut self) -> Result<(), io::Error> {
+        let text_len = self.selector.next_text_len();
+        let num_words = text_len.unwrap_or(self.text_len);
+        if !self.unique_words {
+            self.pending.extend(self.selector.new_words(num_words)?);
+            if self.sentences && text_len.is_none() {
+                for _ in 0..MAX_WORDS_TO_SENTENCE_END {
+                    if self.pending.back().map_or(true, |word| ends_sentence(word)) {
+                        break;
+                    }
+                    self.pending.push_back(self.selector.new_word()?);
+                }
+            }
+            return Ok(());
+        }
+
+        // words drawn without replacement, enough to leave out the recent
+        // ones
+        let words = self
+            .selector
+            .new_unique_words(num_words + self.recent.len())?;
+        let recent: HashSet<&String> = self.recent.iter().collect();
+        let (mut words, recent_words): (Vec<String>, Vec<String>) =
+            words.into_iter().partition(|word| !recent.contains(word));
+        // the selector ran out of words, so recent words are repeated
+        words.extend(recent_words);
+        words.truncate(num_words);
+
+        self.recent.extend(words.iter().cloned());
+        let num_forgotten = self.recent.len().saturating_sub(MAX_RECENT_WORDS);
+        self.recent.drain(..num_forgotten);
+        self.pending.extend(words);
+        Ok(())
+    }
+}
+
+impl Iterator for WordStream {
+    type Item = Result<String, io::Error>;
+
+    /// Returns the next word, or an error if the selector could not
+    /// select one. There is always a next item.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pending.is_empty() {
+            if let Err(error) = self.draw_text() {
+                return Some(Err(error));
+            }
+        }
+        // a selector may decide that a text has no words
+        self.pending.pop_front().map(Ok).or_else(|| {
+            Some(Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the word selector did not select any words",
+            )))
+        })
+    }
+}
+
+impl<T: Seek + io::Read> RawWordSelector<T> {
+    /// Sets the length of the longest word to select, which is at least
+    /// 2.
+    ///
+    /// Selecting words returns an error if the list has no words that
+    /// are short enough.
+    pub fn with_max_word_len(mut self, max_word_len: usize) -> Self {
+        self.max_word_len = max_word_len.max(2);
+        self.update_word_sums();
+        self
+    }
+
+    /// Deals the words of the list in a shuffled order if `shuffle` is
+    /// true, so that every word is selected once before any word is
+    /// selected again, across tests.
+    ///
+    /// Words are then selected irrespective of their frequencies.
+    pub fn with_shuffle(mut self, shuffle: bool) -> Self {
+        self.deck = shuffle.then(|| IndexSampler::new(self.word_lines_sum[26]));
+        self
+    }
+
+    /// Sets the random number generator used to select words.
+    ///
+    /// With a generator made by [`ToipeRng::from_seed`], the same words
+    /// are selected in the same order for the same seed and word list.
+    pub fn with_rng(mut self, rng: ToipeRng) -> Self {
+        self.rng = rng;
+        self
+    }
+}
+
+impl<T: Seek + io::Read> WordSelector for RawWordSelector<T> {
+    fn new_word(&mut self) -> Result<String, io::Error> {
+        let mut word = self.new_word_raw()?;
+        word.make_ascii_lowercase();
+        Ok(word)
+    }
+
+    fn annotation(&self, word: &str) -> Option<String> {
+        self.annotations.get(word).cloned()
+    }
+
+    /// Draws words from the list without replacement, so that each word
+    /// takes `O(1)` amortized time even for large lists.
+    ///
+    /// Words that appear multiple times in the list are returned only
+    /// once. If the list runs out of words, words are repeated.
+    ///
+    /// If the list has frequencies, each word is drawn with a chance
+    /// proportional to its frequency, which takes going through the list
+    /// once for each call. If the words are dealt (see
+    /// [`RawWordSelector::with_shuffle`]), they are taken from the deal,
+    /// and words can be repeated once it is dealt again.
+    fn new_unique_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        let num_lines = self.word_lines_sum[26];
+        if num_lines == 0 || self.word_weights_sum.is_some_and(|sums| sums[26] == 0) {
+            return Err(self.no_words_error());
+        }
+
+        if self.deck.is_none() && self.word_weights_sum.is_some() {
+            let mut words = Vec::with_capacity(num_words);
+            // the list is gone through again if it runs out of words
+            while words.len() < num_words {
+                words.extend(self.sample_weighted_words(num_words - words.len())?);
+            }
+            return Ok(words);
+        }
+
+        let dealt = self.deck.is_some();
+        let mut sampler = self
+            .deck
+            .take()
+            .unwrap_or_else(|| IndexSampler::new(num_lines));
+        let words = self.draw_unique_lines(&mut sampler, num_words);
+        if dealt {
+            self.deck = Some(sampler);
+        }
+        words
+    }
+}
+
+/// Wraps another word selector, taking words from it and adding punctuation to the end of or
+/// around words with a configurable chance. Will capitalize the next word when an end-of-sentence
+/// punctuation mark is used.
+pub struct PunctuatedWordSelector {
+    selector: Box<dyn WordSelector>,
+    next_is_capital: bool,
+    punctuation_chance: f64,
+    rng: ToipeRng,
+}
+
+enum PunctuationType {
+    Capitaizing(char),
+    Ending(char),
+    Surrounding(char, char),
+}
+
+const PUNCTUATION: [PunctuationType; 12] = [
+    PunctuationType::Capitaizing('!'),
+    PunctuationType::Capitaizing('?'),
+    PunctuationType::Capitaizing('.'),
+    PunctuationType::Ending(','),
+    PunctuationType::Ending(':'),
+    PunctuationType::Ending(';'),
+    PunctuationType::Surrounding('\'', '\''),
+    PunctuationType::Surrounding('"', '"'),
+    PunctuationType::Surrounding('(', ')'),
+    PunctuationType::Surrounding('{', '}'),
+    PunctuationType::Surrounding('<', '>'),
+    PunctuationType::Surrounding('[', ']'),
+];
+
+impl PunctuatedWordSelector {
+    /// Creates a PunctuatedWordSelector from another WordSelector, allowing the selection of the
+    /// chance of punctuation.
+    pub fn from_word_selector(
+        word_selector: Box<dyn WordSelector>,
+        punctuation_chance: f64,
+    ) -> Self {
+        Self {
+            selector: word_selector,
+            next_is_capital: true,
+            punctuation_chance,
+            rng: ToipeRng::from_entropy(),
+        }
+    }
+
+    /// Sets the random number generator used to punctuate words.
+    pub fn with_rng(mut self, rng: ToipeRng) -> Self {
+        self.rng = rng;
+        self
+    }
+}
+
+impl PunctuatedWordSelector {
+    /// Adds punctuation to the word with the configured chance.
+    fn punctuate(&mut self, mut word: String) -> String {
+        let will_punctuate = self.rng.gen_bool(self.punctuation_chance);
+        if will_punctuate || self.next_is_capital {
+            let mut chars: VecDeque<char> = word.chars().collect();
+            if self.next_is_capital {
+                // some unicode chars map to multiple chars when uppercased.
                 for c in chars
                     .pop_front()
                     .expect("got empty word")
@@ -319,7 +1463,7 @@ impl WordSelector for PunctuatedWordSelector {
             }
             if will_punctuate {
                 match PUNCTUATION
-                    .choose(&mut rng)
+                    .choose(&mut self.rng)
                     .expect("only returns none if the slice is empty")
                 {
                     PunctuationType::Capitaizing(c) => {
@@ -335,6 +1479,3534 @@ impl WordSelector for PunctuatedWordSelector {
             }
             word = chars.into_iter().collect();
         }
-        Ok(word)
+        word
+    }
+}
+
+impl WordSelector for PunctuatedWordSelector {
+    fn new_word(&mut self) -> Result<String, io::Error> {
+        let word = self.selector.new_word()?;
+        Ok(self.punctuate(word))
+    }
+
+    fn new_unique_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        let words = self.selector.new_unique_words(num_words)?;
+        Ok(words.into_iter().map(|word| self.punctuate(word)).collect())
+    }
+
+    fn annotation(&self, word: &str) -> Option<String> {
+        // without the punctuation and capitalization added to it
+        let word = word
+            .trim_matches(|c: char| !c.is_alphanumeric())
+            .to_lowercase();
+        self.selector.annotation(&word)
+    }
+
+    fn peek_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        let (rng, next_is_capital) = (self.rng.clone(), self.next_is_capital);
+        let words = peek_wrapped(self, num_words);
+        (self.rng, self.next_is_capital) = (rng, next_is_capital);
+        words
+    }
+
+    fn wrapped(&self) -> Option<&dyn WordSelector> {
+        Some(self.selector.as_ref())
+    }
+
+    fn wrapped_mut(&mut self) -> Option<&mut Box<dyn WordSelector>> {
+        Some(&mut self.selector)
+    }
+}
+
+/// Wraps another word selector to put numbers among its words.
+pub struct NumberedWordSelector {
+    selector: Box<dyn WordSelector>,
+    number_chance: f64,
+    rng: ToipeRng,
+}
+
+impl NumberedWordSelector {
+    /// Creates a NumberedWordSelector from another WordSelector, with
+    /// the given chance of each word being a number.
+    pub fn from_word_selector(word_selector: Box<dyn WordSelector>, number_chance: f64) -> Self {
+        Self {
+            selector: word_selector,
+            number_chance,
+            rng: ToipeRng::from_entropy(),
+        }
+    }
+
+    /// Sets the random number generator used to generate numbers and
+    /// decide where they are put.
+    pub fn with_rng(mut self, rng: ToipeRng) -> Self {
+        self.rng = rng;
+        self
+    }
+
+    /// Random number with 1 to 4 digits.
+    fn new_number(rng: &mut impl Rng) -> String {
+        let num_digits = rng.gen_range(1..=4);
+        rng.gen_range(10u32.pow(num_digits - 1)..10u32.pow(num_digits))
+            .to_string()
+    }
+}
+
+impl WordSelector for NumberedWordSelector {
+    fn new_word(&mut self) -> Result<String, io::Error> {
+        if self.rng.gen_bool(self.number_chance) {
+            return Ok(Self::new_number(&mut self.rng));
+        }
+
+        self.selector.new_word()
+    }
+
+    fn new_unique_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        let words = self.selector.new_unique_words(num_words)?;
+
+        Ok(words
+            .into_iter()
+            .map(|word| {
+                if self.rng.gen_bool(self.number_chance) {
+                    Self::new_number(&mut self.rng)
+                } else {
+                    word
+                }
+            })
+            .collect())
+    }
+
+    fn peek_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        let rng = self.rng.clone();
+        let words = peek_wrapped(self, num_words);
+        self.rng = rng;
+        words
+    }
+
+    fn wrapped(&self) -> Option<&dyn WordSelector> {
+        Some(self.selector.as_ref())
+    }
+
+    fn wrapped_mut(&mut self) -> Option<&mut Box<dyn WordSelector>> {
+        Some(&mut self.selector)
+    }
+}
+
+/// Wraps another word selector to capitalize some of its words, for
+/// practicing the shift keys.
+pub struct CapitalizedWordSelector {
+    selector: Box<dyn WordSelector>,
+    capital_chance: f64,
+    rng: ToipeRng,
+}
+
+impl CapitalizedWordSelector {
+    /// Creates a CapitalizedWordSelector from another WordSelector, with
+    /// the given chance of each word being capitalized.
+    pub fn from_word_selector(word_selector: Box<dyn WordSelector>, capital_chance: f64) -> Self {
+        Self {
+            selector: word_selector,
+            capital_chance,
+            rng: ToipeRng::from_entropy(),
+        }
+    }
+
+    /// Sets the random number generator used to decide which words are
+    /// capitalized.
+    pub fn with_rng(mut self, rng: ToipeRng) -> Self {
+        self.rng = rng;
+        self
+    }
+
+    fn maybe_capitalize(&mut self, word: String) -> String {
+        if !self.rng.gen_bool(self.capital_chance) {
+            return word;
+        }
+
+        let mut chars = word.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => word,
+        }
+    }
+}
+
+impl WordSelector for CapitalizedWordSelector {
+    fn new_word(&mut self) -> Result<String, io::Error> {
+        let word = self.selector.new_word()?;
+        Ok(self.maybe_capitalize(word))
+    }
+
+    fn new_unique_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        let words = self.selector.new_unique_words(num_words)?;
+
+        Ok(words
+            .into_iter()
+            .map(|word| self.maybe_capitalize(word))
+            .collect())
+    }
+
+    fn annotation(&self, word: &str) -> Option<String> {
+        // without the capital letter added to it
+        self.selector.annotation(&word.to_lowercase())
+    }
+
+    fn peek_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        let rng = self.rng.clone();
+        let words = peek_wrapped(self, num_words);
+        self.rng = rng;
+        words
+    }
+
+    fn wrapped(&self) -> Option<&dyn WordSelector> {
+        Some(self.selector.as_ref())
+    }
+
+    fn wrapped_mut(&mut self) -> Option<&mut Box<dyn WordSelector>> {
+        Some(&mut self.selector)
+    }
+}
+
+/// Number of words drawn from the wrapped selector before
+/// [`DrillSelector`] gives up on finding a word with a drilled digraph,
+/// or [`NgramSelector`] with a drilled n-gram.
+const MAX_DRAWS_PER_DRILL_WORD: usize = 100;
+
+/// Wraps another word selector to only select words that have at least
+/// one of the given digraphs (pairs of consecutive letters), for
+/// practicing them.
+///
+/// Digraphs are matched ignoring case. If no word with a digraph is
+/// found after a number of draws, the last word drawn is used.
+pub struct DrillSelector {
+    selector: Box<dyn WordSelector>,
+    digraphs: Vec<(char, char)>,
+}
+
+impl DrillSelector {
+    /// Creates a DrillSelector from another WordSelector and the
+    /// digraphs to practice, given in lowercase.
+    pub fn from_word_selector(
+        word_selector: Box<dyn WordSelector>,
+        digraphs: Vec<(char, char)>,
+    ) -> Self {
+        Self {
+            selector: word_selector,
+            digraphs,
+        }
+    }
+
+    fn has_digraph(&self, word: &str) -> bool {
+        let chars: Vec<char> = word.to_lowercase().chars().collect();
+        chars
+            .windows(2)
+            .any(|pair| self.digraphs.contains(&(pair[0], pair[1])))
+    }
+}
+
+impl WordSelector for DrillSelector {
+    fn new_word(&mut self) -> Result<String, io::Error> {
+        let mut word = self.selector.new_word()?;
+        for _ in 1..MAX_DRAWS_PER_DRILL_WORD {
+            if self.has_digraph(&word) {
+                break;
+            }
+            word = self.selector.new_word()?;
+        }
+
+        Ok(word)
+    }
+
+    fn new_unique_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        filter_unique_words(self, num_words, MAX_DRAWS_PER_DRILL_WORD, Self::has_digraph)
+    }
+
+    fn wrapped(&self) -> Option<&dyn WordSelector> {
+        Some(self.selector.as_ref())
+    }
+
+    fn wrapped_mut(&mut self) -> Option<&mut Box<dyn WordSelector>> {
+        Some(&mut self.selector)
+    }
+}
+
+/// Number of n-grams joined into a pseudo-word by [`NgramSelector`].
+const NGRAMS_PER_PSEUDO_WORD: usize = 2;
+
+/// Wraps another word selector to select words that have at least one
+/// of the given n-grams (runs of consecutive letters, like `th` or
+/// `ion`), for practicing the moves between those letters.
+///
+/// N-grams are matched ignoring case. If no word with an n-gram is
+/// found after a number of draws, a pseudo-word made of random n-grams
+/// (like `thion`) is used instead, so that the n-grams are practiced
+/// even with a word list that rarely has them.
+pub struct NgramSelector {
+    selector: Box<dyn WordSelector>,
+    ngrams: Vec<String>,
+    rng: ToipeRng,
+}
+
+impl NgramSelector {
+    /// Creates an NgramSelector from another WordSelector and the
+    /// n-grams to practice, given in lowercase.
+    ///
+    /// Returns an error if no n-gram is given.
+    pub fn from_word_selector(
+        word_selector: Box<dyn WordSelector>,
+        ngrams: Vec<String>,
+    ) -> Result<Self, io::Error> {
+        if ngrams.iter().all(|ngram| ngram.is_empty()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no n-grams to practice",
+            ));
+        }
+
+        Ok(Self {
+            selector: word_selector,
+            ngrams,
+            rng: ToipeRng::from_entropy(),
+        })
+    }
+
+    /// Sets the random number generator used to make pseudo-words.
+    pub fn with_rng(mut self, rng: ToipeRng) -> Self {
+        self.rng = rng;
+        self
+    }
+
+    fn has_ngram(&self, word: &str) -> bool {
+        let word = word.to_lowercase();
+        self.ngrams
+            .iter()
+            .any(|ngram| !ngram.is_empty() && word.contains(ngram.as_str()))
+    }
+
+    fn new_pseudo_word(&mut self) -> String {
+        (0..NGRAMS_PER_PSEUDO_WORD)
+            .map(|_| {
+                self.ngrams
+                    .choose(&mut self.rng)
+                    .expect("there is at least one n-gram")
+                    .as_str()
+            })
+            .collect()
+    }
+}
+
+impl WordSelector for NgramSelector {
+    fn new_word(&mut self) -> Result<String, io::Error> {
+        for _ in 0..MAX_DRAWS_PER_DRILL_WORD {
+            let word = self.selector.new_word()?;
+            if self.has_ngram(&word) {
+                return Ok(word);
+            }
+        }
+
+        Ok(self.new_pseudo_word())
+    }
+
+    fn new_unique_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        filter_unique_words(self, num_words, MAX_DRAWS_PER_DRILL_WORD, Self::has_ngram)
+    }
+
+    fn peek_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        let rng = self.rng.clone();
+        let words = peek_wrapped(self, num_words);
+        self.rng = rng;
+        words
+    }
+
+    fn wrapped(&self) -> Option<&dyn WordSelector> {
+        Some(self.selector.as_ref())
+    }
+
+    fn wrapped_mut(&mut self) -> Option<&mut Box<dyn WordSelector>> {
+        Some(&mut self.selector)
+    }
+}
+
+/// Number of words drawn from the wrapped selector before
+/// [`LettersSelector`] gives up on finding a word made of its letters.
+const MAX_DRAWS_PER_LETTERS_WORD: usize = 10000;
+
+/// Wraps another word selector to only select words made entirely of
+/// the given chars, like the home row keys for learning a keyboard
+/// layout.
+///
+/// Chars are matched ignoring case. Returns an error if no such word is
+/// found after a number of draws, since the word list may have none.
+pub struct LettersSelector {
+    selector: Box<dyn WordSelector>,
+    letters: HashSet<char>,
+}
+
+impl LettersSelector {
+    /// Creates a LettersSelector from another WordSelector and the
+    /// chars that words can have, given in lowercase.
+    pub fn from_word_selector(word_selector: Box<dyn WordSelector>, letters: &str) -> Self {
+        Self {
+            selector: word_selector,
+            letters: letters.chars().collect(),
+        }
+    }
+
+    fn has_only_letters(&self, word: &str) -> bool {
+        word.to_lowercase()
+            .chars()
+            .all(|c| self.letters.contains(&c))
+    }
+}
+
+impl WordSelector for LettersSelector {
+    fn new_word(&mut self) -> Result<String, io::Error> {
+        for _ in 0..MAX_DRAWS_PER_LETTERS_WORD {
+            let word = self.selector.new_word()?;
+            if self.has_only_letters(&word) {
+                return Ok(word);
+            }
+        }
+
+        let mut letters: Vec<char> = self.letters.iter().copied().collect();
+        letters.sort_unstable();
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "no word made of only the letters '{}' was found in the word list",
+                letters.into_iter().collect::<String>()
+            ),
+        ))
+    }
+
+    fn new_unique_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        filter_unique_words(
+            self,
+            num_words,
+            MAX_DRAWS_PER_LETTERS_WORD,
+            Self::has_only_letters,
+        )
+    }
+
+    fn wrapped(&self) -> Option<&dyn WordSelector> {
+        Some(self.selector.as_ref())
+    }
+
+    fn wrapped_mut(&mut self) -> Option<&mut Box<dyn WordSelector>> {
+        Some(&mut self.selector)
+    }
+}
+
+/// Number of words drawn from the wrapped selector before
+/// [`PatternSelector`] gives up on finding a word that matches.
+const MAX_DRAWS_PER_PATTERN_WORD: usize = 10000;
+
+/// Wraps another word selector to only select words that match a
+/// regular expression, like `^[a-m]{4,6}$`, for custom drills.
+///
+/// The pattern is matched against the words as they are returned by the
+/// wrapped selector, so it matches anywhere in a word unless it is
+/// anchored with `^` and `$`. Returns an error if no matching word is
+/// found after a number of draws, since the word list may have none.
+pub struct PatternSelector {
+    selector: Box<dyn WordSelector>,
+    pattern: Regex,
+}
+
+impl PatternSelector {
+    /// Creates a PatternSelector from another WordSelector and the
+    /// pattern that words must match.
+    pub fn from_word_selector(word_selector: Box<dyn WordSelector>, pattern: Regex) -> Self {
+        Self {
+            selector: word_selector,
+            pattern,
+        }
+    }
+}
+
+impl WordSelector for PatternSelector {
+    fn new_word(&mut self) -> Result<String, io::Error> {
+        for _ in 0..MAX_DRAWS_PER_PATTERN_WORD {
+            let word = self.selector.new_word()?;
+            if self.pattern.is_match(&word) {
+                return Ok(word);
+            }
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "no word matching the pattern '{}' was found in the word list",
+                self.pattern
+            ),
+        ))
+    }
+
+    fn new_unique_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        filter_unique_words(
+            self,
+            num_words,
+            MAX_DRAWS_PER_PATTERN_WORD,
+            |selector, word| selector.pattern.is_match(word),
+        )
+    }
+
+    fn wrapped(&self) -> Option<&dyn WordSelector> {
+        Some(self.selector.as_ref())
+    }
+
+    fn wrapped_mut(&mut self) -> Option<&mut Box<dyn WordSelector>> {
+        Some(&mut self.selector)
+    }
+}
+
+/// Number of words drawn from the wrapped selector before
+/// [`ExcludeSelector`] gives up on finding a word that is not excluded.
+const MAX_DRAWS_PER_ALLOWED_WORD: usize = 10000;
+
+/// Wraps another word selector to never select some words, like
+/// offensive words or the odd entries of the OS word list.
+///
+/// Words are compared ignoring case and the punctuation around them.
+/// Returns an error if only excluded words are found after a number of
+/// draws, since the word list may have no other words.
+pub struct ExcludeSelector {
+    selector: Box<dyn WordSelector>,
+    excluded: HashSet<String>,
+}
+
+impl ExcludeSelector {
+    /// Creates an ExcludeSelector from another WordSelector and the
+    /// words to exclude.
+    pub fn from_word_selector(
+        word_selector: Box<dyn WordSelector>,
+        excluded: impl IntoIterator<Item = String>,
+    ) -> Self {
+        Self {
+            selector: word_selector,
+            excluded: excluded
+                .into_iter()
+                .map(|word| Self::normalize(&word))
+                .collect(),
+        }
+    }
+
+    /// Reads the words to exclude from a file with a word on each line.
+    ///
+    /// Like in a word list, anything after a tab on a line is ignored.
+    pub fn read_excluded_words(path: &Path) -> Result<Vec<String>, io::Error> {
+        Ok(std::fs::read_to_string(path)?
+            .lines()
+            .filter_map(|line| {
+                let word = line.split('\t').next().unwrap_or_default().trim();
+                (!word.is_empty()).then(|| word.to_string())
+            })
+            .collect())
+    }
+
+    /// The form of a word that is compared with the excluded words.
+    fn normalize(word: &str) -> String {
+        word.trim_matches(|c: char| !c.is_alphanumeric())
+            .to_lowercase()
+    }
+
+    fn is_allowed(&self, word: &str) -> bool {
+        !self.excluded.contains(&Self::normalize(word))
+    }
+}
+
+impl WordSelector for ExcludeSelector {
+    fn new_word(&mut self) -> Result<String, io::Error> {
+        for _ in 0..MAX_DRAWS_PER_ALLOWED_WORD {
+            let word = self.selector.new_word()?;
+            if self.is_allowed(&word) {
+                return Ok(word);
+            }
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "no word that is not excluded was found in the word list",
+        ))
+    }
+
+    fn new_unique_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        filter_unique_words(
+            self,
+            num_words,
+            MAX_DRAWS_PER_ALLOWED_WORD,
+            Self::is_allowed,
+        )
+    }
+
+    fn wrapped(&self) -> Option<&dyn WordSelector> {
+        Some(self.selector.as_ref())
+    }
+
+    fn wrapped_mut(&mut self) -> Option<&mut Box<dyn WordSelector>> {
+        Some(&mut self.selector)
+    }
+}
+
+/// Number of words drawn for each word selected by [`AdaptiveSelector`].
+#[cfg(feature = "history")]
+const ADAPTIVE_CANDIDATES: usize = 5;
+
+/// Wraps another word selector to prefer words with the letters and
+/// bigrams (two consecutive letters) that are mistyped most often, for
+/// practicing them.
+///
+/// A few words are drawn for each word and the one whose letters and
+/// bigrams have the highest error rates added up is selected, so the
+/// weak ones show up more often without every word having them.
+/// Letters typed fewer than [`MIN_KEY_COUNT`] times and bigrams typed
+/// fewer than [`MIN_BIGRAM_COUNT`] times are not counted as weak. The
+/// error rates are updated with the results of each test (see
+/// [`WordSelector::record_results`]).
+///
+/// Needs the `history` feature.
+#[cfg(feature = "history")]
+pub struct AdaptiveSelector {
+    selector: Box<dyn WordSelector>,
+    keys: HashMap<char, KeyStats>,
+    bigrams: HashMap<(char, char), KeyStats>,
+}
+
+#[cfg(feature = "history")]
+impl AdaptiveSelector {
+    /// Creates an AdaptiveSelector from another WordSelector and the
+    /// mistakes made for each lowercase letter so far.
+    pub fn from_word_selector(
+        word_selector: Box<dyn WordSelector>,
+        keys: HashMap<char, KeyStats>,
+    ) -> Self {
+        Self {
+            selector: word_selector,
+            keys,
+            bigrams: HashMap::new(),
+        }
+    }
+
+    /// Sets the mistakes made for the second letter of each lowercase
+    /// bigram so far.
+    pub fn with_bigrams(mut self, bigrams: HashMap<(char, char), KeyStats>) -> Self {
+        self.bigrams = bigrams;
+        self
+    }
+
+    /// Sum of the error rates of the distinct letters and bigrams of the
+    /// word.
+    fn weakness(&self, word: &str) -> f64 {
+        let letters: Vec<char> = word.to_lowercase().chars().collect();
+        let distinct_letters: HashSet<char> = letters.iter().copied().collect();
+        let distinct_bigrams: HashSet<(char, char)> =
+            letters.windows(2).map(|pair| (pair[0], pair[1])).collect();
+        let letter_weakness: f64 = distinct_letters
+            .iter()
+            .filter_map(|letter| self.keys.get(letter))
+            .filter(|stats| stats.count >= MIN_KEY_COUNT)
+            .map(KeyStats::error_rate)
+            .sum();
+        let bigram_weakness: f64 = distinct_bigrams
+            .iter()
+            .filter_map(|bigram| self.bigrams.get(bigram))
+            .filter(|stats| stats.count >= MIN_BIGRAM_COUNT)
+            .map(KeyStats::error_rate)
+            .sum();
+        letter_weakness + bigram_weakness
+    }
+}
+
+#[cfg(feature = "history")]
+impl WordSelector for AdaptiveSelector {
+    fn new_word(&mut self) -> Result<String, io::Error> {
+        let mut word = self.selector.new_word()?;
+        let mut weakness = self.weakness(&word);
+        for _ in 1..ADAPTIVE_CANDIDATES {
+            let candidate = self.selector.new_word()?;
+            let candidate_weakness = self.weakness(&candidate);
+            if candidate_weakness > weakness {
+                word = candidate;
+                weakness = candidate_weakness;
+            }
+        }
+
+        Ok(word)
+    }
+
+    /// Selects each word from its own candidates, like `new_word`, with
+    /// the candidates of all the words drawn together without
+    /// replacement.
+    fn new_unique_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        let candidates = self
+            .selector
+            .new_unique_words(num_words.saturating_mul(ADAPTIVE_CANDIDATES))?;
+        let mut words: Vec<String> = candidates
+            .chunks(ADAPTIVE_CANDIDATES)
+            .map(|candidates| {
+                candidates
+                    .iter()
+                    .map(|word| (self.weakness(word), word))
+                    .max_by(|(a, _), (b, _)| a.total_cmp(b))
+                    .map(|(_, word)| word.clone())
+                    .expect("chunks are not empty")
+            })
+            .collect();
+
+        while words.len() < num_words {
+            words.push(self.new_word()?);
+        }
+        Ok(words)
+    }
+
+    fn record_results(&mut self, results: &ToipeResults) {
+        for (letter, correct) in results.letter_presses() {
+            self.keys.entry(letter).or_default().record(correct);
+        }
+        for (bigram, correct) in results.bigram_presses() {
+            self.bigrams.entry(bigram).or_default().record(correct);
+        }
+        self.selector.record_results(results)
+    }
+
+    fn wrapped(&self) -> Option<&dyn WordSelector> {
+        Some(self.selector.as_ref())
+    }
+
+    fn wrapped_mut(&mut self) -> Option<&mut Box<dyn WordSelector>> {
+        Some(&mut self.selector)
+    }
+}
+
+/// Returns the words of a given text, in order.
+///
+/// Each test is the whole text (see [`WordSelector::next_text_len`]).
+pub struct TextSelector {
+    words: Vec<String>,
+    next_word: usize,
+}
+
+impl TextSelector {
+    /// Creates a selector over the words of the given text.
+    ///
+    /// Returns an error if the text has no words.
+    pub fn new(text: &str) -> Result<Self, io::Error> {
+        let words: Vec<String> = text
+            .split_whitespace()
+            .map(|word| word.to_string())
+            .collect();
+
+        if words.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "text has no words",
+            ));
+        }
+
+        Ok(Self {
+            words,
+            next_word: 0,
+        })
+    }
+}
+
+impl WordSelector for TextSelector {
+    fn new_word(&mut self) -> Result<String, io::Error> {
+        let word = self.words[self.next_word].clone();
+        self.next_word = (self.next_word + 1) % self.words.len();
+
+        Ok(word)
+    }
+
+    fn next_text_len(&mut self) -> Option<usize> {
+        Some(self.words.len() - self.next_word)
+    }
+
+    fn peek_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        Ok(self
+            .words
+            .iter()
+            .cycle()
+            .skip(self.next_word)
+            .take(num_words)
+            .cloned()
+            .collect())
+    }
+
+    fn reset(&mut self) -> Result<(), io::Error> {
+        self.next_word = 0;
+        Ok(())
+    }
+}
+
+/// Returns a fixed list of words in order, starting again from the
+/// first word after the last one.
+///
+/// Unlike [`TextSelector`], tests are as long as the configured number
+/// of words and each test continues where the last one ended, so the
+/// same words are typed in every run. This makes tests reproducible,
+/// like for checking the results of typing a script of keys or for
+/// benchmarking.
+///
+/// ```
+/// use toipe::textgen::{FixedWordSelector, WordSelector};
+///
+/// let mut selector = FixedWordSelector::new(["the", "quick", "fox"])?;
+/// assert_eq!(selector.new_words(4)?, ["the", "quick", "fox", "the"]);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct FixedWordSelector {
+    words: Vec<String>,
+    next_word: usize,
+}
+
+impl FixedWordSelector {
+    /// Creates a selector over the given words.
+    ///
+    /// Returns an error if there are no words.
+    pub fn new<I>(words: I) -> Result<Self, io::Error>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let words: Vec<String> = words.into_iter().map(Into::into).collect();
+
+        if words.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no words were given",
+            ));
+        }
+
+        Ok(Self {
+            words,
+            next_word: 0,
+        })
+    }
+}
+
+impl WordSelector for FixedWordSelector {
+    fn new_word(&mut self) -> Result<String, io::Error> {
+        let word = self.words[self.next_word].clone();
+        self.next_word = (self.next_word + 1) % self.words.len();
+
+        Ok(word)
+    }
+
+    fn new_unique_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        // the words are distinct as long as the list has enough of them
+        self.new_words(num_words)
+    }
+
+    fn peek_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        Ok(self
+            .words
+            .iter()
+            .cycle()
+            .skip(self.next_word)
+            .take(num_words)
+            .cloned()
+            .collect())
+    }
+
+    fn reset(&mut self) -> Result<(), io::Error> {
+        self.next_word = 0;
+        Ok(())
+    }
+}
+
+/// Number of spaces that a tab is expanded to in code.
+pub const TAB_WIDTH: usize = 4;
+
+/// Selects lines of code from a source file, in order.
+///
+/// Each "word" returned is a whole line, with tabs expanded to
+/// [`TAB_WIDTH`] spaces and trailing whitespace removed. Lines with
+/// chars that cannot be typed (anything other than printable ASCII) are
+/// left out. Lines start from a random line of the file and start again
+/// from the beginning after the end is reached.
+pub struct CodeSelector {
+    lines: Vec<String>,
+    /// line that the selector started from
+    first_line: usize,
+    next_line: usize,
+}
+
+impl CodeSelector {
+    /// Creates a selector over the lines of the given code.
+    ///
+    /// Returns an error if there is no line that can be typed.
+    pub fn new(code: &str) -> Result<Self, io::Error> {
+        let lines: Vec<String> = code
+            .lines()
+            .map(|line| {
+                line.replace('\t', &" ".repeat(TAB_WIDTH))
+                    .trim_end()
+                    .to_string()
+            })
+            .filter(|line| line.chars().all(|c| c == ' ' || c.is_ascii_graphic()))
+            .collect();
+
+        if lines.iter().all(|line| line.is_empty()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "code has no lines that can be typed",
+            ));
+        }
+
+        let next_line = ToipeRng::from_entropy().gen_range(0..lines.len());
+
+        Ok(Self {
+            lines,
+            first_line: next_line,
+            next_line,
+        })
+    }
+
+    /// Chooses the line to start from using the given random number
+    /// generator.
+    pub fn with_rng(mut self, mut rng: ToipeRng) -> Self {
+        self.next_line = rng.gen_range(0..self.lines.len());
+        self.first_line = self.next_line;
+        self
+    }
+
+    /// Create from a source file at the given path.
+    pub fn from_path(path: PathBuf) -> Result<Self, io::Error> {
+        Self::new(&std::fs::read_to_string(path)?)
+    }
+
+    fn skip_empty_lines(&mut self) {
+        while self.lines[self.next_line].is_empty() {
+            self.next_line = (self.next_line + 1) % self.lines.len();
+        }
+    }
+}
+
+impl WordSelector for CodeSelector {
+    fn new_word(&mut self) -> Result<String, io::Error> {
+        let line = self.lines[self.next_line].clone();
+        self.next_line = (self.next_line + 1) % self.lines.len();
+
+        Ok(line)
+    }
+
+    /// Lines of code start at the first non-empty line.
+    fn new_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        self.skip_empty_lines();
+
+        (0..num_words).map(|_| self.new_word()).collect()
+    }
+
+    fn peek_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        self.skip_empty_lines();
+
+        Ok(self
+            .lines
+            .iter()
+            .cycle()
+            .skip(self.next_line)
+            .take(num_words)
+            .cloned()
+            .collect())
+    }
+
+    /// Goes back to the line that the selector started from.
+    fn reset(&mut self) -> Result<(), io::Error> {
+        self.next_line = self.first_line;
+        Ok(())
+    }
+}
+
+/// Parses a character class like `[a-z0-9;:]` into the chars it
+/// matches, in order and without repeats.
+///
+/// The class is written between `[` and `]`, with single chars and
+/// ranges like `a-z`. A `-` at the start or the end of the class is a
+/// literal `-`, and `\`, `]`, `[`, `-` and `^` can be escaped with a
+/// backslash. Only printable ASCII chars other than space can be used,
+/// since the strings are typed as words. Negated classes like `[^a]`
+/// are not supported.
+pub fn parse_char_class(class: &str) -> Result<Vec<char>, String> {
+    let inner = class
+        .strip_prefix('[')
+        .and_then(|class| class.strip_suffix(']'))
+        .ok_or_else(|| "must be written between [ and ], like [a-z0-9]".to_string())?;
+    if inner.starts_with('^') {
+        return Err("negated classes like [^a] are not supported".to_string());
+    }
+
+    // each char along with whether it was escaped
+    let mut tokens = Vec::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped @ ('\\' | ']' | '[' | '-' | '^')) => tokens.push((escaped, true)),
+                Some(escaped) => return Err(format!("unknown escape \\{}", escaped)),
+                None => return Err("ends with a \\ that escapes nothing".to_string()),
+            },
+            '[' | ']' => return Err(format!("{} must be escaped as \\{}", c, c)),
+            c if !c.is_ascii_graphic() => {
+                return Err(format!(
+                    "{:?} cannot be used, only printable ASCII chars other than space can",
+                    c
+                ))
+            }
+            c => tokens.push((c, false)),
+        }
+    }
+
+    let mut matched = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let (c, _) = tokens[i];
+        match tokens.get(i + 1..i + 3) {
+            Some(&[('-', false), (end, _)]) => {
+                if c > end {
+                    return Err(format!("range {}-{} is out of order", c, end));
+                }
+                matched.extend(c..=end);
+                i += 3;
+            }
+            _ => {
+                matched.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    if matched.is_empty() {
+        return Err("has no chars".to_string());
+    }
+    let mut seen = HashSet::new();
+    matched.retain(|&c| seen.insert(c));
+
+    Ok(matched)
+}
+
+/// Generates random strings of chars from a character class, for
+/// practicing symbols and digits that word lists rarely have.
+///
+/// See [`parse_char_class`] for the syntax of the class. Each string
+/// has a random length between the given minimum and maximum,
+/// inclusive.
+pub struct CharsetSelector {
+    chars: Vec<char>,
+    lengths: RangeInclusive<usize>,
+    rng: ToipeRng,
+}
+
+impl CharsetSelector {
+    /// Creates a selector over the chars of the given class.
+    ///
+    /// Returns an error if the class is invalid or if the minimum
+    /// length is 0 or more than the maximum.
+    pub fn new(class: &str, min_len: usize, max_len: usize) -> Result<Self, io::Error> {
+        let chars = parse_char_class(class).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("character class {}: {}", class, err),
+            )
+        })?;
+
+        if min_len == 0 || min_len > max_len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "lengths {} to {} are not valid, the minimum must be between 1 and the maximum",
+                    min_len, max_len
+                ),
+            ));
+        }
+
+        Ok(Self {
+            chars,
+            lengths: min_len..=max_len,
+            rng: ToipeRng::from_entropy(),
+        })
+    }
+
+    /// Sets the random number generator used to generate strings.
+    pub fn with_rng(mut self, rng: ToipeRng) -> Self {
+        self.rng = rng;
+        self
+    }
+}
+
+impl WordSelector for CharsetSelector {
+    fn new_word(&mut self) -> Result<String, io::Error> {
+        let len = self.rng.gen_range(self.lengths.clone());
+
+        Ok((0..len)
+            .map(|_| {
+                *self
+                    .chars
+                    .choose(&mut self.rng)
+                    .expect("class is not empty")
+            })
+            .collect())
+    }
+}
+
+/// Length of quotes to select in [`QuoteSelector`].
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "cli", derive(ArgEnum))]
+pub enum QuoteLength {
+    /// Up to 100 characters.
+    Short,
+    /// 101 to 300 characters.
+    Medium,
+    /// More than 300 characters.
+    Long,
+    /// Any length.
+    Any,
+}
+
+impl QuoteLength {
+    /// Whether a quote with the given number of characters has this
+    /// length.
+    pub fn matches(&self, num_chars: usize) -> bool {
+        match self {
+            Self::Short => num_chars <= 100,
+            Self::Medium => (101..=300).contains(&num_chars),
+            Self::Long => num_chars > 300,
+            Self::Any => true,
+        }
+    }
+}
+
+/// Selects words from randomly chosen quotes, in order.
+///
+/// Words of a quote are returned one after the other, including their
+/// capitalization and punctuation. A new quote is chosen once all the
+/// words of the current one are returned.
+///
+/// Each test is one whole quote (see
+/// [`WordSelector::next_text_len`]).
+pub struct QuoteSelector {
+    /// words of each quote
+    quotes: Vec<Vec<String>>,
+    /// words of the current quote that are yet to be returned
+    remaining_words: VecDeque<String>,
+    rng: ToipeRng,
+}
+
+impl QuoteSelector {
+    /// Creates a selector over the quotes of the given length.
+    ///
+    /// `quotes` should have one quote per line, such as
+    /// [`crate::wordlists::quotes`]. Returns an error if there are no
+    /// quotes of the given length.
+    pub fn new(quotes: &str, length: QuoteLength) -> Result<Self, io::Error> {
+        let quotes: Vec<Vec<String>> = quotes
+            .lines()
+            .map(|quote| quote.trim())
+            .filter(|quote| !quote.is_empty() && length.matches(quote.chars().count()))
+            .map(|quote| {
+                quote
+                    .split_whitespace()
+                    .map(|word| word.to_string())
+                    .collect()
+            })
+            .collect();
+
+        if quotes.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("no quotes of length {:?}", length),
+            ));
+        }
+
+        Ok(Self {
+            quotes,
+            remaining_words: VecDeque::new(),
+            rng: ToipeRng::from_entropy(),
+        })
+    }
+
+    /// Sets the random number generator used to choose quotes.
+    pub fn with_rng(mut self, rng: ToipeRng) -> Self {
+        self.rng = rng;
+        self
+    }
+
+    fn choose_quote(&mut self) {
+        let quote = self
+            .quotes
+            .choose(&mut self.rng)
+            .expect("there is at least one quote");
+        self.remaining_words = quote.iter().cloned().collect();
+    }
+}
+
+impl WordSelector for QuoteSelector {
+    fn new_word(&mut self) -> Result<String, io::Error> {
+        if self.remaining_words.is_empty() {
+            self.choose_quote();
+        }
+
+        Ok(self
+            .remaining_words
+            .pop_front()
+            .expect("quotes are not empty"))
+    }
+
+    fn next_text_len(&mut self) -> Option<usize> {
+        if self.remaining_words.is_empty() {
+            self.choose_quote();
+        }
+
+        Some(self.remaining_words.len())
+    }
+}
+
+/// What to do with tokens that contain digits, like `10,000` or `3.14`.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "cli", derive(ArgEnum))]
+pub enum NumberPolicy {
+    /// Keep numbers as words to type.
+    Keep,
+    /// Leave out words with digits.
+    Strip,
+}
+
+/// Chars that join the text around them without being displayed.
+const WORD_JOINERS: [char; 4] = ['\u{2060}', '\u{200b}', '\u{200d}', '\u{feff}'];
+
+/// Spaces that are not broken across lines, often used to group digits
+/// (`10 000`) or to join a number and its unit (`10 km`).
+const NO_BREAK_SPACES: [char; 4] = ['\u{a0}', '\u{202f}', '\u{2009}', '\u{2007}'];
+
+/// What to do with words that have chars which cannot be typed on a
+/// normal keyboard, like emoji or letters with accents.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "cli", derive(ArgEnum))]
+pub enum UntypeablePolicy {
+    /// Leave out such words.
+    Skip,
+    /// Replace each run of such chars with a `?`.
+    Replace,
+    /// Keep such words as they are.
+    Keep,
+}
+
+/// Splits prose into words that can be typed.
+#[derive(Copy, Clone, Debug)]
+pub struct Sanitizer {
+    /// what to do with words that have digits
+    pub numbers: NumberPolicy,
+    /// what to do with words that have chars that cannot be typed
+    pub untypeable: UntypeablePolicy,
+}
+
+impl Default for Sanitizer {
+    fn default() -> Self {
+        Self {
+            numbers: NumberPolicy::Keep,
+            untypeable: UntypeablePolicy::Skip,
+        }
+    }
+}
+
+impl Sanitizer {
+    /// Splits a line of prose into words.
+    ///
+    /// - word joiners are removed, joining the text around them.
+    /// - no-break spaces between two digits are removed, so that digit
+    ///   groups like `10 000` become one number. Other no-break spaces
+    ///   separate words, so `10 km` becomes `10` and `km`.
+    /// - typographic quotes and dashes are replaced by their ASCII
+    ///   counterparts.
+    /// - words with digits are kept or left out based on
+    ///   [`Sanitizer::numbers`].
+    /// - words that still have chars other than printable ASCII are
+    ///   handled based on [`Sanitizer::untypeable`]. Since word joiners
+    ///   are removed first, an emoji made of multiple chars is replaced
+    ///   by a single `?`.
+    pub fn sanitize_line(&self, line: &str) -> Vec<String> {
+        let chars: Vec<char> = line.chars().filter(|c| !WORD_JOINERS.contains(c)).collect();
+
+        let mut text = String::with_capacity(line.len());
+        for (i, &c) in chars.iter().enumerate() {
+            let c = match c {
+                c if NO_BREAK_SPACES.contains(&c) => {
+                    let between_digits = i > 0
+                        && chars[i - 1].is_ascii_digit()
+                        && chars.get(i + 1).is_some_and(char::is_ascii_digit);
+                    if between_digits {
+                        continue;
+                    }
+                    ' '
+                }
+                '\u{2018}' | '\u{2019}' => '\'',
+                '\u{201c}' | '\u{201d}' => '"',
+                '\u{2013}' | '\u{2014}' => '-',
+                c => c,
+            };
+            text.push(c);
+        }
+
+        text.split_whitespace()
+            .filter(|word| {
+                self.numbers == NumberPolicy::Keep || !word.chars().any(|c| c.is_ascii_digit())
+            })
+            .filter_map(|word| self.handle_untypeable(word))
+            .collect()
+    }
+
+    fn handle_untypeable(&self, word: &str) -> Option<String> {
+        if word.chars().all(|c| c.is_ascii_graphic()) {
+            return Some(word.to_string());
+        }
+
+        match self.untypeable {
+            UntypeablePolicy::Skip => None,
+            UntypeablePolicy::Keep => Some(word.to_string()),
+            UntypeablePolicy::Replace => {
+                let mut replaced = String::with_capacity(word.len());
+                for c in word.chars() {
+                    if c.is_ascii_graphic() {
+                        replaced.push(c);
+                    } else if !replaced.ends_with('?') {
+                        replaced.push('?');
+                    }
+                }
+                Some(replaced)
+            }
+        }
+    }
+}
+
+/// Strips the syntax of Markdown from its lines, leaving the prose, for
+/// typing Markdown files with [`BookSelector::with_markdown`].
+///
+/// Heading, quote and list markers, emphasis and inline code marks,
+/// link and image targets and HTML tags are removed, keeping the text
+/// of links and images. Code blocks between fences and lines that are
+/// only syntax, like rules and table borders, are left out.
+///
+/// ```
+/// use toipe::textgen::MarkdownStripper;
+///
+/// let mut stripper = MarkdownStripper::default();
+/// let line = "## See the **[docs](https://example.com)**";
+/// assert_eq!(stripper.strip_line(line), Some("See the docs".to_string()));
+/// ```
+#[derive(Clone, Debug)]
+pub struct MarkdownStripper {
+    /// char and length of the fence of the code block that the lines
+    /// are in, if any
+    code_fence: Option<(char, usize)>,
+    /// markers at the start of headings, quotes and list items
+    block_markers: Regex,
+    /// definitions of reference links, like `[docs]: https://...`
+    reference_definition: Regex,
+    links: Regex,
+    html_tags: Regex,
+    underscores: Regex,
+}
+
+impl Default for MarkdownStripper {
+    fn default() -> Self {
+        let regex = |pattern| Regex::new(pattern).expect("pattern is valid");
+        Self {
+            code_fence: None,
+            block_markers: regex(r"^\s*(#{1,6}\s+|>\s?|[-*+]\s+|\d+[.)]\s+)*"),
+            reference_definition: regex(r"^\s*\[[^\]]+\]:\s"),
+            links: regex(r"!?\[([^\]]*)\](\([^)]*\)|\[[^\]]*\])"),
+            html_tags: regex(r"</?[A-Za-z][^>]*>"),
+            underscores: regex(r"(^|\W)_+|_+(\W|$)"),
+        }
+    }
+}
+
+impl MarkdownStripper {
+    /// Whether the file at the path is a Markdown file, going by its
+    /// extension.
+    pub fn is_markdown_file(path: &Path) -> bool {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                extension.eq_ignore_ascii_case("md") || extension.eq_ignore_ascii_case("markdown")
+            })
+    }
+
+    /// Returns the prose in the next line of the file, or `None` if the
+    /// line is left out.
+    pub fn strip_line(&mut self, line: &str) -> Option<String> {
+        let trimmed = line.trim();
+        let fence_char = trimmed.chars().next().filter(|c| *c == '`' || *c == '~');
+        let fence_len = fence_char.map_or(0, |c| trimmed.chars().take_while(|&d| d == c).count());
+
+        if let Some((code_char, code_len)) = self.code_fence {
+            if fence_char == Some(code_char) && fence_len >= code_len {
+                self.code_fence = None;
+            }
+            return None;
+        }
+        if let Some(fence_char) = fence_char.filter(|_| fence_len >= 3) {
+            self.code_fence = Some((fence_char, fence_len));
+            return None;
+        }
+
+        let is_only_syntax = !trimmed.is_empty() && trimmed.chars().all(|c| " |:=*_-".contains(c));
+        if is_only_syntax || self.reference_definition.is_match(line) {
+            return None;
+        }
+
+        let line = self.block_markers.replace(line, "");
+        let line = self.links.replace_all(&line, "$1");
+        let line = self.html_tags.replace_all(&line, "");
+        let line = self.underscores.replace_all(&line, "$1$2");
+        Some(
+            line.replace(['*', '`'], "")
+                .replace("~~", "")
+                .replace('|', " ")
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    }
+
+    /// Forgets the code block that the lines are in, for going back to
+    /// the start of the file.
+    pub fn reset(&mut self) {
+        self.code_fence = None;
+    }
+}
+
+/// Where a [`BookSelector`] is in its text.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BookPosition {
+    /// byte offset of the line that the next word is on
+    pub line_offset: u64,
+    /// number of words of that line that come before the next word
+    pub word_in_line: usize,
+}
+
+/// Selects words from a book or an article, in order.
+///
+/// The text is read one line at a time and each line is split into
+/// words using [`Sanitizer::sanitize_line`]. Capitalization and punctuation are
+/// kept as in the text. Words start again from the beginning of the
+/// text after the end is reached.
+///
+/// With [`BookSelector::with_bookmark`], the position after the last
+/// word typed is saved after each completed test, so that the book can
+/// be resumed later.
+pub struct BookSelector<T> {
+    reader: BufReader<T>,
+    sanitizer: Sanitizer,
+    /// words of the line read last that are yet to be returned, along
+    /// with where they are in the text
+    remaining_words: VecDeque<(BookPosition, String)>,
+    /// words returned since the results of a test were last recorded,
+    /// along with where they are in the text
+    returned_words: Vec<(BookPosition, String)>,
+    /// file that the position is saved to, and the key of the book in it
+    bookmark: Option<(PathBuf, String)>,
+    /// strips the Markdown syntax of the lines, if the text is Markdown
+    markdown: Option<MarkdownStripper>,
+    /// where each chapter starts, if the text says so, like the
+    /// documents of an EPUB book
+    chapter_offsets: Option<Vec<u64>>,
+}
+
+impl<T: Seek + io::Read> BookSelector<T> {
+    /// Creates a selector over the text read by `reader`.
+    ///
+    /// Returns an error if the text has no words that can be typed.
+    pub fn new(reader: BufReader<T>, sanitizer: Sanitizer) -> Result<Self, io::Error> {
+        let mut selector = Self {
+            reader,
+            sanitizer,
+            remaining_words: VecDeque::new(),
+            returned_words: Vec::new(),
+            bookmark: None,
+            markdown: None,
+            chapter_offsets: None,
+        };
+        selector.read_words(1)?;
+
+        Ok(selector)
+    }
+
+    /// Whether the text is Markdown, whose syntax is then stripped using
+    /// [`MarkdownStripper`] instead of being typed.
+    pub fn with_markdown(mut self, markdown: bool) -> Result<Self, io::Error> {
+        self.markdown = markdown.then(MarkdownStripper::default);
+        self.reset()?;
+        Ok(self)
+    }
+
+    /// Resumes the book from the position saved for `key` in the
+    /// bookmarks file, if any, and saves the position there after each
+    /// completed test.
+    ///
+    /// Bookmarks that cannot be read or saved are only logged, so that
+    /// the book can still be typed.
+    pub fn with_bookmark(
+        mut self,
+        bookmarks_path: PathBuf,
+        key: String,
+    ) -> Result<Self, io::Error> {
+        let saved = match read_bookmarks(&bookmarks_path) {
+            Ok(bookmarks) => bookmarks.get(&key).copied(),
+            Err(error) => {
+                log::warn!(
+                    "could not read bookmarks from {}: {}",
+                    bookmarks_path.display(),
+                    error
+                );
+                None
+            }
+        };
+        if let Some(position) = saved {
+            self.seek_to(position)?;
+        }
+        self.bookmark = Some((bookmarks_path, key));
+
+        Ok(self)
+    }
+
+    /// Position of the next word in the text.
+    pub fn position(&mut self) -> Result<BookPosition, io::Error> {
+        match self.remaining_words.front() {
+            Some((position, _)) => Ok(*position),
+            None => Ok(BookPosition {
+                line_offset: self.reader.stream_position()?,
+                word_in_line: 0,
+            }),
+        }
+    }
+
+    /// Continues the text from the given position, as returned by
+    /// [`BookSelector::position`].
+    ///
+    /// If the text has changed since, the words continue from whatever
+    /// is at the position now.
+    pub fn seek_to(&mut self, position: BookPosition) -> Result<(), io::Error> {
+        self.rewind_to(position.line_offset)?;
+        self.read_words(1)?;
+        self.remaining_words.retain(|(word_position, _)| {
+            word_position.line_offset != position.line_offset
+                || word_position.word_in_line >= position.word_in_line
+        });
+        self.read_words(1)
+    }
+
+    /// Continues the text from the start of the given line, counting
+    /// from 1.
+    ///
+    /// Returns an error if the text has fewer lines.
+    pub fn seek_to_line(&mut self, line_number: NonZeroUsize) -> Result<(), io::Error> {
+        self.reader.seek(SeekFrom::Start(0))?;
+        let mut line = String::new();
+        for num_lines in 0..line_number.get() - 1 {
+            line.clear();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("text has only {} lines", num_lines),
+                ));
+            }
+        }
+
+        let line_offset = self.reader.stream_position()?;
+        self.seek_to(BookPosition {
+            line_offset,
+            word_in_line: 0,
+        })
+    }
+
+    /// Continues the text from the heading of the given chapter,
+    /// counting from 1.
+    ///
+    /// Chapters are found using [`is_chapter_heading`], unless the text
+    /// says where they are, like for EPUB books. Returns an error if the
+    /// text has fewer chapters.
+    pub fn seek_to_chapter(&mut self, chapter: NonZeroUsize) -> Result<(), io::Error> {
+        if let Some(chapter_offsets) = &self.chapter_offsets {
+            let line_offset = chapter_offsets.get(chapter.get() - 1).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("text has only {} chapters", chapter_offsets.len()),
+                )
+            })?;
+            return self.seek_to(BookPosition {
+                line_offset: *line_offset,
+                word_in_line: 0,
+            });
+        }
+
+        self.reader.seek(SeekFrom::Start(0))?;
+        let mut num_chapters = 0;
+        let mut markdown = self.markdown.as_ref().map(|_| MarkdownStripper::default());
+        // whether the previous line was blank, and its offset if it
+        // looks like a heading
+        let mut after_blank = true;
+        let mut heading_offset = None;
+        let mut line = String::new();
+
+        loop {
+            let line_offset = self.reader.stream_position()?;
+            line.clear();
+            let at_end = self.reader.read_line(&mut line)? == 0;
+            if let Some(markdown) = &mut markdown {
+                match markdown.strip_line(&line) {
+                    Some(prose) => line = prose,
+                    None if !at_end => continue,
+                    None => line.clear(),
+                }
+            }
+            let is_blank = line.trim().is_empty();
+
+            // a heading is a line of its own, unlike those in a table
+            // of contents
+            if let Some(offset) = heading_offset.take() {
+                if is_blank {
+                    num_chapters += 1;
+                    if num_chapters == chapter.get() {
+                        return self.seek_to(BookPosition {
+                            line_offset: offset,
+                            word_in_line: 0,
+                        });
+                    }
+                }
+            }
+            if at_end {
+                break;
+            }
+            if after_blank && is_chapter_heading(&line) {
+                heading_offset = Some(line_offset);
+            }
+            after_blank = is_blank;
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("text has only {} chapters", num_chapters),
+        ))
+    }
+
+    /// Saves the position to the bookmarks file, if there is one.
+    ///
+    /// A bookmarks file that cannot be read is left as it is, so that
+    /// the positions of other books in it are not lost.
+    fn save_bookmark(&self, position: BookPosition) -> Result<(), io::Error> {
+        let (bookmarks_path, key) = match &self.bookmark {
+            Some(bookmark) => bookmark,
+            None => return Ok(()),
+        };
+
+        let mut bookmarks = read_bookmarks(bookmarks_path)?;
+        bookmarks.insert(key.clone(), position);
+        write_bookmarks(bookmarks_path, &bookmarks)
+    }
+
+    /// Position of the word after the last one typed in a test, or of
+    /// the last word if it was not typed to the end.
+    ///
+    /// The words of the test are found at the end of the words
+    /// returned, since tests that were not completed are not recorded.
+    fn position_after_typed(&mut self, results: &ToipeResults) -> Result<BookPosition, io::Error> {
+        let pages = if results.pages.is_empty() {
+            std::slice::from_ref(results)
+        } else {
+            &results.pages
+        };
+        // chars that no key was pressed for are left as they are, and
+        // pages are separated like words
+        let text = pages
+            .iter()
+            .map(|page| {
+                page.char_records()
+                    .iter()
+                    .map(|record| record.expected.unwrap_or('\u{FFFD}'))
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let typed: Vec<&str> = text.split_whitespace().collect();
+
+        let matches = |start: usize| {
+            typed.iter().enumerate().all(|(i, typed_word)| {
+                self.returned_words.get(start + i).is_some_and(|(_, word)| {
+                    let (typed_len, len) = (typed_word.chars().count(), word.chars().count());
+                    // the last word may not have been typed to the end
+                    let lengths_match =
+                        typed_len == len || (i + 1 == typed.len() && typed_len < len);
+                    lengths_match
+                        && typed_word
+                            .chars()
+                            .zip(word.chars())
+                            .all(|(typed, c)| typed == c || typed == '\u{FFFD}')
+                })
+            })
+        };
+        let start = match (0..self.returned_words.len())
+            .rev()
+            .find(|&start| matches(start))
+        {
+            Some(start) => start,
+            None => return self.position(),
+        };
+
+        let next = match typed.last() {
+            Some(last) => {
+                let last_index = start + typed.len() - 1;
+                let (_, word) = &self.returned_words[last_index];
+                if last.chars().count() < word.chars().count() {
+                    last_index
+                } else {
+                    last_index + 1
+                }
+            }
+            None => start,
+        };
+        match self.returned_words.get(next) {
+            Some((position, _)) => Ok(*position),
+            None => self.position(),
+        }
+    }
+
+    /// Goes to the line at the given offset, forgetting the words read
+    /// so far.
+    fn rewind_to(&mut self, line_offset: u64) -> Result<(), io::Error> {
+        self.reader.seek(SeekFrom::Start(line_offset))?;
+        self.remaining_words.clear();
+        self.returned_words.clear();
+        if let Some(markdown) = &mut self.markdown {
+            markdown.reset();
+        }
+        Ok(())
+    }
+
+    /// Reads lines until there are at least `num_words` words to return,
+    /// going back to the beginning of the text when its end is reached.
+    fn read_words(&mut self, num_words: usize) -> Result<(), io::Error> {
+        // number of words to return when the text was last rewound
+        let mut num_words_at_rewind = None;
+        let mut line = String::new();
+
+        while self.remaining_words.len() < num_words {
+            line.clear();
+            let line_offset = self.reader.stream_position()?;
+            if self.reader.read_line(&mut line)? == 0 {
+                if num_words_at_rewind == Some(self.remaining_words.len()) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "text has no words that can be typed",
+                    ));
+                }
+                self.reader.seek(SeekFrom::Start(0))?;
+                if let Some(markdown) = &mut self.markdown {
+                    markdown.reset();
+                }
+                num_words_at_rewind = Some(self.remaining_words.len());
+                continue;
+            }
+
+            let words = match &mut self.markdown {
+                Some(markdown) => match markdown.strip_line(&line) {
+                    Some(prose) => self.sanitizer.sanitize_line(&prose),
+                    None => continue,
+                },
+                None => self.sanitizer.sanitize_line(&line),
+            };
+            self.remaining_words
+                .extend(words.into_iter().enumerate().map(|(word_in_line, word)| {
+                    let position = BookPosition {
+                        line_offset,
+                        word_in_line,
+                    };
+                    (position, word)
+                }));
+        }
+
+        Ok(())
+    }
+}
+
+impl BookSelector<File> {
+    /// Create from a text file at the given path.
+    pub fn from_path(path: PathBuf, sanitizer: Sanitizer) -> Result<Self, io::Error> {
+        let file = File::open(path)?;
+
+        Self::new(BufReader::new(file), sanitizer)
+    }
+}
+
+#[cfg(feature = "epub")]
+impl BookSelector<Cursor<String>> {
+    /// Create from an EPUB book at the given path, using its text in
+    /// reading order (see [`crate::epub::read_text`]).
+    ///
+    /// Each document of the book with text is a chapter for
+    /// [`BookSelector::seek_to_chapter`].
+    pub fn from_epub_path(path: PathBuf, sanitizer: Sanitizer) -> Result<Self, io::Error> {
+        let book = crate::epub::read_text(&path)?;
+        let mut selector = Self::new(BufReader::new(Cursor::new(book.text)), sanitizer)?;
+        selector.chapter_offsets = Some(book.chapter_offsets);
+
+        Ok(selector)
+    }
+}
+
+#[cfg(feature = "pdf")]
+impl BookSelector<Cursor<String>> {
+    /// Create from a PDF file at the given path, using its text without
+    /// headers, footers and hyphenated line breaks (see
+    /// [`crate::pdf::read_text`]).
+    pub fn from_pdf_path(path: PathBuf, sanitizer: Sanitizer) -> Result<Self, io::Error> {
+        let text = crate::pdf::read_text(&path)?;
+
+        Self::new(BufReader::new(Cursor::new(text)), sanitizer)
+    }
+}
+
+impl<T: Seek + io::Read> WordSelector for BookSelector<T> {
+    fn new_word(&mut self) -> Result<String, io::Error> {
+        self.read_words(1)?;
+
+        let (position, word) = self
+            .remaining_words
+            .pop_front()
+            .expect("read_words leaves at least one word");
+        if self.bookmark.is_some() {
+            self.returned_words.push((position, word.clone()));
+        }
+        Ok(word)
+    }
+
+    fn record_results(&mut self, results: &ToipeResults) {
+        let saved = self
+            .position_after_typed(results)
+            .and_then(|position| self.save_bookmark(position));
+        self.returned_words.clear();
+        if let Err(error) = saved {
+            log::warn!("could not save the position in the book: {}", error);
+        }
+    }
+
+    fn peek_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        self.read_words(num_words)?;
+
+        Ok(self
+            .remaining_words
+            .iter()
+            .take(num_words)
+            .map(|(_, word)| word.clone())
+            .collect())
+    }
+
+    fn reset(&mut self) -> Result<(), io::Error> {
+        self.rewind_to(0)?;
+        self.read_words(1)
+    }
+}
+
+/// Whether the word is the last one of a sentence, ending with `.`, `!`,
+/// `?` or `…`, maybe followed by closing quotes or brackets.
+///
+/// ```
+/// use toipe::textgen::ends_sentence;
+///
+/// assert!(ends_sentence("end."));
+/// assert!(ends_sentence("said?\u{201d}"));
+/// assert!(!ends_sentence("Well,"));
+/// ```
+pub fn ends_sentence(word: &str) -> bool {
+    word.trim_end_matches(['"', '\'', ')', ']', '\u{201d}', '\u{2019}'])
+        .ends_with(['.', '!', '?', '\u{2026}'])
+}
+
+/// Whether the line of a plain-text book looks like the heading of a
+/// chapter, like `Chapter 3`, `CHAPTER III. The Spouter-Inn` or `IV.`.
+pub fn is_chapter_heading(line: &str) -> bool {
+    let line = line.trim();
+    let mut words = line.split_whitespace();
+    let first_word = match words.next() {
+        Some(word) => word,
+        None => return false,
+    };
+
+    let is_roman_numeral = |word: &str| {
+        let word = word.trim_end_matches(['.', ':']);
+        !word.is_empty() && word.chars().all(|c| "IVXLC".contains(c))
+    };
+    if first_word.eq_ignore_ascii_case("chapter") {
+        // headings are short, unlike sentences that start with the word
+        line.len() <= 80
+    } else {
+        is_roman_numeral(first_word) && words.next().is_none()
+    }
+}
+
+/// Reads the positions saved by [`BookSelector::with_bookmark`], keyed
+/// by book.
+///
+/// Each line of the file is the line offset, the word in the line and
+/// the key of a book, separated by spaces. A missing file has no
+/// positions.
+fn read_bookmarks(path: &Path) -> Result<HashMap<String, BookPosition>, io::Error> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(error) => return Err(error),
+    };
+
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let mut fields = line.splitn(3, ' ');
+            let line_offset = fields.next().and_then(|field| field.parse().ok());
+            let word_in_line = fields.next().and_then(|field| field.parse().ok());
+            match (line_offset, word_in_line, fields.next()) {
+                (Some(line_offset), Some(word_in_line), Some(key)) => Ok((
+                    key.to_string(),
+                    BookPosition {
+                        line_offset,
+                        word_in_line,
+                    },
+                )),
+                _ => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid bookmark line '{}'", line),
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Writes the positions read by [`read_bookmarks`], creating the
+/// directory of the file if needed.
+fn write_bookmarks(
+    path: &Path,
+    bookmarks: &HashMap<String, BookPosition>,
+) -> Result<(), io::Error> {
+    let mut keys: Vec<&String> = bookmarks.keys().collect();
+    keys.sort();
+    let contents: String = keys
+        .into_iter()
+        .map(|key| {
+            let position = bookmarks[key];
+            format!(
+                "{} {} {}\n",
+                position.line_offset, position.word_in_line, key
+            )
+        })
+        .collect();
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, contents)
+}
+
+/// Generates sentences that sound like a given text, using a
+/// word-level Markov chain built from it.
+///
+/// The text is split into words using [`Sanitizer::sanitize_line`],
+/// keeping capitalization and punctuation. Each word is followed by one
+/// of the words that follow it somewhere in the text, chosen in
+/// proportion to how often they do. Sentences start with a word that
+/// starts a sentence in the text, and a new one is started when the
+/// chain reaches a word that nothing follows.
+pub struct MarkovSelector {
+    /// words that follow each word in the text, with repeats
+    transitions: HashMap<String, Vec<String>>,
+    /// words that start a sentence in the text, with repeats
+    starts: Vec<String>,
+    /// word returned last, if the sentence goes on after it
+    previous_word: Option<String>,
+    rng: ToipeRng,
+}
+
+impl MarkovSelector {
+    /// Builds the chain from the given text.
+    ///
+    /// Returns an error if the text has no words that can be typed.
+    pub fn new(text: &str, sanitizer: Sanitizer) -> Result<Self, io::Error> {
+        let words: Vec<String> = text
+            .lines()
+            .flat_map(|line| sanitizer.sanitize_line(line))
+            .collect();
+
+        let ends_sentence = |word: &str| word.ends_with(['.', '!', '?']);
+        let mut starts = Vec::new();
+        let mut transitions: HashMap<String, Vec<String>> = HashMap::new();
+        for (i, word) in words.iter().enumerate() {
+            if i == 0 || ends_sentence(&words[i - 1]) {
+                starts.push(word.clone());
+            } else {
+                transitions
+                    .entry(words[i - 1].clone())
+                    .or_default()
+                    .push(word.clone());
+            }
+        }
+
+        if starts.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "text has no words that can be typed",
+            ));
+        }
+
+        Ok(Self {
+            transitions,
+            starts,
+            previous_word: None,
+            rng: ToipeRng::from_entropy(),
+        })
+    }
+
+    /// Sets the random number generator used to choose words.
+    pub fn with_rng(mut self, rng: ToipeRng) -> Self {
+        self.rng = rng;
+        self
+    }
+
+    /// Create from a text file at the given path.
+    pub fn from_path(path: PathBuf, sanitizer: Sanitizer) -> Result<Self, io::Error> {
+        Self::new(&std::fs::read_to_string(path)?, sanitizer)
+    }
+}
+
+impl WordSelector for MarkovSelector {
+    fn new_word(&mut self) -> Result<String, io::Error> {
+        let next_words = self
+            .previous_word
+            .as_ref()
+            .and_then(|word| self.transitions.get(word))
+            .unwrap_or(&self.starts);
+        let word = next_words
+            .choose(&mut self.rng)
+            .expect("there is at least one start")
+            .clone();
+        self.previous_word = Some(word.clone());
+
+        Ok(word)
+    }
+}
+
+/// A word of a [`StructuredWordList`], along with what is known about
+/// it.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct WordEntry {
+    pub word: String,
+    /// how often the word is used, as a whole number
+    pub frequency: Option<u64>,
+    /// how hard the word is to type, on any scale used by the list
+    pub difficulty: Option<f64>,
+    /// tags given to the word, like `common` or `animals`
+    pub tags: Vec<String>,
+}
+
+/// A word in a JSON word list, either as a plain string or as an object
+/// with metadata.
+#[cfg(feature = "structured-wordlist")]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonWord {
+    Plain(String),
+    Entry {
+        word: String,
+        #[serde(default)]
+        frequency: Option<u64>,
+        #[serde(default)]
+        difficulty: Option<f64>,
+        #[serde(default)]
+        tags: Vec<String>,
+    },
+}
+
+/// A JSON word list, including the language files of Monkeytype, whose
+/// fields other than the words are ignored.
+#[cfg(feature = "structured-wordlist")]
+#[derive(Deserialize)]
+struct JsonWordList {
+    words: Vec<JsonWord>,
+}
+
+/// A row of a CSV word list, whose tags are separated by `;`.
+#[cfg(feature = "structured-wordlist")]
+#[derive(Deserialize)]
+struct CsvWord {
+    word: String,
+    #[serde(default)]
+    frequency: Option<u64>,
+    #[serde(default)]
+    difficulty: Option<f64>,
+    #[serde(default)]
+    tags: String,
+}
+
+/// A word list in a structured format, whose words can have a
+/// frequency, a difficulty and tags.
+///
+/// In JSON, the list is an object with its `words`, and any other
+/// fields, like a `name`, are ignored. Each word is either a string or
+/// an object with the word and any of its metadata:
+///
+/// ```json
+/// {
+///   "name": "animals",
+///   "words": [
+///     {"word": "cat", "frequency": 120, "difficulty": 1.0, "tags": ["pets"]},
+///     "zebra"
+///   ]
+/// }
+/// ```
+///
+/// In CSV, the first row names the columns: `word`, and any of
+/// `frequency`, `difficulty` and `tags`, with tags separated by `;`:
+///
+/// ```csv
+/// word,frequency,difficulty,tags
+/// cat,120,1.0,pets;short
+/// zebra,,,
+/// ```
+///
+/// Language files of [Monkeytype](https://monkeytype.com), like
+/// `english_1k.json`, are JSON word lists too.
+///
+/// The format is chosen by the extension of the file, `.json` or
+/// `.csv`, or by its content for a file with another extension (see
+/// [`StructuredWordList::is_structured_file`]).
+///
+/// Reading JSON and CSV needs the `structured-wordlist` feature.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct StructuredWordList {
+    pub words: Vec<WordEntry>,
+}
+
+impl StructuredWordList {
+    /// Parses a word list in JSON.
+    #[cfg(feature = "structured-wordlist")]
+    pub fn from_json(text: &str) -> Result<Self, io::Error> {
+        let list: JsonWordList = serde_json::from_str(text)?;
+        let words = list
+            .words
+            .into_iter()
+            .map(|word| match word {
+                JsonWord::Plain(word) => WordEntry {
+                    word,
+                    ..Default::default()
+                },
+                JsonWord::Entry {
+                    word,
+                    frequency,
+                    difficulty,
+                    tags,
+                } => WordEntry {
+                    word,
+                    frequency,
+                    difficulty,
+                    tags,
+                },
+            })
+            .collect();
+
+        Ok(Self { words })
+    }
+
+    /// Returns an error, since parsing JSON needs the
+    /// `structured-wordlist` feature.
+    #[cfg(not(feature = "structured-wordlist"))]
+    pub fn from_json(_text: &str) -> Result<Self, io::Error> {
+        Err(without_structured_wordlist())
+    }
+
+    /// Parses a word list in CSV.
+    #[cfg(feature = "structured-wordlist")]
+    pub fn from_csv(text: &str) -> Result<Self, io::Error> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(text.as_bytes());
+        let mut words = Vec::new();
+        for row in reader.deserialize() {
+            let row: CsvWord =
+                row.map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+            words.push(WordEntry {
+                word: row.word,
+                frequency: row.frequency,
+                difficulty: row.difficulty,
+                tags: row
+                    .tags
+                    .split(';')
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            });
+        }
+
+        Ok(Self { words })
+    }
+
+    /// Returns an error, since parsing CSV needs the
+    /// `structured-wordlist` feature.
+    #[cfg(not(feature = "structured-wordlist"))]
+    pub fn from_csv(_text: &str) -> Result<Self, io::Error> {
+        Err(without_structured_wordlist())
+    }
+
+    /// Reads the words of a plain word list, along with their frequencies
+    /// if they have any (see [`RawWordSelector`]). Annotations are left
+    /// out.
+    pub fn from_plain(text: &str) -> Self {
+        let words = text
+            .lines()
+            .filter_map(|line| {
+                let word = line.split('\t').next().unwrap_or_default().trim();
+                // like in a raw word list, lines not starting with a
+                // letter are not words
+                word.starts_with(char::is_alphabetic).then(|| WordEntry {
+                    word: word.to_string(),
+                    frequency: frequency_of(line),
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        Self { words }
+    }
+
+    /// Adds the words of another list that are not in this one yet.
+    pub fn merge(&mut self, other: StructuredWordList) {
+        let mut seen: HashSet<String> = self.words.iter().map(|entry| entry.word.clone()).collect();
+        self.words.extend(
+            other
+                .words
+                .into_iter()
+                .filter(|entry| seen.insert(entry.word.clone())),
+        );
+    }
+
+    /// Whether the file at the path is a structured word list: a `.json`
+    /// or `.csv` file, or a file that starts with `{` like JSON.
+    ///
+    /// A plain word list cannot start with `{`, since its lines that do
+    /// not start with a letter are ignored.
+    pub fn is_structured_file(path: &Path) -> bool {
+        let has_extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                extension.eq_ignore_ascii_case("json") || extension.eq_ignore_ascii_case("csv")
+            });
+        has_extension || starts_like_json(path).unwrap_or(false)
+    }
+
+    /// Reads a word list from a `.csv` file, or a JSON file otherwise.
+    pub fn from_path(path: PathBuf) -> Result<Self, io::Error> {
+        let text = std::fs::read_to_string(&path)?;
+        let is_csv = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"));
+        if is_csv {
+            Self::from_csv(&text)
+        } else {
+            Self::from_json(&text)
+        }
+    }
+}
+
+/// Error for reading a JSON or CSV word list without the
+/// `structured-wordlist` feature.
+#[cfg(not(feature = "structured-wordlist"))]
+fn without_structured_wordlist() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "cannot read JSON and CSV word lists, toipe was built without the `structured-wordlist` feature",
+    )
+}
+
+/// Whether the first char of the file other than whitespace is `{`.
+fn starts_like_json(path: &Path) -> Result<bool, io::Error> {
+    let mut reader = BufReader::new(File::open(path)?);
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            return Ok(false);
+        }
+        if let Some(&byte) = buffer.iter().find(|byte| !byte.is_ascii_whitespace()) {
+            return Ok(byte == b'{');
+        }
+        let len = buffer.len();
+        reader.consume(len);
+    }
+}
+
+/// Selects random words from a [`StructuredWordList`].
+///
+/// If every word has a frequency, words are selected in proportion to
+/// it, like in a [`RawWordSelector`]. Otherwise every word is equally
+/// likely. Words can be filtered by their metadata before creating the
+/// selector, by removing them from [`StructuredWordList::words`].
+pub struct StructuredSelector {
+    words: Vec<String>,
+    /// frequencies of the words, if every word has one
+    frequencies: Option<Vec<u64>>,
+    /// distribution of the indexes of the words, if they have
+    /// frequencies
+    distribution: Option<WeightedIndex<u64>>,
+    rng: ToipeRng,
+}
+
+impl StructuredSelector {
+    /// Creates a selector over the words of the list.
+    ///
+    /// Returns an error if the list has no words, or if every word has a
+    /// frequency of 0.
+    pub fn new(list: StructuredWordList) -> Result<Self, io::Error> {
+        if list.words.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "word list does not have any words",
+            ));
+        }
+
+        let frequencies: Option<Vec<u64>> =
+            list.words.iter().map(|entry| entry.frequency).collect();
+        let distribution = frequencies
+            .as_ref()
+            .map(WeightedIndex::new)
+            .transpose()
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "every word in the word list has a frequency of 0",
+                )
+            })?;
+
+        Ok(Self {
+            words: list.words.into_iter().map(|entry| entry.word).collect(),
+            frequencies,
+            distribution,
+            rng: ToipeRng::from_entropy(),
+        })
+    }
+
+    /// Sets the random number generator used to select words.
+    pub fn with_rng(mut self, rng: ToipeRng) -> Self {
+        self.rng = rng;
+        self
+    }
+}
+
+impl WordSelector for StructuredSelector {
+    fn new_word(&mut self) -> Result<String, io::Error> {
+        let index = match &self.distribution {
+            Some(distribution) => distribution.sample(&mut self.rng),
+            None => self.rng.gen_range(0..self.words.len()),
+        };
+
+        Ok(self.words[index].clone())
+    }
+
+    /// Draws words without replacement, each with a chance proportional
+    /// to its frequency if the words have frequencies (see
+    /// [`PrioritySample`]).
+    ///
+    /// Words that appear multiple times in the list are returned only
+    /// once. If the list runs out of words, words are repeated.
+    fn new_unique_words(&mut self, num_words: usize) -> Result<Vec<String>, io::Error> {
+        let mut words = Vec::with_capacity(num_words);
+        // the list is gone through again if it runs out of words
+        while words.len() < num_words {
+            let mut sample = PrioritySample::new(num_words - words.len());
+            for (i, word) in self.words.iter().enumerate() {
+                let weight = self
+                    .frequencies
+                    .as_ref()
+                    .map_or(1, |frequencies| frequencies[i]);
+                sample.offer(word, weight, &mut self.rng);
+            }
+            words.extend(sample.into_words());
+        }
+
+        Ok(words)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::results::{results_with, Keystroke};
+    use std::collections::HashSet;
+    use std::time::Instant;
+
+    fn all_words(word_list: &str) -> Vec<String> {
+        // long enough for every word in the lists below
+        let mut selector = RawWordSelector::from_string(word_list.to_string())
+            .unwrap()
+            .with_max_word_len(10);
+        let num_words = selector.word_lines_sum[26];
+        (0..num_words)
+            .map(|i| selector.word_at_index(i).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn indexes_every_word() {
+        let word_list = "apple\nant\nbanana\ncat\ncow\ndog\n";
+        assert_eq!(all_words(word_list), word_list.lines().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn sparse_alphabet() {
+        // no words starting with b, c, or anything after m
+        let word_list = "apple\nant\nday\ndog\nmoon\n";
+        assert_eq!(all_words(word_list), word_list.lines().collect::<Vec<_>>());
+
+        // only letters at the end of the alphabet
+        let word_list = "xylophone\nyak\nzebra\nzoo";
+        assert_eq!(all_words(word_list), word_list.lines().collect::<Vec<_>>());
+
+        // a single letter
+        let word_list = "queen\nquick\nquiet\n";
+        assert_eq!(all_words(word_list), word_list.lines().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn ignores_non_letter_lines() {
+        let word_list = "123\n'tis\napple\n\nbanana\n";
+        assert_eq!(all_words(word_list), vec!["apple", "banana"]);
+    }
+
+    #[test]
+    fn unsorted_word_list() {
+        let word_list = "dog\napple\ncat\n123\nAnt\ncow\n";
+        assert_eq!(all_words(word_list), ["apple", "Ant", "cat", "cow", "dog"]);
+
+        let word_list = "the\t9\nand\t0\nof\t1\ntea\t0\n";
+        let mut selector = RawWordSelector::from_string(word_list.to_string())
+            .unwrap()
+            .with_rng(ToipeRng::from_seed(42));
+        let words: HashSet<String> = (0..1000).map(|_| selector.new_word().unwrap()).collect();
+        assert_eq!(words, HashSet::from(["the".to_string(), "of".to_string()]));
+    }
+
+    #[test]
+    fn mixed_case() {
+        let word_list = "Apple\nant\nBanana\nbat\n";
+        assert_eq!(all_words(word_list), word_list.lines().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn selects_only_present_letters() {
+        let word_list = "cat\ncow\nmoon\nzoo\n";
+        let mut selector = RawWordSelector::from_string(word_list.to_string()).unwrap();
+
+        let words: HashSet<String> = (0..1000).map(|_| selector.new_word().unwrap()).collect();
+        let expected: HashSet<String> = word_list.lines().map(|w| w.to_string()).collect();
+
+        assert_eq!(words, expected);
+    }
+
+    #[test]
+    fn max_word_len() {
+        let word_list = "ant\nbanana\ncat\nelephants\n";
+
+        let mut selector = RawWordSelector::from_string(word_list.to_string())
+            .unwrap()
+            .with_max_word_len(3);
+        let words: HashSet<String> = (0..1000).map(|_| selector.new_word().unwrap()).collect();
+        assert_eq!(words, HashSet::from(["ant".to_string(), "cat".to_string()]));
+
+        let mut selector = RawWordSelector::from_string(word_list.to_string())
+            .unwrap()
+            .with_max_word_len(9);
+        let words: HashSet<String> = (0..1000).map(|_| selector.new_word().unwrap()).collect();
+        assert!(words.contains("elephants"));
+    }
+
+    #[test]
+    fn selection_is_uniform() {
+        // letters with very different numbers of words, with lines that
+        // are not words in between
+        let mut word_list = String::new();
+        for (letter, num_words) in [('a', 1), ('b', 5), ('d', 20), ('m', 2), ('z', 12)] {
+            for i in 0..num_words {
+                let suffix = [b'a' + i / 26, b'a' + i % 26];
+                word_list += &format!("{}{}\n", letter, String::from_utf8_lossy(&suffix));
+            }
+            word_list += "123\n";
+        }
+        let num_words = word_list.lines().count() - 5;
+        let draws_per_word = 500;
+
+        let mut selector = RawWordSelector::from_string(word_list)
+            .unwrap()
+            .with_rng(ToipeRng::from_seed(42));
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for word in selector.new_words(num_words * draws_per_word).unwrap() {
+            *counts.entry(word).or_default() += 1;
+        }
+        assert_eq!(counts.len(), num_words);
+
+        let expected = draws_per_word as f64;
+        let chi_squared: f64 = counts
+            .values()
+            .map(|&count| (count as f64 - expected).powi(2) / expected)
+            .sum();
+        // critical value for 39 degrees of freedom at p = 0.001
+        assert!(chi_squared < 72.05, "{}", chi_squared);
+    }
+
+    #[test]
+    fn words_too_long_are_left_out_of_the_index() {
+        // most words of a are too long, none of b are
+        let mut word_list = String::from("ab\n");
+        for i in 0..200 {
+            word_list += &format!("alphabetical{}\n", char::from(b'a' + i % 26));
+        }
+        for i in 0..20 {
+            word_list += &format!("b{}\n", char::from(b'a' + i));
+        }
+        let draws_per_word = 500;
+
+        let mut selector = RawWordSelector::from_string(word_list)
+            .unwrap()
+            .with_rng(ToipeRng::from_seed(42));
+        // drawing from all the lines would reject 200 of every 221 draws
+        assert_eq!(selector.index.letter_lines_sum[26], 221);
+        assert_eq!(selector.word_lines_sum[26], 21);
+
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for word in selector.new_words(21 * draws_per_word).unwrap() {
+            *counts.entry(word).or_default() += 1;
+        }
+        assert_eq!(counts.len(), 21);
+        let expected = draws_per_word as f64;
+        let chi_squared: f64 = counts
+            .values()
+            .map(|&count| (count as f64 - expected).powi(2) / expected)
+            .sum();
+        // critical value for 20 degrees of freedom at p = 0.001
+        assert!(chi_squared < 45.31, "{}", chi_squared);
+
+        // no words short enough is an error, not an endless search
+        let mut selector = RawWordSelector::from_string("elephants\n".to_string()).unwrap();
+        assert!(selector.new_word().is_err());
+        assert!(selector.new_unique_words(2).is_err());
+        let mut selector = selector.with_max_word_len(9);
+        assert_eq!(selector.new_word().unwrap(), "elephants");
+    }
+
+    #[test]
+    fn seeded_selection_is_reproducible() {
+        let new_selector = |seed| {
+            let selector = RawWordSelector::from_string(
+                crate::wordlists::BuiltInWordlist::Top250
+                    .contents()
+                    .unwrap()
+                    .to_string(),
+            )
+            .unwrap()
+            .with_rng(ToipeRng::from_seed(seed));
+            PunctuatedWordSelector::from_word_selector(Box::new(selector), 0.5)
+                .with_rng(ToipeRng::from_seed(seed))
+        };
+
+        let words = new_selector(42).new_words(50).unwrap();
+        assert_eq!(new_selector(42).new_words(50).unwrap(), words);
+        assert_ne!(new_selector(43).new_words(50).unwrap(), words);
+    }
+
+    #[test]
+    fn annotations() {
+        let word_list = "bonjour\thello\nchat\tcat\nmerci\n";
+        let mut selector = RawWordSelector::from_string(word_list.to_string()).unwrap();
+
+        let words: HashSet<String> = (0..1000).map(|_| selector.new_word().unwrap()).collect();
+        let expected: HashSet<String> = ["bonjour", "chat", "merci"]
+            .iter()
+            .map(|w| w.to_string())
+            .collect();
+        assert_eq!(words, expected);
+
+        assert_eq!(selector.annotation("bonjour"), Some("hello".to_string()));
+        assert_eq!(selector.annotation("chat"), Some("cat".to_string()));
+        assert_eq!(selector.annotation("merci"), None);
+
+        let selector = PunctuatedWordSelector::from_word_selector(Box::new(selector), 1.0);
+        assert_eq!(selector.annotation("(Bonjour)"), Some("hello".to_string()));
+    }
+
+    #[test]
+    fn shuffle_deals_every_word() {
+        let word_list = "ant\nbee\ncat\ndog\neel\n";
+        let mut selector = RawWordSelector::from_string(word_list.to_string())
+            .unwrap()
+            .with_shuffle(true);
+
+        let expected: HashSet<String> = word_list.lines().map(|w| w.to_string()).collect();
+        for _ in 0..3 {
+            // a deal across two texts
+            let mut words = selector.new_words(2).unwrap();
+            words.extend(selector.new_words(3).unwrap());
+            assert_eq!(words.into_iter().collect::<HashSet<_>>(), expected);
+        }
+
+        // unique words are taken from the deal
+        let mut words = selector.new_unique_words(3).unwrap();
+        words.extend(selector.new_unique_words(2).unwrap());
+        assert_eq!(words.into_iter().collect::<HashSet<_>>(), expected);
+    }
+
+    #[test]
+    fn frequencies() {
+        let word_list = "and\t0\nof\t1\nthe\t9\n";
+        let mut selector = RawWordSelector::from_string(word_list.to_string())
+            .unwrap()
+            .with_rng(ToipeRng::from_seed(42));
+
+        let words = selector.new_words(1000).unwrap();
+        let num_the = words.iter().filter(|word| *word == "the").count();
+        assert!(!words.contains(&"and".to_string()));
+        assert!((850..950).contains(&num_the), "{}", num_the);
+        // frequencies are not annotations
+        assert_eq!(selector.annotation("the"), None);
+
+        let words = selector.new_unique_words(3).unwrap();
+        assert_eq!(words.iter().filter(|word| *word == "and").count(), 0);
+
+        // a word without a frequency makes it the plain format
+        let word_list = "and\t0\nof\t1\nthe\n";
+        let mut selector = RawWordSelector::from_string(word_list.to_string()).unwrap();
+        let words: HashSet<String> = (0..1000).map(|_| selector.new_word().unwrap()).collect();
+        assert_eq!(words.len(), 3);
+        assert_eq!(selector.annotation("and"), Some("0".to_string()));
+
+        assert!(RawWordSelector::from_string("and\t0\n".to_string()).is_err());
+    }
+
+    #[test]
+    fn index_sampler_draws_every_index_once() {
+        let mut rng = ToipeRng::from_entropy();
+        for len in [0, 1, 2, 10, 100] {
+            let mut sampler = IndexSampler::new(len);
+            let mut indices: Vec<u64> = std::iter::from_fn(|| sampler.next(&mut rng)).collect();
+            indices.sort();
+            assert_eq!(indices, (0..len).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn unique_words() {
+        let word_list: String = (b'a'..=b'z')
+            .map(|c| format!("{}{}\n", c as char, c as char))
+            .collect();
+        let mut selector = RawWordSelector::from_string(word_list).unwrap();
+
+        let words = selector.new_unique_words(26).unwrap();
+        assert_eq!(words.iter().collect::<HashSet<_>>().len(), 26);
+
+        // not enough words, so words are repeated after all are used
+        let words = selector.new_unique_words(30).unwrap();
+        assert_eq!(words.len(), 30);
+        assert_eq!(words[..26].iter().collect::<HashSet<_>>().len(), 26);
+    }
+
+    #[test]
+    fn unique_words_skips_duplicates_in_list() {
+        let word_list = "apple\napple\nbanana\ncherry\ncherry\n";
+        let mut selector = RawWordSelector::from_string(word_list.to_string()).unwrap();
+
+        let mut words = selector.new_unique_words(3).unwrap();
+        words.sort();
+        assert_eq!(words, vec!["apple", "banana", "cherry"]);
+    }
+
+    #[test]
+    fn unique_words_follow_frequencies() {
+        // sorted lists are read from the reader and the others from memory
+        for word_list in ["and\t0\nof\t1\nthe\t9\n", "the\t9\nof\t1\nand\t0\n"] {
+            let mut selector = RawWordSelector::from_string(word_list.to_string())
+                .unwrap()
+                .with_rng(ToipeRng::from_seed(42));
+
+            let num_the_first = (0..1000)
+                .map(|_| selector.new_unique_words(2).unwrap())
+                .filter(|words| {
+                    let mut sorted = words.clone();
+                    sorted.sort();
+                    assert_eq!(sorted, ["of", "the"]);
+                    words[0] == "the"
+                })
+                .count();
+            assert!((850..950).contains(&num_the_first), "{}", num_the_first);
+        }
+
+        // repeated lines of a word count together
+        let mut selector = RawWordSelector::from_string("of\t1\nthe\t1\nthe\t8\n".to_string())
+            .unwrap()
+            .with_rng(ToipeRng::from_seed(42));
+        let num_the = (0..1000)
+            .filter(|_| selector.new_unique_words(1).unwrap() == ["the"])
+            .count();
+        assert!((850..950).contains(&num_the), "{}", num_the);
+    }
+
+    #[test]
+    fn quote_selector() {
+        let quotes = "Short one.\n\nAnother, short one!\n".to_string() + &"long ".repeat(30);
+        let mut selector = QuoteSelector::new(&quotes, QuoteLength::Short).unwrap();
+
+        for _ in 0..10 {
+            let num_words = selector.next_text_len().unwrap();
+            let quote = selector.new_words(num_words).unwrap().join(" ");
+            assert!(quote == "Short one." || quote == "Another, short one!");
+        }
+
+        let mut selector = QuoteSelector::new(&quotes, QuoteLength::Medium).unwrap();
+        assert_eq!(selector.next_text_len(), Some(30));
+        assert_eq!(selector.new_word().unwrap(), "long");
+        assert_eq!(selector.next_text_len(), Some(29));
+
+        assert!(QuoteSelector::new(&quotes, QuoteLength::Long).is_err());
+    }
+
+    #[test]
+    fn builtin_quotes_are_ascii() {
+        let quotes = crate::wordlists::quotes();
+        assert!(quotes.is_ascii());
+        for length in [QuoteLength::Short, QuoteLength::Medium, QuoteLength::Long] {
+            assert!(QuoteSelector::new(quotes, length).is_ok());
+        }
+    }
+
+    const PROSE: &str = "The ship\u{2019}s log read: \u{201c}Sailed 10,000 leagues\u{2014}at 3.14 knots.\u{201d}\n\
+                         \n\
+                         It weighed 12\u{202f}500 tonnes and drew 8\u{a0}m of water; its crew\u{2060}man was n\u{e9}e Smith.\n";
+
+    #[test]
+    fn sanitize_keeps_numbers() {
+        let words: Vec<String> = PROSE
+            .lines()
+            .flat_map(|line| Sanitizer::default().sanitize_line(line))
+            .collect();
+
+        assert_eq!(
+            words.join(" "),
+            "The ship's log read: \"Sailed 10,000 leagues-at 3.14 knots.\" \
+             It weighed 12500 tonnes and drew 8 m of water; its crewman was Smith."
+        );
+    }
+
+    #[test]
+    fn sanitize_strips_numbers() {
+        let words: Vec<String> = PROSE
+            .lines()
+            .flat_map(|line| {
+                Sanitizer {
+                    numbers: NumberPolicy::Strip,
+                    ..Default::default()
+                }
+                .sanitize_line(line)
+            })
+            .collect();
+
+        assert_eq!(
+            words.join(" "),
+            "The ship's log read: \"Sailed leagues-at knots.\" \
+             It weighed tonnes and drew m of water; its crewman was Smith."
+        );
+    }
+
+    #[test]
+    fn sanitize_untypeable() {
+        let line = "Caf\u{e9} time \u{1f44d}\u{1f3fd} \u{2615}! \u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+        let sanitize = |untypeable| {
+            Sanitizer {
+                untypeable,
+                ..Default::default()
+            }
+            .sanitize_line(line)
+            .join(" ")
+        };
+
+        assert_eq!(sanitize(UntypeablePolicy::Skip), "time");
+        assert_eq!(sanitize(UntypeablePolicy::Replace), "Caf? time ? ?! ?");
+        assert_eq!(
+            sanitize(UntypeablePolicy::Keep),
+            "Caf\u{e9} time \u{1f44d}\u{1f3fd} \u{2615}! \u{1f468}\u{1f469}\u{1f467}"
+        );
+    }
+
+    #[test]
+    fn book_selector_wraps_around() {
+        let reader = BufReader::new(Cursor::new(PROSE.to_string()));
+        let mut selector = BookSelector::new(reader, Sanitizer::default()).unwrap();
+
+        let words = selector.new_words(26).unwrap();
+        assert_eq!(words[..3], ["The", "ship's", "log"]);
+        assert_eq!(words[4], "\"Sailed");
+        assert_eq!(words[5], "10,000");
+        assert_eq!(words[22..], ["Smith.", "The", "ship's", "log"]);
+
+        let reader = BufReader::new(Cursor::new("42 \u{e9}t\u{e9}\n".to_string()));
+        let sanitizer = Sanitizer {
+            numbers: NumberPolicy::Strip,
+            ..Default::default()
+        };
+        assert!(BookSelector::new(reader, sanitizer).is_err());
+    }
+
+    #[test]
+    fn book_selector_bookmarks() {
+        let new_selector = || {
+            let reader = BufReader::new(Cursor::new(PROSE.to_string()));
+            BookSelector::new(reader, Sanitizer::default()).unwrap()
+        };
+
+        let mut selector = new_selector();
+        assert_eq!(selector.position().unwrap(), BookPosition::default());
+        selector.new_words(10).unwrap();
+        let position = selector.position().unwrap();
+        assert_eq!(position.word_in_line, 1);
+        let next_words = selector.peek_words(5).unwrap();
+
+        let mut resumed = new_selector();
+        resumed.seek_to(position).unwrap();
+        assert_eq!(resumed.new_words(5).unwrap(), next_words);
+
+        let dir = std::env::temp_dir().join(format!("toipe-bookmarks-{}", std::process::id()));
+        let bookmarks = dir.join("data").join("bookmarks");
+        let mut selector = new_selector()
+            .with_bookmark(bookmarks.clone(), "/books/prose.txt".to_string())
+            .unwrap();
+        selector.new_words(10).unwrap();
+        selector.save_bookmark(position).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&bookmarks).unwrap(),
+            format!("{} 1 /books/prose.txt\n", position.line_offset)
+        );
+
+        // each book has its own position
+        let mut other = new_selector()
+            .with_bookmark(bookmarks.clone(), "other book".to_string())
+            .unwrap();
+        assert_eq!(other.position().unwrap(), BookPosition::default());
+        other.save_bookmark(BookPosition::default()).unwrap();
+
+        let mut resumed = new_selector()
+            .with_bookmark(bookmarks, "/books/prose.txt".to_string())
+            .unwrap();
+        assert_eq!(resumed.new_words(5).unwrap(), next_words);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn book_selector_bookmarks_the_last_word_typed() {
+        let dir = std::env::temp_dir().join(format!("toipe-typed-bookmark-{}", std::process::id()));
+        let bookmarks = dir.join("bookmarks");
+        let new_selector = || {
+            let reader = BufReader::new(Cursor::new(PROSE.to_string()));
+            BookSelector::new(reader, Sanitizer::default())
+                .unwrap()
+                .with_bookmark(bookmarks.clone(), "prose".to_string())
+                .unwrap()
+        };
+        let typed = |text: &str| {
+            let now = Instant::now();
+            let keystrokes = text
+                .chars()
+                .enumerate()
+                .map(|(position, c)| Keystroke {
+                    at: now,
+                    position,
+                    expected: c,
+                    typed: Some(c),
+                    correct: true,
+                })
+                .collect();
+            results_with(now, now, keystrokes)
+        };
+
+        // the test was ended halfway through its fourth word
+        let mut selector = new_selector();
+        let words = selector.new_words(10).unwrap();
+        let half_word: String = words[3].chars().take(2).collect();
+        let text = format!("{} {}", words[..3].join(" "), half_word);
+        selector.record_results(&typed(&text));
+        assert_eq!(new_selector().new_words(2).unwrap(), words[3..5]);
+
+        // words of tests that were not completed are skipped
+        let mut selector = new_selector();
+        selector.new_words(4).unwrap();
+        let words = selector.new_words(10).unwrap();
+        selector.record_results(&typed(&format!("{} ", words[..2].join(" "))));
+        assert_eq!(new_selector().new_word().unwrap(), words[2]);
+
+        // a bookmarks file that cannot be read is left as it is
+        std::fs::write(&bookmarks, "not a bookmark\n").unwrap();
+        let mut selector = new_selector();
+        let words = selector.new_words(3).unwrap();
+        selector.record_results(&typed(&words.join(" ")));
+        assert_eq!(
+            std::fs::read_to_string(&bookmarks).unwrap(),
+            "not a bookmark\n"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn book_selector_jumps_to_chapters_and_lines() {
+        let text = "Contents\nChapter 1\nChapter 2\n\nCHAPTER I. The Start\n\nCall me Ishmael.\n\n\
+                    II.\n\nSome years ago.\n\nChapter three was long, she said.\nAnd more.\n";
+        let mut selector =
+            BookSelector::new(BufReader::new(Cursor::new(text)), Sanitizer::default()).unwrap();
+        let number = |n| NonZeroUsize::new(n).unwrap();
+
+        selector.seek_to_chapter(number(1)).unwrap();
+        assert_eq!(selector.new_words(3).unwrap(), ["CHAPTER", "I.", "The"]);
+        selector.seek_to_chapter(number(2)).unwrap();
+        assert_eq!(selector.new_words(2).unwrap(), ["II.", "Some"]);
+        assert!(selector.seek_to_chapter(number(3)).is_err());
+
+        selector.seek_to_line(number(7)).unwrap();
+        assert_eq!(selector.new_word().unwrap(), "Call");
+        selector.seek_to_line(number(1)).unwrap();
+        assert_eq!(selector.new_word().unwrap(), "Contents");
+        assert!(selector.seek_to_line(number(100)).is_err());
+
+        assert!(is_chapter_heading("Chapter 12"));
+        assert!(is_chapter_heading(
+            "  CHAPTER XLII. The Whiteness of the Whale.\n"
+        ));
+        assert!(is_chapter_heading("XIV."));
+        assert!(!is_chapter_heading("I went to sea."));
+        assert!(!is_chapter_heading(""));
+    }
+
+    #[test]
+    fn markdown_is_stripped() {
+        let mut stripper = MarkdownStripper::default();
+        let mut strip = |line| stripper.strip_line(line);
+
+        assert_eq!(strip("# Notes").unwrap(), "Notes");
+        assert_eq!(
+            strip("> - Use **bold** and _emphasis_ in snake_case `code`").unwrap(),
+            "Use bold and emphasis in snake_case code"
+        );
+        assert_eq!(
+            strip("1. See [the docs](https://example.com) and ![a cat][cat].").unwrap(),
+            "See the docs and a cat."
+        );
+        assert_eq!(
+            strip("A <b>bold</b> ~~old~~ claim").unwrap(),
+            "A bold old claim"
+        );
+        assert_eq!(strip("| a | b |").unwrap(), "a b");
+        assert_eq!(strip("").unwrap(), "");
+        assert_eq!(strip("|---|:-:|"), None);
+        assert_eq!(strip("***"), None);
+        assert_eq!(strip("[cat]: https://example.com/cat.png"), None);
+
+        assert_eq!(strip("```rust"), None);
+        assert_eq!(strip("let x = 1;"), None);
+        assert_eq!(strip("```"), None);
+        assert_eq!(strip("Back to prose").unwrap(), "Back to prose");
+
+        assert!(MarkdownStripper::is_markdown_file(Path::new("notes.MD")));
+        assert!(!MarkdownStripper::is_markdown_file(Path::new("book.txt")));
+    }
+
+    #[test]
+    fn book_selector_strips_markdown() {
+        let text = "# Title\n\n```\ncode here\n```\n\nSome *prose*.\n";
+        let mut selector =
+            BookSelector::new(BufReader::new(Cursor::new(text)), Sanitizer::default())
+                .unwrap()
+                .with_markdown(true)
+                .unwrap();
+        assert_eq!(
+            selector.new_words(5).unwrap(),
+            ["Title", "Some", "prose.", "Title", "Some"]
+        );
+    }
+
+    #[test]
+    fn peek_and_reset() {
+        let mut selector = TextSelector::new("the quick brown fox").unwrap();
+        assert_eq!(selector.new_word().unwrap(), "the");
+        assert_eq!(
+            selector.peek_words(4).unwrap(),
+            ["quick", "brown", "fox", "the"]
+        );
+        assert_eq!(selector.new_word().unwrap(), "quick");
+        selector.reset().unwrap();
+        assert_eq!(selector.new_word().unwrap(), "the");
+
+        let reader = BufReader::new(Cursor::new(PROSE.to_string()));
+        let mut selector = BookSelector::new(reader, Sanitizer::default()).unwrap();
+        // peeking past the end reads the text again, more than once
+        let peeked = selector.peek_words(50).unwrap();
+        assert_eq!(selector.new_words(50).unwrap(), peeked);
+        selector.reset().unwrap();
+        assert_eq!(selector.peek_words(3).unwrap(), ["The", "ship's", "log"]);
+
+        let mut selector = CodeSelector::new("\na\nb\n").unwrap();
+        let peeked = selector.peek_words(3).unwrap();
+        assert!(!peeked[0].is_empty());
+        assert_eq!(selector.new_words(3).unwrap(), peeked);
+        selector.reset().unwrap();
+        assert_eq!(selector.new_words(3).unwrap(), peeked);
+
+        // wrappers peek through the selector they wrap, without taking
+        // its words
+        let selector = TextSelector::new("one two three four").unwrap();
+        let selector = PunctuatedWordSelector::from_word_selector(Box::new(selector), 0.5)
+            .with_rng(ToipeRng::from_seed(7));
+        let selector = DrillSelector::from_word_selector(Box::new(selector), vec![('o', 'u')]);
+        let mut selector = NumberedWordSelector::from_word_selector(Box::new(selector), 0.3)
+            .with_rng(ToipeRng::from_seed(7));
+        let peeked = selector.peek_words(6).unwrap();
+        assert_eq!(selector.peek_words(6).unwrap(), peeked);
+        assert_eq!(selector.new_words(6).unwrap(), peeked);
+
+        // wrappers reset the selector they wrap
+        let selector = TextSelector::new("one two").unwrap();
+        let mut selector = NumberedWordSelector::from_word_selector(Box::new(selector), 0.0);
+        selector.new_word().unwrap();
+        selector.reset().unwrap();
+        assert_eq!(selector.new_word().unwrap(), "one");
+
+        // and default to its other hooks
+        let selector = QuoteSelector::new("Short one.\n", QuoteLength::Short).unwrap();
+        let mut selector = ExcludeSelector::from_word_selector(Box::new(selector), vec![]);
+        assert_eq!(selector.next_text_len(), Some(2));
+        let selector = RawWordSelector::from_string("bonjour\thello\n".to_string()).unwrap();
+        let mut selector = ExcludeSelector::from_word_selector(Box::new(selector), vec![]);
+        selector.new_word().unwrap();
+        assert_eq!(selector.annotation("bonjour"), Some("hello".to_string()));
+        assert_eq!(
+            selector.peek_words(1).unwrap_err().kind(),
+            io::ErrorKind::Unsupported
+        );
+
+        let mut selector = RawWordSelector::from_string("apple\n".to_string()).unwrap();
+        assert_eq!(
+            selector.peek_words(1).unwrap_err().kind(),
+            io::ErrorKind::Unsupported
+        );
+    }
+
+    #[test]
+    fn markov_selector() {
+        let text = "The cat sat. The dog sat on the mat.\nA cat ran!";
+        let mut selector = MarkovSelector::new(text, Sanitizer::default())
+            .unwrap()
+            .with_rng(ToipeRng::from_seed(1));
+
+        let words = selector.new_words(200).unwrap();
+        let mut previous: Option<&str> = None;
+        for word in &words {
+            let follows = match previous {
+                None | Some("sat." | "mat." | "ran!") => ["The", "A"].contains(&word.as_str()),
+                Some("The") => ["cat", "dog"].contains(&word.as_str()),
+                Some("sat") => word == "on",
+                Some("on") => word == "the",
+                Some("the") => word == "mat.",
+                Some("cat") => ["sat.", "ran!"].contains(&word.as_str()),
+                Some("dog") => word == "sat",
+                Some("A") => word == "cat",
+                Some(_) => false,
+            };
+            assert!(follows, "{:?} after {:?}", word, previous);
+            previous = Some(word);
+        }
+        assert!(words.iter().any(|word| word == "A"));
+
+        assert!(MarkovSelector::new("\u{2615}\n", Sanitizer::default()).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "history")]
+    fn adaptive_selector() {
+        let new_selector = |keys| {
+            let selector = RawWordSelector::from_string("bee\ncat\ndog\nfox\nhen\n".to_string())
+                .unwrap()
+                .with_rng(ToipeRng::from_seed(1));
+            AdaptiveSelector::from_word_selector(Box::new(selector), keys)
+        };
+        let num_bees = |selector: &mut AdaptiveSelector| {
+            let words = selector.new_words(100).unwrap();
+            words.iter().filter(|word| *word == "bee").count()
+        };
+
+        let weak_b = HashMap::from([(
+            'b',
+            KeyStats {
+                count: MIN_KEY_COUNT,
+                errors: 5,
+            },
+        )]);
+        assert!(num_bees(&mut new_selector(weak_b)) > 50);
+
+        // too few presses to tell
+        let weak_b = HashMap::from([(
+            'b',
+            KeyStats {
+                count: MIN_KEY_COUNT - 1,
+                errors: 5,
+            },
+        )]);
+        assert!(num_bees(&mut new_selector(weak_b)) < 40);
+
+        // "ee" is only in "bee", while "e" is in "hen" too
+        let weak_ee = HashMap::from([(
+            ('e', 'e'),
+            KeyStats {
+                count: MIN_BIGRAM_COUNT,
+                errors: 3,
+            },
+        )]);
+        let mut selector = new_selector(HashMap::new()).with_bigrams(weak_ee);
+        assert!(num_bees(&mut selector) > 50);
+    }
+
+    #[test]
+    fn punctuated_word_selector() {
+        let new_selector = |chance| {
+            let selector = RawWordSelector::from_string("word\n".to_string()).unwrap();
+            PunctuatedWordSelector::from_word_selector(Box::new(selector), chance)
+        };
+
+        // only the first word starts a sentence
+        let words = new_selector(0.0).new_words(5).unwrap();
+        assert_eq!(words, ["Word", "word", "word", "word", "word"]);
+
+        let words = new_selector(1.0).new_words(100).unwrap();
+        assert!(words[0].contains("Word"));
+        for pair in words.windows(2) {
+            assert!(pair[1] != "word", "every word is punctuated");
+            let ends_sentence = ['!', '?', '.'].iter().any(|&c| pair[0].ends_with(c));
+            assert_eq!(pair[1].contains("Word"), ends_sentence);
+        }
+    }
+
+    #[test]
+    fn text_selector() {
+        let mut selector = TextSelector::new("  the quick\tbrown fox ").unwrap();
+
+        for _ in 0..2 {
+            let num_words = selector.next_text_len().unwrap();
+            assert_eq!(
+                selector.new_words(num_words).unwrap(),
+                ["the", "quick", "brown", "fox"]
+            );
+        }
+
+        assert!(TextSelector::new(" ").is_err());
+    }
+
+    #[test]
+    fn fixed_word_selector() {
+        let mut selector = FixedWordSelector::new(["the", "quick", "fox"]).unwrap();
+
+        assert_eq!(selector.peek_words(2).unwrap(), ["the", "quick"]);
+        assert_eq!(selector.new_words(2).unwrap(), ["the", "quick"]);
+        assert_eq!(selector.new_words(2).unwrap(), ["fox", "the"]);
+        assert_eq!(selector.next_text_len(), None);
+        selector.reset().unwrap();
+        assert_eq!(
+            selector.new_unique_words(3).unwrap(),
+            ["the", "quick", "fox"]
+        );
+
+        assert!(FixedWordSelector::new(Vec::<String>::new()).is_err());
+    }
+
+    #[test]
+    fn code_selector() {
+        let code = "fn main() {\n\tprintln!(\"hi \u{1f44b}\");   \n\n\tlet x = 1;\n}\n";
+        let mut selector = CodeSelector::new(code).unwrap();
+
+        let mut lines = selector.new_words(8).unwrap();
+        assert!(!lines[0].is_empty());
+        // rotate to start from the first line of the code
+        let first_line = lines.iter().position(|line| line == "fn main() {").unwrap();
+        lines.rotate_left(first_line);
+        assert_eq!(lines[..4], ["fn main() {", "", "    let x = 1;", "}"]);
+
+        assert!(CodeSelector::new("\n\u{1f44b}\n").is_err());
+    }
+
+    #[test]
+    fn drill_selector() {
+        let selector = RawWordSelector::from_string("the\nand\nof\nthis\n".to_string()).unwrap();
+        let mut selector = DrillSelector::from_word_selector(Box::new(selector), vec![('t', 'h')]);
+
+        for word in selector.new_words(100).unwrap() {
+            assert!(word == "the" || word == "this", "{}", word);
+        }
+
+        let mut words = selector.new_unique_words(2).unwrap();
+        words.sort();
+        assert_eq!(words, ["the", "this"]);
+        // repeated once there are no other words with the digraph
+        assert_eq!(selector.new_unique_words(3).unwrap().len(), 3);
+
+        // falls back to any word when no word has the digraph
+        let selector = RawWordSelector::from_string("and\nof\n".to_string()).unwrap();
+        let mut selector = DrillSelector::from_word_selector(Box::new(selector), vec![('t', 'h')]);
+        assert_eq!(selector.new_words(10).unwrap().len(), 10);
+        assert_eq!(selector.new_unique_words(10).unwrap().len(), 10);
+    }
+
+    #[test]
+    fn ngram_selector() {
+        let selector = RawWordSelector::from_string("action\nand\nof\nthe\n".to_string()).unwrap();
+        let mut selector = NgramSelector::from_word_selector(
+            Box::new(selector),
+            vec!["th".to_string(), "ion".to_string()],
+        )
+        .unwrap();
+        for word in selector.new_words(100).unwrap() {
+            assert!(word == "the" || word == "action", "{}", word);
+        }
+
+        // makes pseudo-words when no word has the n-grams
+        let selector = RawWordSelector::from_string("and\nof\n".to_string()).unwrap();
+        let mut selector = NgramSelector::from_word_selector(
+            Box::new(selector),
+            vec!["th".to_string(), "ion".to_string()],
+        )
+        .unwrap()
+        .with_rng(ToipeRng::from_seed(42));
+        for word in selector.new_words(10).unwrap() {
+            assert!(["thth", "thion", "ionth", "ionion"].contains(&word.as_str()));
+        }
+
+        let selector = RawWordSelector::from_string("and\n".to_string()).unwrap();
+        assert!(NgramSelector::from_word_selector(Box::new(selector), vec![]).is_err());
+    }
+
+    #[test]
+    fn letters_selector() {
+        let selector = TextSelector::new("add the Ask which fall").unwrap();
+        let mut selector = LettersSelector::from_word_selector(Box::new(selector), "asdfjkl;");
+        for word in selector.new_words(100).unwrap() {
+            assert!(["add", "Ask", "fall"].contains(&word.as_str()), "{}", word);
+        }
+
+        let selector = RawWordSelector::from_string("add\nask\nfall\nthe\n".to_string()).unwrap();
+        let mut selector = LettersSelector::from_word_selector(Box::new(selector), "asdfjkl;");
+        let mut words = selector.new_unique_words(3).unwrap();
+        words.sort();
+        assert_eq!(words, ["add", "ask", "fall"]);
+
+        let selector = RawWordSelector::from_string("the\nwhich\n".to_string()).unwrap();
+        let mut selector = LettersSelector::from_word_selector(Box::new(selector), "asdf");
+        assert!(selector.new_word().is_err());
+        assert!(selector.new_unique_words(2).is_err());
+    }
+
+    #[test]
+    fn pattern_selector() {
+        let selector =
+            RawWordSelector::from_string("ant\nbear\ncamel\nlion\nzebra\n".to_string()).unwrap();
+        let pattern = Regex::new("^[a-m]{4,6}$").unwrap();
+        let mut selector = PatternSelector::from_word_selector(Box::new(selector), pattern);
+        for word in selector.new_words(100).unwrap() {
+            assert!(["bear", "camel"].contains(&word.as_str()), "{}", word);
+        }
+
+        let selector = RawWordSelector::from_string("the\nwhich\n".to_string()).unwrap();
+        let pattern = Regex::new("q").unwrap();
+        let mut selector = PatternSelector::from_word_selector(Box::new(selector), pattern);
+        assert!(selector.new_word().is_err());
+    }
+
+    #[test]
+    fn exclude_selector() {
+        let selector =
+            RawWordSelector::from_string("ant\nbear\ncamel\nlion\n".to_string()).unwrap();
+        let excluded = vec!["Bear".to_string(), "lion".to_string()];
+        let mut selector = ExcludeSelector::from_word_selector(Box::new(selector), excluded);
+        for word in selector.new_words(100).unwrap() {
+            assert!(["ant", "camel"].contains(&word.as_str()), "{}", word);
+        }
+        let mut words = selector.new_unique_words(2).unwrap();
+        words.sort();
+        assert_eq!(words, ["ant", "camel"]);
+
+        let selector = RawWordSelector::from_string("the\n".to_string()).unwrap();
+        // punctuation and case are ignored
+        let selector = PunctuatedWordSelector::from_word_selector(Box::new(selector), 1.0);
+        let mut selector =
+            ExcludeSelector::from_word_selector(Box::new(selector), ["the".to_string()]);
+        assert!(selector.new_word().is_err());
+    }
+
+    #[test]
+    fn capitalized_word_selector() {
+        let selector = RawWordSelector::from_string("word\n".to_string()).unwrap();
+        let mut selector = CapitalizedWordSelector::from_word_selector(Box::new(selector), 0.3)
+            .with_rng(ToipeRng::from_seed(1));
+
+        let words = selector.new_words(1000).unwrap();
+        let num_capitalized = words.iter().filter(|word| *word == "Word").count();
+        assert!((200..400).contains(&num_capitalized), "{}", num_capitalized);
+        assert!(words.iter().all(|word| word == "word" || word == "Word"));
+    }
+
+    #[test]
+    fn numbered_word_selector() {
+        let selector = RawWordSelector::from_string("word\n".to_string()).unwrap();
+        let mut selector = NumberedWordSelector::from_word_selector(Box::new(selector), 0.5);
+
+        let words = selector.new_words(1000).unwrap();
+        let numbers: Vec<&String> = words.iter().filter(|word| *word != "word").collect();
+
+        assert!(!numbers.is_empty() && numbers.len() < words.len());
+        for number in numbers {
+            assert!((1..=4).contains(&number.len()));
+            assert!(number.chars().all(|c| c.is_ascii_digit()));
+            assert!(number.len() == 1 || !number.starts_with('0'));
+        }
+    }
+
+    #[test]
+    fn char_classes() {
+        assert_eq!(
+            parse_char_class("[a-c0-2]").unwrap(),
+            "abc012".chars().collect::<Vec<_>>()
+        );
+        assert_eq!(parse_char_class("[;:a;]").unwrap(), vec![';', ':', 'a']);
+        assert_eq!(parse_char_class("[-a-]").unwrap(), vec!['-', 'a']);
+        assert_eq!(
+            parse_char_class(r"[\]\\\-\^]").unwrap(),
+            vec![']', '\\', '-', '^']
+        );
+        // escaped dash is not a range
+        assert_eq!(parse_char_class(r"[a\-c]").unwrap(), vec!['a', '-', 'c']);
+
+        for invalid in [
+            "a-z", "[a-z", "[]", "[^a]", "[z-a]", "[a b]", "[é]", "[a[]", r"[a\]", r"[\d]",
+        ] {
+            assert!(parse_char_class(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn charset_selector() {
+        let mut selector = CharsetSelector::new("[0-9;:]", 2, 4)
+            .unwrap()
+            .with_rng(ToipeRng::from_seed(42));
+
+        for word in selector.new_words(100).unwrap() {
+            assert!((2..=4).contains(&word.len()));
+            assert!(word
+                .chars()
+                .all(|c| c.is_ascii_digit() || c == ';' || c == ':'));
+        }
+
+        assert!(CharsetSelector::new("[a-z]", 0, 4).is_err());
+        assert!(CharsetSelector::new("[a-z]", 5, 4).is_err());
+        assert!(CharsetSelector::new("a-z", 1, 4).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "structured-wordlist")]
+    fn structured_word_list() {
+        let json = r#"{
+            "name": "animals",
+            "language": "en",
+            "words": [
+                {"word": "cat", "frequency": 3, "difficulty": 1.5, "tags": ["pets"]},
+                "zebra"
+            ]
+        }"#;
+        let list = StructuredWordList::from_json(json).unwrap();
+        assert_eq!(
+            list.words,
+            [
+                WordEntry {
+                    word: "cat".to_string(),
+                    frequency: Some(3),
+                    difficulty: Some(1.5),
+                    tags: vec!["pets".to_string()],
+                },
+                WordEntry {
+                    word: "zebra".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+        assert!(StructuredWordList::from_json("[\"cat\"]").is_err());
+
+        let csv = "word,frequency,tags\ncat,3,pets; short\ndog,1,\nemu,0,birds\n";
+        let list = StructuredWordList::from_csv(csv).unwrap();
+        assert_eq!(list.words[0].tags, ["pets", "short"]);
+        assert_eq!(list.words[1].tags, Vec::<String>::new());
+        assert_eq!(list.words[2].difficulty, None);
+
+        // every word has a frequency, so emu is never selected
+        let mut selector = StructuredSelector::new(list)
+            .unwrap()
+            .with_rng(ToipeRng::from_seed(42));
+        let words = selector.new_words(1000).unwrap();
+        let num_cat = words.iter().filter(|word| *word == "cat").count();
+        assert!(!words.contains(&"emu".to_string()));
+        assert!((700..800).contains(&num_cat), "{}", num_cat);
+        let mut words = selector.new_unique_words(2).unwrap();
+        words.sort();
+        assert_eq!(words, ["cat", "dog"]);
+
+        assert!(StructuredSelector::new(StructuredWordList::default()).is_err());
+        assert!(StructuredWordList::is_structured_file(Path::new(
+            "words.JSON"
+        )));
+        assert!(!StructuredWordList::is_structured_file(Path::new("words")));
+    }
+
+    #[test]
+    #[cfg(feature = "structured-wordlist")]
+    fn monkeytype_language() {
+        let json = r#"{
+            "name": "english",
+            "noLazyMode": true,
+            "orderedByFrequency": true,
+            "bcp47": "en-US",
+            "words": ["the", "be", "of"]
+        }"#;
+        let list = StructuredWordList::from_json(json).unwrap();
+        assert_eq!(list.words.len(), 3);
+
+        // detected by content without the extension
+        let path = std::env::temp_dir().join(format!("toipe-monkeytype-{}", std::process::id()));
+        std::fs::write(&path, format!("\n  {}", json)).unwrap();
+        let is_structured = StructuredWordList::is_structured_file(&path);
+        std::fs::write(&path, "the\nbe\n").unwrap();
+        let is_plain = !StructuredWordList::is_structured_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(is_structured);
+        assert!(is_plain);
+    }
+
+    #[test]
+    #[cfg(feature = "structured-wordlist")]
+    fn merge_word_lists() {
+        let mut list = StructuredWordList::from_json(
+            r#"{"language": "en", "words": [{"word": "cat", "tags": ["pets"]}]}"#,
+        )
+        .unwrap();
+        list.merge(StructuredWordList::from_plain(
+            "# animals\ncat\t5\nowl\t3\n",
+        ));
+
+        assert_eq!(
+            list.words,
+            vec![
+                WordEntry {
+                    word: "cat".to_string(),
+                    tags: vec!["pets".to_string()],
+                    ..Default::default()
+                },
+                WordEntry {
+                    word: "owl".to_string(),
+                    frequency: Some(3),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn word_list_index_cache() {
+        let dir = std::env::temp_dir().join(format!("toipe-index-{}", std::process::id()));
+        let word_list = dir.join("words");
+        let cache = dir.join("cache").join("index");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&word_list, "ant\nbee\ncat\n").unwrap();
+
+        let selector =
+            RawWordSelector::from_path_with_index_cache(word_list.clone(), &cache).unwrap();
+        let index = selector.index().unwrap();
+        let stamp = FileStamp::of(&File::open(&word_list).unwrap()).unwrap();
+        let cached = std::fs::read_to_string(&cache).unwrap();
+        assert_eq!(
+            WordListIndex::parse_cache(&cached, &stamp),
+            Some(index.clone())
+        );
+
+        // the cached index is used as long as the file is the same
+        let mut selector =
+            RawWordSelector::from_path_with_index_cache(word_list.clone(), &cache).unwrap();
+        assert_eq!(selector.index(), Some(index));
+        assert_eq!(selector.word_at_index(2).unwrap(), "cat");
+
+        // and rebuilt once it changes
+        std::fs::write(&word_list, "ant\nbee\ncat\ndog\n").unwrap();
+        let mut selector =
+            RawWordSelector::from_path_with_index_cache(word_list.clone(), &cache).unwrap();
+        assert_eq!(selector.word_at_index(3).unwrap(), "dog");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn word_stream() {
+        let selector = RawWordSelector::from_string("ant\nbee\ncat\ndog\n".to_string()).unwrap();
+        let mut stream = WordStream::new(Box::new(selector), 3).with_unique_words(true);
+
+        // words are drawn three at a time
+        let words: Vec<String> = stream.by_ref().take(4).map(Result::unwrap).collect();
+        let rest = stream.next_text().unwrap();
+        assert_eq!(rest.len(), 2);
+        let mut all_words = words.clone();
+        all_words.extend(rest);
+        all_words.sort();
+        all_words.dedup();
+        assert_eq!(all_words.len(), 4);
+
+        // words drawn but not streamed are dropped for a new text
+        stream.next().unwrap().unwrap();
+        stream.start_text();
+        assert_eq!(stream.next_text().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn word_stream_ends_texts_at_sentences() {
+        let book = "One two three. Four five six seven! Eight\n";
+        let selector =
+            BookSelector::new(BufReader::new(Cursor::new(book)), Sanitizer::default()).unwrap();
+        let mut stream = WordStream::new(Box::new(selector), 2).with_sentences(true);
+
+        assert_eq!(stream.next_text().unwrap(), ["One", "two", "three."]);
+        assert_eq!(
+            stream.next_text().unwrap(),
+            ["Four", "five", "six", "seven!"]
+        );
+        // the book starts over after its end
+        assert_eq!(
+            stream.next_text().unwrap(),
+            ["Eight", "One", "two", "three."]
+        );
+
+        // texts without sentences end after a while
+        let selector = RawWordSelector::from_string("ant\nbee\n".to_string()).unwrap();
+        let mut stream = WordStream::new(Box::new(selector), 2).with_sentences(true);
+        assert_eq!(
+            stream.next_text().unwrap().len(),
+            2 + MAX_WORDS_TO_SENTENCE_END
+        );
+    }
+
+    #[test]
+    fn empty_word_list() {
+        assert!(RawWordSelector::from_string("".to_string()).is_err());
+        assert!(RawWordSelector::from_string("123\n\n".to_string()).is_err());
     }
 }
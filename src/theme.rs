@@ -0,0 +1,96 @@
+//! Colors used by the terminal UI.
+//!
+//! See [`Theme`].
+
+#[cfg(feature = "cli")]
+use clap::ArgEnum;
+use termion::color::AnsiValue;
+
+use crate::term_caps::{self, Background};
+use crate::tui::Text;
+
+/// Which theme to use.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "cli", derive(ArgEnum))]
+pub enum ThemeChoice {
+    /// Detect the background of the terminal and pick the light or
+    /// dark theme accordingly.
+    Auto,
+    /// For terminals with a dark background.
+    Dark,
+    /// For terminals with a light background.
+    Light,
+}
+
+impl ThemeChoice {
+    /// Returns the theme for this choice.
+    ///
+    /// See [`term_caps::detect_background`] for when this can be
+    /// invoked with [`ThemeChoice::Auto`]. The dark theme is used if
+    /// the background cannot be detected.
+    pub fn theme(&self) -> Theme {
+        match self {
+            Self::Dark => Theme::DARK,
+            Self::Light => Theme::LIGHT,
+            Self::Auto => match term_caps::detect_background() {
+                Some(Background::Light) => Theme::LIGHT,
+                Some(Background::Dark) | None => Theme::DARK,
+            },
+        }
+    }
+}
+
+/// Colors of the different kinds of text in the terminal UI.
+#[derive(Copy, Clone, Debug)]
+pub struct Theme {
+    /// correctly typed chars
+    pub correct: AnsiValue,
+    /// wrongly typed chars
+    pub incorrect: AnsiValue,
+    /// keyboard shortcuts and highlighted results
+    pub accent: AnsiValue,
+    /// speeds in the results
+    pub speed: AnsiValue,
+    /// stats that are neither good nor bad, like a fair accuracy
+    pub warning: AnsiValue,
+    /// text yet to be typed and hints, or `None` to display them faint
+    pub dim: Option<AnsiValue>,
+}
+
+impl Theme {
+    /// Theme for dark backgrounds.
+    pub const DARK: Theme = Theme {
+        correct: AnsiValue(10),
+        incorrect: AnsiValue(1),
+        accent: AnsiValue(4),
+        speed: AnsiValue(2),
+        warning: AnsiValue(3),
+        dim: None,
+    };
+
+    /// Theme for light backgrounds, where faint text is often hard to
+    /// read.
+    pub const LIGHT: Theme = Theme {
+        correct: AnsiValue(2),
+        incorrect: AnsiValue(1),
+        accent: AnsiValue(4),
+        speed: AnsiValue(2),
+        // yellow is hard to read on light backgrounds
+        warning: AnsiValue(130),
+        dim: Some(AnsiValue(244)),
+    };
+
+    /// Displays the text dimmed, for text yet to be typed and hints.
+    pub fn dim(&self, text: Text) -> Text {
+        match self.dim {
+            Some(color) => text.with_color(color),
+            None => text.with_faint(),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DARK
+    }
+}
@@ -1,22 +1,63 @@
 //! Utilities for the terminal UI of toipe.
 
 use std::{
+    collections::HashMap,
     fmt::Display,
-    io::{stdout, Stdout, Write},
+    io::{self, stdout, Stdout, Write},
+    ops::Range,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, Once,
+    },
 };
 
+#[cfg(feature = "cli")]
+use clap::ArgEnum;
 use termion::{
     clear,
     color::{self, Color},
-    cursor::{self, DetectCursorPos},
+    cursor,
     raw::{IntoRawMode, RawTerminal},
     style, terminal_size,
 };
 
+use crate::textgen::ends_sentence;
+use crate::theme::Theme;
 use crate::ToipeError;
 use anyhow::Result;
 
-const MIN_LINE_WIDTH: usize = 50;
+/// Default maximum width of a line of words, in columns (see
+/// [`ToipeTui::with_max_columns`]).
+pub const DEFAULT_MAX_COLUMNS: u16 = 80;
+
+/// Maximum number of lines of text to be typed shown at a time, so that
+/// the text stays readable on very tall terminals.
+const MAX_TEXT_LINES: usize = 24;
+
+/// Number of columns that a char takes on the terminal: 2 for wide
+/// chars like kana and CJK ideographs, 1 for the rest.
+pub fn char_width(c: char) -> usize {
+    match c {
+        '\u{1100}'..='\u{115f}'
+        | '\u{2e80}'..='\u{303e}'
+        | '\u{3041}'..='\u{33ff}'
+        | '\u{3400}'..='\u{4dbf}'
+        | '\u{4e00}'..='\u{9fff}'
+        | '\u{a000}'..='\u{a4cf}'
+        | '\u{ac00}'..='\u{d7a3}'
+        | '\u{f900}'..='\u{faff}'
+        | '\u{fe30}'..='\u{fe4f}'
+        | '\u{ff00}'..='\u{ff60}'
+        | '\u{ffe0}'..='\u{ffe6}' => 2,
+        _ => 1,
+    }
+}
+
+/// Number of columns that a string takes on the terminal (see
+/// [`char_width`]).
+pub fn str_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
 
 /// Describes something that has a printable length.
 ///
@@ -48,19 +89,27 @@ pub struct Text {
     text: String,
     /// actual number of char width taken when printed on the terminal
     length: usize,
+    /// marker shown below the text in ASCII mode
+    marker: Option<char>,
+    /// whether the line ending with this text continues on the next line
+    continued: bool,
 }
 
 impl Text {
     /// Constructs a new Text from a raw string
     ///
+    /// Wide chars, like kana, count as two (see [`char_width`]).
+    ///
     /// NOTE: ensure that this string does not itself have formatting
-    /// characters, zero-width characters or multi-width characters.
+    /// characters or zero-width characters.
     pub fn new(text: String) -> Self {
-        let length = text.len();
+        let length = str_width(&text);
         Self {
             raw_text: text.clone(),
             text,
             length,
+            marker: None,
+            continued: false,
         }
     }
 
@@ -100,6 +149,24 @@ impl Text {
         );
         self
     }
+
+    /// adds a marker to be shown below the text in ASCII mode, where
+    /// styles and colors are not displayed
+    ///
+    /// See [`ToipeTui::with_ascii`].
+    pub fn with_marker(mut self, marker: char) -> Self {
+        self.marker = Some(marker);
+        self
+    }
+
+    /// adds a marker after the text to show that the line ending with it
+    /// continues on the next line, like when a long line is wrapped
+    ///
+    /// The marker is not part of the text or its length.
+    pub fn with_continuation(mut self) -> Self {
+        self.continued = true;
+        self
+    }
 }
 
 impl HasLength for Text {
@@ -120,7 +187,7 @@ impl From<String> for Text {
     /// Constructs a new Text from a raw string
     ///
     /// NOTE: ensure that this string does not itself have formatting
-    /// characters, zero-width characters or multi-width characters.
+    /// characters or zero-width characters.
     fn from(text: String) -> Self {
         Self::new(text)
     }
@@ -130,7 +197,7 @@ impl From<&str> for Text {
     /// Constructs a new Text from a raw string
     ///
     /// NOTE: ensure that this string does not itself have formatting
-    /// characters, zero-width characters or multi-width characters.
+    /// characters or zero-width characters.
     fn from(text: &str) -> Self {
         Self::new(text.to_string())
     }
@@ -140,7 +207,7 @@ impl From<char> for Text {
     /// Constructs a new Text from a character
     ///
     /// NOTE: ensure that this character is itself not a formatting
-    /// character, zero-width character or a multi-width character.
+    /// character or a zero-width character.
     fn from(c: char) -> Self {
         Self::new(c.to_string())
     }
@@ -154,7 +221,7 @@ impl Display for Text {
 }
 
 /// the position of a line of words
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 struct LinePos {
     /// y-position of line in the terminal window
     pub y: u16,
@@ -162,6 +229,35 @@ struct LinePos {
     pub x: u16,
     /// length (number of chars) in this line
     pub length: u16,
+    /// column of each char from the first, which differs from its index
+    /// after wide chars
+    pub columns: Vec<u16>,
+}
+
+impl LinePos {
+    fn new(x: u16, y: u16, text: &str) -> Self {
+        let mut column = 0;
+        let columns: Vec<u16> = text
+            .chars()
+            .map(|c| {
+                let char_column = column;
+                column += char_width(c) as u16;
+                char_column
+            })
+            .collect();
+        Self {
+            y,
+            x,
+            length: columns.len() as u16,
+            columns,
+        }
+    }
+
+    /// Position of the char at the given index in the line.
+    fn pos_of(&self, index: u16) -> (u16, u16) {
+        let column = self.columns.get(index as usize).copied().unwrap_or(index);
+        (self.x + column, self.y)
+    }
 }
 
 /// TODO: document this
@@ -181,7 +277,7 @@ impl CursorPos {
     }
 
     pub fn next(&mut self) -> (u16, u16) {
-        let line = self.lines[self.cur_line];
+        let line = &self.lines[self.cur_line];
         let max_chars_index = line.length - 1;
 
         if self.cur_char_in_line < max_chars_index {
@@ -216,17 +312,380 @@ impl CursorPos {
     }
 
     pub fn cur_pos(&self) -> (u16, u16) {
-        let line = self.lines[self.cur_line];
-        (line.x + self.cur_char_in_line, line.y)
+        self.lines[self.cur_line].pos_of(self.cur_char_in_line)
+    }
+
+    /// Position of the char at the given index in the text, or `None`
+    /// if the text is shorter.
+    pub fn pos_of(&self, index: usize) -> Option<(u16, u16)> {
+        let mut index = index;
+        for line in &self.lines {
+            if index < line.length as usize {
+                return Some(line.pos_of(index as u16));
+            }
+            index -= line.length as usize;
+        }
+        None
+    }
+}
+
+/// Where the hints (like keyboard shortcuts) are displayed.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "cli", derive(ArgEnum))]
+pub enum HudPosition {
+    /// Above the text.
+    Top,
+    /// Below the text.
+    Bottom,
+    /// Hints are not displayed.
+    Off,
+}
+
+/// Error returned when the terminal is too small to display the text.
+///
+/// Displayed like the [`ToipeError`] it holds.
+#[derive(Debug)]
+pub struct TerminalTooSmall(pub ToipeError);
+
+impl Display for TerminalTooSmall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for TerminalTooSmall {}
+
+/// Marker shown below the char to be typed next in ASCII mode.
+const CARET_MARKER: char = '^';
+
+/// Marker shown after a line that continues on the next line (see
+/// [`Text::with_continuation`]).
+const CONTINUATION_MARKER: char = '↩';
+
+/// [`CONTINUATION_MARKER`] in ASCII mode.
+const ASCII_CONTINUATION_MARKER: char = '\\';
+
+/// Top left, top right, bottom left and bottom right corners, and
+/// horizontal and vertical edges of the box around an overlay (see
+/// [`ToipeTui::display_overlay`]).
+const OVERLAY_BORDER: [char; 6] = ['┌', '┐', '└', '┘', '─', '│'];
+
+/// [`OVERLAY_BORDER`] in ASCII mode.
+const ASCII_OVERLAY_BORDER: [char; 6] = ['+', '+', '+', '+', '-', '|'];
+
+/// Number of lines of each char of large text (see [`big_text`]).
+const BIG_TEXT_HEIGHT: usize = 5;
+
+/// Chars that can be displayed as large text, each drawn with `#` in
+/// place of a block.
+const BIG_CHARS: [(char, [&str; BIG_TEXT_HEIGHT]); 13] = [
+    ('0', ["####", "#  #", "#  #", "#  #", "####"]),
+    ('1', ["  # ", " ## ", "  # ", "  # ", " ###"]),
+    ('2', ["####", "   #", "####", "#   ", "####"]),
+    ('3', ["####", "   #", " ###", "   #", "####"]),
+    ('4', ["#  #", "#  #", "####", "   #", "   #"]),
+    ('5', ["####", "#   ", "####", "   #", "####"]),
+    ('6', ["####", "#   ", "####", "#  #", "####"]),
+    ('7', ["####", "   #", "  # ", " #  ", " #  "]),
+    ('8', ["####", "#  #", "####", "#  #", "####"]),
+    ('9', ["####", "#  #", "####", "   #", "####"]),
+    ('.', [" ", " ", " ", " ", "#"]),
+    ('%', ["#  #", "   #", "  # ", " #  ", "#  #"]),
+    (' ', ["  ", "  ", "  ", "  ", "  "]),
+];
+
+/// Lines that draw the text in large chars made of `block`, with a
+/// column between chars. Chars without a large version are left out.
+fn big_text(text: &str, block: char) -> [String; BIG_TEXT_HEIGHT] {
+    let mut lines: [String; BIG_TEXT_HEIGHT] = Default::default();
+    let glyphs = text.chars().filter_map(|c| {
+        BIG_CHARS
+            .iter()
+            .find(|(big_c, _)| *big_c == c)
+            .map(|(_, glyph)| glyph)
+    });
+    for (i, glyph) in glyphs.enumerate() {
+        for (line, glyph_line) in lines.iter_mut().zip(glyph) {
+            if i > 0 {
+                line.push(' ');
+            }
+            line.extend(glyph_line.chars().map(|c| if c == '#' { block } else { c }));
+        }
+    }
+    lines
+}
+
+/// Something the terminal UI can be displayed on.
+///
+/// This is the standard output in raw mode for the `toipe` binary. See
+/// [`FakeTerminal`] for running the UI without a terminal.
+pub trait Terminal: Write {
+    /// Width and height of the terminal, in columns and lines.
+    fn size(&self) -> io::Result<(u16, u16)>;
+
+    /// Suspends the program like ctrl-z does outside of raw mode,
+    /// returning once it is continued (like with `fg`).
+    ///
+    /// Does nothing by default.
+    fn suspend(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Gives the terminal back to the shell, like leaving raw mode, once
+    /// the UI is closed.
+    ///
+    /// Does nothing by default.
+    fn restore(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Whether the program was stopped from outside and continued since
+    /// this was last called, like with `kill -STOP` and `kill -CONT`,
+    /// setting the terminal up again if so.
+    ///
+    /// Returns false by default.
+    fn take_continued(&mut self) -> io::Result<bool> {
+        Ok(false)
+    }
+}
+
+/// Set when the program is continued after being stopped (see
+/// [`Terminal::take_continued`]).
+static CONTINUED: AtomicBool = AtomicBool::new(false);
+
+/// Handles `SIGCONT`, which is sent when the program is continued.
+extern "C" fn on_continue(_signal: libc::c_int) {
+    CONTINUED.store(true, Ordering::Relaxed);
+}
+
+/// Keeps track of the program being continued, once for all terminals.
+fn watch_for_continue() {
+    static WATCH: Once = Once::new();
+    WATCH.call_once(|| {
+        let handler: extern "C" fn(libc::c_int) = on_continue;
+        // SAFETY: the handler only sets an atomic flag, which can be
+        // done from a signal handler
+        unsafe {
+            libc::signal(libc::SIGCONT, handler as libc::sighandler_t);
+        }
+    });
+}
+
+impl Terminal for RawTerminal<Stdout> {
+    fn size(&self) -> io::Result<(u16, u16)> {
+        terminal_size()
+    }
+
+    /// Leaves raw mode so that the shell gets a normal terminal, and
+    /// stops the program with `SIGTSTP`. Raw mode is entered again
+    /// when the program is continued.
+    fn suspend(&mut self) -> io::Result<()> {
+        self.suspend_raw_mode()?;
+        // SAFETY: raise only sends a signal to this process
+        if unsafe { libc::raise(libc::SIGTSTP) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // continued after stopping itself, not from outside
+        CONTINUED.store(false, Ordering::Relaxed);
+        self.activate_raw_mode()
+    }
+
+    fn restore(&mut self) -> io::Result<()> {
+        self.suspend_raw_mode()
+    }
+
+    /// Enters raw mode again if continued, since the shell may have left
+    /// it while the program was stopped.
+    fn take_continued(&mut self) -> io::Result<bool> {
+        watch_for_continue();
+        if !CONTINUED.swap(false, Ordering::Relaxed) {
+            return Ok(false);
+        }
+        self.activate_raw_mode()?;
+        Ok(true)
+    }
+}
+
+/// Placeholder for the second column of a wide char on the screen of a
+/// [`FakeTerminal`], left out of the lines returned.
+const WIDE_CHAR_TAIL: char = '\0';
+
+/// Terminal of a fixed size that records everything written to it.
+///
+/// Useful for testing, along with [`crate::input::KeyEvents::from_reader`]
+/// for scripted key presses. Clones share the same output, so one clone
+/// can be given to [`ToipeTui::from_terminal`] and another used to
+/// inspect what was displayed or to resize the terminal.
+#[derive(Clone, Debug)]
+pub struct FakeTerminal {
+    size: Arc<Mutex<(u16, u16)>>,
+    output: Arc<Mutex<Vec<u8>>>,
+    continued: Arc<AtomicBool>,
+}
+
+impl FakeTerminal {
+    /// Creates a terminal with the given width and height.
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            size: Arc::new(Mutex::new((width, height))),
+            output: Arc::new(Mutex::new(Vec::new())),
+            continued: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Acts like the program was stopped from outside and continued
+    /// (see [`Terminal::take_continued`]).
+    pub fn stop_and_continue(&self) {
+        self.continued.store(true, Ordering::Relaxed);
+    }
+
+    /// Changes the width and height of the terminal.
+    pub fn resize(&self, width: u16, height: u16) {
+        *self.size.lock().unwrap() = (width, height);
+    }
+
+    /// Everything written to the terminal so far, including escape
+    /// sequences.
+    pub fn output(&self) -> String {
+        String::from_utf8_lossy(&self.output.lock().unwrap()).into_owned()
+    }
+
+    /// Lines of text currently on the screen, without styles and colors
+    /// and with trailing spaces removed.
+    ///
+    /// Only the escape sequences written by [`ToipeTui`] that change
+    /// what is on the screen are interpreted: moving the cursor and
+    /// clearing the screen or a line.
+    pub fn screen(&self) -> Vec<String> {
+        let (width, height) = *self.size.lock().unwrap();
+        let (width, height) = (width as usize, height as usize);
+        let mut screen = vec![vec![' '; width]; height];
+        let (mut x, mut y) = (0, 0);
+
+        let output = self.output();
+        let mut chars = output.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '\x1b' {
+                if let Some(line) = screen.get_mut(y) {
+                    if let Some(cell) = line.get_mut(x) {
+                        *cell = c;
+                    }
+                    // the second column of a wide char
+                    if char_width(c) > 1 {
+                        if let Some(cell) = line.get_mut(x + 1) {
+                            *cell = WIDE_CHAR_TAIL;
+                        }
+                    }
+                }
+                x += char_width(c);
+                continue;
+            }
+            if chars.next_if_eq(&'[').is_none() {
+                continue;
+            }
+
+            // control sequence: parameters followed by a final byte
+            let mut params = String::new();
+            let mut command = None;
+            for c in chars.by_ref() {
+                if ('\x40'..='\x7e').contains(&c) {
+                    command = Some(c);
+                    break;
+                }
+                params.push(c);
+            }
+            let mut numbers = params.split(';').map(|n| n.parse::<usize>().ok());
+            let mut next_number = |default| numbers.next().flatten().unwrap_or(default);
+            match command {
+                Some('H') => {
+                    y = next_number(1).saturating_sub(1);
+                    x = next_number(1).saturating_sub(1);
+                }
+                Some('C') => x += next_number(1),
+                Some('D') => x = x.saturating_sub(next_number(1)),
+                Some('J') if params == "2" => {
+                    screen = vec![vec![' '; width]; height];
+                }
+                Some('K') => {
+                    if let Some(line) = screen.get_mut(y) {
+                        let start = if params == "2" { 0 } else { x.min(width) };
+                        line[start..].fill(' ');
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        screen
+            .into_iter()
+            .map(|line| {
+                line.into_iter()
+                    .filter(|&c| c != WIDE_CHAR_TAIL)
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+}
+
+impl Write for FakeTerminal {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.output.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Terminal for FakeTerminal {
+    fn size(&self) -> io::Result<(u16, u16)> {
+        Ok(*self.size.lock().unwrap())
+    }
+
+    fn take_continued(&mut self) -> io::Result<bool> {
+        Ok(self.continued.swap(false, Ordering::Relaxed))
     }
 }
 
 /// terminal UI of toipe
 pub struct ToipeTui {
-    stdout: RawTerminal<Stdout>,
+    terminal: Box<dyn Terminal>,
     cursor_pos: CursorPos,
     track_lines: bool,
-    bottom_lines_len: usize,
+    /// where the hints are displayed
+    hud: HudPosition,
+    /// whether wrapped lines of words end with a continuation marker
+    wrap_markers: bool,
+    /// maximum width of a line of words, in columns
+    max_columns: u16,
+    /// whether the top third of the screen is left for the large
+    /// readout of the speed and accuracy
+    streamer: bool,
+    /// number of lines taken by the hints
+    hud_lines_len: usize,
+    /// whether to avoid styles and colors
+    ascii: bool,
+    /// colors of the text
+    theme: Theme,
+    /// markers shown below the chars of the text in ASCII mode
+    markers: HashMap<(u16, u16), char>,
+    /// position of the char with the caret marker below it in ASCII mode
+    caret: Option<(u16, u16)>,
+    /// lines displayed on the screen, by their Y-position, along with
+    /// their X-position, for redisplaying them after an overlay
+    displayed_lines: HashMap<u16, (u16, Vec<Text>)>,
+    /// chars of the text displayed since its lines were displayed, like
+    /// the ones typed, by their position
+    displayed_chars: HashMap<(u16, u16), Text>,
+    /// Y-positions of the lines covered by the overlay, if one is
+    /// displayed
+    overlay_rows: Option<Range<u16>>,
+    /// whether the terminal was reset and given back (see
+    /// [`ToipeTui::close`])
+    closed: bool,
 }
 
 type MaybeError<T = ()> = Result<T>;
@@ -236,39 +695,138 @@ impl ToipeTui {
     ///
     /// NOTE: does not clear the screen when initialized.
     pub fn new() -> Self {
+        Self::from_terminal(stdout().into_raw_mode().unwrap())
+    }
+
+    /// Displays the TUI on the given terminal.
+    ///
+    /// NOTE: does not clear the screen when initialized.
+    pub fn from_terminal<T>(terminal: T) -> Self
+    where
+        T: Terminal + 'static,
+    {
         Self {
-            stdout: stdout().into_raw_mode().unwrap(),
+            terminal: Box::new(terminal),
             cursor_pos: CursorPos::new(),
             track_lines: false,
-            bottom_lines_len: 0,
+            hud: HudPosition::Bottom,
+            wrap_markers: false,
+            max_columns: DEFAULT_MAX_COLUMNS,
+            streamer: false,
+            hud_lines_len: 0,
+            ascii: false,
+            theme: Theme::default(),
+            markers: HashMap::new(),
+            caret: None,
+            displayed_lines: HashMap::new(),
+            displayed_chars: HashMap::new(),
+            overlay_rows: None,
+            closed: false,
         }
     }
 
+    /// Enables or disables ASCII mode.
+    ///
+    /// For terminals and fonts that cannot display styles (like faint
+    /// text) and colors, such as the Linux virtual console. In ASCII
+    /// mode:
+    /// - all text is displayed without styles and colors.
+    /// - the text to be typed is displayed with an empty line below
+    ///   each line. A `^` is shown in this line below the char to be
+    ///   typed next, along with markers added using
+    ///   [`Text::with_marker`] below other chars.
+    /// - the cursor is a steady block instead of a blinking bar.
+    pub fn with_ascii(mut self, ascii: bool) -> Self {
+        self.ascii = ascii;
+        self
+    }
+
+    /// Sets where the hints displayed using
+    /// [`ToipeTui::display_hud`] are placed.
+    pub fn with_hud(mut self, hud: HudPosition) -> Self {
+        self.hud = hud;
+        self
+    }
+
+    /// Enables or disables markers at the end of lines of words that
+    /// continue on the next line (see [`ToipeTui::display_words`]).
+    ///
+    /// Useful when the words are a passage, like a quote, where lines
+    /// are wrapped in the middle of a sentence.
+    pub fn with_wrap_markers(mut self, wrap_markers: bool) -> Self {
+        self.wrap_markers = wrap_markers;
+        self
+    }
+
+    /// Sets the maximum width of a line of words, in columns, so that
+    /// lines stay readable on very wide terminals.
+    ///
+    /// Lines are otherwise 40% of the width of the terminal.
+    pub fn with_max_columns(mut self, max_columns: u16) -> Self {
+        self.max_columns = max_columns;
+        self
+    }
+
+    /// Enables or disables streamer mode, where the top third of the
+    /// screen is left for a large readout of the speed and accuracy
+    /// (see [`ToipeTui::display_readout`]) and the text is centered
+    /// below it.
+    pub fn with_streamer(mut self, streamer: bool) -> Self {
+        self.streamer = streamer;
+        self
+    }
+
+    /// Sets the colors of the text.
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Colors of the text.
+    pub fn theme(&self) -> Theme {
+        self.theme
+    }
+
+    /// Width and height of the terminal, in columns and lines.
+    pub fn size(&self) -> MaybeError<(u16, u16)> {
+        Ok(self.terminal.size()?)
+    }
+
     pub fn reset(&mut self) {
         self.cursor_pos = CursorPos::new();
+        self.displayed_chars.clear();
+        self.markers.clear();
+        self.caret = None;
     }
 
     // TODO: make this private
-    /// Flushes stdout
+    /// Flushes the terminal
     pub fn flush(&mut self) -> MaybeError {
-        self.stdout.flush()?;
+        self.terminal.flush()?;
         Ok(())
     }
 
     /// Resets the TUI.
     ///
     /// Clears screen, moves cursor to the center and changes cursor to
-    /// a blinking bar.
+    /// a blinking bar (a steady block in ASCII mode).
     pub fn reset_screen(&mut self) -> MaybeError {
-        let (sizex, sizey) = terminal_size()?;
+        let (sizex, sizey) = self.terminal.size()?;
 
         write!(
-            self.stdout,
-            "{}{}{}",
+            self.terminal,
+            "{}{}",
             clear::All,
             cursor::Goto(sizex / 2, sizey / 2),
-            cursor::BlinkingBar
         )?;
+        self.displayed_lines.clear();
+        self.displayed_chars.clear();
+        self.overlay_rows = None;
+        if self.ascii {
+            write!(self.terminal, "{}", cursor::SteadyBlock)?;
+        } else {
+            write!(self.terminal, "{}", cursor::BlinkingBar)?;
+        }
         self.flush()?;
 
         Ok(())
@@ -291,25 +849,63 @@ impl ToipeTui {
     }
 
     /// Same as [`display_a_line`] but without the flush.
-    fn display_a_line_raw<T, U>(&mut self, text: U) -> MaybeError
+    fn display_a_line_raw<T>(&mut self, text: T) -> MaybeError
     where
-        U: AsRef<[T]>,
-        [T]: HasLength,
-        T: Display,
+        T: AsRef<[Text]>,
     {
         let len = text.as_ref().length() as u16;
-        write!(self.stdout, "{}", cursor::Left(len / 2),)?;
+        write!(self.terminal, "{}", cursor::Left(len / 2),)?;
+
+        for t in text.as_ref() {
+            self.display_raw_text(t)?;
+        }
+        write!(self.terminal, "{}", cursor::Left(len),)?;
+
+        Ok(())
+    }
+
+    /// Displays a line of text centered horizontally at the given
+    /// Y-position, without flushing.
+    fn display_a_line_at<T>(&mut self, text: T, y: u16) -> MaybeError
+    where
+        T: AsRef<[Text]>,
+    {
+        let (sizex, _) = self.terminal.size()?;
+        let len = text.as_ref().length() as u16;
+        let x = (sizex / 2).saturating_sub(len / 2).max(1);
+        self.display_a_line_from(text, x, y)
+    }
+
+    /// Displays a line of text ending at the right edge of the screen
+    /// at the given Y-position, without flushing.
+    fn display_a_line_right_at<T>(&mut self, text: T, y: u16) -> MaybeError
+    where
+        T: AsRef<[Text]>,
+    {
+        let (sizex, _) = self.terminal.size()?;
+        let len = text.as_ref().length() as u16;
+        let x = (sizex + 1).saturating_sub(len).max(1);
+        self.display_a_line_from(text, x, y)
+    }
+
+    /// Displays a line of text starting at the given position, without
+    /// flushing.
+    fn display_a_line_from<T>(&mut self, text: T, x: u16, y: u16) -> MaybeError
+    where
+        T: AsRef<[Text]>,
+    {
+        write!(self.terminal, "{}", cursor::Goto(x, y))?;
 
         // TODO: find a better way to enable this only in certain contexts
         if self.track_lines {
-            let (x, y) = self.stdout.cursor_pos()?;
-            self.cursor_pos.lines.push(LinePos { x, y, length: len });
+            let line_text: String = text.as_ref().iter().map(|t| t.text().as_str()).collect();
+            self.cursor_pos.lines.push(LinePos::new(x, y, &line_text));
         }
+        self.displayed_lines.insert(y, (x, text.as_ref().to_vec()));
 
         for t in text.as_ref() {
             self.display_raw_text(t)?;
         }
-        write!(self.stdout, "{}", cursor::Left(len),)?;
 
         Ok(())
     }
@@ -321,120 +917,294 @@ impl ToipeTui {
     ///
     /// - The lines are centered vertically and each line itself is
     ///   centered horizontally.
-    // Ref for this generic thingy: https://stackoverflow.com/a/50056925/11199009
-    // TODO: document the generic stuff
-    pub fn display_lines<T, U>(&mut self, lines: &[T]) -> MaybeError
+    pub fn display_lines<T>(&mut self, lines: &[T]) -> MaybeError
     where
-        T: AsRef<[U]>,
-        [U]: HasLength,
-        U: Display,
+        T: AsRef<[Text]>,
     {
-        let (sizex, sizey) = terminal_size()?;
+        let (_, sizey) = self.terminal.size()?;
+        self.display_lines_spaced(lines, 1, sizey / 2)
+    }
 
-        let line_offset = lines.len() as u16 / 2;
+    /// Same as [`display_lines`] but with `spacing - 1` empty lines
+    /// between each line, centered around the line at `center_y`.
+    fn display_lines_spaced<T>(&mut self, lines: &[T], spacing: u16, center_y: u16) -> MaybeError
+    where
+        T: AsRef<[Text]>,
+    {
+        let line_offset = lines.len() as u16 * spacing / 2;
 
         for (line_no, line) in lines.iter().enumerate() {
-            write!(
-                self.stdout,
-                "{}",
-                cursor::Goto(sizex / 2, sizey / 2 + (line_no as u16) - line_offset)
+            self.display_a_line_at(
+                line.as_ref(),
+                center_y + (line_no as u16) * spacing - line_offset,
             )?;
-            self.display_a_line_raw(line.as_ref())?;
         }
         self.flush()?;
 
         Ok(())
     }
 
-    /// Displays multiple lines of text at the bottom of the screen.
+    /// Displays hints at the top or the bottom of the screen, or not at
+    /// all, as set using [`ToipeTui::with_hud`]. Each line of hints ends
+    /// at the right edge of the screen.
     ///
-    /// See [`display_lines`] for more information.
-    pub fn display_lines_bottom<T, U>(&mut self, lines: &[T]) -> MaybeError
+    /// The lines taken by the hints are left out of the space for the
+    /// text. See [`display_lines`] for more information.
+    pub fn display_hud<T>(&mut self, lines: &[T]) -> MaybeError
     where
-        T: AsRef<[U]>,
-        [U]: HasLength,
-        U: Display,
+        T: AsRef<[Text]>,
     {
-        let (sizex, sizey) = terminal_size()?;
-
-        let line_offset = lines.len() as u16;
-        self.bottom_lines_len = lines.len();
+        let (_, sizey) = self.terminal.size()?;
+
+        let first_line_y = match self.hud {
+            HudPosition::Top => 2,
+            HudPosition::Bottom => (sizey - 1).saturating_sub(lines.len() as u16),
+            HudPosition::Off => {
+                self.hud_lines_len = 0;
+                return Ok(());
+            }
+        };
+        self.hud_lines_len = lines.len();
 
         for (line_no, line) in lines.iter().enumerate() {
-            write!(
-                self.stdout,
-                "{}",
-                cursor::Goto(sizex / 2, sizey - 1 + (line_no as u16) - line_offset)
-            )?;
-            self.display_a_line_raw(line.as_ref())?;
+            self.display_a_line_right_at(line.as_ref(), first_line_y + line_no as u16)?;
         }
         self.flush()?;
 
         Ok(())
     }
 
-    // TODO: document this
-    pub fn display_words(&mut self, words: &[String]) -> MaybeError<Vec<Text>> {
-        self.reset();
-        let mut current_len = 0;
-        let mut max_word_len = 0;
-        let mut line = Vec::new();
-        let mut lines = Vec::new();
-        let (terminal_width, terminal_height) = terminal_size()?;
+    /// Number of lines at the top of the screen left for the readout in
+    /// streamer mode (see [`ToipeTui::with_streamer`]).
+    fn readout_lines(&self, terminal_height: u16) -> u16 {
+        if self.streamer {
+            terminal_height / 3
+        } else {
+            0
+        }
+    }
+
+    /// Y-position of the middle of the text to be typed, which is below
+    /// the readout in streamer mode.
+    fn text_center_y(&self, terminal_height: u16) -> u16 {
+        let readout_lines = self.readout_lines(terminal_height);
+        readout_lines + (terminal_height - readout_lines) / 2
+    }
+
+    /// Number of terminal lines taken by each line of the text to be
+    /// typed, including the empty lines below it.
+    fn text_line_spacing(&self) -> u16 {
+        if self.ascii {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Maximum width of a line of words for the current terminal size.
+    fn max_words_width(&self, terminal_width: u16) -> u16 {
         // 40% of terminal width
-        let max_width = terminal_width * 2 / 5;
+        (terminal_width * 2 / 5).min(self.max_columns)
+    }
+
+    /// Length of the longest word that fits in a line of words for the
+    /// current terminal size, along with the space after it.
+    pub fn max_word_len(&self) -> MaybeError<usize> {
+        let (terminal_width, _) = self.terminal.size()?;
+        Ok((self.max_words_width(terminal_width) as usize).saturating_sub(1))
+    }
+
+    /// Splits words into lines that fit the given width.
+    ///
+    /// Each line is a slice of the words in it.
+    fn wrap_words(words: &[String], max_width: u16) -> Vec<&[String]> {
         const MAX_WORDS_PER_LINE: usize = 10;
-        // eprintln!("max width is {}", max_width);
 
-        for word in words {
-            max_word_len = std::cmp::max(max_word_len, word.len() + 1);
-            let new_len = current_len + word.len() as u16 + 1;
-            if line.len() < MAX_WORDS_PER_LINE && new_len <= max_width {
+        let mut lines = Vec::new();
+        let mut line_start = 0;
+        let mut current_len = 0;
+
+        for (i, word) in words.iter().enumerate() {
+            let new_len = current_len + str_width(word) as u16 + 1;
+            if i == line_start || (i - line_start < MAX_WORDS_PER_LINE && new_len <= max_width) {
                 // add to line
-                line.push(word.clone());
-                current_len += word.len() as u16 + 1
+                current_len = new_len;
             } else {
-                // add an extra space at the end of each line because
-                //  user will instinctively type a space after every word
-                //  (at least I did)
-                lines.push(Text::from(line.join(" ") + " ").with_faint());
-
-                // clear line
-                line = vec![word.clone()];
-                current_len = word.len() as u16 + 1;
+                lines.push(&words[line_start..i]);
+
+                // start a new line
+                line_start = i;
+                current_len = str_width(word) as u16 + 1;
             }
         }
 
         // last line wasn't added in loop
-        // last line doesn't have an extra space at the end
-        //   - the typing test stops as soon as the user types last char
-        //   - won't hang there waiting for user to type space
-        lines.push(Text::from(line.join(" ")).with_faint());
-
-        max_word_len = std::cmp::max(max_word_len + 1, MIN_LINE_WIDTH);
-        if lines.len() + self.bottom_lines_len + 2 > terminal_height as usize {
-            return Err(ToipeError::from(format!(
+        lines.push(&words[line_start..]);
+
+        lines
+    }
+
+    /// Splits words into pages such that each page fits on the screen
+    /// when displayed using [`ToipeTui::display_words`].
+    ///
+    /// Must be invoked after the hints are displayed, since they
+    /// take up space on the screen.
+    pub fn paginate_words(&self, words: &[String]) -> MaybeError<Vec<Vec<String>>> {
+        let (terminal_width, _) = self.terminal.size()?;
+        let max_lines = self.max_text_lines()?;
+
+        let pages = Self::wrap_words(words, self.max_words_width(terminal_width))
+            .chunks(max_lines)
+            .map(|lines| lines.concat())
+            .collect();
+
+        Ok(pages)
+    }
+
+    /// Splits words into pages of whole sentences (see
+    /// [`ends_sentence`]) such that each page fits on the screen when
+    /// displayed using [`ToipeTui::display_words`].
+    ///
+    /// A sentence that does not fit on a page by itself is split like in
+    /// [`ToipeTui::paginate_words`]. Must be invoked after the hints are
+    /// displayed, since they take up space on the screen.
+    pub fn paginate_sentences(&self, words: &[String]) -> MaybeError<Vec<Vec<String>>> {
+        let (terminal_width, _) = self.terminal.size()?;
+        let max_width = self.max_words_width(terminal_width);
+        let max_lines = self.max_text_lines()?;
+        let fits = |words: &[String]| Self::wrap_words(words, max_width).len() <= max_lines;
+
+        let mut pages = Vec::new();
+        let mut page: Vec<String> = Vec::new();
+        for sentence in words.split_inclusive(|word| ends_sentence(word)) {
+            let page_with_sentence = [page.as_slice(), sentence].concat();
+            if fits(&page_with_sentence) {
+                page = page_with_sentence;
+                continue;
+            }
+
+            if !page.is_empty() {
+                pages.push(std::mem::take(&mut page));
+            }
+            if fits(sentence) {
+                page = sentence.to_vec();
+            } else {
+                let mut sentence_pages = self.paginate_words(sentence)?;
+                // the rest of the sentence can share a page with the next
+                page = sentence_pages.pop().unwrap_or_default();
+                pages.extend(sentence_pages);
+            }
+        }
+        if !page.is_empty() {
+            pages.push(page);
+        }
+
+        Ok(pages)
+    }
+
+    /// Splits lines of code into pages such that each page fits on the
+    /// screen when displayed using [`ToipeTui::display_code`].
+    ///
+    /// Must be invoked after the hints are displayed, since they take up
+    /// space on the screen.
+    pub fn paginate_code(&self, lines: &[String]) -> MaybeError<Vec<Vec<String>>> {
+        let max_lines = self.max_text_lines()?;
+
+        Ok(lines.chunks(max_lines).map(|page| page.to_vec()).collect())
+    }
+
+    /// Maximum number of lines of text to be typed that fit on the
+    /// screen along with the hints, up to [`MAX_TEXT_LINES`].
+    fn max_text_lines(&self) -> MaybeError<usize> {
+        let (_, terminal_height) = self.terminal.size()?;
+        let spacing = self.text_line_spacing() as usize;
+        let reserved_lines = self.hud_lines_len + 2 + self.readout_lines(terminal_height) as usize;
+        let max_lines = (terminal_height as usize).saturating_sub(reserved_lines) / spacing;
+        if max_lines == 0 {
+            return Err(TerminalTooSmall(ToipeError::from(format!(
                 "Terminal height is too short! Toipe requires at least {} lines, got {} lines",
-                lines.len() + self.bottom_lines_len + 2,
+                reserved_lines + spacing,
                 terminal_height,
-            ))
+            )))
             .into());
-        } else if max_word_len > terminal_width as usize {
-            return Err(ToipeError::from(format!(
+        }
+
+        Ok(max_lines.min(MAX_TEXT_LINES))
+    }
+
+    /// Displays words as lines of text centered on the screen.
+    ///
+    /// Words are wrapped to 40% of the terminal width, up to the
+    /// maximum set by [`ToipeTui::with_max_columns`]. Returns the lines
+    /// displayed, which are the text to be typed.
+    ///
+    /// Returns an error if the words do not fit on the screen. Use
+    /// [`ToipeTui::paginate_words`] to split long texts.
+    pub fn display_words(&mut self, words: &[String]) -> MaybeError<Vec<Text>> {
+        self.reset();
+        let (terminal_width, terminal_height) = self.terminal.size()?;
+        let max_width = self.max_words_width(terminal_width);
+        // the longest word and the space after it, with a column to spare
+        let required_width = words
+            .iter()
+            .map(|word| str_width(word) + 2)
+            .max()
+            .unwrap_or(0);
+
+        let wrapped_lines = Self::wrap_words(words, max_width);
+        let num_lines = wrapped_lines.len();
+        let lines: Vec<Text> = wrapped_lines
+            .into_iter()
+            .enumerate()
+            .map(|(line_no, line)| {
+                if line_no + 1 < num_lines {
+                    // add an extra space at the end of each line because
+                    //  user will instinctively type a space after every word
+                    //  (at least I did)
+                    let line = self.theme.dim(Text::from(line.join(" ") + " "));
+                    if self.wrap_markers {
+                        line.with_continuation()
+                    } else {
+                        line
+                    }
+                } else {
+                    // last line doesn't have an extra space at the end
+                    //   - the typing test stops as soon as the user types last char
+                    //   - won't hang there waiting for user to type space
+                    self.theme.dim(Text::from(line.join(" ")))
+                }
+            })
+            .collect();
+
+        let spacing = self.text_line_spacing();
+        let required_height = lines.len() * spacing as usize
+            + self.hud_lines_len
+            + 2
+            + self.readout_lines(terminal_height) as usize;
+        if required_height > terminal_height as usize {
+            return Err(TerminalTooSmall(ToipeError::from(format!(
+                "Terminal height is too short! Toipe requires at least {} lines, got {} lines",
+                required_height, terminal_height,
+            )))
+            .into());
+        } else if required_width > terminal_width as usize {
+            return Err(TerminalTooSmall(ToipeError::from(format!(
                 "Terminal width is too low! Toipe requires at least {} columns, got {} columns",
-                max_word_len, terminal_width,
-            ))
+                required_width, terminal_width,
+            )))
             .into());
         }
 
         self.track_lines = true;
-        self.display_lines(
+        self.display_lines_spaced(
             lines
                 .iter()
                 .cloned()
                 .map(|line| [line])
                 .collect::<Vec<[Text; 1]>>()
                 .as_slice(),
+            spacing,
+            self.text_center_y(terminal_height),
         )?;
         self.track_lines = false;
 
@@ -444,25 +1214,426 @@ impl ToipeTui {
         Ok(lines)
     }
 
-    /// Displays a [`Text`].
-    pub fn display_raw_text<T>(&mut self, text: &T) -> MaybeError
+    /// Displays lines of code, keeping their indentation.
+    ///
+    /// The lines are aligned to the left of a block that is centered on
+    /// the screen. Each line except the last gets a space at its end, in
+    /// place of the newline to be typed. Returns the lines displayed,
+    /// which are the text to be typed.
+    ///
+    /// Returns an error if the lines do not fit on the screen. Use
+    /// [`ToipeTui::paginate_code`] to split long code.
+    pub fn display_code(&mut self, lines: &[String]) -> MaybeError<Vec<Text>> {
+        self.reset();
+        let (terminal_width, terminal_height) = self.terminal.size()?;
+
+        let num_lines = lines.len();
+        let lines: Vec<Text> = lines
+            .iter()
+            .enumerate()
+            .map(|(line_no, line)| {
+                if line_no + 1 < num_lines {
+                    self.theme.dim(Text::from(line.clone() + " "))
+                } else {
+                    self.theme.dim(Text::from(line.clone()))
+                }
+            })
+            .collect();
+
+        let max_line_len = lines.iter().map(|line| line.length()).max().unwrap_or(0);
+        let spacing = self.text_line_spacing();
+        let required_height = lines.len() * spacing as usize
+            + self.hud_lines_len
+            + 2
+            + self.readout_lines(terminal_height) as usize;
+        if required_height > terminal_height as usize {
+            return Err(TerminalTooSmall(ToipeError::from(format!(
+                "Terminal height is too short! Toipe requires at least {} lines, got {} lines",
+                required_height, terminal_height,
+            )))
+            .into());
+        } else if max_line_len + 2 > terminal_width as usize {
+            return Err(TerminalTooSmall(ToipeError::from(format!(
+                "Terminal width is too low! Toipe requires at least {} columns, got {} columns",
+                max_line_len + 2,
+                terminal_width,
+            )))
+            .into());
+        }
+
+        let x = (terminal_width / 2)
+            .saturating_sub(max_line_len as u16 / 2)
+            .max(1);
+        let line_offset = lines.len() as u16 * spacing / 2;
+        for (line_no, line) in lines.iter().enumerate() {
+            let y = self.text_center_y(terminal_height) + (line_no as u16) * spacing - line_offset;
+            write!(self.terminal, "{}", cursor::Goto(x, y))?;
+            self.cursor_pos.lines.push(LinePos::new(x, y, line.text()));
+            self.displayed_lines.insert(y, (x, vec![line.clone()]));
+            self.display_raw_text(line)?;
+        }
+
+        self.move_to_cur_pos()?;
+        self.flush()?;
+
+        Ok(lines)
+    }
+
+    /// Displays the speed in words per minute and the accuracy as a
+    /// percentage in large digits, centered in the top third of the
+    /// screen, in streamer mode (see [`ToipeTui::with_streamer`]).
+    ///
+    /// Replaces the readout displayed before. Nothing is displayed if
+    /// streamer mode is off or the readout does not fit.
+    pub fn display_readout(&mut self, wpm: f64, accuracy: f64) -> MaybeError {
+        let (_, sizey) = self.terminal.size()?;
+        let top_y = match self.hud {
+            HudPosition::Top => 2 + self.hud_lines_len as u16,
+            HudPosition::Bottom | HudPosition::Off => 1,
+        };
+        let bottom_y = self.readout_lines(sizey);
+        // the large digits and the labels below them
+        let height = BIG_TEXT_HEIGHT as u16 + 1;
+        if bottom_y < top_y + height {
+            return Ok(());
+        }
+
+        let block = if self.ascii { '#' } else { '█' };
+        let wpm_lines = big_text(&format!("{:.0}", wpm), block);
+        let accuracy_lines = big_text(&format!("{:.1}%", accuracy), block);
+        let gap = "      ";
+        let wpm_width = wpm_lines[0].chars().count();
+        let accuracy_width = accuracy_lines[0].chars().count();
+        let mut lines: Vec<Vec<Text>> = wpm_lines
+            .iter()
+            .zip(&accuracy_lines)
+            .map(|(wpm_line, accuracy_line)| {
+                vec![
+                    Text::from(wpm_line.as_str()).with_color(self.theme.accent),
+                    Text::from(gap),
+                    Text::from(accuracy_line.as_str()),
+                ]
+            })
+            .collect();
+        lines.push(vec![
+            self.theme
+                .dim(Text::from(format!("{:^width$}", "wpm", width = wpm_width))),
+            Text::from(gap),
+            self.theme.dim(Text::from(format!(
+                "{:^width$}",
+                "accuracy",
+                width = accuracy_width
+            ))),
+        ]);
+
+        let first_y = top_y + (bottom_y - top_y - height) / 2;
+        for y in top_y..bottom_y {
+            write!(
+                self.terminal,
+                "{}{}",
+                cursor::Goto(1, y),
+                clear::CurrentLine
+            )?;
+            self.displayed_lines.remove(&y);
+        }
+        for (line_no, line) in lines.iter().enumerate() {
+            self.display_a_line_at(line, first_y + line_no as u16)?;
+        }
+        self.flush()?;
+
+        Ok(())
+    }
+
+    /// Displays an annotation of the text being typed, like the meaning
+    /// of the current word, dimmed and centered above the text.
+    ///
+    /// Replaces the annotation displayed before, which is only cleared
+    /// if `annotation` is `None`. Nothing is displayed if there is no
+    /// space above the text.
+    pub fn display_annotation(&mut self, annotation: Option<&str>) -> MaybeError {
+        let text = annotation.map(|annotation| self.theme.dim(Text::from(annotation)));
+        self.display_above_text(text)
+    }
+
+    /// Displays the number of seconds left before the test starts,
+    /// centered above the text like an annotation.
+    ///
+    /// The countdown is cleared if `secs_left` is `None`.
+    pub fn display_countdown(&mut self, secs_left: Option<u64>) -> MaybeError {
+        let text = secs_left.map(|secs_left| {
+            Text::from(format!("starting in {}", secs_left)).with_color(self.theme.accent)
+        });
+        self.display_above_text(text)
+    }
+
+    /// Displays that the test is paused, centered above the text like an
+    /// annotation.
+    ///
+    /// The notice is cleared if `paused` is false.
+    pub fn display_paused(&mut self, paused: bool) -> MaybeError {
+        let text = paused.then(|| {
+            Text::from("paused, press any key to resume or ctrl-c to quit")
+                .with_color(self.theme.accent)
+        });
+        self.display_above_text(text)
+    }
+
+    /// Displays a line two lines above the first line of the text,
+    /// replacing the line displayed there before. The line is cleared if
+    /// `text` is `None`.
+    fn display_above_text(&mut self, text: Option<Text>) -> MaybeError {
+        let first_line_y = match self.cursor_pos.lines.first() {
+            Some(line) => line.y,
+            None => return Ok(()),
+        };
+        let hud_end_y = match self.hud {
+            HudPosition::Top => 1 + self.hud_lines_len as u16,
+            HudPosition::Bottom | HudPosition::Off => 0,
+        };
+        // leave an empty line between the annotation and the text
+        let y = match first_line_y.checked_sub(2) {
+            Some(y) if y > hud_end_y => y,
+            _ => return Ok(()),
+        };
+
+        write!(
+            self.terminal,
+            "{}{}",
+            cursor::Goto(1, y),
+            clear::CurrentLine
+        )?;
+        self.displayed_lines.remove(&y);
+        if let Some(text) = text {
+            self.display_a_line_at([text], y)?;
+        }
+        self.move_to_cur_pos()?;
+        self.flush()?;
+
+        Ok(())
+    }
+
+    /// Displays lines in a box in the middle of the screen, over what is
+    /// displayed there, like a list of keyboard shortcuts.
+    ///
+    /// The lines are aligned to the left inside the box. Use
+    /// [`ToipeTui::hide_overlay`] to restore the screen under the box.
+    pub fn display_overlay<T>(&mut self, lines: &[T]) -> MaybeError
     where
-        T: Display,
+        T: AsRef<[Text]>,
     {
-        write!(self.stdout, "{}", text)?;
+        let (sizex, sizey) = self.terminal.size()?;
+        let [top_left, top_right, bottom_left, bottom_right, horizontal, vertical] = if self.ascii {
+            ASCII_OVERLAY_BORDER
+        } else {
+            OVERLAY_BORDER
+        };
+
+        // a space on either side of the lines
+        let inner_width = lines
+            .iter()
+            .map(|line| line.as_ref().length())
+            .max()
+            .unwrap_or(0)
+            + 2;
+        let height = lines.len() as u16 + 2;
+        let x = (sizex / 2)
+            .saturating_sub((inner_width as u16 + 2) / 2)
+            .max(1);
+        let y = (sizey / 2).saturating_sub(height / 2).max(1);
+        let edge = horizontal.to_string().repeat(inner_width);
+
+        write!(
+            self.terminal,
+            "{}{}{}{}{}",
+            cursor::Save,
+            cursor::Goto(x, y),
+            top_left,
+            edge,
+            top_right
+        )?;
+        for (line_no, line) in lines.iter().enumerate() {
+            write!(
+                self.terminal,
+                "{}{} ",
+                cursor::Goto(x, y + 1 + line_no as u16),
+                vertical
+            )?;
+            for text in line.as_ref() {
+                self.display_raw_text(text)?;
+            }
+            let padding = inner_width - 1 - line.as_ref().length();
+            write!(self.terminal, "{}{}", " ".repeat(padding), vertical)?;
+        }
+        write!(
+            self.terminal,
+            "{}{}{}{}",
+            cursor::Goto(x, y + height - 1),
+            bottom_left,
+            edge,
+            bottom_right
+        )?;
+        self.overlay_rows = Some(y..y + height);
+        self.flush()?;
+
+        Ok(())
+    }
+
+    /// Hides the overlay displayed using [`ToipeTui::display_overlay`],
+    /// redisplaying the lines that were under it.
+    ///
+    /// Only what is under the overlay is redisplayed: the lines displayed
+    /// there, the chars of the text displayed since, like the ones
+    /// typed, and their markers in ASCII mode. The cursor is moved back
+    /// to where it was.
+    pub fn hide_overlay(&mut self) -> MaybeError {
+        let rows = match self.overlay_rows.take() {
+            Some(rows) => rows,
+            None => return Ok(()),
+        };
+
+        self.redisplay_rows(rows)?;
+        write!(self.terminal, "{}", cursor::Restore)?;
+        self.flush()?;
+
+        Ok(())
+    }
+
+    /// Suspends the program (see [`Terminal::suspend`]), clearing the
+    /// screen for the shell, and redisplays everything once it is
+    /// continued.
+    ///
+    /// What is redisplayed is the same as for
+    /// [`ToipeTui::hide_overlay`], over the whole screen.
+    pub fn suspend(&mut self) -> MaybeError {
+        write!(
+            self.terminal,
+            "{}{}{}",
+            clear::All,
+            cursor::SteadyBlock,
+            cursor::Goto(1, 1)
+        )?;
+        self.flush()?;
+
+        self.terminal.suspend()?;
+
+        self.redisplay_all()
+    }
+
+    /// Whether the program was stopped from outside and continued since
+    /// this was last called (see [`Terminal::take_continued`]), in which
+    /// case everything is redisplayed like after
+    /// [`ToipeTui::suspend`], since the shell may have written over it.
+    pub fn take_continued(&mut self) -> MaybeError<bool> {
+        if !self.terminal.take_continued()? {
+            return Ok(false);
+        }
+        self.redisplay_all()?;
+        Ok(true)
+    }
+
+    /// Clears the screen and redisplays everything on it.
+    fn redisplay_all(&mut self) -> MaybeError {
+        let (_, sizey) = self.terminal.size()?;
+        write!(self.terminal, "{}", clear::All)?;
+        if !self.ascii {
+            write!(self.terminal, "{}", cursor::BlinkingBar)?;
+        }
+        self.overlay_rows = None;
+        self.redisplay_rows(1..sizey + 1)?;
+        self.move_to_cur_pos()?;
+        self.flush()?;
+
+        Ok(())
+    }
+
+    /// Redisplays the lines, chars of the text and ASCII markers that
+    /// were displayed on the given rows.
+    fn redisplay_rows(&mut self, rows: Range<u16>) -> MaybeError {
+        for y in rows.clone() {
+            write!(
+                self.terminal,
+                "{}{}",
+                cursor::Goto(1, y),
+                clear::CurrentLine
+            )?;
+            if let Some((x, line)) = self.displayed_lines.get(&y).cloned() {
+                write!(self.terminal, "{}", cursor::Goto(x, y))?;
+                for text in &line {
+                    self.display_raw_text(text)?;
+                }
+            }
+        }
+        let chars: Vec<((u16, u16), Text)> = self
+            .displayed_chars
+            .iter()
+            .filter(|((_, y), _)| rows.contains(y))
+            .map(|(&pos, text)| (pos, text.clone()))
+            .collect();
+        for ((x, y), text) in chars {
+            write!(self.terminal, "{}", cursor::Goto(x, y))?;
+            self.display_raw_text(&text)?;
+        }
+        if self.ascii {
+            // markers are displayed on the line below their char
+            let marked: Vec<(u16, u16)> = self
+                .markers
+                .keys()
+                .copied()
+                .chain(self.caret)
+                .filter(|&(_, y)| rows.contains(&(y + 1)))
+                .collect();
+            for pos in marked {
+                self.display_marker(pos)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Displays a [`Text`].
+    ///
+    /// Styles and colors are left out in ASCII mode.
+    pub fn display_raw_text(&mut self, text: &Text) -> MaybeError {
+        if self.ascii {
+            write!(self.terminal, "{}", text.text())?;
+        } else {
+            write!(self.terminal, "{}", text)?;
+        }
+
+        if text.continued && self.ascii {
+            write!(self.terminal, "{}", ASCII_CONTINUATION_MARKER)?;
+        } else if text.continued {
+            write!(
+                self.terminal,
+                "{}",
+                self.theme.dim(Text::from(CONTINUATION_MARKER))
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Displays the text in place of the char to be typed next and moves
+    /// the cursor to the next char.
+    ///
+    /// NOTE: only call this with [`Text`]s containing one character.
+    pub fn display_char(&mut self, text: &Text) -> MaybeError {
+        self.record_char(self.cursor_pos.cur_pos(), text);
+        self.display_raw_text(text)?;
+        self.move_to_next_char()?;
+
         Ok(())
     }
 
     /// Hides the cursor.
     pub fn hide_cursor(&mut self) -> MaybeError {
-        write!(self.stdout, "{}", cursor::Hide)?;
+        write!(self.terminal, "{}", cursor::Hide)?;
         self.flush()?;
         Ok(())
     }
 
     /// Shows the cursor.
     pub fn show_cursor(&mut self) -> MaybeError {
-        write!(self.stdout, "{}", cursor::Show)?;
+        write!(self.terminal, "{}", cursor::Show)?;
         self.flush()?;
         Ok(())
     }
@@ -475,69 +1646,286 @@ impl ToipeTui {
     ///
     /// The text is described by a slice of [`Text`].
     // TODO: enforce single character constrainst in compile time
-    pub fn replace_text<T>(&mut self, text: T) -> MaybeError
-    where
-        T: Display,
-    {
+    pub fn replace_text(&mut self, text: Text) -> MaybeError {
         self.move_to_prev_char()?;
+        self.record_char(self.cursor_pos.cur_pos(), &text);
         self.display_raw_text(&text)?;
         self.move_to_cur_pos()?;
 
         Ok(())
     }
 
-    /// Moves the cursor to the next char
-    pub fn move_to_next_char(&mut self) -> MaybeError {
-        let (x, y) = self.cursor_pos.next();
-        write!(self.stdout, "{}", cursor::Goto(x, y))?;
+    /// Displays texts in place of consecutive chars, starting `before`
+    /// chars previous to the cursor, without moving the cursor.
+    ///
+    /// NOTE: only call this with [`Text`]s containing one character.
+    pub fn redisplay_chars(&mut self, before: usize, texts: &[Text]) -> MaybeError {
+        let (cur_line, cur_char_in_line) =
+            (self.cursor_pos.cur_line, self.cursor_pos.cur_char_in_line);
+
+        for _ in 0..before {
+            self.cursor_pos.prev();
+        }
+        for text in texts {
+            let pos = self.cursor_pos.cur_pos();
+            write!(self.terminal, "{}", cursor::Goto(pos.0, pos.1))?;
+            self.record_char(pos, text);
+            self.display_raw_text(text)?;
+            if self.ascii {
+                self.display_marker(pos)?;
+            }
+            self.cursor_pos.next();
+        }
+
+        self.cursor_pos.cur_line = cur_line;
+        self.cursor_pos.cur_char_in_line = cur_char_in_line;
+        self.move_to_cur_pos()?;
 
         Ok(())
     }
 
-    /// Moves the cursor to the previous char
-    pub fn move_to_prev_char(&mut self) -> MaybeError {
-        let (x, y) = self.cursor_pos.prev();
-        write!(self.stdout, "{}", cursor::Goto(x, y))?;
+    /// Displays the text in place of the char at the given index in the
+    /// text, without moving the cursor.
+    ///
+    /// NOTE: only call this with [`Text`]s containing one character.
+    pub fn display_char_at(&mut self, index: usize, text: &Text) -> MaybeError {
+        let pos = match self.cursor_pos.pos_of(index) {
+            Some(pos) => pos,
+            None => return Ok(()),
+        };
+
+        write!(self.terminal, "{}", cursor::Goto(pos.0, pos.1))?;
+        self.record_char(pos, text);
+        self.display_raw_text(text)?;
+        if self.ascii {
+            self.display_marker(pos)?;
+        }
+        self.move_to_cur_pos()?;
 
         Ok(())
     }
 
-    /// Moves the cursor to just before the character to be typed next
-    pub fn move_to_cur_pos(&mut self) -> MaybeError {
-        let (x, y) = self.cursor_pos.cur_pos();
-        write!(self.stdout, "{}", cursor::Goto(x, y))?;
+    /// Records a char of the text displayed at the given position, for
+    /// redisplaying it after an overlay, along with its marker in ASCII
+    /// mode.
+    fn record_char(&mut self, pos: (u16, u16), text: &Text) {
+        self.displayed_chars.insert(pos, text.clone());
+        if !self.ascii {
+            return;
+        }
+
+        match text.marker {
+            Some(marker) => self.markers.insert(pos, marker),
+            None => self.markers.remove(&pos),
+        };
+    }
+
+    /// Displays the marker below the char at the given position in
+    /// ASCII mode.
+    fn display_marker(&mut self, (x, y): (u16, u16)) -> MaybeError {
+        let marker = if self.caret == Some((x, y)) {
+            CARET_MARKER
+        } else {
+            *self.markers.get(&(x, y)).unwrap_or(&' ')
+        };
+        write!(self.terminal, "{}{}", cursor::Goto(x, y + 1), marker)?;
 
         Ok(())
     }
 
+    /// Moves the cursor to the given position, moving the caret marker
+    /// along with it in ASCII mode.
+    fn move_to(&mut self, (x, y): (u16, u16)) -> MaybeError {
+        if self.ascii {
+            if let Some(prev_caret) = self.caret.replace((x, y)) {
+                self.display_marker(prev_caret)?;
+            }
+            self.display_marker((x, y))?;
+        }
+        write!(self.terminal, "{}", cursor::Goto(x, y))?;
+
+        Ok(())
+    }
+
+    /// Moves the cursor to the next char
+    pub fn move_to_next_char(&mut self) -> MaybeError {
+        let pos = self.cursor_pos.next();
+        self.move_to(pos)
+    }
+
+    /// Moves the cursor to the previous char
+    pub fn move_to_prev_char(&mut self) -> MaybeError {
+        let pos = self.cursor_pos.prev();
+        self.move_to(pos)
+    }
+
+    /// Moves the cursor to just before the character to be typed next
+    pub fn move_to_cur_pos(&mut self) -> MaybeError {
+        let pos = self.cursor_pos.cur_pos();
+        self.move_to(pos)
+    }
+
     /// Returns the current line the cursor is on
     pub fn current_line(&self) -> usize {
         self.cursor_pos.cur_line
     }
-}
-
-impl Default for ToipeTui {
-    fn default() -> Self {
-        Self::new()
-    }
-}
 
-impl Drop for ToipeTui {
-    /// Resets terminal.
+    /// Resets the terminal and gives it back to the shell (see
+    /// [`Terminal::restore`]), so that other output can be printed.
     ///
-    /// Clears screen and sets the cursor to a non-blinking block.
+    /// Clears screen and sets the cursor to a non-blinking block. The
+    /// terminal is restored even if it cannot be cleared, and the first
+    /// error is returned. Does nothing if the TUI was closed already.
+    /// Otherwise, it is closed when dropped, but errors are only logged
+    /// then.
     ///
     /// TODO: print error message when terminal height/width is too small.
     /// Take a look at https://github.com/Samyak2/toipe/pull/28#discussion_r851784291 for more info.
-    fn drop(&mut self) {
-        write!(
-            self.stdout,
+    pub fn close(&mut self) -> MaybeError {
+        if self.closed {
+            return Ok(());
+        }
+        // the terminal is restored even if it could not be cleared, and
+        // not touched again when dropped
+        self.closed = true;
+        let cleared = write!(
+            self.terminal,
             "{}{}{}",
             clear::All,
             cursor::SteadyBlock,
             cursor::Goto(1, 1)
         )
-        .expect("Could not reset terminal while exiting");
-        self.flush().expect("Could not flush stdout while exiting");
+        .and_then(|()| self.terminal.flush());
+        let restored = self.terminal.restore();
+        cleared?;
+        restored?;
+
+        Ok(())
+    }
+}
+
+impl Default for ToipeTui {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for ToipeTui {
+    /// Closes the TUI if [`ToipeTui::close`] was not called, ignoring
+    /// any error in doing so.
+    fn drop(&mut self) {
+        if let Err(error) = self.close() {
+            log::warn!("could not reset the terminal: {}", error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn passages_are_shown_a_screen_of_sentences_at_a_time() {
+        let words: Vec<String> = (1..=40)
+            .map(|n| {
+                format!(
+                    "Sentence number {} has{} more words in it.",
+                    n,
+                    " a few".repeat(n % 4)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+            .split(' ')
+            .map(String::from)
+            .collect();
+        let tui = ToipeTui::from_terminal(FakeTerminal::new(100, 30));
+
+        let pages = tui.paginate_sentences(&words).unwrap();
+        assert!(pages.len() > 1);
+        assert_eq!(pages.concat(), words);
+        for page in &pages {
+            assert_eq!(page[0], "Sentence");
+            assert!(page.last().unwrap().ends_with('.'), "{:?}", page);
+            assert_eq!(tui.paginate_words(page).unwrap().len(), 1);
+        }
+        // the same words split by the screen alone end mid-sentence
+        assert_ne!(tui.paginate_words(&words).unwrap(), pages);
+
+        // sentences longer than a screen are split
+        let long_sentence = vec!["word".to_string(); 1000];
+        let pages = tui.paginate_sentences(&long_sentence).unwrap();
+        assert_eq!(pages, tui.paginate_words(&long_sentence).unwrap());
+    }
+
+    #[test]
+    fn overlay_restores_the_typed_text_under_it() {
+        for ascii in [false, true] {
+            let terminal = FakeTerminal::new(100, 30);
+            let mut tui = ToipeTui::from_terminal(terminal.clone()).with_ascii(ascii);
+            let words: Vec<String> = ["the", "quick", "brown", "fox"]
+                .iter()
+                .map(|word| word.to_string())
+                .collect();
+            tui.display_words(&words).unwrap();
+            tui.display_char(&Text::from('t')).unwrap();
+            tui.display_char(&Text::from('x').with_marker('x')).unwrap();
+            let screen = terminal.screen();
+
+            tui.display_overlay(&[[Text::from("an overlay over the words")]])
+                .unwrap();
+            assert_ne!(terminal.screen(), screen);
+            tui.hide_overlay().unwrap();
+
+            assert_eq!(terminal.screen(), screen, "ascii: {}", ascii);
+        }
+    }
+
+    /// Terminal that fails every write, counting how many times it was
+    /// restored.
+    struct BrokenTerminal {
+        restores: Rc<RefCell<usize>>,
+    }
+
+    impl Write for BrokenTerminal {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "terminal is gone",
+            ))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Terminal for BrokenTerminal {
+        fn size(&self) -> io::Result<(u16, u16)> {
+            Ok((100, 30))
+        }
+
+        fn restore(&mut self) -> io::Result<()> {
+            *self.restores.borrow_mut() += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn terminal_is_restored_once_when_closing_fails() {
+        let restores = Rc::new(RefCell::new(0));
+        let mut tui = ToipeTui::from_terminal(BrokenTerminal {
+            restores: Rc::clone(&restores),
+        });
+
+        // errors are returned by close, after restoring the terminal anyway
+        assert!(tui.close().is_err());
+        assert_eq!(*restores.borrow(), 1);
+        // and it is not closed again when dropped
+        assert!(tui.close().is_ok());
+        drop(tui);
+        assert_eq!(*restores.borrow(), 1);
     }
 }
@@ -1,4 +1,11 @@
 //! Built-in wordlists, system wordlist and utils for retrieving them.
+//!
+//! Built-in word lists are grouped by [`Language`]. See
+//! [`Language::wordlists`] for the ones available in each language.
+use std::env;
+use std::path::PathBuf;
+
+#[cfg(feature = "cli")]
 use clap::ArgEnum;
 use include_flate::flate;
 
@@ -11,10 +18,13 @@ flate!(static TOP_10000: str        from "src/word_lists/top10000");
 flate!(static TOP_25000: str        from "src/word_lists/top25000");
 flate!(static TOP_MISSPELLED: str   from "src/word_lists/commonly_misspelled");
 
+flate!(static QUOTES: str           from "src/quotes/english");
+
 /// Word lists with top English words.
 ///
 /// See [variants](#variants) for details on each word list.
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ArgEnum, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "cli", derive(ArgEnum))]
 pub enum BuiltInWordlist {
     /// Source: [wordfrequency.info](https://www.wordfrequency.info/samples.asp) (top 60K lemmas sample).
     Top250,
@@ -50,7 +60,9 @@ pub enum BuiltInWordlist {
 }
 
 impl BuiltInWordlist {
-    /// Contents of the word list as a static string.
+    /// Contents of the English word list as a static string.
+    ///
+    /// See [`Language::wordlist_contents`] for other languages.
     ///
     /// Note: BuiltInWordlist::OS returns a None since we only know the path of it.
     /// Reading the file can take time (and memory) as the file can be large.
@@ -69,9 +81,131 @@ impl BuiltInWordlist {
     }
 }
 
+/// Languages that built-in word lists are available in.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "cli", derive(ArgEnum))]
+pub enum Language {
+    /// English.
+    En,
+}
+
+/// Built-in word lists of each language, with the default one first.
+const REGISTRY: &[(Language, &[BuiltInWordlist])] = &[(
+    Language::En,
+    &[
+        BuiltInWordlist::Top250,
+        BuiltInWordlist::Top500,
+        BuiltInWordlist::Top1000,
+        BuiltInWordlist::Top2500,
+        BuiltInWordlist::Top5000,
+        BuiltInWordlist::Top10000,
+        BuiltInWordlist::Top25000,
+        BuiltInWordlist::CommonlyMisspelled,
+    ],
+)];
+
+impl Language {
+    /// All the languages, in order.
+    pub fn all() -> impl Iterator<Item = Language> {
+        REGISTRY.iter().map(|(language, _)| *language)
+    }
+
+    /// Short code of the language, like `en`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::En => "en",
+        }
+    }
+
+    /// Built-in word lists available in the language, with the default
+    /// one first.
+    pub fn wordlists(&self) -> &'static [BuiltInWordlist] {
+        REGISTRY
+            .iter()
+            .find(|(language, _)| language == self)
+            .map_or(&[], |(_, wordlists)| wordlists)
+    }
+
+    /// Word list used when none is given.
+    pub fn default_wordlist(&self) -> BuiltInWordlist {
+        self.wordlists()[0]
+    }
+
+    /// Contents of the word list in the language, or `None` if it is not
+    /// available in the language.
+    ///
+    /// Like [`BuiltInWordlist::contents`], this is `None` for
+    /// [`BuiltInWordlist::OS`].
+    pub fn wordlist_contents(&self, wordlist: BuiltInWordlist) -> Option<&'static str> {
+        if !self.wordlists().contains(&wordlist) {
+            return None;
+        }
+        match self {
+            Self::En => wordlist.contents(),
+        }
+    }
+}
+
+/// Name of a built-in word list in a language, like `en/top250`.
+#[cfg(feature = "cli")]
+pub fn wordlist_name(language: Language, wordlist: BuiltInWordlist) -> String {
+    format!(
+        "{}/{}",
+        language.code(),
+        wordlist
+            .to_possible_value()
+            .map(|value| value.get_name())
+            .unwrap_or("unknown")
+    )
+}
+
 /// Path to the default word list file in Linux/Unix-based systems.
 ///
 /// Note: the OS word list varies a lot from system to system and usually
 /// has more than 100,000 words. This can lead to difficult and esoteric
 /// words appearing in the test, reducing your typing speed.
 pub const OS_WORDLIST_PATH: &str = "/usr/share/dict/words";
+
+/// Path of the cached index of the OS word list (see
+/// [`crate::textgen::RawWordSelector::from_path_with_index_cache`]).
+///
+/// This is `toipe/os-words-index` under `$XDG_CACHE_HOME`, or under
+/// `~/.cache` if that is not set. Returns `None` if neither variable is
+/// set.
+pub fn os_wordlist_index_path() -> Option<PathBuf> {
+    let cache_dir = env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("HOME")
+                .filter(|home| !home.is_empty())
+                .map(|home| PathBuf::from(home).join(".cache"))
+        })?;
+
+    Some(cache_dir.join("toipe").join("os-words-index"))
+}
+
+/// Built-in collection of English quotes, one per line.
+///
+/// Quotes are from public domain works and speeches, and only use
+/// **ASCII** characters.
+pub fn quotes() -> &'static str {
+    &QUOTES
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry() {
+        for language in Language::all() {
+            assert!(!language.wordlists().is_empty());
+            for &wordlist in language.wordlists() {
+                assert!(language.wordlist_contents(wordlist).is_some());
+            }
+            assert!(language.wordlist_contents(BuiltInWordlist::OS).is_none());
+        }
+        assert_eq!(Language::En.default_wordlist(), BuiltInWordlist::Top250);
+    }
+}
@@ -0,0 +1,1472 @@
+//! End-to-end typing tests on a fake terminal with scripted keys,
+//! covering combinations of modes.
+
+#![cfg(feature = "cli")]
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Read};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use clap::StructOpt;
+use termion::event::Key;
+use toipe::config::ToipeConfig;
+use toipe::end_condition::{Signal, WordCount};
+use toipe::export::ExportedResults;
+use toipe::input::KeyEvents;
+use toipe::results::ToipeResults;
+use toipe::session::ToipeSession;
+use toipe::textgen::{FixedWordSelector, RawWordSelector};
+use toipe::tui::{FakeTerminal, ToipeTui};
+use toipe::{certificate, commands, Toipe};
+
+const CTRL_C: &str = "\x03";
+const BACKSPACE: &str = "\x7f";
+const ESC: &str = "\x1b";
+const CTRL_Z: &str = "\x1a";
+
+/// Text with chars that are not ASCII but take a single column.
+const UNICODE_TEXT: &str = "naïve café über jalapeño déjà vu façade smörgåsbord";
+
+/// A step of a key script.
+enum Step {
+    Type(String),
+    Wait(Duration),
+}
+
+/// Key presses typed in order, with pauses in between.
+///
+/// Reading from the script blocks during pauses, like a person who
+/// stopped typing.
+struct KeyScript {
+    steps: VecDeque<Step>,
+    /// bytes of the keys being typed that were not read yet
+    pending: VecDeque<u8>,
+}
+
+impl KeyScript {
+    fn new(steps: Vec<Step>) -> Self {
+        Self {
+            steps: steps.into(),
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl Read for KeyScript {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pending.is_empty() {
+            match self.steps.pop_front() {
+                None => return Ok(0),
+                Some(Step::Wait(duration)) => thread::sleep(duration),
+                Some(Step::Type(keys)) => self.pending.extend(keys.bytes()),
+            }
+        }
+
+        self.pending.read(buf)
+    }
+}
+
+/// Modes that can be combined in a typing test.
+#[derive(Clone, Copy, Debug)]
+struct Modes {
+    punctuation: bool,
+    timed: bool,
+    confirm_words: bool,
+    ascii: bool,
+    unicode: bool,
+    hardcore: bool,
+}
+
+impl Modes {
+    fn all() -> Vec<Modes> {
+        (0..64)
+            .map(|bits| Modes {
+                punctuation: bits & 1 != 0,
+                timed: bits & 2 != 0,
+                confirm_words: bits & 4 != 0,
+                ascii: bits & 8 != 0,
+                unicode: bits & 16 != 0,
+                hardcore: bits & 32 != 0,
+            })
+            .collect()
+    }
+
+    fn args(&self) -> Vec<&'static str> {
+        let mut args = vec!["-n", "10"];
+        if self.punctuation {
+            args.push("-p");
+        }
+        if self.timed {
+            args.extend(["-t", "1"]);
+        }
+        if self.confirm_words {
+            args.push("--confirm-words");
+        }
+        if self.ascii {
+            args.push("--ascii");
+        }
+        if self.unicode {
+            args.extend(["--text", UNICODE_TEXT]);
+        }
+        if self.hardcore {
+            args.push("--hardcore");
+        }
+        args
+    }
+
+    /// Whether the flags for these modes are accepted together.
+    fn valid(&self) -> bool {
+        // punctuation is only added to words from a word list, and
+        // confirmed words are corrected as a whole
+        !(self.punctuation && self.unicode || self.confirm_words && self.hardcore)
+    }
+}
+
+/// Parses the args given after `toipe`, with the dark theme whose
+/// colors are checked in the output.
+fn parse_args(args: &[&str]) -> clap::Result<ToipeConfig> {
+    ToipeConfig::try_parse_from(["toipe", "--theme", "dark"].iter().chain(args))
+}
+
+/// Toipe parsing the given args, on a fake terminal of 100x30 that is
+/// returned to check the screen.
+fn toipe_with(args: &[&str]) -> (Toipe, FakeTerminal) {
+    let config = parse_args(args).unwrap_or_else(|err| panic!("{:?}: {}", args, err));
+    let terminal = FakeTerminal::new(100, 30);
+    let toipe = Toipe::with_tui(config, ToipeTui::from_terminal(terminal.clone()))
+        .unwrap_or_else(|err| panic!("{:?}: {}", args, err));
+    (toipe, terminal)
+}
+
+/// Keys typed by a script.
+fn script(steps: Vec<Step>) -> KeyEvents {
+    KeyEvents::from_reader(KeyScript::new(steps))
+}
+
+/// Path in the temp dir unique to this process, ending with `name`.
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("toipe-{}-{}", std::process::id(), name))
+}
+
+/// Text to be typed, as displayed on the screen.
+fn text_on_screen(terminal: &FakeTerminal) -> String {
+    terminal
+        .screen()
+        .iter()
+        // continuation markers of wrapped lines
+        .map(|line| line.trim().trim_end_matches(['↩', '\\']).trim_end())
+        // hints and the caret marker in ASCII mode
+        .filter(|line| !line.is_empty() && !line.contains("ctrl-r") && *line != "^")
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Runs one typing test in the given modes with keys from the script
+/// made for the displayed text.
+///
+/// Returns the results, the text and the screen after the test.
+fn run_test<F>(modes: Modes, steps: F) -> (ToipeResults, String, String)
+where
+    F: FnOnce(&str) -> Vec<Step>,
+{
+    let (mut toipe, terminal) = toipe_with(&modes.args());
+
+    let text = text_on_screen(&terminal);
+    let mut keys = script(steps(&text));
+    let (to_restart, results) = toipe
+        .test(&mut keys)
+        .unwrap_or_else(|err| panic!("{:?}: {}", modes, err));
+    assert!(!to_restart, "{:?}", modes);
+
+    (results, text, terminal.screen().join("\n"))
+}
+
+/// Runs a test in the given modes, typing one wrong char that is
+/// corrected right away, or left in place of the first char in
+/// hardcore mode, where it cannot be corrected.
+fn check_scenario(modes: Modes) {
+    // chars of the text typed after the wrong char
+    let skipped = usize::from(modes.hardcore);
+    let (results, text, screen) = if modes.timed {
+        run_test(modes, |text| {
+            let half: String = text
+                .chars()
+                .take(text.chars().count() / 2)
+                .skip(skipped)
+                .collect();
+            vec![
+                Step::Type("#".to_string()),
+                Step::Type(BACKSPACE.to_string()),
+                Step::Type(half),
+                Step::Wait(Duration::from_millis(1500)),
+                Step::Type(CTRL_C.to_string()),
+            ]
+        })
+    } else {
+        run_test(modes, |text| {
+            // the last word is confirmed too
+            let space = if modes.confirm_words { " " } else { "" };
+            let rest: String = text.chars().skip(skipped).collect();
+            vec![
+                Step::Type("#".to_string()),
+                Step::Type(BACKSPACE.to_string()),
+                Step::Type(rest + space),
+                Step::Type(CTRL_C.to_string()),
+            ]
+        })
+    };
+
+    assert!(results.completed, "{:?}", modes);
+    assert_eq!(results.total_char_errors, 1, "{:?}", modes);
+    assert_eq!(results.final_uncorrected_errors, skipped, "{:?}", modes);
+    assert_eq!(results.attempted_corrections, skipped, "{:?}", modes);
+    let num_typed = if modes.timed {
+        text.chars().count() / 2
+    } else {
+        text.chars().count()
+    };
+    assert_eq!(results.total_chars_in_text, num_typed, "{:?}", modes);
+    assert_eq!(
+        results.final_chars_typed_correctly,
+        num_typed - skipped,
+        "{:?}",
+        modes
+    );
+
+    // the results screen is shown
+    assert!(screen.contains("Accuracy: "), "{:?}: {}", modes, screen);
+    if modes.unicode {
+        assert_eq!(text, UNICODE_TEXT, "{:?}", modes);
+    }
+}
+
+#[test]
+fn invalid_mode_combinations_are_rejected() {
+    for modes in Modes::all() {
+        assert_eq!(
+            parse_args(&modes.args()).is_ok(),
+            modes.valid(),
+            "{:?}",
+            modes
+        );
+    }
+}
+
+#[test]
+fn untimed_scenarios() {
+    for modes in Modes::all() {
+        if modes.valid() && !modes.timed {
+            check_scenario(modes);
+        }
+    }
+}
+
+#[test]
+fn timed_scenarios() {
+    // each test takes a second, so run them at the same time
+    let handles: Vec<_> = Modes::all()
+        .into_iter()
+        .filter(|modes| modes.valid() && modes.timed)
+        .map(|modes| thread::spawn(move || check_scenario(modes)))
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+#[test]
+fn restart_and_quit() {
+    let modes = Modes::all()[0];
+    let (mut toipe, terminal) = toipe_with(&modes.args());
+
+    // restart in the middle of a test
+    let first_text = text_on_screen(&terminal);
+    let mut keys = script(vec![
+        Step::Type(first_text[..5].to_string()),
+        Step::Type("\x12".to_string()),
+    ]);
+    let (to_restart, results) = toipe.test(&mut keys).unwrap();
+    assert!(to_restart);
+    assert!(!results.completed);
+
+    // quit in the middle of the next test
+    toipe.restart().unwrap();
+    let mut keys = script(vec![Step::Type(CTRL_C.to_string())]);
+    let (to_restart, results) = toipe.test(&mut keys).unwrap();
+    assert!(!to_restart);
+    assert!(!results.completed);
+}
+
+#[test]
+fn too_small_terminal_is_an_error() {
+    let config = parse_args(&[]).unwrap();
+    let tui = ToipeTui::from_terminal(FakeTerminal::new(100, 3));
+
+    assert!(Toipe::with_tui(config, tui).is_err());
+}
+
+#[test]
+fn typed_chars_replace_the_text_in_place() {
+    for modes in Modes::all() {
+        if !modes.valid() || modes.timed {
+            continue;
+        }
+
+        let (mut toipe, terminal) = toipe_with(&modes.args());
+        let text = text_on_screen(&terminal);
+
+        // quit just before the end, while the text is still displayed
+        let almost_all: String = text.chars().take(text.chars().count() - 1).collect();
+        let mut keys = script(vec![Step::Type(almost_all), Step::Type(CTRL_C.to_string())]);
+        toipe.test(&mut keys).unwrap();
+
+        assert_eq!(text_on_screen(&terminal), text, "{:?}", modes);
+    }
+}
+
+#[test]
+fn blind_test_does_not_mark_typed_chars() {
+    let (mut toipe, terminal) = toipe_with(&["--text", "abc", "--blind"]);
+    let output_before = terminal.output().len();
+
+    let mut keys = script(vec![
+        Step::Type("ax".to_string()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    let (_, results) = toipe.test(&mut keys).unwrap();
+
+    assert_eq!(results.total_char_errors, 1);
+    // colors of correct and wrong chars in the dark theme
+    let output = &terminal.output()[output_before..];
+    assert!(!output.contains("\x1b[38;5;10m"));
+    assert!(!output.contains("\x1b[38;5;1m"));
+}
+
+#[test]
+fn hardcore_test_ignores_corrections() {
+    let (mut toipe, terminal) = toipe_with(&["--text", "abc", "--hardcore"]);
+
+    let mut keys = script(vec![
+        Step::Type(format!("ax{}c", BACKSPACE)),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    let (_, results) = toipe.test(&mut keys).unwrap();
+
+    assert!(results.completed);
+    assert_eq!(results.attempted_corrections, 1);
+    assert_eq!(results.final_uncorrected_errors, 1);
+    assert!(terminal
+        .screen()
+        .join("\n")
+        .contains("Hardcore: 1 corrections attempted"));
+}
+
+#[test]
+fn live_accuracy_is_colored_by_thresholds() {
+    let (mut toipe, terminal) = toipe_with(&[
+        "--text",
+        "abcd",
+        "--live-accuracy",
+        "--accuracy-thresholds",
+        "90,40",
+    ]);
+    assert!(terminal.screen().join("\n").contains("accuracy 100.0%"));
+
+    // quit before the end, while the hints are still displayed
+    let mut keys = script(vec![
+        Step::Type("ax".to_string()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    toipe.test(&mut keys).unwrap();
+
+    assert!(terminal.screen().join("\n").contains("accuracy  50.0%"));
+    // fair accuracy in the dark theme
+    assert!(terminal.output().contains("\x1b[38;5;3m 50.0%"));
+}
+
+#[test]
+fn test_ends_when_accuracy_drops_below_minimum() {
+    let (mut toipe, terminal) =
+        toipe_with(&["--text", "abcdefghijklmnopqrst", "--min-accuracy", "90"]);
+
+    // 90% after 10 chars is not below the minimum, 82% after 11 is
+    let mut keys = script(vec![
+        Step::Type("abcdefghix".to_string()),
+        Step::Type("y".to_string()),
+        Step::Type("more keys are ignored".to_string()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    let (to_restart, results) = toipe.test(&mut keys).unwrap();
+
+    assert!(!to_restart);
+    assert!(!results.completed);
+    assert_eq!(results.total_chars_typed, 11);
+    assert!(terminal
+        .screen()
+        .join("\n")
+        .contains("Test ended: accuracy dropped to 81.8%, below the minimum of 90%"));
+}
+
+#[test]
+fn test_ends_at_first_error() {
+    let (mut toipe, _) = toipe_with(&["--text", "abcd efgh", "--stop-on-error"]);
+
+    let mut keys = script(vec![
+        Step::Type("abx".to_string()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    let (to_restart, results) = toipe.test(&mut keys).unwrap();
+
+    assert!(!to_restart);
+    assert!(results.completed);
+    assert_eq!(results.total_chars_typed, 3);
+    assert_eq!(results.total_char_errors, 1);
+}
+
+#[test]
+fn custom_word_selector_and_keys() {
+    let config = ToipeConfig {
+        theme: toipe::theme::ThemeChoice::Dark,
+        num_words: 3,
+        ..Default::default()
+    };
+    let terminal = FakeTerminal::new(100, 30);
+    let words = RawWordSelector::from_string("kiwi\n".to_string()).unwrap();
+    let toipe = Toipe::with_tui(config, ToipeTui::from_terminal(terminal.clone()))
+        .unwrap()
+        .with_word_selector(Box::new(words))
+        .unwrap();
+    assert_eq!(text_on_screen(&terminal), "kiwi kiwi kiwi");
+
+    let mut keys: Vec<Key> = "kiwi kiwi kiwi".chars().map(Key::Char).collect();
+    keys.push(Key::Ctrl('c'));
+    let mut session = ToipeSession::from_toipe(toipe);
+    session.run(&mut KeyEvents::from_keys(keys)).unwrap();
+
+    assert_eq!(session.results().len(), 1);
+    assert_eq!(session.results()[0].total_words, 3);
+}
+
+#[test]
+fn fixed_words_make_tests_reproducible() {
+    let run = || {
+        let config = ToipeConfig {
+            theme: toipe::theme::ThemeChoice::Dark,
+            num_words: 3,
+            ..Default::default()
+        };
+        let terminal = FakeTerminal::new(100, 30);
+        let words = FixedWordSelector::new(["kiwi", "fig", "lime", "plum"]).unwrap();
+        let mut toipe = Toipe::with_tui(config, ToipeTui::from_terminal(terminal.clone()))
+            .unwrap()
+            .with_word_selector(Box::new(words))
+            .unwrap();
+        assert_eq!(text_on_screen(&terminal), "kiwi fig lime");
+
+        let mut keys = script(vec![
+            Step::Type("kiwi fog lime".to_string()),
+            Step::Type(CTRL_C.to_string()),
+        ]);
+        let (_, results) = toipe.test(&mut keys).unwrap();
+
+        // the next test goes on from where this one ended
+        toipe.restart().unwrap();
+        assert_eq!(text_on_screen(&terminal), "plum kiwi fig");
+        results
+    };
+
+    let (first, second) = (run(), run());
+    assert_eq!(first.total_char_errors, 1);
+    assert_eq!(first.total_char_errors, second.total_char_errors);
+    assert_eq!(first.total_chars_typed, second.total_chars_typed);
+    assert_eq!(first.keystrokes.len(), second.keystrokes.len());
+}
+
+#[test]
+fn custom_end_conditions() {
+    let (toipe, _) = toipe_with(&["--text", "one two three"]);
+    let flag = Arc::new(AtomicBool::new(false));
+    let mut toipe = toipe
+        .with_end_condition(WordCount(2))
+        .with_end_condition(Signal(Arc::clone(&flag)));
+
+    let mut keys = script(vec![
+        Step::Type("one two th".to_string()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    let (_, results) = toipe.test(&mut keys).unwrap();
+    assert!(results.completed);
+    assert_eq!(results.total_chars_typed, 8);
+
+    // the signal ends the test even when no key is pressed
+    toipe.restart().unwrap();
+    let setter = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(200));
+        flag.store(true, Ordering::Relaxed);
+    });
+    let mut keys = script(vec![
+        Step::Type("o".to_string()),
+        Step::Wait(Duration::from_secs(1)),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    let (_, results) = toipe.test(&mut keys).unwrap();
+    setter.join().unwrap();
+    assert!(results.completed);
+    assert_eq!(results.total_chars_typed, 1);
+}
+
+#[test]
+fn status_socket_reports_progress() {
+    let path = temp_path("scenario.sock");
+    let (mut toipe, _) =
+        toipe_with(&["--text", "ab cd", "--status-socket", path.to_str().unwrap()]);
+    let mut status = BufReader::new(UnixStream::connect(&path).unwrap());
+    let mut read_line = || {
+        let mut line = String::new();
+        status.read_line(&mut line).unwrap();
+        line
+    };
+    assert_eq!(read_line(), "idle\n");
+
+    let mut keys = script(vec![
+        Step::Type("ab".to_string()),
+        Step::Wait(Duration::from_millis(100)),
+        Step::Type(" cd".to_string()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    toipe.test(&mut keys).unwrap();
+
+    assert!(read_line().starts_with("typing wpm=0.0 progress=20.0"));
+    assert!(read_line().starts_with("typing wpm="));
+    let progress: Vec<String> = (0..3).map(|_| read_line()).collect();
+    assert!(progress[2].ends_with(" progress=80.0\n"), "{:?}", progress);
+    assert!(read_line().starts_with("done wpm="));
+
+    drop(toipe);
+    assert!(!path.exists());
+}
+
+#[test]
+fn streamer_mode_shows_large_readout() {
+    let (mut toipe, terminal) = toipe_with(&["--text", "ab cd", "--streamer"]);
+
+    let mut keys = script(vec![
+        Step::Type("ax".to_string()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    toipe.test(&mut keys).unwrap();
+
+    // the readout is in the top third, and the text below it
+    let screen = terminal.screen();
+    let labels_y = screen
+        .iter()
+        .position(|line| line.contains("wpm") && line.contains("accuracy"))
+        .unwrap();
+    let text_y = screen
+        .iter()
+        .position(|line| line.trim() == "ab cd")
+        .unwrap();
+    assert!(labels_y < 10, "{}", labels_y);
+    assert!(text_y > 10, "{}", text_y);
+    assert!(screen[labels_y - 1].contains('█'));
+}
+
+#[test]
+fn restart_waits_for_terminal_to_be_resized() {
+    let (mut toipe, terminal) = toipe_with(&[]);
+
+    terminal.resize(100, 3);
+    let resizer = {
+        let terminal = terminal.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            assert!(terminal.screen().join("\n").contains("Resize the terminal"));
+            terminal.resize(100, 30);
+        })
+    };
+
+    let mut keys = script(vec![Step::Wait(Duration::from_secs(1))]);
+    assert!(toipe.restart_when_fits(&mut keys).unwrap());
+    resizer.join().unwrap();
+    assert!(!terminal.screen().join("\n").contains("Resize the terminal"));
+
+    // quit while waiting
+    terminal.resize(100, 3);
+    let mut keys = script(vec![Step::Type(CTRL_C.to_string())]);
+    assert!(!toipe.restart_when_fits(&mut keys).unwrap());
+}
+
+#[test]
+fn repeat_shows_the_same_words() {
+    let (mut toipe, terminal) = toipe_with(&[]);
+    let text = text_on_screen(&terminal);
+
+    // repeat in the middle of a test
+    let mut keys = script(vec![
+        Step::Type(text[..5].to_string()),
+        Step::Type("\x10".to_string()),
+    ]);
+    let (to_restart, _) = toipe.test(&mut keys).unwrap();
+    assert!(to_restart);
+    toipe.restart().unwrap();
+    assert_eq!(text_on_screen(&terminal), text);
+
+    // repeat from the results
+    let mut keys = script(vec![
+        Step::Type(text.clone()),
+        Step::Type("\x10".to_string()),
+    ]);
+    let (to_restart, results) = toipe.test(&mut keys).unwrap();
+    assert!(to_restart);
+    assert!(results.completed);
+    toipe.restart().unwrap();
+    assert_eq!(text_on_screen(&terminal), text);
+
+    // new words after a restart
+    let mut keys = script(vec![Step::Type("\x12".to_string())]);
+    toipe.test(&mut keys).unwrap();
+    toipe.restart().unwrap();
+    assert_ne!(text_on_screen(&terminal), text);
+}
+
+#[test]
+fn results_details_show_the_rhythm() {
+    let (_, _, screen) = run_test(Modes::all()[0], |text| {
+        vec![
+            Step::Type(text.to_string()),
+            Step::Type("d".to_string()),
+            Step::Type(CTRL_C.to_string()),
+        ]
+    });
+    assert!(screen.contains("95th percentile"), "{}", screen);
+    assert!(screen.contains("0-10ms"), "{}", screen);
+}
+
+#[test]
+fn hints_are_at_the_right_edge() {
+    let (_toipe, terminal) = toipe_with(&["--text", "abc"]);
+
+    let screen = terminal.screen();
+    let hints = screen
+        .iter()
+        .find(|line| line.contains("esc to pause"))
+        .unwrap_or_else(|| panic!("{}", screen.join("\n")));
+    // followed by a space, like every hint
+    assert_eq!(hints.trim_end().chars().count(), 99, "{}", hints);
+    assert!(hints.trim_end().ends_with("ctrl-c to quit"), "{}", hints);
+}
+
+#[test]
+fn results_show_mistakes_in_the_text() {
+    let (mut toipe, terminal) = toipe_with(&["--text", "abc def"]);
+
+    let mut keys = script(vec![
+        Step::Type(format!("#{}abc dxf", BACKSPACE)),
+        Step::Type("m".to_string()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    let (to_restart, _) = toipe.test(&mut keys).unwrap();
+
+    assert!(!to_restart);
+    let screen = terminal.screen().join("\n");
+    assert!(screen.contains("Mistakes in the text"), "{}", screen);
+    assert!(screen.contains("abc def"), "{}", screen);
+    assert!(
+        screen.contains("d to see details, m to see summary, c to copy, ctrl-r to restart"),
+        "{}",
+        screen
+    );
+    // "a" was typed wrong once and "e" is still wrong
+    let output = terminal.output();
+    let mistakes = &output[output.rfind("Mistakes in the text").unwrap()..];
+    assert!(mistakes.contains("\x1b[2m\x1b[38;5;1ma"), "{:?}", mistakes);
+    assert!(mistakes.contains("\x1b[38;5;1m\x1b[4me"), "{:?}", mistakes);
+}
+
+#[test]
+fn rounds_end_with_a_summary() {
+    let (mut toipe, terminal) = toipe_with(&["--rounds", "2", "-n", "5"]);
+    assert!(terminal.screen().join("\n").contains("round 1 of 2"));
+
+    // the next round starts without showing the results
+    let text = text_on_screen(&terminal);
+    let mut keys = script(vec![Step::Type(text)]);
+    let (to_restart, results) = toipe.test(&mut keys).unwrap();
+    assert!(to_restart);
+    assert!(results.completed);
+    toipe.restart().unwrap();
+    assert!(terminal.screen().join("\n").contains("round 2 of 2"));
+
+    let text = text_on_screen(&terminal);
+    let mut keys = script(vec![Step::Type(text), Step::Type(CTRL_C.to_string())]);
+    let (to_restart, results) = toipe.test(&mut keys).unwrap();
+    assert!(!to_restart);
+    assert!(results.completed);
+    let screen = terminal.screen().join("\n");
+    assert!(screen.contains("Average of 2 rounds"), "{}", screen);
+    assert!(screen.contains("Round 2: "), "{}", screen);
+}
+
+#[test]
+fn words_out_of_time_are_skipped() {
+    let (mut toipe, terminal) = toipe_with(&["--text", "aa bb cc", "--word-budget", "100"]);
+
+    // "aa " has 300ms, after which the cursor moves to "bb"
+    let mut keys = script(vec![
+        Step::Type("a".to_string()),
+        Step::Wait(Duration::from_millis(500)),
+        Step::Type(BACKSPACE.to_string()),
+        Step::Type("bb cc".to_string()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    let (_, results) = toipe.test(&mut keys).unwrap();
+
+    assert!(results.completed);
+    assert_eq!(results.failed_words, 1);
+    assert_eq!(results.total_char_errors, 0);
+    assert_eq!(results.final_uncorrected_errors, 1);
+    assert!(terminal
+        .screen()
+        .join("\n")
+        .contains("Words failed: 1 (ran out of time)"));
+}
+
+#[test]
+fn pace_caret_moves_ahead() {
+    let (mut toipe, terminal) =
+        toipe_with(&["--text", "aaaa bbbb cccc dddd", "--ascii", "--pace", "120"]);
+
+    // 10 chars per second
+    let mut keys = script(vec![
+        Step::Type("a".to_string()),
+        Step::Wait(Duration::from_millis(650)),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    toipe.test(&mut keys).unwrap();
+
+    let screen = terminal.screen();
+    let text_line = screen
+        .iter()
+        .position(|line| line.contains("aaaa"))
+        .unwrap();
+    let markers = &screen[text_line + 1];
+    let text_start = screen[text_line].find('a').unwrap();
+    let pace = markers.find('*').expect("pace caret") - text_start;
+    assert!((5..=8).contains(&pace), "{:?}", screen);
+}
+
+#[test]
+fn time_attack_ends_when_the_deadline_catches_up() {
+    let (mut toipe, terminal) = toipe_with(&["--text", "aaaa bbbb cccc dddd", "--time-attack=120"]);
+
+    // the deadline passes the second char after about 200ms
+    let mut keys = script(vec![
+        Step::Type("a".to_string()),
+        Step::Wait(Duration::from_millis(500)),
+        Step::Type("aaa".to_string()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    let (to_restart, results) = toipe.test(&mut keys).unwrap();
+
+    assert!(!to_restart);
+    assert!(results.completed);
+    assert_eq!(results.total_chars_typed, 1);
+    let screen = terminal.screen().join("\n");
+    assert!(
+        screen.contains("Time attack: kept up with the deadline until 120."),
+        "{}",
+        screen
+    );
+}
+
+#[test]
+fn ghost_replays_the_best_run() {
+    let history = temp_path("ghost");
+    std::fs::write(&history, "best\tcustom text\t60\t0,100,200,300,400,500\n").unwrap();
+    let (mut toipe, terminal) = toipe_with(&[
+        "--text",
+        "aaaa bbbb cccc dddd",
+        "--ascii",
+        "--ghost",
+        "--history",
+        history.to_str().unwrap(),
+    ]);
+    std::fs::remove_file(&history).unwrap();
+
+    // the ghost stops after typing 6 chars
+    let mut keys = script(vec![
+        Step::Type("a".to_string()),
+        Step::Wait(Duration::from_millis(800)),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    toipe.test(&mut keys).unwrap();
+
+    let screen = terminal.screen();
+    let text_line = screen
+        .iter()
+        .position(|line| line.contains("aaaa"))
+        .unwrap();
+    let text_start = screen[text_line].find('a').unwrap();
+    let ghost = screen[text_line + 1].find('*').expect("ghost caret") - text_start;
+    assert_eq!(ghost, 6, "{:?}", screen);
+}
+
+#[test]
+fn pb_pace_is_shown_when_typing_as_fast_as_the_best_run() {
+    for (best_wpm, on_pace) in [(10, true), (100000, false)] {
+        let history = temp_path("pb");
+        std::fs::write(
+            &history,
+            format!("best\tcustom text\t{}\t0,100\n", best_wpm),
+        )
+        .unwrap();
+        let (mut toipe, terminal) = toipe_with(&[
+            "--text",
+            "aaaa bbbb cccc dddd eeee ffff",
+            "--pb-pace",
+            "--history",
+            history.to_str().unwrap(),
+        ]);
+        std::fs::remove_file(&history).unwrap();
+
+        let mut keys = script(vec![
+            Step::Type("aaaa bbbb cccc ".to_string()),
+            Step::Wait(Duration::from_millis(50)),
+            Step::Type("dddd eeee".to_string()),
+            Step::Wait(Duration::from_millis(50)),
+            Step::Type(CTRL_C.to_string()),
+        ]);
+        toipe.test(&mut keys).unwrap();
+
+        assert_eq!(terminal.output().contains("PB pace"), on_pace);
+    }
+}
+
+#[test]
+fn books_resume_where_they_were_left_off() {
+    let dir = temp_path("book");
+    std::fs::create_dir_all(&dir).unwrap();
+    let book = dir.join("book.txt");
+    std::fs::write(&book, "alpha bravo charlie\ndelta echo foxtrot\n").unwrap();
+    let history = dir.join("history");
+    let new_toipe = |extra_args: &[&str]| {
+        let mut args = vec![
+            "--book",
+            book.to_str().unwrap(),
+            "-n",
+            "3",
+            "--history",
+            history.to_str().unwrap(),
+        ];
+        args.extend(extra_args);
+        toipe_with(&args)
+    };
+
+    let (mut toipe, terminal) = new_toipe(&[]);
+    assert!(text_on_screen(&terminal).contains("alpha bravo charlie"));
+    let mut keys = script(vec![
+        Step::Type("alpha bravo charlie".to_string()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    toipe.test(&mut keys).unwrap();
+
+    let (_toipe, terminal) = new_toipe(&[]);
+    assert!(text_on_screen(&terminal).contains("delta echo foxtrot"));
+
+    let (_toipe, terminal) = new_toipe(&["--restart-book"]);
+    assert!(text_on_screen(&terminal).contains("alpha bravo charlie"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn books_can_start_from_a_chapter_or_line() {
+    let dir = temp_path("chapters");
+    std::fs::create_dir_all(&dir).unwrap();
+    let book = dir.join("book.txt");
+    std::fs::write(
+        &book,
+        "Chapter 1\n\nalpha bravo\n\nChapter 2\n\ncharlie delta\n",
+    )
+    .unwrap();
+    let history = dir.join("history");
+
+    for (start, text) in [
+        (["--chapter", "2"], "Chapter 2 charlie"),
+        (["--from-line", "3"], "alpha bravo Chapter"),
+    ] {
+        let mut args = vec![
+            "--book",
+            book.to_str().unwrap(),
+            "-n",
+            "3",
+            "--history",
+            history.to_str().unwrap(),
+        ];
+        args.extend(start);
+        let (_toipe, terminal) = toipe_with(&args);
+        assert!(text_on_screen(&terminal).contains(text), "{:?}", text);
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn markdown_books_are_typed_without_their_syntax() {
+    let dir = temp_path("markdown");
+    std::fs::create_dir_all(&dir).unwrap();
+    let notes = dir.join("notes.md");
+    std::fs::write(
+        &notes,
+        "# Notes\n\nType **this** [line](https://example.com).\n",
+    )
+    .unwrap();
+    let history = dir.join("history");
+
+    let (_toipe, terminal) = toipe_with(&[
+        "--book",
+        notes.to_str().unwrap(),
+        "-n",
+        "4",
+        "--history",
+        history.to_str().unwrap(),
+    ]);
+    assert!(text_on_screen(&terminal).contains("Notes Type this line."));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn terminal_can_be_closed_before_the_end() {
+    let (mut toipe, terminal) = toipe_with(&["--text", "the fox"]);
+    assert!(text_on_screen(&terminal).contains("the fox"));
+
+    toipe.shutdown().unwrap();
+    assert!(terminal.screen().iter().all(|line| line.trim().is_empty()));
+
+    // closing again, or dropping, does not touch the terminal
+    let output_len = terminal.output().len();
+    toipe.shutdown().unwrap();
+    drop(toipe);
+    assert_eq!(terminal.output().len(), output_len);
+}
+
+#[test]
+fn wrapped_passages_have_continuation_markers() {
+    let text = "the quick brown fox jumps over the lazy dog ".repeat(5);
+    let (_toipe, terminal) = toipe_with(&["--text", text.trim()]);
+
+    let lines: Vec<String> = terminal
+        .screen()
+        .into_iter()
+        .filter(|line| line.contains("fox") || line.contains("dog"))
+        .collect();
+    assert!(lines.len() > 1, "{:?}", lines);
+    let (last, wrapped) = lines.split_last().unwrap();
+    assert!(
+        wrapped.iter().all(|line| line.ends_with(" ↩")),
+        "{:?}",
+        lines
+    );
+    assert!(!last.contains('↩'), "{:?}", lines);
+}
+
+#[test]
+fn keys_are_ignored_during_the_countdown() {
+    let (mut toipe, terminal) = toipe_with(&["--text", "abc", "--countdown=1"]);
+
+    let mut keys = script(vec![
+        Step::Type("x".to_string()),
+        Step::Wait(Duration::from_millis(1200)),
+        Step::Type("abc".to_string()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    let (_, results) = toipe.test(&mut keys).unwrap();
+
+    assert!(results.completed);
+    assert_eq!(results.total_char_errors, 0);
+    // the timer started when the countdown ended
+    assert!(results.duration() < Duration::from_millis(1000));
+    assert!(terminal.output().contains("starting in 1"));
+}
+
+#[test]
+fn first_keys_can_be_ignored() {
+    let (mut toipe, _) = toipe_with(&["--text", "abc", "--ignore-first-keys", "2"]);
+
+    let mut keys = script(vec![
+        Step::Type("\n\n".to_string()),
+        Step::Type("abc".to_string()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    let (_, results) = toipe.test(&mut keys).unwrap();
+
+    assert!(results.completed);
+    assert_eq!(results.total_char_errors, 0);
+    assert_eq!(results.keystrokes.len(), 3);
+}
+
+#[test]
+fn kana_are_typed_as_romaji() {
+    let (mut toipe, terminal) = toipe_with(&["--text", "ねこ ラーメン", "--romaji"]);
+    assert_eq!(text_on_screen(&terminal), "ねこ ラーメン");
+
+    let mut keys = script(vec![
+        Step::Type("nek\x7fko ra-men".to_string()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    let (_, results) = toipe.test(&mut keys).unwrap();
+
+    assert!(results.completed);
+    assert_eq!(results.total_char_errors, 0);
+    assert_eq!(results.total_chars_in_text, 7);
+}
+
+#[test]
+fn bursts_of_keys_are_not_dropped() {
+    let (mut toipe, terminal) = toipe_with(&["-n", "100", "--seed", "1"]);
+    let text = text_on_screen(&terminal);
+
+    // bursts of 50 chars every 100ms, far faster than 300 wpm
+    let chars: Vec<char> = text.chars().collect();
+    let mut steps: Vec<Step> = chars
+        .chunks(50)
+        .flat_map(|burst| {
+            [
+                Step::Type(burst.iter().collect()),
+                Step::Wait(Duration::from_millis(100)),
+            ]
+        })
+        .collect();
+    steps.push(Step::Type(CTRL_C.to_string()));
+    let mut keys = script(steps);
+    let (_, results) = toipe.test(&mut keys).unwrap();
+
+    assert!(results.completed);
+    assert_eq!(results.final_chars_typed_correctly, chars.len());
+    assert_eq!(results.total_char_errors, 0);
+    assert_eq!(results.keystrokes.len(), chars.len());
+    assert!(results.wpm() > 300.0, "{}", results.wpm());
+}
+
+#[test]
+fn paused_time_is_not_counted() {
+    let (mut toipe, terminal) = toipe_with(&["--text", "abc"]);
+
+    // the key that resumes the test is not typed
+    let mut keys = script(vec![
+        Step::Type("a".to_string()),
+        Step::Type(ESC.to_string()),
+        Step::Wait(Duration::from_millis(1200)),
+        Step::Type("x".to_string()),
+        Step::Type("bc".to_string()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    let (_, results) = toipe.test(&mut keys).unwrap();
+
+    assert!(results.completed);
+    assert_eq!(results.total_char_errors, 0);
+    assert_eq!(results.pauses.len(), 1);
+    assert!(results.duration() < Duration::from_millis(1000));
+    assert!(terminal
+        .output()
+        .contains("paused, press any key to resume"));
+}
+
+#[test]
+fn ctrl_c_quits_a_paused_test() {
+    let (mut toipe, _) = toipe_with(&["--text", "abc"]);
+
+    let mut keys = script(vec![
+        Step::Type("a".to_string()),
+        Step::Type(ESC.to_string()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    let (to_restart, results) = toipe.test(&mut keys).unwrap();
+
+    assert!(!to_restart);
+    assert!(!results.completed);
+    assert_eq!(results.pauses.len(), 1);
+}
+
+#[test]
+fn suspended_time_is_paused() {
+    let (mut toipe, terminal) = toipe_with(&["--text", "abc"]);
+
+    // the fake terminal is continued right away, and the test stays
+    // paused until a key is pressed
+    let mut keys = script(vec![
+        Step::Type("a".to_string()),
+        Step::Type(CTRL_Z.to_string()),
+        Step::Wait(Duration::from_millis(1200)),
+        Step::Type("x".to_string()),
+        Step::Type("bc".to_string()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    let (_, results) = toipe.test(&mut keys).unwrap();
+
+    assert!(results.completed);
+    assert_eq!(results.total_char_errors, 0);
+    assert_eq!(results.pauses.len(), 1);
+    assert!(results.duration() < Duration::from_millis(1000));
+    // displayed when paused and again when redisplayed after continuing
+    assert_eq!(
+        terminal
+            .output()
+            .matches("paused, press any key to resume")
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn stopping_from_outside_pauses_the_test() {
+    let (mut toipe, terminal) = toipe_with(&["--text", "abc"]);
+
+    // like `kill -STOP` and `kill -CONT` from another terminal
+    let stopped_terminal = terminal.clone();
+    let stopper = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(300));
+        stopped_terminal.stop_and_continue();
+    });
+    let mut keys = script(vec![
+        Step::Type("a".to_string()),
+        Step::Wait(Duration::from_millis(1200)),
+        Step::Type("x".to_string()),
+        Step::Type("bc".to_string()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    let (_, results) = toipe.test(&mut keys).unwrap();
+    stopper.join().unwrap();
+
+    assert!(results.completed);
+    assert_eq!(results.total_char_errors, 0);
+    assert_eq!(results.pauses.len(), 1);
+    assert!(results.duration() < Duration::from_millis(1000));
+    assert!(terminal
+        .output()
+        .contains("paused, press any key to resume"));
+}
+
+#[test]
+fn keyboard_shortcuts_are_shown_over_the_results() {
+    let (mut toipe, terminal) = toipe_with(&["--text", "abc"]);
+
+    // any key hides the shortcuts without doing anything else
+    let mut keys = script(vec![
+        Step::Type("abc".to_string()),
+        Step::Type("?".to_string()),
+        Step::Type("d".to_string()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    let (to_restart, _) = toipe.test(&mut keys).unwrap();
+
+    assert!(!to_restart);
+    assert!(terminal.output().contains("Keyboard shortcuts"));
+    let screen = terminal.screen().join("\n");
+    assert!(!screen.contains("Keyboard shortcuts"));
+    assert!(screen.contains("Accuracy: "));
+}
+
+#[cfg(not(feature = "clipboard"))]
+#[test]
+fn results_are_kept_when_there_is_no_clipboard() {
+    let (mut toipe, terminal) = toipe_with(&["--text", "abc"]);
+
+    let mut keys = script(vec![
+        Step::Type("abc".to_string()),
+        Step::Type("c".to_string()),
+        Step::Type("d".to_string()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    let (to_restart, _) = toipe.test(&mut keys).unwrap();
+
+    assert!(!to_restart);
+    assert!(terminal.output().contains("No clipboard available"));
+    let uncopied_results = toipe.take_uncopied_results();
+    assert_eq!(uncopied_results.len(), 1);
+    assert!(uncopied_results[0].starts_with("Took "));
+    assert!(uncopied_results[0].contains("\nAccuracy: 100.0%\n"));
+    assert!(toipe.take_uncopied_results().is_empty());
+}
+
+#[test]
+fn endless_test_ends_with_ctrl_c() {
+    let (mut toipe, terminal) = toipe_with(&["--endless", "-n", "5"]);
+    let text = text_on_screen(&terminal);
+
+    // finish the first page and type a bit of the next one
+    let mut keys = script(vec![
+        Step::Type(text.clone()),
+        Step::Type("abc".to_string()),
+        Step::Type(CTRL_C.to_string()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    let (to_restart, results) = toipe.test(&mut keys).unwrap();
+
+    assert!(!to_restart);
+    assert!(results.completed);
+    assert_eq!(results.pages.len(), 2);
+    assert_eq!(results.total_chars_in_text, text.len() + 3);
+    assert!(terminal.screen().join("\n").contains("Accuracy: "));
+}
+
+#[test]
+fn checkpoints_get_the_chars_typed_so_far() {
+    let (toipe, terminal) = toipe_with(&["--endless", "-n", "5"]);
+    let checkpoints = Rc::new(RefCell::new(Vec::new()));
+    let mut toipe = toipe.on_checkpoint(Duration::ZERO, {
+        let checkpoints = Rc::clone(&checkpoints);
+        move |results: &ToipeResults| {
+            checkpoints.borrow_mut().push(results.clone());
+            Err(anyhow::anyhow!("disk full"))
+        }
+    });
+    let text = text_on_screen(&terminal);
+
+    let mut keys = script(vec![
+        Step::Type(text.clone()),
+        Step::Type("abc".to_string()),
+        Step::Type(CTRL_C.to_string()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    // the test goes on even though the checkpoints cannot be saved
+    let (_, results) = toipe.test(&mut keys).unwrap();
+    assert!(results.completed);
+
+    // checkpoints are taken while a page is typed, not only after it
+    let checkpoints = checkpoints.borrow();
+    assert!(checkpoints.len() > 3);
+    assert!(checkpoints.iter().all(|results| !results.completed));
+    let last = checkpoints.last().unwrap();
+    assert_eq!(last.pages.len(), 2);
+    assert_eq!(last.total_chars_in_text, text.len() + 3);
+}
+
+#[test]
+fn annotations_are_shown_above_the_text() {
+    let word_list = temp_path("vocab");
+    std::fs::write(&word_list, "bonjour\thello\nchat\tcat\n").unwrap();
+    let (mut toipe, terminal) = toipe_with(&["-n", "4", "-f", word_list.to_str().unwrap()]);
+    std::fs::remove_file(&word_list).unwrap();
+
+    let meaning = |word: &str| if word == "bonjour" { "hello" } else { "cat" };
+    let screen = terminal.screen();
+    let text_y = screen
+        .iter()
+        .position(|line| line.contains("bonjour") || line.contains("chat"))
+        .unwrap();
+    let words: Vec<String> = screen[text_y]
+        .split_whitespace()
+        .map(String::from)
+        .collect();
+
+    // type the first word and move to the second
+    let mut keys = script(vec![
+        Step::Type(format!("{} ", words[0])),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    toipe.test(&mut keys).unwrap();
+
+    assert_eq!(terminal.screen()[text_y - 2].trim(), meaning(&words[1]));
+}
+
+#[test]
+fn shuffled_words_are_all_typed_before_repeating() {
+    let word_list = temp_path("shuffle");
+    std::fs::write(&word_list, "ant\nbear\ncamel\ndeer\n").unwrap();
+    let (mut toipe, terminal) =
+        toipe_with(&["-f", word_list.to_str().unwrap(), "-n", "2", "--shuffle"]);
+    std::fs::remove_file(&word_list).unwrap();
+
+    let first_text = text_on_screen(&terminal);
+    toipe.restart().unwrap();
+    let second_text = text_on_screen(&terminal);
+
+    let mut words: Vec<&str> = first_text
+        .split(' ')
+        .chain(second_text.split(' '))
+        .collect();
+    words.sort_unstable();
+    assert_eq!(words, ["ant", "bear", "camel", "deer"]);
+}
+
+#[test]
+fn preview_is_not_timed() {
+    let (mut toipe, terminal) = toipe_with(&["--preview"]);
+    let text = text_on_screen(&terminal);
+
+    let mut keys = script(vec![
+        // ignored in the preview
+        Step::Type("abc".to_string()),
+        Step::Wait(Duration::from_millis(500)),
+        Step::Type("\r".to_string()),
+        Step::Type(text.clone()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    let (_, results) = toipe.test(&mut keys).unwrap();
+
+    assert!(terminal.output().contains("to begin"));
+    assert!(results.completed);
+    assert_eq!(results.total_char_errors, 0);
+    assert!(results.duration() < Duration::from_millis(500));
+}
+
+#[test]
+fn certifications_are_timed_tests() {
+    let (mut toipe, terminal) = toipe_with(&["certify", "--duration", "1", "--name", "Ada"]);
+    let text_name = toipe.config().text_name();
+    let text = text_on_screen(&terminal);
+
+    let half: String = text.chars().take(text.chars().count() / 2).collect();
+    let mut keys = script(vec![
+        Step::Type(half),
+        Step::Wait(Duration::from_millis(1500)),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    let (_, results) = toipe.test(&mut keys).unwrap();
+    assert!(results.completed);
+    assert!(results.duration() < Duration::from_millis(1500));
+
+    let mut certificate = Vec::new();
+    commands::certify("Ada", &text_name, Some(&results), None, &mut certificate).unwrap();
+    let certificate = String::from_utf8(certificate).unwrap();
+    assert!(certificate.contains("Ada"), "{}", certificate);
+    assert!(
+        certificate.contains("typed a 1 second test"),
+        "{}",
+        certificate
+    );
+    assert!(certificate.contains(&certificate::text_hash(&results)));
+
+    assert!(commands::certify("Ada", &text_name, None, None, &mut Vec::new()).is_err());
+}
+
+#[test]
+fn exported_results_can_be_verified() {
+    let dir = temp_path("export");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("results.json");
+    let args = ["--seed", "7", "-n", "5"];
+
+    let (mut toipe, terminal) = toipe_with(&args);
+    let args = args.map(String::from);
+    let text = text_on_screen(&terminal);
+    let mut keys = script(vec![
+        Step::Type(text.clone()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    let (_, results) = toipe.test(&mut keys).unwrap();
+    let exported = ExportedResults::new(&results, toipe.config(), &args);
+    assert_eq!(exported.text, text);
+    assert_eq!(
+        exported
+            .word_records
+            .iter()
+            .map(|record| record.word.as_str())
+            .collect::<Vec<_>>(),
+        text.split_whitespace().collect::<Vec<_>>()
+    );
+    exported.save(&path).unwrap();
+
+    let mut out = Vec::new();
+    commands::verify(&path, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("checksum: ok"), "{}", out);
+    assert!(out.contains("text: ok, selected by seed 7"), "{}", out);
+
+    // speeds that do not match the counts are caught
+    let edited = ExportedResults {
+        wpm: exported.wpm + 10.0,
+        ..exported.clone()
+    };
+    edited.save(&path).unwrap();
+    assert!(commands::verify(&path, &mut Vec::new()).is_err());
+
+    // as are texts that the seed does not select
+    let mut edited = ExportedResults {
+        text: exported.text.replacen(' ', "x ", 1),
+        ..exported
+    };
+    edited.checksum = edited.expected_checksum();
+    edited.save(&path).unwrap();
+    let mut out = Vec::new();
+    assert!(commands::verify(&path, &mut out).is_err());
+    assert!(String::from_utf8(out).unwrap().contains("checksum: ok"));
+
+    // texts that depend on more than the options are not selected again
+    let mut edited = ExportedResults {
+        args: [&args[..], &["--adaptive".to_string()]].concat(),
+        ..edited
+    };
+    edited.checksum = edited.expected_checksum();
+    edited.save(&path).unwrap();
+    let mut out = Vec::new();
+    let _ = commands::verify(&path, &mut out);
+    let out = String::from_utf8(out).unwrap();
+    assert!(
+        out.contains("text: not checked, the text of --adaptive"),
+        "{}",
+        out
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn text_stays_readable_on_huge_terminals() {
+    for (max_columns, expected_width) in [("80", 80), ("40", 40)] {
+        let config = parse_args(&["-n", "1000", "--max-columns", max_columns]).unwrap();
+        let terminal = FakeTerminal::new(400, 120);
+        let _toipe = Toipe::with_tui(config, ToipeTui::from_terminal(terminal.clone())).unwrap();
+
+        let screen = terminal.screen();
+        let text_lines: Vec<&String> = screen
+            .iter()
+            .filter(|line| !line.trim().is_empty() && !line.contains("ctrl-r"))
+            .collect();
+        assert!(text_lines.len() <= 24, "{}", text_lines.len());
+        for line in &text_lines {
+            let text = line.trim();
+            assert!(text.chars().count() <= expected_width, "{:?}", text);
+            // centered on the screen
+            let left = line.chars().take_while(|c| *c == ' ').count();
+            assert!(
+                left.abs_diff((400 - text.chars().count()) / 2) <= 1,
+                "{:?}",
+                line
+            );
+        }
+        assert!(text_lines[0].trim().chars().count() > expected_width / 2);
+    }
+
+    assert!(parse_args(&["--max-columns", "5"]).is_err());
+}
+
+#[test]
+fn narrow_terminals_get_shorter_words() {
+    let config = parse_args(&["-n", "10"]).unwrap();
+    let terminal = FakeTerminal::new(20, 30);
+    let mut toipe = Toipe::with_tui(config, ToipeTui::from_terminal(terminal.clone())).unwrap();
+
+    // a line of words is 8 columns wide, with the space after each word
+    let text = text_on_screen(&terminal);
+    let words: Vec<&str> = text.split_whitespace().collect();
+    assert_eq!(words.len(), 10, "{:?}", terminal.screen());
+    assert!(words.iter().all(|word| word.len() <= 7), "{:?}", words);
+
+    let mut keys = script(vec![
+        Step::Type(text.clone()),
+        Step::Type(CTRL_C.to_string()),
+    ]);
+    let (_, results) = toipe.test(&mut keys).unwrap();
+    assert!(results.completed);
+}